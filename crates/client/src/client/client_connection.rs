@@ -17,10 +17,14 @@ use std::sync::Arc;
 
 use futures::Future;
 
-use proto::error::ProtoError;
+use proto::error::{ProtoError, ProtoResult};
 use proto::xfer::{DnsRequestSender, DnsResponse};
 
+use op::{Message, MessageFinalizer, MessageVerifier};
 use rr::dnssec::Signer;
+#[cfg(feature = "dnssec")]
+use rr::dnssec::TSigner;
+use rr::Record;
 
 /// Trait for client connections
 pub trait ClientConnection: 'static + Sized + Send {
@@ -34,3 +38,53 @@ pub trait ClientConnection: 'static + Sized + Send {
     /// Construct a new stream for use in the Client
     fn new_stream(&self, signer: Option<Arc<Signer>>) -> Self::SenderFuture;
 }
+
+/// The signers with which the client connections authenticate the messages they send
+///
+/// A connection which was created with a TSIG signer, e.g. `UdpClientConnection::with_tsigner`,
+///  signs all messages with it, otherwise updates are signed with the SIG0 signer of the client.
+pub enum MessageSigner {
+    /// SIG(0), signs updates with the private key of a KEY record in the zone
+    Sig0(Arc<Signer>),
+    /// TSIG, signs all messages with a secret shared with the server
+    #[cfg(feature = "dnssec")]
+    TSig(TSigner),
+}
+
+impl MessageSigner {
+    /// Returns the TSIG signer of the connection if there is one, otherwise the SIG0 signer
+    pub(crate) fn select(
+        tsigner: &Option<Arc<MessageSigner>>,
+        signer: Option<Arc<Signer>>,
+    ) -> Option<Arc<MessageSigner>> {
+        tsigner
+            .clone()
+            .or_else(|| signer.map(|signer| Arc::new(MessageSigner::Sig0(signer))))
+    }
+}
+
+impl MessageFinalizer for MessageSigner {
+    fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>> {
+        match *self {
+            MessageSigner::Sig0(ref signer) => signer.finalize_message(message, current_time),
+            #[cfg(feature = "dnssec")]
+            MessageSigner::TSig(ref signer) => signer.finalize_message(message, current_time),
+        }
+    }
+
+    fn should_finalize_message(&self, message: &Message) -> bool {
+        match *self {
+            MessageSigner::Sig0(ref signer) => signer.should_finalize_message(message),
+            #[cfg(feature = "dnssec")]
+            MessageSigner::TSig(ref signer) => signer.should_finalize_message(message),
+        }
+    }
+
+    fn response_verifier(&self, message: &Message) -> Option<Box<MessageVerifier>> {
+        match *self {
+            MessageSigner::Sig0(ref signer) => signer.response_verifier(message),
+            #[cfg(feature = "dnssec")]
+            MessageSigner::TSig(ref signer) => signer.response_verifier(message),
+        }
+    }
+}
@@ -20,6 +20,8 @@ use rand;
 use error::*;
 use op::{Message, MessageType, OpCode, Query, UpdateMessage};
 use rr::dnssec::Signer;
+#[cfg(feature = "dnssec")]
+use rr::dnssec::TSigner;
 use rr::rdata::NULL;
 use rr::{DNSClass, IntoRecordSet, Name, RData, Record, RecordType};
 
@@ -88,6 +90,56 @@ where
     }
 }
 
+#[cfg(feature = "dnssec")]
+impl<F, S>
+    ClientFuture<
+        DnsMultiplexerConnect<F, S, TSigner>,
+        DnsMultiplexer<S, TSigner, Box<DnsStreamHandle>>,
+        DnsMultiplexerSerialResponse,
+    >
+where
+    F: Future<Item = S, Error = ProtoError> + Send + 'static,
+    S: DnsClientStream + Send + 'static,
+{
+    /// Spawns a new ClientFuture Stream, which signs all requests with TSIG. This uses a default
+    ///  timeout of 5 seconds for all requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of bytes that can be used to send/receive DNS messages
+    ///              (see TcpClientStream or UdpClientStream)
+    /// * `stream_handle` - The handle for the `stream` on which bytes can be sent/received.
+    /// * `signer` - The TSIG signer, with a secret shared with the server
+    pub fn with_tsigner(
+        stream: F,
+        stream_handle: Box<DnsStreamHandle>,
+        signer: Arc<TSigner>,
+    ) -> (Self, BasicClientHandle<DnsMultiplexerSerialResponse>) {
+        Self::with_timeout_and_tsigner(stream, stream_handle, Duration::from_secs(5), signer)
+    }
+
+    /// Spawns a new ClientFuture Stream, which signs all requests with TSIG.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - A stream of bytes that can be used to send/receive DNS messages
+    ///              (see TcpClientStream or UdpClientStream)
+    /// * `stream_handle` - The handle for the `stream` on which bytes can be sent/received.
+    /// * `timeout_duration` - All requests may fail due to lack of response, this is the time to
+    ///                        wait for a response before canceling the request.
+    /// * `signer` - The TSIG signer, with a secret shared with the server
+    pub fn with_timeout_and_tsigner(
+        stream: F,
+        stream_handle: Box<DnsStreamHandle>,
+        timeout_duration: Duration,
+        signer: Arc<TSigner>,
+    ) -> (Self, BasicClientHandle<DnsMultiplexerSerialResponse>) {
+        let mp =
+            DnsMultiplexer::with_timeout(stream, stream_handle, timeout_duration, Some(signer));
+        Self::connect(mp)
+    }
+}
+
 impl<F, S, R> ClientFuture<F, S, R>
 where
     F: Future<Item = S, Error = ProtoError> + 'static + Send,
@@ -20,6 +20,8 @@
 mod key_format;
 mod keypair;
 mod signer;
+#[cfg(feature = "dnssec")]
+mod tsigner;
 
 use proto::rr::dnssec;
 
@@ -35,6 +37,8 @@ pub use self::dnssec::PublicKeyEnum;
 pub use self::signer::Signer;
 pub use self::dnssec::SupportedAlgorithms;
 pub use self::dnssec::TrustAnchor;
+#[cfg(feature = "dnssec")]
+pub use self::tsigner::TSigner;
 pub use self::dnssec::tbs;
 pub use self::dnssec::TBS;
 pub use self::dnssec::Verifier;
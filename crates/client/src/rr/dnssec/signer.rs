@@ -338,6 +338,11 @@ impl Signer {
         &self.key
     }
 
+    /// Returns the public key as it is published, i.e. the DNSKEY or the KEY
+    pub fn key_rdata(&self) -> &RData {
+        &self.key_rdata
+    }
+
     /// Returns the duration that this signature is valid for
     pub fn sig_duration(&self) -> Duration {
         self.sig_duration
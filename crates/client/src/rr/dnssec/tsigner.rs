@@ -0,0 +1,435 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! signer for TSIG, the transaction signatures of a secret shared with the server
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use proto::error::ProtoResult;
+
+use op::{Message, MessageFinalizer, MessageVerifier};
+use rr::rdata::tsig::{self, TsigAlgorithm};
+use rr::rdata::{DNSSECRData, TSIG};
+use rr::{Name, RData, Record};
+
+/// Signs messages with TSIG, and verifies the TSIG of messages which are received
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS (TSIG), November 2020](https://tools.ietf.org/html/rfc8945)
+///
+/// The key is a secret shared by the client and the server, which both know it by the same name.
+///  As a `MessageFinalizer`, every message sent with the signer is signed, not only updates,
+///  so that zone transfers and queries are authenticated as well.
+#[derive(Clone)]
+pub struct TSigner {
+    key: Vec<u8>,
+    algorithm: TsigAlgorithm,
+    signer_name: Name,
+    fudge: u16,
+}
+
+impl TSigner {
+    /// Returns a new TSigner
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - the secret shared with the server
+    /// * `algorithm` - the MAC algorithm, one of the HMAC-SHA2 algorithms
+    /// * `signer_name` - the name of the key, the server must know the key by the same name
+    /// * `fudge` - seconds of difference permitted between the clocks of the client and server,
+    ///             300 is recommended
+    pub fn new(
+        key: Vec<u8>,
+        algorithm: TsigAlgorithm,
+        signer_name: Name,
+        fudge: u16,
+    ) -> ProtoResult<Self> {
+        if !algorithm.is_supported() {
+            return Err(format!("unsupported tsig algorithm: {}", algorithm).into());
+        }
+
+        Ok(TSigner {
+            key,
+            algorithm,
+            signer_name,
+            fudge,
+        })
+    }
+
+    /// The secret shared with the server
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// The MAC algorithm
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// The name of the key, i.e. the owner of the TSIG records
+    pub fn signer_name(&self) -> &Name {
+        &self.signer_name
+    }
+
+    /// Seconds of difference permitted between the time a message is signed and verified
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// Returns the MAC of the data with the key
+    pub fn sign(&self, tbs: &[u8]) -> ProtoResult<Vec<u8>> {
+        self.algorithm.mac_data(&self.key, tbs)
+    }
+
+    /// Returns the MAC of the message, the first message of a request or a response
+    ///
+    /// # Arguments
+    ///
+    /// * `previous_hash` - the MAC of the request when signing a response, otherwise None
+    /// * `message` - the message to sign, its ID must be the original ID of the TSIG
+    /// * `pre_tsig` - the TSIG to sign, without a MAC
+    pub fn sign_message(
+        &self,
+        previous_hash: Option<&[u8]>,
+        message: &Message,
+        pre_tsig: &TSIG,
+    ) -> ProtoResult<Vec<u8>> {
+        tsig::message_tbs(previous_hash, message, pre_tsig, &self.signer_name, true)
+            .and_then(|tbs| self.sign(&tbs))
+    }
+
+    /// Verifies the TSIG of a message as it was received
+    ///
+    /// The time of the TSIG is not checked, see `TSIG::is_in_time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous_hash` - the MAC of the request when verifying a response, or of the previous
+    ///                     message of a TCP stream, e.g. an AXFR
+    /// * `message` - the message as it was received
+    /// * `first_message` - false for the second and later messages of a TCP stream
+    ///
+    /// # Return
+    ///
+    /// The verified TSIG, its MAC is the previous hash of a response or the next message
+    pub fn verify_message_byte(
+        &self,
+        previous_hash: Option<&[u8]>,
+        message: &[u8],
+        first_message: bool,
+    ) -> ProtoResult<TSIG> {
+        let (tbs, record) = tsig::signed_bitmessage_to_buf(previous_hash, message, first_message)?;
+        let tsig = if let RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) = *record.rdata() {
+            tsig.clone()
+        } else {
+            return Err("the last record is not a tsig".into());
+        };
+
+        if record.name() != &self.signer_name || tsig.algorithm() != &self.algorithm {
+            return Err(format!("tsig was not signed by key: {}", self.signer_name).into());
+        }
+
+        self.algorithm.verify_mac(&self.key, &tbs, tsig.mac())?;
+        Ok(tsig)
+    }
+}
+
+impl fmt::Debug for TSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the key is a secret, and must not end up in the logs
+        f.debug_struct("TSigner")
+            .field("algorithm", &self.algorithm)
+            .field("signer_name", &self.signer_name)
+            .field("fudge", &self.fudge)
+            .finish()
+    }
+}
+
+impl MessageFinalizer for TSigner {
+    fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>> {
+        debug!("signing message with tsig: {}", message.id());
+        let pre_tsig = TSIG::new(
+            self.algorithm.clone(),
+            u64::from(current_time),
+            self.fudge,
+            Vec::new(),
+            message.id(),
+            0,
+            Vec::new(),
+        );
+
+        let mac = self.sign_message(None, message, &pre_tsig)?;
+        let tsig = tsig::make_tsig_record(self.signer_name.clone(), pre_tsig.set_mac(mac));
+
+        Ok(vec![tsig])
+    }
+
+    fn should_finalize_message(&self, _: &Message) -> bool {
+        true
+    }
+
+    fn response_verifier(&self, message: &Message) -> Option<Box<MessageVerifier>> {
+        let request_mac = message.sig0().last().and_then(|record| match *record.rdata() {
+            RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => Some(tsig.mac().to_vec()),
+            _ => None,
+        });
+
+        Some(Box::new(TsigResponseVerifier {
+            signer: self.clone(),
+            previous_mac: request_mac.unwrap_or_default(),
+            first_message: true,
+        }))
+    }
+}
+
+/// Verifies the responses to a request which was signed with a `TSigner`
+///
+/// Every response must be signed with the key of the request. The MAC of the first response
+///  covers the MAC of the request, that of each later message of a zone transfer covers the MAC
+///  of the message before it. Unsigned responses, e.g. the BADKEY and BADSIG errors of a server
+///  which did not verify the request, are rejected.
+struct TsigResponseVerifier {
+    signer: TSigner,
+    previous_mac: Vec<u8>,
+    first_message: bool,
+}
+
+impl MessageVerifier for TsigResponseVerifier {
+    fn verify_response(&mut self, response: &[u8]) -> ProtoResult<()> {
+        let tsig = self.signer.verify_message_byte(
+            Some(&self.previous_mac),
+            response,
+            self.first_message,
+        )?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| "Current time is before the Unix epoch.")?
+            .as_secs();
+        if !tsig.is_in_time(now) {
+            return Err(format!(
+                "tsig time: {} is outside of the fudge: {}",
+                tsig.time(),
+                tsig.fudge()
+            ).into());
+        }
+
+        self.previous_mac = tsig.mac().to_vec();
+        self.first_message = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "openssl", feature = "ring"))]
+mod tests {
+    use std::str::FromStr;
+
+    use op::{Message, Query};
+    use rr::{Name, RecordType};
+
+    use super::*;
+
+    fn signer() -> TSigner {
+        TSigner::new(
+            b"a secret shared by the client and server".to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str("key.example.com.").unwrap(),
+            300,
+        ).unwrap()
+    }
+
+    fn message() -> Message {
+        let mut message = Message::new();
+        message.set_id(42).add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message
+    }
+
+    #[test]
+    fn test_unsupported_algorithm() {
+        assert!(
+            TSigner::new(
+                vec![1, 2, 3],
+                TsigAlgorithm::Unknown(Name::from_str("hmac-md5.sig-alg.reg.int.").unwrap()),
+                Name::from_str("key.example.com.").unwrap(),
+                300,
+            ).is_err()
+        );
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let signer = signer();
+        let mut message = message();
+        message.finalize(&signer, 1_500_000_000).unwrap();
+        assert_eq!(message.sig0().len(), 1);
+
+        let bytes = message.to_vec().unwrap();
+        let tsig = signer.verify_message_byte(None, &bytes, true).unwrap();
+        assert_eq!(tsig.time(), 1_500_000_000);
+        assert_eq!(tsig.oid(), 42);
+        assert!(tsig.is_in_time(1_500_000_100));
+
+        // the read message keeps the TSIG apart, at the end of the message
+        let read = Message::from_vec(&bytes).unwrap();
+        assert!(read.additionals().is_empty());
+        assert_eq!(read.sig0(), message.sig0());
+    }
+
+    #[test]
+    fn test_verify_fails_on_changes() {
+        let signer = signer();
+        let mut message = message();
+        message.finalize(&signer, 1_500_000_000).unwrap();
+        let bytes = message.to_vec().unwrap();
+
+        // flip the recursion desired bit
+        let mut changed = bytes.clone();
+        changed[2] ^= 0x01;
+        assert!(signer.verify_message_byte(None, &changed, true).is_err());
+
+        // a different secret
+        let other = TSigner::new(
+            b"another secret".to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str("key.example.com.").unwrap(),
+            300,
+        ).unwrap();
+        assert!(other.verify_message_byte(None, &bytes, true).is_err());
+
+        // a different name for the same secret
+        let other = TSigner::new(
+            signer.key().to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str("other.example.com.").unwrap(),
+            300,
+        ).unwrap();
+        assert!(other.verify_message_byte(None, &bytes, true).is_err());
+
+        // a response must be verified with the MAC of the request
+        assert!(signer.verify_message_byte(Some(&[1, 2]), &bytes, true).is_err());
+    }
+
+    #[test]
+    fn test_sign_response() {
+        let signer = signer();
+        let mut request = message();
+        request.finalize(&signer, 1_500_000_000).unwrap();
+        let request_bytes = request.to_vec().unwrap();
+        let request_tsig = signer
+            .verify_message_byte(None, &request_bytes, true)
+            .unwrap();
+
+        let mut response = message();
+        let pre_tsig = TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            1_500_000_001,
+            300,
+            Vec::new(),
+            response.id(),
+            0,
+            Vec::new(),
+        );
+        let mac = signer
+            .sign_message(Some(request_tsig.mac()), &response, &pre_tsig)
+            .unwrap();
+        response.add_tsig(tsig::make_tsig_record(
+            signer.signer_name().clone(),
+            pre_tsig.set_mac(mac),
+        ));
+
+        let response_bytes = response.to_vec().unwrap();
+        assert!(
+            signer
+                .verify_message_byte(Some(request_tsig.mac()), &response_bytes, true)
+                .is_ok()
+        );
+        assert!(
+            signer
+                .verify_message_byte(None, &response_bytes, true)
+                .is_err()
+        );
+    }
+
+    fn signed_response(signer: &TSigner, previous_mac: &[u8], first_message: bool) -> Vec<u8> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut response = message();
+        let pre_tsig = TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            now,
+            300,
+            Vec::new(),
+            response.id(),
+            0,
+            Vec::new(),
+        );
+        let tbs = tsig::message_tbs(
+            Some(previous_mac),
+            &response,
+            &pre_tsig,
+            signer.signer_name(),
+            first_message,
+        ).unwrap();
+        let mac = signer.sign(&tbs).unwrap();
+        response.add_tsig(tsig::make_tsig_record(
+            signer.signer_name().clone(),
+            pre_tsig.set_mac(mac),
+        ));
+        response.to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_response_verifier() {
+        let signer = signer();
+        let mut request = message();
+        request.finalize(&signer, 1_500_000_000).unwrap();
+        let request_mac = match *request.sig0()[0].rdata() {
+            RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => tsig.mac().to_vec(),
+            _ => panic!("not a tsig"),
+        };
+
+        // the messages of a zone transfer are chained
+        let first = signed_response(&signer, &request_mac, true);
+        let first_mac = signer
+            .verify_message_byte(Some(&request_mac), &first, true)
+            .unwrap()
+            .mac()
+            .to_vec();
+        let second = signed_response(&signer, &first_mac, false);
+
+        let mut verifier = signer.response_verifier(&request).unwrap();
+        assert!(verifier.verify_response(&first).is_ok());
+        assert!(verifier.verify_response(&second).is_ok());
+
+        // the second message doesn't cover the MAC of the request
+        let mut verifier = signer.response_verifier(&request).unwrap();
+        assert!(verifier.verify_response(&second).is_err());
+
+        // a message may not be replayed in the chain
+        let mut verifier = signer.response_verifier(&request).unwrap();
+        assert!(verifier.verify_response(&first).is_ok());
+        assert!(verifier.verify_response(&first).is_err());
+
+        // unsigned responses are rejected
+        let mut verifier = signer.response_verifier(&request).unwrap();
+        assert!(verifier.verify_response(&message().to_vec().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_key() {
+        let signer = signer();
+        let debug = format!("{:?}", signer);
+        assert!(debug.contains("HmacSha256"));
+        assert!(!debug.contains(&format!("{:?}", signer.key())));
+    }
+}
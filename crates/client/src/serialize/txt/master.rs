@@ -14,8 +14,13 @@
  * limitations under the License.
  */
 use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use failure::Fail;
+
 use error::*;
 use rr::{DNSClass, IntoRecordSet, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
 use serialize::txt::master_lex::{Lexer, Token};
@@ -121,17 +126,46 @@ use serialize::txt::parse_rdata::RDataParser;
 /// ;               Semicolon is used to start a comment; the remainder of
 ///                 the line is ignored.
 /// ```
+///
+/// Along with these, the `$TTL` entry of RFC 2308 and BIND's `$GENERATE` entry are supported.
 #[derive(Default)]
-pub struct Parser;
+pub struct Parser {
+    class: Option<DNSClass>,
+}
+
+/// Maximum depth of nested `$INCLUDE` files
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Maximum number of records of a `$GENERATE` entry
+const MAX_GENERATE_RECORDS: u32 = 65_536;
+
+/// The file, if any, from which the entries are being parsed
+struct Source<'p> {
+    file: Option<&'p Path>,
+    depth: usize,
+    /// The class of the records before the first which states one, that of the including file
+    class: Option<DNSClass>,
+}
 
 impl Parser {
     /// Returns a new Zone file parser
     pub fn new() -> Self {
-        Parser
+        Parser::default()
+    }
+
+    /// Sets the class of the records before the first record which states one
+    ///
+    /// By default the class must be stated, root hints files for example state none.
+    pub fn set_default_class(&mut self, class: DNSClass) -> &mut Self {
+        self.class = Some(class);
+        self
     }
 
     /// Parse a file from the Lexer
     ///
+    /// The files of any `$INCLUDE` entries are relative to the current directory, see
+    ///  `parse_file` to have them relative to the zone file.
+    ///
     /// # Return
     ///
     /// A pair of the Zone origin name and a map of all Keys to RecordSets
@@ -140,14 +174,110 @@ impl Parser {
         lexer: Lexer,
         origin: Option<Name>,
     ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
-        let mut lexer = lexer;
         let mut records: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+        let source = Source {
+            file: None,
+            depth: 0,
+            class: self.class,
+        };
+
+        let origin = self.parse_entries(lexer, origin, None, &source, &mut records)?;
+        Self::zone(origin, records)
+    }
+
+    /// Parse a zone file
+    ///
+    /// The files of any `$INCLUDE` entries are relative to the directory of the file including
+    ///  them.
+    ///
+    /// # Return
+    ///
+    /// A pair of the Zone origin name and a map of all Keys to RecordSets
+    pub fn parse_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        origin: Option<Name>,
+    ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
+        let mut records: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
+
+        let class = self.class;
+        let origin = self.parse_included(path.as_ref(), origin, None, class, 0, &mut records)?;
+        Self::zone(origin, records)
+    }
+
+    fn zone(
+        origin: Option<Name>,
+        records: BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
+        let origin = origin.ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
+        })?;
+        Ok((origin, records))
+    }
+
+    /// Reads and parses the file, the records are added to those already parsed
+    fn parse_included(
+        &mut self,
+        path: &Path,
+        origin: Option<Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        depth: usize,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<Option<Name>> {
+        let mut buf = String::new();
+        if let Err(e) = File::open(path).and_then(|mut file| file.read_to_string(&mut buf)) {
+            let message = format!("failed to read {}: {}", path.display(), e);
+            return Err(e.context(ParseErrorKind::Msg(message)).into());
+        }
+
+        let source = Source {
+            file: Some(path),
+            depth,
+            class,
+        };
+        self.parse_entries(Lexer::new(&buf), origin, ttl, &source, records)
+    }
+
+    /// Parses the entries, errors are reported with the file and line of the failing entry
+    fn parse_entries(
+        &mut self,
+        lexer: Lexer,
+        origin: Option<Name>,
+        ttl: Option<u32>,
+        source: &Source,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<Option<Name>> {
+        let mut line = 1;
+        self.parse_lines(lexer, origin, ttl, source, records, &mut line)
+            .map_err(|e| {
+                let location = match source.file {
+                    Some(file) => format!("{}:{}", file.display(), line),
+                    None => format!("line {}", line),
+                };
+
+                let message = format!("{}: {}", location, e);
+                e.context(ParseErrorKind::Msg(message)).into()
+            })
+    }
+
+    /// Parses the entries, returning the last origin
+    fn parse_lines(
+        &mut self,
+        lexer: Lexer,
+        origin: Option<Name>,
+        ttl: Option<u32>,
+        source: &Source,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+        line: &mut usize,
+    ) -> ParseResult<Option<Name>> {
+        let mut lexer = lexer;
 
         let mut origin: Option<Name> = origin;
         let mut current_name: Option<Name> = None;
         let mut rtype: Option<RecordType> = None;
-        let mut ttl: Option<u32> = None;
-        let mut class: Option<DNSClass> = None;
+        let mut ttl: Option<u32> = ttl;
+        let mut class: Option<DNSClass> = source.class;
         let mut state = State::StartLine;
 
         while let Some(t) = lexer.next_token()? {
@@ -155,10 +285,12 @@ impl Parser {
                 State::StartLine => {
                     // current_name is not reset on the next line b/c it might be needed from the previous
                     rtype = None;
+                    *line = lexer.line();
 
                     match t {
                         // if Dollar, then $INCLUDE or $ORIGIN
-                        Token::Include => unimplemented!(),
+                        Token::Include => State::Include(Vec::new()),
+                        Token::Generate => State::Generate(Vec::new()),
                         Token::Origin => State::Origin,
                         Token::Ttl => State::Ttl,
 
@@ -197,17 +329,49 @@ impl Parser {
                         _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
                     }
                 }
-                State::Include => unimplemented!(),
+                State::Include(parts) => match t {
+                    Token::CharData(part) => {
+                        let mut parts = parts;
+                        parts.push(part);
+                        State::Include(parts)
+                    }
+                    Token::Quoted(part) => {
+                        let mut parts = parts;
+                        parts.push(Self::quoted_to_string(part)?);
+                        State::Include(parts)
+                    }
+                    Token::EOL => {
+                        self.include(&parts, origin.as_ref(), ttl, class, source, records)?;
+                        State::StartLine
+                    }
+                    _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                },
+                State::Generate(parts) => match t {
+                    Token::CharData(part) => {
+                        let mut parts = parts;
+                        parts.push(part);
+                        State::Generate(parts)
+                    }
+                    Token::Quoted(part) => {
+                        let mut parts = parts;
+                        parts.push(Self::quoted_to_string(part)?);
+                        State::Generate(parts)
+                    }
+                    Token::EOL => {
+                        Self::generate(&parts, origin.as_ref(), ttl, class, records)?;
+                        State::StartLine
+                    }
+                    _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                },
                 State::TtlClassType => {
                     match t {
                         // if number, TTL
                         // Token::Number(ref num) => ttl = Some(*num),
                         // One of Class or Type (these cannot be overlapping!)
                         Token::CharData(data) => {
-                            // if it's a number it's a ttl
-                            let result: ParseResult<u32> = Self::parse_time(&data);
-                            if result.is_ok() {
-                                ttl = result.ok();
+                            // if it's a number it's a ttl, the units alone would be e.g. DS
+                            if data.chars().next().map_or(false, |c| c.is_numeric()) {
+                                ttl = Some(Self::parse_time(&data)?);
                                 State::TtlClassType // hm, should this go to just ClassType?
                             } else {
                                 // if can parse DNSClass, then class
@@ -242,7 +406,7 @@ impl Parser {
                                         ParseErrorKind::Message("record type not specified"),
                                     )
                                 })?,
-                                record_parts.iter().map(|s| s.as_ref()),
+                                record_parts.iter().map(|s| s.as_slice()),
                                 origin.as_ref(),
                             )?;
 
@@ -298,32 +462,16 @@ impl Parser {
                             record.set_rdata(rdata);
 
                             // add to the map
-                            let key = RrKey::new(LowerName::new(record.name()), record.rr_type());
-
-                            match rtype.unwrap() {
-                                RecordType::SOA => {
-                                    let set = record.into_record_set();
-                                    if records.insert(key, set).is_some() {
-                                        return Err(
-                                            ParseErrorKind::Message(
-                                                "SOA is already \
-                                                 specified",
-                                            ).into(),
-                                        );
-                                    }
-                                }
-                                _ => {
-                                    // add a Vec if it's not there, then add the record to the list
-                                    let set = records.entry(key).or_insert_with(
-                                        || RecordSet::new(record.name(), record.rr_type(), 0),
-                                    );
-                                    set.insert(record, 0);
-                                }
-                            }
+                            Self::insert(record, records)?;
 
                             State::StartLine
                         }
                         Token::CharData(part) => {
+                            let mut record_parts = record_parts;
+                            record_parts.push(part.into_bytes());
+                            State::Record(record_parts)
+                        }
+                        Token::Quoted(part) => {
                             let mut record_parts = record_parts;
                             record_parts.push(part);
                             State::Record(record_parts)
@@ -331,7 +479,7 @@ impl Parser {
                         // TODO: we should not tokenize the list...
                         Token::List(list) => {
                             let mut record_parts = record_parts;
-                            record_parts.extend(list);
+                            record_parts.extend(list.into_iter().map(String::into_bytes));
                             State::Record(record_parts)
                         }
                         _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
@@ -340,12 +488,298 @@ impl Parser {
             }
         }
 
-        //
-        // build the Authority and return.
-        let origin = origin.ok_or_else(|| {
-            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
+        // the last line may be an $INCLUDE or $GENERATE without a line ending
+        match state {
+            State::Include(parts) => {
+                self.include(&parts, origin.as_ref(), ttl, class, source, records)?
+            }
+            State::Generate(parts) => Self::generate(&parts, origin.as_ref(), ttl, class, records)?,
+            _ => (),
+        }
+
+        Ok(origin)
+    }
+
+    /// Parses the records of an `$INCLUDE <file-name> [<domain-name>]` entry
+    ///
+    /// The file is relative to the directory of the including file, and the origin, if not
+    ///  specified, is that of the including file. The origin of the including file is not changed.
+    ///  Records of the included file which state no class have that of the including file.
+    fn include(
+        &mut self,
+        parts: &[String],
+        origin: Option<&Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        source: &Source,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<()> {
+        if parts.is_empty() || parts.len() > 2 {
+            return Err(ParseErrorKind::Message(
+                "$INCLUDE takes a file name and an optional domain name",
+            ).into());
+        }
+
+        if source.depth >= MAX_INCLUDE_DEPTH {
+            return Err(ParseErrorKind::Msg(format!(
+                "$INCLUDE of {} exceeds the maximum depth of {}",
+                parts[0], MAX_INCLUDE_DEPTH
+            )).into());
+        }
+
+        let include_origin = match parts.get(1) {
+            Some(name) => Some(Name::parse(name, origin)?),
+            None => origin.cloned(),
+        };
+
+        let path = match source.file.and_then(Path::parent) {
+            Some(dir) => dir.join(&parts[0]),
+            None => PathBuf::from(&parts[0]),
+        };
+
+        let depth = source.depth + 1;
+        self.parse_included(&path, include_origin, ttl, class, depth, records)?;
+        Ok(())
+    }
+
+    /// Adds the record to its RecordSet, there may be only one SOA
+    fn insert(record: Record, records: &mut BTreeMap<RrKey, RecordSet>) -> ParseResult<()> {
+        let key = RrKey::new(LowerName::new(record.name()), record.rr_type());
+
+        match record.rr_type() {
+            RecordType::SOA => {
+                let set = record.into_record_set();
+                if records.insert(key, set).is_some() {
+                    return Err(ParseErrorKind::Message("SOA is already specified").into());
+                }
+            }
+            _ => {
+                // add a Vec if it's not there, then add the record to the list
+                let set = records
+                    .entry(key)
+                    .or_insert_with(|| RecordSet::new(record.name(), record.rr_type(), 0));
+                set.insert(record, 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the records of a `$GENERATE <range> <lhs> [<ttl>] [<class>] <type> <rhs>` entry
+    ///
+    /// The range is `<start>-<stop>[/<step>]`, a record is generated for each value in it, with
+    ///  the `$`s of the `lhs` owner name and the `rhs` record data replaced by the value, see
+    ///  `expand`. The TTL and class, if not specified, are those of the preceding records.
+    fn generate(
+        parts: &[String],
+        origin: Option<&Name>,
+        ttl: Option<u32>,
+        class: Option<DNSClass>,
+        records: &mut BTreeMap<RrKey, RecordSet>,
+    ) -> ParseResult<()> {
+        if parts.len() < 4 {
+            return Err(ParseErrorKind::Message(
+                "$GENERATE takes a range, an owner name, a record type and the record data",
+            ).into());
+        }
+
+        let (start, stop, step) = Self::parse_range(&parts[0])?;
+        let lhs = &parts[1];
+
+        let mut ttl = ttl;
+        let mut class = class;
+        let mut rest = parts[2..].iter();
+        let rtype = loop {
+            let part = rest.next().ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message("record type not specified"))
+            })?;
+
+            if part.chars().next().map_or(false, |c| c.is_numeric()) {
+                ttl = Some(Self::parse_time(part)?);
+            } else if let Ok(c) = DNSClass::from_str(part) {
+                class = Some(c);
+            } else {
+                break RecordType::from_str(part)?;
+            }
+        };
+
+        let rhs = rest.collect::<Vec<_>>();
+        if rhs.is_empty() {
+            return Err(ParseErrorKind::Message("record data not specified").into());
+        }
+
+        let ttl = ttl.ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("record ttl not specified"))
         })?;
-        Ok((origin, records))
+        let class = class.ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("record class not specified"))
+        })?;
+
+        let mut value = start;
+        while value <= stop {
+            let name = Name::parse(&Self::expand(lhs, value)?, origin)?;
+            let rdata_parts = rhs
+                .iter()
+                .map(|part| Self::expand(part, value))
+                .collect::<ParseResult<Vec<_>>>()?;
+            let rdata = RData::parse(rtype, rdata_parts.iter().map(|s| s.as_bytes()), origin)?;
+
+            let mut record = Record::from_rdata(name, ttl, rtype, rdata);
+            record.set_dns_class(class);
+            Self::insert(record, records)?;
+
+            value = match value.checked_add(step) {
+                Some(value) => value,
+                None => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    /// The quoted strings of `$INCLUDE` and `$GENERATE` are text, the octets must be UTF-8
+    fn quoted_to_string(octets: Vec<u8>) -> ParseResult<String> {
+        String::from_utf8(octets).map_err(|e| {
+            ParseErrorKind::Msg(format!(
+                "quoted string is not UTF-8: {}",
+                String::from_utf8_lossy(e.as_bytes())
+            )).into()
+        })
+    }
+
+    /// Parses the `<start>-<stop>[/<step>]` range of a `$GENERATE` entry, of at most 65536 values
+    fn parse_range(range: &str) -> ParseResult<(u32, u32, u32)> {
+        let invalid = || ParseError::from(ParseErrorKind::Msg(format!("invalid range: {}", range)));
+
+        let (bounds, step) = match range.find('/') {
+            Some(i) => (&range[..i], u32::from_str(&range[i + 1..]).map_err(|_| invalid())?),
+            None => (range, 1),
+        };
+        let i = bounds.find('-').ok_or_else(invalid)?;
+        let start = u32::from_str(&bounds[..i]).map_err(|_| invalid())?;
+        let stop = u32::from_str(&bounds[i + 1..]).map_err(|_| invalid())?;
+
+        if start > stop || step == 0 {
+            return Err(invalid());
+        }
+
+        // one less than the number of records, which may not fit in a u32
+        if (stop - start) / step >= MAX_GENERATE_RECORDS {
+            return Err(ParseErrorKind::Msg(format!(
+                "range: {} generates more than {} records",
+                range, MAX_GENERATE_RECORDS
+            )).into());
+        }
+
+        Ok((start, stop, step))
+    }
+
+    /// Replaces the `$`s of a `$GENERATE` template with the value
+    ///
+    /// `$` may also be given as `${<offset>[,<width>[,<base>]]}`, the value with the offset added,
+    ///  zero padded to the width, in the base `d`, `o`, `x`, `X`, or in the reversed nibbles `n`
+    ///  or `N` of reverse zones. `\$` is a literal `$`.
+    fn expand(template: &str, value: u32) -> ParseResult<String> {
+        let mut expanded = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&'$') => {
+                    chars.next();
+                    expanded.push('$');
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    let mut modifiers = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => modifiers.push(ch),
+                            None => {
+                                return Err(ParseErrorKind::Msg(format!(
+                                    "unclosed modifiers in: {}",
+                                    template
+                                )).into())
+                            }
+                        }
+                    }
+
+                    Self::push_modified(&mut expanded, &modifiers, value)?;
+                }
+                '$' => expanded.push_str(&value.to_string()),
+                ch => expanded.push(ch),
+            }
+        }
+
+        Ok(expanded)
+    }
+
+    /// Pushes the value as specified by the `<offset>[,<width>[,<base>]]` modifiers
+    fn push_modified(expanded: &mut String, modifiers: &str, value: u32) -> ParseResult<()> {
+        let invalid = || {
+            ParseError::from(ParseErrorKind::Msg(format!(
+                "invalid modifiers: {{{}}}",
+                modifiers
+            )))
+        };
+
+        let mut modifiers = modifiers.split(',');
+        let offset = modifiers
+            .next()
+            .map_or(Ok(0), i64::from_str)
+            .map_err(|_| invalid())?;
+        let width = modifiers
+            .next()
+            .map_or(Ok(0), usize::from_str)
+            .map_err(|_| invalid())?;
+        let base = modifiers.next().unwrap_or("d");
+        if modifiers.next().is_some() {
+            return Err(invalid());
+        }
+
+        let value = i64::from(value) + offset;
+        if value < 0 {
+            return Err(ParseErrorKind::Msg(format!(
+                "{} with offset {} is negative",
+                value - offset,
+                offset
+            )).into());
+        }
+
+        match base {
+            "d" => expanded.push_str(&format!("{:01$}", value, width)),
+            "o" => expanded.push_str(&format!("{:01$o}", value, width)),
+            "x" => expanded.push_str(&format!("{:01$x}", value, width)),
+            "X" => expanded.push_str(&format!("{:01$X}", value, width)),
+            "n" | "N" => {
+                // the nibbles from the least significant, as labels, the width includes the dots
+                let mut value = value;
+                let mut width = width;
+                loop {
+                    let nibble = format!("{:x}", value & 0xf);
+                    if base == "N" {
+                        expanded.push_str(&nibble.to_uppercase());
+                    } else {
+                        expanded.push_str(&nibble);
+                    }
+                    value >>= 4;
+                    width = width.saturating_sub(1);
+
+                    if width == 0 && value == 0 {
+                        break;
+                    }
+                    expanded.push('.');
+                    width = width.saturating_sub(1);
+                    if width == 0 && value == 0 {
+                        break;
+                    }
+                }
+            }
+            _ => return Err(invalid()),
+        }
+
+        Ok(())
     }
 
     /// parses the string following the rules from:
@@ -422,12 +856,12 @@ impl Parser {
     }
 }
 
-#[allow(unused)]
 enum State {
     StartLine,    // start of line, @, $<WORD>, Name, Blank
     TtlClassType, // [<TTL>] [<class>] <type>,
     Ttl,          // $TTL <time>
-    Record(Vec<String>),
-    Include, // $INCLUDE <filename>
+    Record(Vec<Vec<u8>>), // the raw octets of the record data tokens
+    Include(Vec<String>), // $INCLUDE <filename> [<domain-name>]
+    Generate(Vec<String>), // $GENERATE <range> <lhs> [<ttl>] [<class>] <type> <rhs>
     Origin,
 }
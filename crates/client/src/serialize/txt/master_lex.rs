@@ -5,7 +5,6 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::char;
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -15,6 +14,7 @@ use error::*;
 pub struct Lexer<'a> {
     txt: Peekable<Chars<'a>>,
     state: State,
+    line: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -23,13 +23,20 @@ impl<'a> Lexer<'a> {
         Lexer {
             txt: txt.chars().peekable(),
             state: State::StartLine,
+            line: 1,
         }
     }
 
+    /// The line of the text being lexed, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
     /// Return the next Token in the string
     pub fn next_token(&mut self) -> LexerResult<Option<Token>> {
         let mut char_data_vec: Option<Vec<String>> = None;
         let mut char_data: Option<String> = None;
+        let mut octets: Option<Vec<u8>> = None;
 
         for i in 0..4096 {
             // max chars in a single lex, helps with issues in the lexer...
@@ -52,6 +59,13 @@ impl<'a> Lexer<'a> {
                         }
                         // white space at the start of line is a Blank
                         Some(ch) if ch.is_whitespace() => self.state = State::Blank,
+                        // control entries are only at the start of a line, elsewhere $ is data
+                        //  e.g. in the templates of $GENERATE
+                        Some('$') => {
+                            self.next_char();
+                            char_data = Some(String::new());
+                            self.state = State::Dollar;
+                        }
                         Some(_) => self.state = State::RestOfLine,
                         None => {
                             self.state = State::EOF;
@@ -62,29 +76,24 @@ impl<'a> Lexer<'a> {
                     match ch {
                         Some('@') => self.state = State::At,
                         Some('(') => {
-                            self.txt.next();
+                            self.next_char();
                             char_data_vec = Some(Vec::new());
                             self.state = State::List;
                         }
                         Some(')') => {
                             return Err(LexerErrorKind::IllegalCharacter(ch.unwrap_or(')')).into())
                         }
-                        Some('$') => {
-                            self.txt.next();
-                            char_data = Some(String::new());
-                            self.state = State::Dollar;
-                        }
                         Some('\r') | Some('\n') => {
                             self.state = State::EOL;
                         }
                         Some('"') => {
-                            self.txt.next();
-                            char_data = Some(String::new());
+                            self.next_char();
+                            octets = Some(Vec::new());
                             self.state = State::Quote;
                         }
                         Some(';') => self.state = State::Comment { is_list: false },
                         Some(ch) if ch.is_whitespace() => {
-                            self.txt.next();
+                            self.next_char();
                         } // gobble other whitespace
                         Some(ch) if !ch.is_control() && !ch.is_whitespace() => {
                             char_data = Some(String::new());
@@ -98,7 +107,7 @@ impl<'a> Lexer<'a> {
                 }
                 State::Blank => {
                     // consume the whitespace
-                    self.txt.next();
+                    self.next_char();
                     self.state = State::RestOfLine;
                     return Ok(Some(Token::Blank));
                 }
@@ -110,7 +119,7 @@ impl<'a> Lexer<'a> {
                             self.state = State::EOL;
                         }, // out of the comment
                         Some(_) => {
-                            self.txt.next();
+                            self.next_char();
                         } // advance the token by default and maintain state
                         None => {
                             self.state = State::EOF;
@@ -122,17 +131,18 @@ impl<'a> Lexer<'a> {
                         // end and gobble the '"'
                         Some('"') => {
                             self.state = State::RestOfLine;
-                            self.txt.next();
-                            return Ok(Some(Token::CharData(
-                                char_data.take().unwrap_or_else(|| "".into()),
-                            )));
+                            self.next_char();
+                            return Ok(Some(Token::Quoted(octets.take().unwrap_or_default())));
                         }
                         Some('\\') => {
-                            Self::push_to_str(&mut char_data, self.escape_seq()?)?;
+                            let octet = self.escape_seq()?;
+                            Self::push_to_octets(&mut octets, &octet)?;
                         }
                         Some(ch) => {
-                            self.txt.next();
-                            Self::push_to_str(&mut char_data, ch)?;
+                            self.next_char();
+                            let mut buf = [0; 4];
+                            let ch = ch.encode_utf8(&mut buf);
+                            Self::push_to_octets(&mut octets, ch.as_bytes())?;
                         }
                         None => return Err(LexerErrorKind::UnclosedQuotedString.into()),
                     }
@@ -141,7 +151,7 @@ impl<'a> Lexer<'a> {
                     match ch {
                         // even this is a little broad for what's actually possible in a dollar...
                         Some('A'...'Z') => {
-                            self.txt.next();
+                            self.next_char();
                             Self::push_to_str(&mut char_data, ch.unwrap())?;
                         }
                         // finishes the Dollar...
@@ -160,21 +170,21 @@ impl<'a> Lexer<'a> {
                                 return Ok(Some(Token::Origin));
                             } else if "TTL" == dollar {
                                 return Ok(Some(Token::Ttl));
+                            } else if "GENERATE" == dollar {
+                                return Ok(Some(Token::Generate));
                             } else {
-                                return Err(LexerErrorKind::UnrecognizedDollar(
-                                    char_data.take().unwrap_or_else(|| "".into()),
-                                ).into());
+                                return Err(LexerErrorKind::UnrecognizedDollar(dollar).into());
                             }
                         }
                     }
                 }
                 State::List => match ch {
                     Some(';') => {
-                        self.txt.next();
+                        self.next_char();
                         self.state = State::Comment { is_list: true }
                     }
                     Some(')') => {
-                        self.txt.next();
+                        self.next_char();
                         self.state = State::RestOfLine;
                         return char_data_vec
                             .take()
@@ -183,7 +193,7 @@ impl<'a> Lexer<'a> {
                             }).map(|v| Some(Token::List(v)));
                     }
                     Some(ch) if ch.is_whitespace() => {
-                        self.txt.next();
+                        self.next_char();
                     }
                     Some(ch) if !ch.is_control() && !ch.is_whitespace() => {
                         char_data = Some(String::new());
@@ -224,7 +234,7 @@ impl<'a> Lexer<'a> {
                         // TODO: this next one can be removed, but will keep unescaping for quoted strings
                         //Some('\\') => { try!(Self::push_to_str(&mut char_data, try!(self.escape_seq()))); },
                         Some(ch) if !ch.is_control() && !ch.is_whitespace() => {
-                            self.txt.next();
+                            self.next_char();
                             Self::push_to_str(&mut char_data, ch)?;
                         }
                         Some(ch) => return Err(LexerErrorKind::UnrecognizedChar(ch).into()),
@@ -239,16 +249,16 @@ impl<'a> Lexer<'a> {
                     }
                 }
                 State::At => {
-                    self.txt.next();
+                    self.next_char();
                     self.state = State::RestOfLine;
                     return Ok(Some(Token::At));
                 }
                 State::EOL => match ch {
                     Some('\r') => {
-                        self.txt.next();
+                        self.next_char();
                     }
                     Some('\n') => {
-                        self.txt.next();
+                        self.next_char();
                         self.state = State::StartLine;
                         return Ok(Some(Token::EOL));
                     }
@@ -257,7 +267,7 @@ impl<'a> Lexer<'a> {
                 },
                 // to exhaust all cases, this should never be run...
                 State::EOF => {
-                    self.txt.next(); // making sure we consume the last... it will always return None after.
+                    self.next_char(); // making sure we consume the last... it will always return None after.
                     return Ok(None);
                 }
             }
@@ -276,50 +286,46 @@ impl<'a> Lexer<'a> {
             })
     }
 
-    fn escape_seq(&mut self) -> LexerResult<char> {
+    fn push_to_octets(collect: &mut Option<Vec<u8>>, octets: &[u8]) -> LexerResult<()> {
+        collect
+            .as_mut()
+            .ok_or_else(|| LexerErrorKind::IllegalState("collect is None").into())
+            .map(|v| v.extend_from_slice(octets))
+    }
+
+    /// Decodes the escape at the `\`, an escaped decimal octet `\DDD` is the raw octet, not the
+    ///  UTF-8 encoding of the char of that value
+    fn escape_seq(&mut self) -> LexerResult<Vec<u8>> {
         // escaped character, let's decode it.
-        self.txt.next(); // consume the escape
+        self.next_char(); // consume the escape
         let ch = self
             .peek()
             .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))?;
 
         if !ch.is_control() {
             if ch.is_numeric() {
-                // in this case it's an excaped octal: \DDD
-                let d1: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-                let d2: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-                let d3: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-
-                let val: u32 = (d1 << 16) + (d2 << 8) + d3;
-                let ch: char = char::from_u32(val)
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::UnrecognizedOctet(val)))?;
-
-                Ok(ch)
+                // in this case it's an escaped decimal octet: \DDD
+                let mut val: u32 = 0;
+                for _ in 0..3 {
+                    let c = self
+                        .next_char()
+                        .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))?; // gobble
+                    let d = c
+                        .to_digit(10)
+                        .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))?;
+                    val = val * 10 + d;
+                }
+
+                if val > 255 {
+                    return Err(LexerErrorKind::UnrecognizedOctet(val).into());
+                }
+
+                Ok(vec![val as u8])
             } else {
                 // this is an excaped char: \X
-                self.txt.next(); // gobble the char
-                Ok(ch)
+                self.next_char(); // gobble the char
+                let mut buf = [0; 4];
+                Ok(ch.encode_utf8(&mut buf).as_bytes().to_vec())
             }
         } else {
             Err(LexerErrorKind::IllegalCharacter(ch).into())
@@ -329,6 +335,15 @@ impl<'a> Lexer<'a> {
     fn peek(&mut self) -> Option<char> {
         self.txt.peek().cloned()
     }
+
+    /// Consumes the next char, counting the lines
+    fn next_char(&mut self) -> Option<char> {
+        let ch = self.txt.next();
+        if ch == Some('\n') {
+            self.line += 1;
+        }
+        ch
+    }
 }
 
 #[doc(hidden)]
@@ -355,8 +370,10 @@ pub enum Token {
     Blank,
     /// (..) TODO, this is probably wrong, List maybe should just skip line endings
     List(Vec<String>),
-    /// [a-zA-Z, non-control utf8, ., -, 0-9]+, ".*"
+    /// [a-zA-Z, non-control utf8, ., -, 0-9]+
     CharData(String),
+    /// ".*", the octets of a quoted <character-string>, with the escapes decoded
+    Quoted(Vec<u8>),
     /// @
     At,
     /// $INCLUDE
@@ -365,6 +382,8 @@ pub enum Token {
     Origin,
     /// $TTL
     Ttl,
+    /// $GENERATE
+    Generate,
     /// \n or \r\n
     EOL,
 }
@@ -461,28 +480,41 @@ mod lex_test {
     fn quoted_txt() {
         assert_eq!(
             Lexer::new("\"Quoted\"").next_token().unwrap().unwrap(),
-            Token::CharData("Quoted".to_string())
+            Token::Quoted(b"Quoted".to_vec())
         );
         assert_eq!(
             Lexer::new("\";@$\"").next_token().unwrap().unwrap(),
-            Token::CharData(";@$".to_string())
+            Token::Quoted(b";@$".to_vec())
         );
         assert_eq!(
             Lexer::new("\"some \\A\"").next_token().unwrap().unwrap(),
-            Token::CharData("some A".to_string())
+            Token::Quoted(b"some A".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\Aa\"").next_token().unwrap().unwrap(),
-            Token::CharData("aAa".to_string())
+            Token::Quoted(b"aAa".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\$\"").next_token().unwrap().unwrap(),
-            Token::CharData("a$".to_string())
+            Token::Quoted(b"a$".to_vec())
         );
         assert_eq!(
             Lexer::new("\"a\\077\"").next_token().unwrap().unwrap(),
-            Token::CharData("a\u{707}".to_string())
+            Token::Quoted(b"aM".to_vec())
         );
+        assert_eq!(
+            Lexer::new("\"a\\009\\034\"").next_token().unwrap().unwrap(),
+            Token::Quoted(b"a\t\"".to_vec())
+        );
+        assert_eq!(
+            Lexer::new("\"\\128\\255\\000\"").next_token().unwrap().unwrap(),
+            Token::Quoted(vec![128, 255, 0])
+        );
+        assert_eq!(
+            Lexer::new("\"♥\\♥\"").next_token().unwrap().unwrap(),
+            Token::Quoted("♥♥".as_bytes().to_vec())
+        );
+        assert!(Lexer::new("\"a\\256\"").next_token().is_err());
 
         assert!(Lexer::new("\"a\\\"").next_token().is_err());
         assert!(Lexer::new("\"a\\0\"").next_token().is_err());
@@ -492,7 +524,7 @@ mod lex_test {
 
         assert_eq!(
             next_token(&mut lexer).unwrap(),
-            Token::CharData("multi\nline\ntext".to_string())
+            Token::Quoted(b"multi\nline\ntext".to_vec())
         );
         assert_eq!(next_token(&mut lexer), None);
 
@@ -500,7 +532,7 @@ mod lex_test {
 
         assert_eq!(
             next_token(&mut lexer).unwrap(),
-            Token::CharData("multi\r\nline\r\ntext".to_string())
+            Token::Quoted(b"multi\r\nline\r\ntext".to_vec())
         );
         assert_eq!(next_token(&mut lexer).unwrap(), Token::EOL);
         assert_eq!(next_token(&mut lexer), None);
@@ -551,6 +583,10 @@ mod lex_test {
             Token::Origin
         );
         assert_eq!(next_token(&mut Lexer::new("$TTL")).unwrap(), Token::Ttl);
+        assert_eq!(
+            next_token(&mut Lexer::new("$GENERATE")).unwrap(),
+            Token::Generate
+        );
         assert_eq!(next_token(&mut Lexer::new("\n")), Some(Token::EOL));
         assert_eq!(next_token(&mut Lexer::new("\r\n")), Some(Token::EOL));
     }
@@ -758,4 +794,64 @@ $INCLUDE <SUBSYS>ISI-MAILBOXES.TXT",
         );
         assert!(next_token(&mut lexer).is_none());
     }
+
+    #[test]
+    fn generate() {
+        let mut lexer = Lexer::new("$GENERATE 1-10/2 host-${0,3,d} A 10.0.0.$\n");
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::Generate);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("1-10/2".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("host-${0,3,d}".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("A".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("10.0.0.$".to_string())
+        );
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::EOL);
+
+        // a template may start with $
+        let mut lexer = Lexer::new("$GENERATE 1-2 $ PTR ${-1}.example.com.");
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::Generate);
+        next_token(&mut lexer);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("$".to_string())
+        );
+        next_token(&mut lexer);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("${-1}.example.com.".to_string())
+        );
+    }
+
+    #[test]
+    fn line() {
+        let mut lexer = Lexer::new("a A 10.0.0.1\n(b\n c)\n\"multi\nline\" ; comment\nd");
+        assert_eq!(lexer.line(), 1);
+        next_token(&mut lexer); // a
+        next_token(&mut lexer); // A
+        next_token(&mut lexer); // 10.0.0.1
+        assert_eq!(lexer.line(), 1);
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::EOL);
+        assert_eq!(lexer.line(), 2);
+        next_token(&mut lexer); // (b c)
+        assert_eq!(lexer.line(), 3);
+        next_token(&mut lexer); // EOL
+        next_token(&mut lexer); // "multi\nline"
+        assert_eq!(lexer.line(), 5);
+        next_token(&mut lexer); // EOL
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("d".to_string())
+        );
+        assert_eq!(lexer.line(), 6);
+    }
 }
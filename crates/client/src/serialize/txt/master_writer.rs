@@ -0,0 +1,78 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! writer of master files
+
+use std::io::{self, Write};
+
+use rr::{Name, Record, RecordSet, RecordType};
+
+/// Writes records as the entries of a master file, which can be read back with the `Parser`
+///
+/// Each record is a single line in its presentation format, see the `Display` of `Record`, with
+///  the names fully qualified.
+///
+/// ```text
+/// $ORIGIN example.com.
+/// example.com. 3600 IN SOA sns.dns.icann.org. noc.dns.icann.org. 2015082403 7200 3600 1209600 3600
+/// example.com. 86400 IN NS a.iana-servers.net.
+/// www.example.com. 86400 IN A 93.184.216.34
+/// ```
+pub struct Writer<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Writer<W> {
+    /// Returns a new writer of the entries to `out`
+    pub fn new(out: W) -> Self {
+        Writer { out }
+    }
+
+    /// Writes the zone, the SOA first, followed by all other records in the order given
+    ///
+    /// The RRSIGs of each record set follow its records.
+    pub fn write_zone<'r, I: IntoIterator<Item = &'r RecordSet>>(
+        &mut self,
+        origin: &Name,
+        record_sets: I,
+    ) -> io::Result<()> {
+        let (soa, record_sets): (Vec<_>, Vec<_>) = record_sets
+            .into_iter()
+            .partition(|rrset| rrset.record_type() == RecordType::SOA);
+
+        self.write_origin(origin)?;
+        for rrset in soa.into_iter().chain(record_sets) {
+            self.write_record_set(rrset)?;
+        }
+
+        self.out.flush()
+    }
+
+    /// Writes the `$ORIGIN` entry
+    pub fn write_origin(&mut self, origin: &Name) -> io::Result<()> {
+        writeln!(self.out, "$ORIGIN {}", origin)
+    }
+
+    /// Writes the records of the set and their RRSIGs
+    pub fn write_record_set(&mut self, rrset: &RecordSet) -> io::Result<()> {
+        for record in rrset.iter().chain(rrset.rrsigs()) {
+            self.write_record(record)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the record as a single line
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        writeln!(self.out, "{}", record)
+    }
+
+    /// Returns the underlying output
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
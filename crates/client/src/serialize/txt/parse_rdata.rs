@@ -16,13 +16,15 @@
 
 //! record data enum variants
 
+use std::str;
+
 use error::*;
-use rr::rdata::DNSSECRecordType;
+use rr::rdata::{DNSSECRData, DNSSECRecordType};
 use rr::{Name, RData, RecordType};
 use serialize::txt::rdata_parsers::*;
 
 pub trait RDataParser: Sized {
-    fn parse<'i, I: Iterator<Item = &'i str>>(
+    fn parse<'i, I: Iterator<Item = &'i [u8]>>(
         record_type: RecordType,
         tokens: I,
         origin: Option<&Name>,
@@ -30,63 +32,105 @@ pub trait RDataParser: Sized {
 }
 
 impl RDataParser for RData {
-    /// Parse the RData from a set of Tokens
-    fn parse<'i, I: Iterator<Item = &'i str>>(
+    /// Parse the RData from a set of Tokens, the raw octets of each token
+    ///
+    /// The <character-string>s of TXT and CAA are taken as octets, all other tokens must be UTF-8.
+    fn parse<'i, I: Iterator<Item = &'i [u8]>>(
         record_type: RecordType,
         tokens: I,
         origin: Option<&Name>,
     ) -> ParseResult<Self> {
-        let rdata = match record_type {
-            RecordType::A => RData::A(a::parse(tokens)?),
-            RecordType::AAAA => RData::AAAA(aaaa::parse(tokens)?),
-            RecordType::ANY => panic!("parsing ANY doesn't make sense"), // valid panic, never should happen
-            RecordType::AXFR => panic!("parsing AXFR doesn't make sense"), // valid panic, never should happen
-            RecordType::CAA => caa::parse(tokens).map(RData::CAA)?,
-            RecordType::CNAME => RData::CNAME(name::parse(tokens, origin)?),
-            RecordType::IXFR => panic!("parsing IXFR doesn't make sense"), // valid panic, never should happen
-            RecordType::MX => RData::MX(mx::parse(tokens, origin)?),
-            RecordType::NULL => RData::NULL(null::parse(tokens)?),
-            RecordType::NS => RData::NS(name::parse(tokens, origin)?),
-            RecordType::OPENPGPKEY => RData::OPENPGPKEY(openpgpkey::parse(tokens)?),
-            RecordType::OPT => panic!("parsing OPT doesn't make sense"), // valid panic, never should happen
-            RecordType::PTR => RData::PTR(name::parse(tokens, origin)?),
-            RecordType::SOA => RData::SOA(soa::parse(tokens, origin)?),
-            RecordType::SRV => RData::SRV(srv::parse(tokens, origin)?),
-            RecordType::SSHFP => RData::SSHFP(sshfp::parse(tokens)?),
-            RecordType::TLSA => RData::TLSA(tlsa::parse(tokens)?),
-            RecordType::TXT => RData::TXT(txt::parse(tokens)?),
-            RecordType::DNSSEC(DNSSECRecordType::SIG) => panic!("parsing SIG doesn't make sense"), // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => {
-                panic!("DNSKEY should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::KEY) => {
-                panic!("KEY should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::DS) => {
-                panic!("DS should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::NSEC) => {
-                panic!("NSEC should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::NSEC3) => {
-                panic!("NSEC3 should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => {
-                panic!("NSEC3PARAM should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::RRSIG) => {
-                panic!("RRSIG should be dynamically generated")
-            } // valid panic, never should happen
-            RecordType::DNSSEC(DNSSECRecordType::Unknown(code)) => {
-                panic!("Unknown dnssec record type, if you want to support this type, please file an issue against TRust-DNS: {}", code)
-            } // valid panic, never should happen
-            RecordType::Unknown(code) => {
-                // TODO: add a way to associate generic record types to the zone
-                panic!("Unknown record type, if you want to support this type, please file an issue against TRust-DNS: {}", code)
-            }
-            RecordType::ZERO => RData::ZERO,
-        };
+        // RFC 3597, the data of any type may be in the generic encoding
+        let mut tokens = tokens.peekable();
+        if tokens.peek() == Some(&&b"\\#"[..]) {
+            tokens.next();
+            return generic::parse(record_type, to_str(tokens)?.into_iter());
+        }
 
-        Ok(rdata)
+        match record_type {
+            RecordType::CAA => caa::parse(tokens).map(RData::CAA),
+            RecordType::TXT => txt::parse(tokens).map(RData::TXT),
+            _ => parse_str(record_type, to_str(tokens)?.into_iter(), origin),
+        }
     }
 }
+
+/// Converts the tokens to UTF-8 strings
+fn to_str<'i, I: Iterator<Item = &'i [u8]>>(tokens: I) -> ParseResult<Vec<&'i str>> {
+    tokens
+        .map(|token| {
+            str::from_utf8(token).map_err(|_| {
+                ParseErrorKind::Msg(format!(
+                    "record data is not UTF-8: {}",
+                    String::from_utf8_lossy(token)
+                )).into()
+            })
+        }).collect()
+}
+
+/// Parse the RData from a set of UTF-8 Tokens
+fn parse_str<'i, I: Iterator<Item = &'i str>>(
+    record_type: RecordType,
+    tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<RData> {
+    let rdata = match record_type {
+        // the queries and pseudo records of messages, which are not in zone files
+        RecordType::ANY
+        | RecordType::AXFR
+        | RecordType::IXFR
+        | RecordType::OPT
+        | RecordType::DNSSEC(DNSSECRecordType::TSIG) => {
+            return Err(ParseErrorKind::Msg(format!(
+                "record data of {} can not be parsed, it is not a record of zones",
+                record_type
+            )).into())
+        }
+        RecordType::A => RData::A(a::parse(tokens)?),
+        RecordType::AAAA => RData::AAAA(aaaa::parse(tokens)?),
+        RecordType::CAA => caa::parse(tokens.map(str::as_bytes)).map(RData::CAA)?,
+        RecordType::CNAME => RData::CNAME(name::parse(tokens, origin)?),
+        RecordType::DNAME => RData::DNAME(name::parse(tokens, origin)?),
+        RecordType::MX => RData::MX(mx::parse(tokens, origin)?),
+        RecordType::NULL => RData::NULL(null::parse(tokens)?),
+        RecordType::NS => RData::NS(name::parse(tokens, origin)?),
+        RecordType::OPENPGPKEY => RData::OPENPGPKEY(openpgpkey::parse(tokens)?),
+        RecordType::PTR => RData::PTR(name::parse(tokens, origin)?),
+        RecordType::SOA => RData::SOA(soa::parse(tokens, origin)?),
+        RecordType::SRV => RData::SRV(srv::parse(tokens, origin)?),
+        RecordType::SSHFP => RData::SSHFP(sshfp::parse(tokens)?),
+        RecordType::TLSA => RData::TLSA(tlsa::parse(tokens)?),
+        RecordType::TXT => RData::TXT(txt::parse(tokens.map(str::as_bytes))?),
+        RecordType::DNSSEC(DNSSECRecordType::DNSKEY) => {
+            RData::DNSSEC(DNSSECRData::DNSKEY(dnskey::parse(tokens)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::DS) => {
+            RData::DNSSEC(DNSSECRData::DS(ds::parse(tokens)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::KEY) => {
+            RData::DNSSEC(DNSSECRData::KEY(key::parse(tokens)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::NSEC) => {
+            RData::DNSSEC(DNSSECRData::NSEC(nsec::parse(tokens, origin)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::NSEC3) => {
+            RData::DNSSEC(DNSSECRData::NSEC3(nsec3::parse(tokens)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => {
+            RData::DNSSEC(DNSSECRData::NSEC3PARAM(nsec3param::parse(tokens)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+        | RecordType::DNSSEC(DNSSECRecordType::SIG) => {
+            RData::DNSSEC(DNSSECRData::SIG(sig::parse(tokens, origin)?))
+        }
+        RecordType::DNSSEC(DNSSECRecordType::Unknown(_)) | RecordType::Unknown(_) => {
+            return Err(ParseErrorKind::Msg(format!(
+                "record data of {} must be in the generic \\# format",
+                record_type
+            )).into())
+        }
+        RecordType::ZERO => RData::ZERO,
+    };
+
+    Ok(rdata)
+}
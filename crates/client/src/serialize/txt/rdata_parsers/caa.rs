@@ -16,6 +16,8 @@
 
 //! mail exchange, email, record
 
+use std::str;
+
 use proto::rr::rdata::caa;
 use proto::rr::rdata::caa::{Property, Value};
 
@@ -43,14 +45,18 @@ use rr::rdata::CAA;
 ///    Value:  Is the <character-string> encoding of the value field as
 ///       specified in [RFC1035], Section 5.1.
 /// ```
-pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<CAA> {
-    let flags_str: &str = tokens.next().ok_or_else(|| {
-        ParseError::from(ParseErrorKind::Message("caa flags not present"))
-    })?;
-    let tag_str: &str = tokens.next().ok_or_else(|| {
-        ParseError::from(ParseErrorKind::Message("caa tag not present"))
-    })?;
-    let value_str: &str = tokens.next().ok_or_else(|| {
+///
+/// The value is the raw octets of the <character-string>, the flags and tag must be UTF-8.
+pub fn parse<'i, I: Iterator<Item = &'i [u8]>>(mut tokens: I) -> ParseResult<CAA> {
+    let flags_str: &str = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("caa flags not present")))
+        .and_then(to_str)?;
+    let tag_str: &str = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("caa tag not present")))
+        .and_then(to_str)?;
+    let value: &[u8] = tokens.next().ok_or_else(|| {
         ParseError::from(ParseErrorKind::Message("caa value not present"))
     })?;
 
@@ -79,14 +85,14 @@ pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<CAA>
         // TODO: this is a slight dup of the match logic in caa::read_value(..)
         match tag {
             Property::Issue | Property::IssueWild => {
-                let value = caa::read_issuer(value_str.as_bytes())?;
+                let value = caa::read_issuer(value)?;
                 Value::Issuer(value.0, value.1)
             }
             Property::Iodef => {
-                let url = caa::read_iodef(value_str.as_bytes())?;
+                let url = caa::read_iodef(value)?;
                 Value::Url(url)
             }
-            Property::Unknown(_) => Value::Unknown(value_str.as_bytes().to_vec()),
+            Property::Unknown(_) => Value::Unknown(value.to_vec()),
         }
     };
 
@@ -98,6 +104,11 @@ pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<CAA>
     })
 }
 
+fn to_str(token: &[u8]) -> ParseResult<&str> {
+    str::from_utf8(token)
+        .map_err(|_| ParseErrorKind::Message("caa flags and tag must be UTF-8").into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,8 +116,8 @@ mod tests {
     #[test]
     fn test_parsing() {
         //nocerts       CAA 0 issue \";\"
-        assert!(parse(vec!["0", "issue", ";"].into_iter()).is_ok());
+        assert!(parse(vec![&b"0"[..], b"issue", b";"].into_iter()).is_ok());
         // certs         CAA 0 issuewild \"example.net\"
-        assert!(parse(vec!["0", "issue", "example.net"].into_iter()).is_ok());
+        assert!(parse(vec![&b"0"[..], b"issue", b"example.net"].into_iter()).is_ok());
     }
 }
@@ -0,0 +1,73 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! public key record data for signing zone records
+
+use data_encoding::BASE64;
+
+use super::{joined, number};
+use error::*;
+use rr::dnssec::Algorithm;
+use rr::rdata::DNSKEY;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-2.2)
+///
+/// ```text
+/// 2.2.  The DNSKEY RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Flag field MUST be represented as an unsigned decimal integer.
+///    Given the currently defined flags, the possible values are: 0, 256,
+///    and 257.
+///
+///    The Protocol Field MUST be represented as an unsigned decimal integer
+///    with a value of 3.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic as specified in Appendix A.1.
+///
+///    The Public Key field MUST be represented as a Base64 encoding of the
+///    Public Key.  Whitespace is allowed within the Base64 text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DNSKEY> {
+    let flags: u16 = number(&mut tokens, "flags")?;
+    let protocol: u8 = number(&mut tokens, "protocol")?;
+    let algorithm = Algorithm::from_u8(number(&mut tokens, "algorithm")?)?;
+    let public_key = BASE64.decode(joined(tokens).as_bytes())?;
+
+    if protocol != 3 {
+        return Err(ParseErrorKind::Msg(format!("DNSKEY protocol must be 3: {}", protocol)).into());
+    }
+    if public_key.is_empty() {
+        return Err(ParseErrorKind::Message("DNSKEY public key field missing").into());
+    }
+
+    Ok(DNSKEY::new(
+        flags & 0b0000_0001_0000_0000 != 0,
+        flags & 0b0000_0000_0000_0001 != 0,
+        flags & 0b0000_0000_1000_0000 != 0,
+        algorithm,
+        public_key,
+    ))
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["257", "2", "8", "AQID"].into_iter()).is_err());
+    assert!(parse(vec!["257", "3", "8"].into_iter()).is_err());
+
+    let dnskey = parse(vec!["257", "3", "8", "AQ", "ID"].into_iter()).unwrap();
+    assert!(dnskey.zone_key());
+    assert!(dnskey.secure_entry_point());
+    assert!(!dnskey.revoke());
+    assert_eq!(dnskey.algorithm(), Algorithm::RSASHA256);
+    assert_eq!(dnskey.public_key(), &[1, 2, 3]);
+}
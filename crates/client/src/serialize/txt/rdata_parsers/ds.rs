@@ -0,0 +1,74 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! delegation signer record data, the digest of a child zone's key
+
+use super::{joined, number, HEX};
+use error::*;
+use rr::dnssec::{Algorithm, DigestType};
+use rr::rdata::DS;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-5.3)
+///
+/// ```text
+/// 5.3.  The DS RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic specified in Appendix A.1.
+///
+///    The Digest Type field MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Digest MUST be represented as a sequence of case-insensitive
+///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
+///    text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DS> {
+    let key_tag: u16 = number(&mut tokens, "key tag")?;
+    let algorithm = Algorithm::from_u8(number(&mut tokens, "algorithm")?)?;
+    let digest_type = DigestType::from_u8(number(&mut tokens, "digest type")?)?;
+    let digest = HEX.decode(joined(tokens).as_bytes())?;
+
+    if digest.is_empty() {
+        return Err(ParseErrorKind::Message("DS digest field missing").into());
+    }
+
+    Ok(DS::new(key_tag, algorithm, digest_type, digest))
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["60485", "5", "1"].into_iter()).is_err());
+
+    assert_eq!(
+        parse(
+            vec![
+                "60485",
+                "5",
+                "1",
+                "2BB183AF5F22588179A53B0A",
+                "98631FAD1A292118",
+            ].into_iter()
+        ).unwrap(),
+        DS::new(
+            60485,
+            Algorithm::RSASHA1,
+            DigestType::SHA1,
+            vec![
+                0x2B, 0xB1, 0x83, 0xAF, 0x5F, 0x22, 0x58, 0x81, 0x79, 0xA5, 0x3B, 0x0A, 0x98, 0x63,
+                0x1F, 0xAD, 0x1A, 0x29, 0x21, 0x18,
+            ],
+        )
+    );
+}
@@ -0,0 +1,90 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! record data in the generic encoding of RFC 3597, for any record type
+
+use proto::serialize::binary::{BinDecoder, Restrict};
+
+use super::{joined, number, HEX};
+use error::*;
+use rr::{RData, RecordType};
+
+/// Parse the RData from the tokens following the `\#`
+///
+/// [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5)
+///
+/// ```text
+/// 5.  Text Representation
+///
+///    The RDATA section of an RR of unknown type is represented as a
+///    sequence of white space separated words as follows:
+///
+///       The special token \# (a backslash immediately followed by a hash
+///       sign), which identifies the RDATA as having the generic encoding
+///       defined herein rather than a traditional type-specific encoding.
+///
+///       An unsigned decimal integer specifying the RDATA length in octets.
+///
+///       Zero or more words of hexadecimal data encoding the actual RDATA
+///       field, each containing an even number of hexadecimal digits.
+/// ```
+///
+/// The data of known types is decoded as if received in a message.
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    record_type: RecordType,
+    mut tokens: I,
+) -> ParseResult<RData> {
+    let length: u16 = number(&mut tokens, "rdata length")?;
+    let data = HEX.decode(joined(tokens).as_bytes())?;
+
+    if data.len() != length as usize {
+        return Err(ParseErrorKind::Msg(format!(
+            "rdata length {} does not match the {} octets of data",
+            length,
+            data.len()
+        )).into());
+    }
+
+    let mut decoder = BinDecoder::new(&data);
+    let rdata = RData::read(&mut decoder, record_type, Restrict::new(length))?;
+
+    if !decoder.is_empty() {
+        return Err(ParseErrorKind::Msg(format!(
+            "{} octets of data remain after the {} rdata",
+            decoder.len(),
+            record_type
+        )).into());
+    }
+
+    Ok(rdata)
+}
+
+#[test]
+fn test_parsing() {
+    use rr::rdata::NULL;
+    use std::net::Ipv4Addr;
+
+    assert_eq!(
+        parse(RecordType::A, vec!["4", "0A000001"].into_iter()).unwrap(),
+        RData::A(Ipv4Addr::new(10, 0, 0, 1))
+    );
+    assert_eq!(
+        parse(RecordType::Unknown(65280), vec!["3", "ab", "CDEF"].into_iter()).unwrap(),
+        RData::Unknown {
+            code: 65280,
+            rdata: NULL::with(vec![0xab, 0xcd, 0xef]),
+        }
+    );
+    assert_eq!(
+        parse(RecordType::NULL, vec!["0"].into_iter()).unwrap(),
+        RData::NULL(NULL::new())
+    );
+
+    assert!(parse(RecordType::A, ::std::iter::empty()).is_err());
+    assert!(parse(RecordType::A, vec!["4", "0A00"].into_iter()).is_err());
+    assert!(parse(RecordType::A, vec!["5", "0A00000100"].into_iter()).is_err());
+}
@@ -0,0 +1,56 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! public key record data for SIG(0) and other uses
+
+use data_encoding::BASE64;
+
+use super::{joined, number};
+use error::*;
+use rr::dnssec::Algorithm;
+use rr::rdata::key::{KeyTrust, KeyUsage, Protocol, UpdateScope};
+use rr::rdata::KEY;
+
+/// Parse the RData from a set of Tokens
+///
+/// The presentation format is that of DNSKEY, see
+///  [RFC 2535](https://tools.ietf.org/html/rfc2535#section-7.1)
+///
+/// ```text
+/// <flags> <protocol> <algorithm> <public key>
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<KEY> {
+    let flags: u16 = number(&mut tokens, "flags")?;
+    let protocol = Protocol::from(number::<u8, _>(&mut tokens, "protocol")?);
+    let algorithm = Algorithm::from_u8(number(&mut tokens, "algorithm")?)?;
+    let public_key = BASE64.decode(joined(tokens).as_bytes())?;
+
+    if flags & 0b0001_0000_0000_0000 != 0 {
+        return Err(ParseErrorKind::Message("KEY extended flags are not supported").into());
+    }
+
+    Ok(KEY::new(
+        KeyTrust::from(flags),
+        KeyUsage::from(flags),
+        UpdateScope::from(flags),
+        protocol,
+        algorithm,
+        public_key,
+    ))
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["4096", "3", "8", "AQID"].into_iter()).is_err());
+
+    let key = parse(vec!["256", "3", "8", "AQID"].into_iter()).unwrap();
+    assert_eq!(key.key_usage(), KeyUsage::Zone);
+    assert_eq!(key.protocol(), Protocol::DNSSec);
+    assert_eq!(key.algorithm(), Algorithm::RSASHA256);
+    assert_eq!(key.public_key(), &[1, 2, 3]);
+}
@@ -19,15 +19,56 @@
 // TODO: these should each be it's own struct, it would make parsing and decoding a little cleaner
 //  and also a little more ergonomic when accessing.
 // each of these module's has the parser for that rdata embedded, to keep the file sizes down...
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use error::*;
+
 pub mod a;
 pub mod aaaa;
 pub mod caa;
+pub mod dnskey;
+pub mod ds;
+pub mod generic;
+pub mod key;
 pub mod mx;
 pub mod name;
+pub mod nsec;
+pub mod nsec3;
+pub mod nsec3param;
 pub mod null;
 pub mod openpgpkey;
+pub mod sig;
 pub mod soa;
 pub mod srv;
 pub mod sshfp;
 pub mod tlsa;
 pub mod txt;
+
+/// Hex, as in the record data of SSHFP, TLSA and DS, and the generic encoding of RFC 3597
+const HEX: ::data_encoding::Encoding = new_encoding! {
+    symbols: "0123456789abcdef",
+    ignore: " \t\r\n",
+    translate_from: "ABCDEF",
+    translate_to: "abcdef",
+};
+
+/// Parses the next token as a number, `field` names it if it is missing
+fn number<'i, T, I>(tokens: &mut I, field: &str) -> ParseResult<T>
+where
+    T: FromStr<Err = ParseIntError>,
+    I: Iterator<Item = &'i str>,
+{
+    tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken(field.to_string())))
+        .and_then(|s| s.parse().map_err(ParseError::from))
+}
+
+/// The remaining tokens joined, e.g. for keys and digests which may be split by whitespace
+fn joined<'i, I: Iterator<Item = &'i str>>(tokens: I) -> String {
+    tokens.fold(String::new(), |mut joined, token| {
+        joined.push_str(token);
+        joined
+    })
+}
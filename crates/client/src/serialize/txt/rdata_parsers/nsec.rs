@@ -0,0 +1,75 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! next secure record data, the proof of the non-existence of names and types
+
+use std::str::FromStr;
+
+use error::*;
+use rr::rdata::NSEC;
+use rr::{Name, RecordType};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-4.2)
+///
+/// ```text
+/// 4.2.  The NSEC RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Next Domain Name field is represented as a domain name.
+///
+///    The Type Bit Maps field is represented as a sequence of RR type
+///    mnemonics.  When the mnemonic is not known, the TYPE representation
+///    as described in [RFC3597], Section 5, MUST be used.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<NSEC> {
+    let next_domain_name = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("next domain name".into())))
+        .and_then(|s| Name::parse(s, origin).map_err(ParseError::from))?;
+    let type_bit_maps = parse_type_bit_maps(tokens)?;
+
+    Ok(NSEC::new(next_domain_name, type_bit_maps))
+}
+
+/// Parses the record types of the type bit maps, of NSEC and NSEC3
+pub fn parse_type_bit_maps<'i, I: Iterator<Item = &'i str>>(
+    tokens: I,
+) -> ParseResult<Vec<RecordType>> {
+    tokens
+        .map(|s| RecordType::from_str(s).map_err(ParseError::from))
+        .collect()
+}
+
+#[test]
+fn test_parsing() {
+    let origin = Name::from_str("example.com.").unwrap();
+
+    assert!(parse(::std::iter::empty(), Some(&origin)).is_err());
+    assert!(parse(vec!["host", "A", "BOGUS"].into_iter(), Some(&origin)).is_err());
+
+    assert_eq!(
+        parse(
+            vec!["host", "A", "RRSIG", "NSEC", "TYPE1234"].into_iter(),
+            Some(&origin)
+        ).unwrap(),
+        NSEC::new(
+            Name::from_str("host.example.com.").unwrap(),
+            vec![
+                RecordType::A,
+                RecordType::from_str("RRSIG").unwrap(),
+                RecordType::from_str("NSEC").unwrap(),
+                RecordType::Unknown(1234),
+            ],
+        )
+    );
+}
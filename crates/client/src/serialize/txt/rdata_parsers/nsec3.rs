@@ -0,0 +1,115 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! hashed next secure record data, the proof of the non-existence of names and types
+
+use data_encoding::BASE32_DNSSEC;
+
+use super::nsec::parse_type_bit_maps;
+use super::{number, HEX};
+use error::*;
+use rr::dnssec::Nsec3HashAlgorithm;
+use rr::rdata::NSEC3;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3.3)
+///
+/// ```text
+/// 3.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  The Salt field is represented as "-" (without the
+///       quotes) when the Salt Length field has a value of 0.
+///
+///    o  The Hash Length field is not represented.
+///
+///    o  The Next Hashed Owner Name field is represented as an unpadded
+///       sequence of case-insensitive base32 digits, without whitespace.
+///
+///    o  The Type Bit Maps field is represented as a sequence of RR type
+///       mnemonics.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NSEC3> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_parameters(&mut tokens)?;
+    let next_hashed_owner_name = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("next hashed owner".into())))
+        .and_then(|s| BASE32_DNSSEC.decode(s.as_bytes()).map_err(ParseError::from))?;
+    let type_bit_maps = parse_type_bit_maps(tokens)?;
+
+    Ok(NSEC3::new(
+        hash_algorithm,
+        opt_out,
+        iterations,
+        salt,
+        next_hashed_owner_name,
+        type_bit_maps,
+    ))
+}
+
+/// Parses the hash algorithm, flags, iterations and salt, of NSEC3 and NSEC3PARAM
+pub fn parse_parameters<'i, I: Iterator<Item = &'i str>>(
+    tokens: &mut I,
+) -> ParseResult<(Nsec3HashAlgorithm, bool, u16, Vec<u8>)> {
+    let hash_algorithm = Nsec3HashAlgorithm::from_u8(number(tokens, "hash algorithm")?)?;
+    let flags: u8 = number(tokens, "flags")?;
+    let iterations: u16 = number(tokens, "iterations")?;
+    let salt = match tokens.next() {
+        Some("-") => Vec::new(),
+        Some(salt) => HEX.decode(salt.as_bytes())?,
+        None => return Err(ParseErrorKind::MissingToken("salt".into()).into()),
+    };
+
+    Ok((hash_algorithm, flags & 0b0000_0001 != 0, iterations, salt))
+}
+
+#[test]
+fn test_parsing() {
+    use rr::RecordType;
+
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["1", "1", "12", "aabbccdd"].into_iter()).is_err());
+
+    assert_eq!(
+        parse(
+            vec![
+                "1",
+                "1",
+                "12",
+                "aabbccdd",
+                "2T7B4G4VSA5SMI47K61MV5BV1A22BOJR",
+                "MX",
+                "DNSKEY",
+            ].into_iter()
+        ).unwrap(),
+        NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            true,
+            12,
+            vec![0xaa, 0xbb, 0xcc, 0xdd],
+            BASE32_DNSSEC
+                .decode(b"2t7b4g4vsa5smi47k61mv5bv1a22bojr")
+                .unwrap(),
+            vec![RecordType::MX, "DNSKEY".parse().unwrap()],
+        )
+    );
+}
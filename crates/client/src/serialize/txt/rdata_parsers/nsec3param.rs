@@ -0,0 +1,59 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! parameters of the hashed names of NSEC3 records
+
+use super::nsec3::parse_parameters;
+use error::*;
+use rr::rdata::NSEC3PARAM;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-4.3)
+///
+/// ```text
+/// 4.3.  Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    o  The Hash Algorithm field is represented as an unsigned decimal
+///       integer.  The value has a maximum of 255.
+///
+///    o  The Flags field is represented as an unsigned decimal integer.
+///       The value has a maximum value of 255.
+///
+///    o  The Iterations field is represented as an unsigned decimal
+///       integer.  The value is between 0 and 65535, inclusive.
+///
+///    o  The Salt Length field is not represented.
+///
+///    o  The Salt field is represented as a sequence of case-insensitive
+///       hexadecimal digits.  Whitespace is not allowed within the
+///       sequence.  This field is represented as "-" (without the quotes)
+///       when the Salt Length field is zero.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NSEC3PARAM> {
+    let (hash_algorithm, opt_out, iterations, salt) = parse_parameters(&mut tokens)?;
+
+    if tokens.next().is_some() {
+        return Err(ParseErrorKind::Message("too many fields for NSEC3PARAM").into());
+    }
+
+    Ok(NSEC3PARAM::new(hash_algorithm, opt_out, iterations, salt))
+}
+
+#[test]
+fn test_parsing() {
+    use rr::dnssec::Nsec3HashAlgorithm;
+
+    assert!(parse(vec!["1", "0", "12"].into_iter()).is_err());
+
+    assert_eq!(
+        parse(vec!["1", "0", "12", "-"].into_iter()).unwrap(),
+        NSEC3PARAM::new(Nsec3HashAlgorithm::SHA1, false, 12, vec![])
+    );
+}
@@ -20,7 +20,10 @@ use error::*;
 use rr::rdata::NULL;
 
 /// Parse the RData from a set of Tokens
+///
+/// NULL has no presentation format of its own, it may only be given in the generic encoding of
+///  RFC 3597, see `generic::parse`.
 #[allow(unused)]
 pub fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NULL> {
-    unimplemented!()
+    Err(ParseErrorKind::Message("NULL record data must be in the generic \\# format").into())
 }
@@ -0,0 +1,155 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! signature record data, of RRSIG and SIG(0)
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use data_encoding::BASE64;
+
+use super::{joined, number};
+use error::*;
+use rr::dnssec::Algorithm;
+use rr::rdata::SIG;
+use rr::{Name, RecordType};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-3.2)
+///
+/// ```text
+/// 3.2.  The RRSIG RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Type Covered field is represented as an RR type mnemonic.  When
+///    the mnemonic is not known, the TYPE representation as described in
+///    [RFC3597], Section 5, MUST be used.
+///
+///    The Algorithm field value MUST be represented either as an unsigned
+///    decimal integer or as an algorithm mnemonic, as specified in Appendix
+///    A.1.
+///
+///    The Labels field value MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Original TTL field value MUST be represented as an unsigned
+///    decimal integer.
+///
+///    The Signature Expiration Time and Inception Time field values MUST be
+///    represented either as an unsigned decimal integer indicating seconds
+///    since 1 January 1970 00:00:00 UTC, or in the form YYYYMMDDHHmmSS in
+///    UTC, where:
+///
+///       YYYY is the year (0001-9999, but see Section 3.1.5);
+///       MM is the month number (01-12);
+///       DD is the day of the month (01-31);
+///       HH is the hour, in 24 hour notation (00-23);
+///       mm is the minute (00-59); and
+///       SS is the second (00-59).
+///
+///    Note that it is always possible to distinguish between these two
+///    formats because the YYYYMMDDHHmmSS format will always be exactly 14
+///    digits, while the decimal representation of a 32-bit unsigned
+///    integer can never be longer than 10 digits.
+///
+///    The Key Tag field MUST be represented as an unsigned decimal integer.
+///
+///    The Signer's Name field value MUST be represented as a domain name.
+///
+///    The Signature field is represented as a Base64 encoding of the
+///    signature.  Whitespace is allowed within the Base64 text.
+/// ```
+pub fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<SIG> {
+    let type_covered = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("type covered".into())))
+        .and_then(|s| RecordType::from_str(s).map_err(ParseError::from))?;
+    let algorithm = Algorithm::from_u8(number(&mut tokens, "algorithm")?)?;
+    let num_labels: u8 = number(&mut tokens, "labels")?;
+    let original_ttl: u32 = number(&mut tokens, "original ttl")?;
+    let sig_expiration = parse_time(tokens.next(), "signature expiration")?;
+    let sig_inception = parse_time(tokens.next(), "signature inception")?;
+    let key_tag: u16 = number(&mut tokens, "key tag")?;
+    let signer_name = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("signer's name".into())))
+        .and_then(|s| Name::parse(s, origin).map_err(ParseError::from))?;
+    let sig = BASE64.decode(joined(tokens).as_bytes())?;
+
+    if sig.is_empty() {
+        return Err(ParseErrorKind::Message("signature field missing").into());
+    }
+
+    Ok(SIG::new(
+        type_covered,
+        algorithm,
+        num_labels,
+        original_ttl,
+        sig_expiration,
+        sig_inception,
+        key_tag,
+        signer_name,
+        sig,
+    ))
+}
+
+/// Parses the seconds since the epoch, or the `YYYYMMDDHHmmSS` form
+fn parse_time(token: Option<&str>, field: &str) -> ParseResult<u32> {
+    let token = token.ok_or_else(|| ParseErrorKind::MissingToken(field.to_string()))?;
+
+    if token.len() == 14 {
+        let time = NaiveDateTime::parse_from_str(token, "%Y%m%d%H%M%S")
+            .map_err(|e| ParseErrorKind::Msg(format!("invalid {}: {}: {}", field, token, e)))?;
+        // serial number arithmetic, RFC 4034 section 3.1.5
+        Ok(time.timestamp() as u32)
+    } else {
+        token.parse().map_err(ParseError::from)
+    }
+}
+
+#[test]
+fn test_parsing() {
+    let origin = Name::from_str("example.com.").unwrap();
+
+    assert!(parse(::std::iter::empty(), Some(&origin)).is_err());
+    assert!(
+        parse(
+            vec!["A", "8", "3", "3600", "20190230000000", "1", "1", "example.com.", "AQID"].into_iter(),
+            Some(&origin)
+        ).is_err()
+    );
+
+    let sig = parse(
+        vec![
+            "A",
+            "8",
+            "3",
+            "3600",
+            "20190102030405",
+            "1546300800",
+            "12345",
+            "example.com.",
+            "AQ",
+            "ID",
+        ].into_iter(),
+        Some(&origin),
+    ).unwrap();
+    assert_eq!(sig.type_covered(), RecordType::A);
+    assert_eq!(sig.algorithm(), Algorithm::RSASHA256);
+    assert_eq!(sig.num_labels(), 3);
+    assert_eq!(sig.original_ttl(), 3600);
+    assert_eq!(sig.sig_expiration(), 1_546_398_245);
+    assert_eq!(sig.sig_inception(), 1_546_300_800);
+    assert_eq!(sig.key_tag(), 12345);
+    assert_eq!(sig.signer_name(), &origin);
+    assert_eq!(sig.sig(), &[1, 2, 3]);
+}
@@ -7,16 +7,10 @@
 
 //! SSHFP records for SSH public key fingerprints
 
+use super::HEX;
 use error::*;
 use rr::rdata::SSHFP;
 
-const HEX: ::data_encoding::Encoding = new_encoding! {
-    symbols: "0123456789abcdef",
-    ignore: " \t\r\n",
-    translate_from: "ABCDEF",
-    translate_to: "abcdef",
-};
-
 /// Parse the RData from a set of Tokens
 ///
 /// [RFC 4255](https://tools.ietf.org/html/rfc4255#section-3.2)
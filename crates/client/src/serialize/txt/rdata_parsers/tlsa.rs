@@ -9,17 +9,11 @@
 
 //! tlsa records for storing TLS authentication records
 
+use super::HEX;
 use error::*;
 use rr::rdata::TLSA;
 use rr::rdata::tlsa::CertUsage;
 
-const HEX: ::data_encoding::Encoding = new_encoding!{
-    symbols: "0123456789abcdef",
-    ignore: " \t\r\n",
-    translate_from: "ABCDEF",
-    translate_to: "abcdef",
-};
-
 fn to_u8(data: &str) -> ParseResult<u8> {
     u8::from_str_radix(data, 10).map_err(ParseError::from)
 }
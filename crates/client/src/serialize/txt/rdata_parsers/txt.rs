@@ -19,8 +19,8 @@
 use error::*;
 use rr::rdata::TXT;
 
-/// Parse the RData from a set of Tokens
-pub fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<TXT> {
-    let txt_data: Vec<String> = tokens.map(|s| s.to_string()).collect();
-    Ok(TXT::new(txt_data))
+/// Parse the RData from a set of Tokens, the raw octets of the <character-string>s
+pub fn parse<'i, I: Iterator<Item = &'i [u8]>>(tokens: I) -> ParseResult<TXT> {
+    let txt_data: Vec<&[u8]> = tokens.collect();
+    Ok(TXT::from_bytes(txt_data))
 }
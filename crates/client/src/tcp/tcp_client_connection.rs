@@ -15,9 +15,11 @@ use tokio_tcp::TcpStream;
 use proto::tcp::{TcpClientConnect, TcpClientStream};
 use proto::xfer::{DnsMultiplexer, DnsMultiplexerConnect, DnsRequestSender};
 
-use client::ClientConnection;
+use client::{ClientConnection, MessageSigner};
 use error::*;
 use rr::dnssec::Signer;
+#[cfg(feature = "dnssec")]
+use rr::dnssec::TSigner;
 
 /// Tcp client connection
 ///
@@ -26,6 +28,7 @@ use rr::dnssec::Signer;
 pub struct TcpClientConnection {
     name_server: SocketAddr,
     timeout: Duration,
+    tsigner: Option<Arc<MessageSigner>>,
 }
 
 impl TcpClientConnection {
@@ -55,16 +58,39 @@ impl TcpClientConnection {
         Ok(TcpClientConnection {
             name_server,
             timeout,
+            tsigner: None,
+        })
+    }
+
+    /// Creates a new client connection which signs all messages with TSIG
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - address of the name server to use for queries
+    /// * `timeout` - the time to wait for a response
+    /// * `signer` - the TSIG signer, with a secret shared with the name server
+    #[cfg(feature = "dnssec")]
+    pub fn with_tsigner(
+        name_server: SocketAddr,
+        timeout: Duration,
+        signer: TSigner,
+    ) -> ClientResult<Self> {
+        Ok(TcpClientConnection {
+            name_server,
+            timeout,
+            tsigner: Some(Arc::new(MessageSigner::TSig(signer))),
         })
     }
 }
 
 impl ClientConnection for TcpClientConnection {
-    type Sender = DnsMultiplexer<TcpClientStream<TcpStream>, Signer>;
+    type Sender = DnsMultiplexer<TcpClientStream<TcpStream>, MessageSigner>;
     type Response = <Self::Sender as DnsRequestSender>::DnsResponseFuture;
-    type SenderFuture = DnsMultiplexerConnect<TcpClientConnect, TcpClientStream<TcpStream>, Signer>;
+    type SenderFuture =
+        DnsMultiplexerConnect<TcpClientConnect, TcpClientStream<TcpStream>, MessageSigner>;
 
     fn new_stream(&self, signer: Option<Arc<Signer>>) -> Self::SenderFuture {
+        let signer = MessageSigner::select(&self.tsigner, signer);
         let (tcp_client_stream, handle) =
             TcpClientStream::<TcpStream>::with_timeout(self.name_server, self.timeout);
         DnsMultiplexer::new(tcp_client_stream, handle, signer)
@@ -14,9 +14,11 @@ use std::time::Duration;
 use proto::udp::{UdpClientConnect, UdpClientStream};
 use proto::xfer::DnsRequestSender;
 
-use client::ClientConnection;
+use client::{ClientConnection, MessageSigner};
 use error::*;
 use rr::dnssec::Signer;
+#[cfg(feature = "dnssec")]
+use rr::dnssec::TSigner;
 
 /// UDP based DNS Client connection
 ///
@@ -25,6 +27,7 @@ use rr::dnssec::Signer;
 pub struct UdpClientConnection {
     name_server: SocketAddr,
     timeout: Duration,
+    tsigner: Option<Arc<MessageSigner>>,
 }
 
 impl UdpClientConnection {
@@ -39,19 +42,44 @@ impl UdpClientConnection {
 
     /// Allows a custom timeout
     pub fn with_timeout(name_server: SocketAddr, timeout: Duration) -> ClientResult<Self> {
-        Ok(UdpClientConnection {name_server, timeout})
+        Ok(UdpClientConnection {
+            name_server,
+            timeout,
+            tsigner: None,
+        })
+    }
+
+    /// Creates a new client connection which signs all messages with TSIG
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - address of the name server to use for queries
+    /// * `timeout` - the time to wait for a response
+    /// * `signer` - the TSIG signer, with a secret shared with the name server
+    #[cfg(feature = "dnssec")]
+    pub fn with_tsigner(
+        name_server: SocketAddr,
+        timeout: Duration,
+        signer: TSigner,
+    ) -> ClientResult<Self> {
+        Ok(UdpClientConnection {
+            name_server,
+            timeout,
+            tsigner: Some(Arc::new(MessageSigner::TSig(signer))),
+        })
     }
 }
 
 impl ClientConnection for UdpClientConnection {
-    type Sender = UdpClientStream<Signer>;
+    type Sender = UdpClientStream<MessageSigner>;
     type Response = <Self::Sender as DnsRequestSender>::DnsResponseFuture;
-    type SenderFuture = UdpClientConnect<Signer>;
+    type SenderFuture = UdpClientConnect<MessageSigner>;
 
     fn new_stream(
         &self,
         signer: Option<Arc<Signer>>,
     ) -> Self::SenderFuture {
+        let signer = MessageSigner::select(&self.tsigner, signer);
         UdpClientStream::with_timeout_and_signer(self.name_server, self.timeout, signer)
     }
 }
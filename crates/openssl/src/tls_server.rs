@@ -15,6 +15,7 @@ use trust_dns_proto::error::{ProtoError, ProtoResult};
 
 pub use openssl::pkcs12::{ParsedPkcs12, Pkcs12};
 pub use openssl::pkey::{PKey, Private};
+pub use openssl::ssl::{NameType, SslRef};
 pub use openssl::stack::Stack;
 pub use openssl::x509::X509;
 pub use tokio_openssl::SslAcceptorExt;
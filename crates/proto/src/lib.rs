@@ -12,7 +12,6 @@
 //! TRust-DNS Protocol library
 
 extern crate byteorder;
-#[cfg(feature = "dnssec")]
 extern crate data_encoding;
 #[cfg(test)]
 extern crate env_logger;
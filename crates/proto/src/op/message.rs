@@ -336,6 +336,17 @@ impl Message {
         self
     }
 
+    /// Add a TSIG record, i.e. sign this message
+    ///
+    /// Like SIG0, this is kept apart from the additionals, it must be the last record of the
+    ///  message. Generally this will be handled by the client and not need to be used directly
+    #[cfg(feature = "dnssec")]
+    pub fn add_tsig(&mut self, record: Record) -> &mut Self {
+        assert_eq!(RecordType::DNSSEC(DNSSECRecordType::TSIG), record.rr_type());
+        self.sig0.push(record);
+        self
+    }
+
     /// Gets the header of the Message
     pub fn header(&self) -> &Header {
         &self.header
@@ -517,7 +528,8 @@ impl Message {
     ///
     /// # Return value
     ///
-    /// The sig0, i.e. signed record, for verifying the sending and package integrity
+    /// The sig0, i.e. signed record, for verifying the sending and package integrity. A TSIG,
+    ///  which also comes at the end of the message, is included here as well.
     pub fn sig0(&self) -> &[Record] {
         &self.sig0
     }
@@ -577,7 +589,8 @@ impl Message {
             } else {
                 match record.rr_type() {
                     #[cfg(feature = "dnssec")]
-                    RecordType::DNSSEC(DNSSECRecordType::SIG) => {
+                    RecordType::DNSSEC(DNSSECRecordType::SIG)
+                    | RecordType::DNSSEC(DNSSECRecordType::TSIG) => {
                         saw_sig0 = true;
                         sig0s.push(record);
                     }
@@ -640,6 +653,8 @@ impl Message {
                 // SIG0's are special, and come at the very end of the message
                 #[cfg(feature = "dnssec")]
                 RecordType::DNSSEC(DNSSECRecordType::SIG) => self.add_sig0(fin),
+                #[cfg(feature = "dnssec")]
+                RecordType::DNSSEC(DNSSECRecordType::TSIG) => self.add_tsig(fin),
                 _ => self.add_additional(fin),
             };
         }
@@ -672,6 +687,31 @@ pub trait MessageFinalizer: Send + Sync + 'static {
     ///
     /// A vector to append to the additionals section of the message, sorted in the order as they should appear in the message.
     fn finalize_message(&self, message: &Message, current_time: u32) -> ProtoResult<Vec<Record>>;
+
+    /// Returns true if the message should be finalized before it is sent
+    ///
+    /// By default only updates are, e.g. with SIG0. A TSIG signer also signs queries, and zone
+    ///  transfers in particular.
+    fn should_finalize_message(&self, message: &Message) -> bool {
+        message.op_code() == OpCode::Update
+    }
+
+    /// Returns a verifier for the responses to the message, which was just finalized
+    ///
+    /// By default responses are not verified, e.g. with SIG0. A TSIG signer verifies that every
+    ///  response is signed with its key.
+    fn response_verifier(&self, _message: &Message) -> Option<Box<MessageVerifier>> {
+        None
+    }
+}
+
+/// Verifies the responses to a finalized message, see `MessageFinalizer::response_verifier`
+pub trait MessageVerifier: Send + 'static {
+    /// Verifies the next response to the message, as it was received
+    ///
+    /// All the messages of a response which spans more than one message, e.g. a zone transfer,
+    ///  are passed in the order in which they were received.
+    fn verify_response(&mut self, response: &[u8]) -> ProtoResult<()>;
 }
 
 /// A MessageFinalizer which does nothing
@@ -15,10 +15,11 @@
  * limitations under the License.
  */
 
+use data_encoding::BASE32_DNSSEC;
+
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use super::{Digest, DigestType};
 use error::*;
-#[cfg(any(feature = "openssl", feature = "ring"))]
 use rr::Name;
 #[cfg(any(feature = "openssl", feature = "ring"))]
 use serialize::binary::{BinEncodable, BinEncoder};
@@ -172,6 +173,25 @@ impl Nsec3HashAlgorithm {
         };
         DigestType::SHA1.digest_all(&[to_digest, salt])
     }
+
+    /// Returns the owner name of the NSEC3 record for a hashed name
+    ///
+    /// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3)
+    ///
+    /// ```text
+    /// 3.  The NSEC3 Resource Record
+    ///
+    ///    The owner name for the NSEC3 RR is the base32 encoding of the hashed
+    ///    owner name prepended as a single label to the name of the zone.
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - the hash of a name in the zone, see `hash()`
+    /// * `zone` - the name of the zone, i.e. the owner of the NSEC3PARAM record
+    pub fn hashed_owner_name(hash: &[u8], zone: &Name) -> ProtoResult<Name> {
+        Name::from_labels(vec![BASE32_DNSSEC.encode(hash)]).map(|label| label.append_domain(zone))
+    }
 }
 
 impl From<Nsec3HashAlgorithm> for u8 {
@@ -286,6 +306,22 @@ fn test_known_hashes() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "openssl", feature = "ring"))]
+fn test_hashed_owner_name() {
+    use std::str::FromStr;
+
+    let zone = Name::from_str("example.").unwrap();
+    let hash = Nsec3HashAlgorithm::SHA1
+        .hash(&[0xAA, 0xBB, 0xCC, 0xDD], &zone, 12)
+        .unwrap();
+
+    assert_eq!(
+        Nsec3HashAlgorithm::hashed_owner_name(hash.as_ref(), &zone).unwrap(),
+        Name::from_str("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example.").unwrap()
+    );
+}
+
 #[cfg(test)]
 #[cfg(any(feature = "openssl", feature = "ring"))]
 fn hash_with_base32(name: &str) -> String {
@@ -16,6 +16,9 @@
 
 //! public key record data for signing zone records
 
+use std::fmt;
+
+use data_encoding::BASE64;
 use error::*;
 use rr::dnssec::{Algorithm, Digest, DigestType};
 use rr::record_data::RData;
@@ -322,6 +325,34 @@ impl From<DNSKEY> for RData {
     }
 }
 
+/// ```text
+/// <FLAGS> <PROTOCOL> <ALGORITHM> <PUBLIC KEY>
+/// ```
+///
+/// The public key is base64 encoded.
+impl fmt::Display for DNSKEY {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut flags: u16 = 0;
+        if self.zone_key {
+            flags |= 0b0000_0001_0000_0000
+        }
+        if self.secure_entry_point {
+            flags |= 0b0000_0000_0000_0001
+        }
+        if self.revoke {
+            flags |= 0b0000_0000_1000_0000
+        }
+
+        write!(
+            f,
+            "{} 3 {} {}",
+            flags,
+            u8::from(self.algorithm),
+            BASE64.encode(&self.public_key)
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<DNSKEY> {
     let flags: u16 = decoder.read_u16()?.unverified(/*used as a bitfield, this is safe*/);
@@ -16,6 +16,9 @@
 
 //! pointer record from parent zone to child zone for dnskey proof
 
+use std::fmt;
+
+use data_encoding::HEXUPPER;
 use error::*;
 use rr::dnssec::{Algorithm, DigestType};
 use serialize::binary::*;
@@ -184,6 +187,24 @@ impl DS {
     }
 }
 
+/// ```text
+/// <KEY TAG> <ALGORITHM> <DIGEST TYPE> <DIGEST>
+/// ```
+///
+/// The digest is hex encoded.
+impl fmt::Display for DS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key_tag,
+            u8::from(self.algorithm),
+            u8::from(self.digest_type),
+            HEXUPPER.encode(&self.digest)
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<DS> {
     let start_idx = decoder.index();
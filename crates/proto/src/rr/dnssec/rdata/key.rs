@@ -16,6 +16,9 @@
 
 //! public key record data for signing zone records
 
+use std::fmt;
+
+use data_encoding::BASE64;
 use error::*;
 use rr::dnssec::Algorithm;
 use rr::record_data::RData;
@@ -771,6 +774,27 @@ impl From<KEY> for RData {
     }
 }
 
+/// ```text
+/// <FLAGS> <PROTOCOL> <ALGORITHM> <PUBLIC KEY>
+/// ```
+///
+/// The public key is base64 encoded.
+impl fmt::Display for KEY {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags =
+            u16::from(self.key_trust) | u16::from(self.key_usage) | u16::from(self.signatory);
+
+        write!(
+            f,
+            "{} {} {} {}",
+            flags,
+            u8::from(self.protocol),
+            u8::from(self.algorithm),
+            BASE64.encode(&self.public_key)
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 #[allow(deprecated)]
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<KEY> {
@@ -26,6 +26,9 @@ pub mod nsec;
 pub mod nsec3;
 pub mod nsec3param;
 pub mod sig;
+pub mod tsig;
+
+use std::fmt;
 
 use error::*;
 use rr::rdata::null;
@@ -39,6 +42,7 @@ pub use self::nsec::NSEC;
 pub use self::nsec3::NSEC3;
 pub use self::nsec3param::NSEC3PARAM;
 pub use self::sig::SIG;
+pub use self::tsig::TSIG;
 
 /// The type of the resource record, for DNSSEC-specific records.
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
@@ -66,6 +70,10 @@ pub enum DNSSECRecordType {
     /// for now, we enable/disable SIG(0) in exactly the same circumstances that
     /// we enable/disable DNSSEC. This may change in the future.
     SIG,
+    /// RFC 8945	Transaction Signature
+    ///
+    /// Like SIG(0), this authenticates messages rather than zone data.
+    TSIG,
     /// Unknown or not yet supported DNSSec record type
     Unknown(u16),
 }
@@ -81,6 +89,7 @@ impl From<u16> for DNSSECRecordType {
             51 => DNSSECRecordType::NSEC3PARAM,
             46 => DNSSECRecordType::RRSIG,
             24 => DNSSECRecordType::SIG,
+            250 => DNSSECRecordType::TSIG,
             _ => DNSSECRecordType::Unknown(value),
         }
     }
@@ -97,6 +106,7 @@ impl From<DNSSECRecordType> for &'static str {
             DNSSECRecordType::NSEC3PARAM => "NSEC3PARAM",
             DNSSECRecordType::RRSIG => "RRSIG",
             DNSSECRecordType::SIG => "SIG",
+            DNSSECRecordType::TSIG => "TSIG",
             DNSSECRecordType::Unknown(..) => "DnsSecUnknown",
         }
     }
@@ -113,6 +123,7 @@ impl From<DNSSECRecordType> for u16 {
             DNSSECRecordType::NSEC3PARAM => 51,
             DNSSECRecordType::RRSIG => 46,
             DNSSECRecordType::SIG => 24,
+            DNSSECRecordType::TSIG => 250,
             DNSSECRecordType::Unknown(value) => value,
         }
     }
@@ -446,6 +457,11 @@ pub enum DNSSECRData {
     /// ```
     SIG(SIG),
 
+    /// [RFC 8945, Secret Key Transaction Authentication for DNS (TSIG), November 2020](https://tools.ietf.org/html/rfc8945)
+    ///
+    /// The transaction signature of a message, see `TSIG` for the fields of the RDATA.
+    TSIG(TSIG),
+
     /// Unknown or unsupported DNSSec record data
     Unknown {
         /// RecordType code
@@ -494,6 +510,10 @@ impl DNSSECRData {
                 debug!("reading SIG");
                 sig::read(decoder, rdata_length).map(DNSSECRData::SIG)
             }
+            DNSSECRecordType::TSIG => {
+                debug!("reading TSIG");
+                tsig::read(decoder, rdata_length).map(DNSSECRData::TSIG)
+            }
             DNSSECRecordType::Unknown(code) => {
                 debug!("reading unknown dnssec: {}", code);
                 null::read(decoder, rdata_length).map(|rdata| DNSSECRData::Unknown { code, rdata })
@@ -510,6 +530,7 @@ impl DNSSECRData {
             DNSSECRData::NSEC3(ref nsec3) => nsec3::emit(encoder, nsec3),
             DNSSECRData::NSEC3PARAM(ref nsec3param) => nsec3param::emit(encoder, nsec3param),
             DNSSECRData::SIG(ref sig) => sig::emit(encoder, sig),
+            DNSSECRData::TSIG(ref tsig) => tsig::emit(encoder, tsig),
             DNSSECRData::Unknown { ref rdata, .. } => null::emit(encoder, rdata),
         }
     }
@@ -523,7 +544,24 @@ impl DNSSECRData {
             DNSSECRData::NSEC3(..) => DNSSECRecordType::NSEC3,
             DNSSECRData::NSEC3PARAM(..) => DNSSECRecordType::NSEC3PARAM,
             DNSSECRData::SIG(..) => DNSSECRecordType::SIG,
+            DNSSECRData::TSIG(..) => DNSSECRecordType::TSIG,
             DNSSECRData::Unknown { code, .. } => DNSSECRecordType::Unknown(code),
         }
     }
 }
+
+impl fmt::Display for DNSSECRData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DNSSECRData::DS(ref ds) => write!(f, "{}", ds),
+            DNSSECRData::KEY(ref key) => write!(f, "{}", key),
+            DNSSECRData::DNSKEY(ref dnskey) => write!(f, "{}", dnskey),
+            DNSSECRData::NSEC(ref nsec) => write!(f, "{}", nsec),
+            DNSSECRData::NSEC3(ref nsec3) => write!(f, "{}", nsec3),
+            DNSSECRData::NSEC3PARAM(ref nsec3param) => write!(f, "{}", nsec3param),
+            DNSSECRData::SIG(ref sig) => write!(f, "{}", sig),
+            DNSSECRData::TSIG(ref tsig) => write!(f, "{}", tsig),
+            DNSSECRData::Unknown { ref rdata, .. } => write!(f, "{}", rdata),
+        }
+    }
+}
@@ -16,6 +16,8 @@
 
 //! negative cache proof for non-existence
 
+use std::fmt;
+
 use super::nsec3;
 use error::*;
 use rr::{Name, RecordType};
@@ -110,6 +112,29 @@ impl NSEC {
     }
 }
 
+/// ```text
+/// <NEXT DOMAIN NAME> <TYPE>...
+/// ```
+impl fmt::Display for NSEC {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.next_domain_name)?;
+        write_type_bit_maps(f, &self.type_bit_maps)
+    }
+}
+
+/// Writes the record types of the bit maps, in order, each preceded by a space
+pub(crate) fn write_type_bit_maps(f: &mut fmt::Formatter, types: &[RecordType]) -> fmt::Result {
+    let mut types = types.to_vec();
+    types.sort();
+    types.dedup();
+
+    for record_type in types {
+        write!(f, " {}", record_type)?;
+    }
+
+    Ok(())
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<NSEC> {
     let start_idx = decoder.index();
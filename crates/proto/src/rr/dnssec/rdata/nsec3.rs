@@ -17,7 +17,10 @@
 //! hashed negative cache proof for non-existence
 
 use std::collections::BTreeMap;
+use std::fmt;
 
+use data_encoding::{BASE32_DNSSEC, HEXUPPER};
+use super::nsec::write_type_bit_maps;
 use error::*;
 use rr::dnssec::Nsec3HashAlgorithm;
 use rr::RecordType;
@@ -239,6 +242,37 @@ impl NSEC3 {
     }
 }
 
+/// [RFC 5155, NSEC3, March 2008](https://tools.ietf.org/html/rfc5155#section-3.3)
+///
+/// ```text
+/// <HASH ALGORITHM> <FLAGS> <ITERATIONS> <SALT> <NEXT HASHED OWNER NAME> <TYPE>...
+/// ```
+///
+/// The salt is hex encoded, or `-` if empty, and the next hashed owner name is base32hex encoded.
+impl fmt::Display for NSEC3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} ",
+            u8::from(self.hash_algorithm),
+            if self.opt_out { 1 } else { 0 },
+            self.iterations
+        )?;
+        write_salt(f, &self.salt)?;
+        write!(f, " {}", BASE32_DNSSEC.encode(&self.next_hashed_owner_name))?;
+        write_type_bit_maps(f, &self.type_bit_maps)
+    }
+}
+
+/// Writes the salt as hex, `-` if there is none
+pub(crate) fn write_salt(f: &mut fmt::Formatter, salt: &[u8]) -> fmt::Result {
+    if salt.is_empty() {
+        f.write_str("-")
+    } else {
+        f.write_str(&HEXUPPER.encode(salt))
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<NSEC3> {
     let start_idx = decoder.index();
@@ -16,6 +16,9 @@
 
 //! parameters used for the nsec3 hash method
 
+use std::fmt;
+
+use super::nsec3::write_salt;
 use error::*;
 use rr::dnssec::Nsec3HashAlgorithm;
 use serialize::binary::*;
@@ -158,6 +161,24 @@ impl NSEC3PARAM {
     }
 }
 
+/// ```text
+/// <HASH ALGORITHM> <FLAGS> <ITERATIONS> <SALT>
+/// ```
+///
+/// The salt is hex encoded, or `-` if empty.
+impl fmt::Display for NSEC3PARAM {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} ",
+            u8::from(self.hash_algorithm),
+            if self.opt_out { 1 } else { 0 },
+            self.iterations
+        )?;
+        write_salt(f, &self.salt)
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder) -> ProtoResult<NSEC3PARAM> {
     let hash_algorithm =
@@ -16,6 +16,9 @@
 
 //! signature record for signing queries, updates, and responses
 
+use std::fmt;
+
+use data_encoding::BASE64;
 use error::*;
 use rr::dnssec::Algorithm;
 use rr::{Name, RecordType};
@@ -448,6 +451,68 @@ impl SIG {
     }
 }
 
+/// [RFC 4034, DNSSEC Resource Records, March 2005](https://tools.ietf.org/html/rfc4034#section-3.2)
+///
+/// ```text
+/// <TYPE COVERED> <ALGORITHM> <LABELS> <ORIGINAL TTL> <EXPIRATION> <INCEPTION> <KEY TAG>
+///     <SIGNER'S NAME> <SIGNATURE>
+/// ```
+///
+/// The times are in the `YYYYMMDDHHmmSS` form, in UTC, and the signature is base64 encoded.
+impl fmt::Display for SIG {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} ",
+            self.type_covered,
+            u8::from(self.algorithm),
+            self.num_labels,
+            self.original_ttl
+        )?;
+        write_time(f, self.sig_expiration)?;
+        f.write_str(" ")?;
+        write_time(f, self.sig_inception)?;
+        write!(
+            f,
+            " {} {} {}",
+            self.key_tag,
+            self.signer_name,
+            BASE64.encode(&self.sig)
+        )
+    }
+}
+
+/// Writes the seconds since the epoch as `YYYYMMDDHHmmSS`
+fn write_time(f: &mut fmt::Formatter, time: u32) -> fmt::Result {
+    // the civil date of the days since the epoch, from the algorithms of
+    //  http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = time / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let (year, month) = if month < 10 {
+        (year_of_era + era * 400, month + 3)
+    } else {
+        (year_of_era + era * 400 + 1, month - 9)
+    };
+
+    let seconds = time % 86_400;
+    write!(
+        f,
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<SIG> {
     let start_idx = decoder.index();
@@ -577,3 +642,26 @@ fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_display() {
+    use std::str::FromStr;
+
+    let rdata = SIG::new(
+        RecordType::A,
+        Algorithm::RSASHA256,
+        3,
+        3600,
+        // 2038-01-19T03:14:07 and 2000-02-29T00:00:00
+        0x7FFF_FFFF,
+        951_782_400,
+        12345,
+        Name::from_str("example.com.").unwrap(),
+        vec![1, 2, 3],
+    );
+
+    assert_eq!(
+        rdata.to_string(),
+        "A 8 3 3600 20380119031407 20000229000000 12345 example.com. AQID"
+    );
+}
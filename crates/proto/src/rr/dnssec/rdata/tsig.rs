@@ -0,0 +1,751 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! transaction signature for authenticating queries, updates, zone transfers and responses
+
+use std::fmt;
+
+use data_encoding::BASE64;
+
+use error::*;
+use op::{Header, Query};
+use rr::dnssec::rdata::{DNSSECRData, DNSSECRecordType};
+#[cfg(any(feature = "openssl", feature = "ring"))]
+use rr::dnssec::DigestType;
+use rr::{DNSClass, Name, RData, Record, RecordType};
+use serialize::binary::*;
+
+/// [RFC 8945, Secret Key Transaction Authentication for DNS (TSIG), November 2020](https://tools.ietf.org/html/rfc8945)
+///
+/// The RDATA of a TSIG record is, in order: the algorithm name (never compressed), the 48 bit
+///  time at which the message was signed, the fudge in seconds permitted around that time, the
+///  16 bit length prefixed MAC, the original ID of the message, an extended RCODE for TSIG
+///  errors and the 16 bit length prefixed other data, which is empty unless the error is BADTIME.
+///
+/// The record itself is always of class ANY with a TTL of 0, owned by the name of the key. It is
+///  the last record of the additional section, and is never cached.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct TSIG {
+    algorithm: TsigAlgorithm,
+    time: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    oid: u16,
+    error: u16,
+    other: Vec<u8>,
+}
+
+impl TSIG {
+    /// Constructs a new TSIG
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the MAC algorithm, see `TsigAlgorithm`
+    /// * `time` - seconds since the epoch at which the message was signed, only 48 bits are used
+    /// * `fudge` - seconds of difference from `time` permitted when the message is verified
+    /// * `mac` - the MAC over the message and the TSIG variables
+    /// * `oid` - the original ID of the message, before any forwarding changed it
+    /// * `error` - the extended RCODE of a TSIG error, e.g. BADSIG, or 0
+    /// * `other` - the server's time in a BADTIME error, otherwise empty
+    pub fn new(
+        algorithm: TsigAlgorithm,
+        time: u64,
+        fudge: u16,
+        mac: Vec<u8>,
+        oid: u16,
+        error: u16,
+        other: Vec<u8>,
+    ) -> Self {
+        TSIG {
+            algorithm,
+            time,
+            fudge,
+            mac,
+            oid,
+            error,
+            other,
+        }
+    }
+
+    /// The algorithm used to calculate the MAC
+    pub fn algorithm(&self) -> &TsigAlgorithm {
+        &self.algorithm
+    }
+
+    /// Seconds since the epoch at which the message was signed
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Seconds of difference from `time` permitted when the message is verified
+    pub fn fudge(&self) -> u16 {
+        self.fudge
+    }
+
+    /// The MAC of the message
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    /// The ID of the message when it was signed
+    pub fn oid(&self) -> u16 {
+        self.oid
+    }
+
+    /// The extended RCODE of the TSIG error, 0 if there is none
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// Other data, the server's time in a BADTIME error
+    pub fn other(&self) -> &[u8] {
+        &self.other
+    }
+
+    /// Returns true if the time is within the fudge of the time at which the message was signed
+    pub fn is_in_time(&self, now: u64) -> bool {
+        let fudge = u64::from(self.fudge);
+        now + fudge >= self.time && now <= self.time + fudge
+    }
+
+    /// Returns this TSIG with the specified MAC, e.g. once the MAC over the message is calculated
+    pub fn set_mac(self, mac: Vec<u8>) -> Self {
+        TSIG { mac, ..self }
+    }
+
+    /// Emits the TSIG variables which are covered by the MAC, after the message itself
+    ///
+    /// These are the name of the key, the class and TTL of the record, and all the fields of the
+    ///  RDATA except for the MAC and the original ID. Names are emitted in canonical form.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoder` - the encoder of the data to be signed
+    /// * `key_name` - the name of the key, i.e. the owner of the TSIG record
+    pub fn emit_tsig_for_mac(&self, encoder: &mut BinEncoder, key_name: &Name) -> ProtoResult<()> {
+        key_name.to_lowercase().emit_as_canonical(encoder, true)?;
+        DNSClass::ANY.emit(encoder)?;
+        encoder.emit_u32(0)?; // TTL
+        self.algorithm
+            .to_name()
+            .to_lowercase()
+            .emit_as_canonical(encoder, true)?;
+        emit_time(encoder, self.time)?;
+        encoder.emit_u16(self.fudge)?;
+        encoder.emit_u16(self.error)?;
+        encoder.emit_u16(self.other.len() as u16)?;
+        encoder.emit_vec(&self.other)?;
+        Ok(())
+    }
+
+    /// Emits only the timers, which the MAC of all but the first message of a TCP stream covers
+    pub fn emit_timers_for_mac(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        emit_time(encoder, self.time)?;
+        encoder.emit_u16(self.fudge)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for TSIG {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {}",
+            self.algorithm,
+            self.time,
+            self.fudge,
+            self.mac.len(),
+            BASE64.encode(&self.mac),
+            self.oid,
+            self.error,
+            self.other.len()
+        )?;
+
+        if !self.other.is_empty() {
+            write!(f, " {}", BASE64.encode(&self.other))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The algorithm of the MAC of a TSIG, identified by a name in the record
+///
+/// Only the HMAC-SHA2 algorithms are supported, others are kept by name so that the records of
+///  other servers can still be read, and answered with BADKEY.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum TsigAlgorithm {
+    /// hmac-sha256.
+    HmacSha256,
+    /// hmac-sha384.
+    HmacSha384,
+    /// hmac-sha512.
+    HmacSha512,
+    /// An algorithm which is not supported, e.g. hmac-md5.sig-alg.reg.int.
+    Unknown(Name),
+}
+
+impl TsigAlgorithm {
+    /// Returns the name of the algorithm, as it appears in the record
+    pub fn to_name(&self) -> Name {
+        match *self {
+            TsigAlgorithm::HmacSha256 => Name::from_ascii("hmac-sha256."),
+            TsigAlgorithm::HmacSha384 => Name::from_ascii("hmac-sha384."),
+            TsigAlgorithm::HmacSha512 => Name::from_ascii("hmac-sha512."),
+            TsigAlgorithm::Unknown(ref name) => Ok(name.clone()),
+        }.expect("the algorithm names are valid")
+    }
+
+    /// Returns the algorithm with the name, names are not case sensitive
+    pub fn from_name(name: Name) -> Self {
+        let supported = [
+            TsigAlgorithm::HmacSha256,
+            TsigAlgorithm::HmacSha384,
+            TsigAlgorithm::HmacSha512,
+        ];
+
+        let algorithm = supported
+            .iter()
+            .find(|algorithm| algorithm.to_name() == name)
+            .cloned();
+        algorithm.unwrap_or(TsigAlgorithm::Unknown(name))
+    }
+
+    /// Returns true if MACs can be calculated with this algorithm
+    pub fn is_supported(&self) -> bool {
+        match *self {
+            TsigAlgorithm::Unknown(..) => false,
+            _ => cfg!(any(feature = "openssl", feature = "ring")),
+        }
+    }
+
+    /// Returns the length of the MACs of the algorithm, zero if it is not supported
+    pub fn output_len(&self) -> usize {
+        match *self {
+            TsigAlgorithm::HmacSha256 => 32,
+            TsigAlgorithm::HmacSha384 => 48,
+            TsigAlgorithm::HmacSha512 => 64,
+            TsigAlgorithm::Unknown(..) => 0,
+        }
+    }
+
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn digest_type(&self) -> ProtoResult<DigestType> {
+        match *self {
+            TsigAlgorithm::HmacSha256 => Ok(DigestType::SHA256),
+            TsigAlgorithm::HmacSha384 => Ok(DigestType::SHA384),
+            TsigAlgorithm::HmacSha512 => Ok(DigestType::SHA512),
+            TsigAlgorithm::Unknown(ref name) => {
+                Err(format!("unsupported tsig algorithm: {}", name).into())
+            }
+        }
+    }
+
+    /// Calculates the MAC of the message with the key
+    #[cfg(all(not(feature = "ring"), feature = "openssl"))]
+    pub fn mac_data(&self, key: &[u8], message: &[u8]) -> ProtoResult<Vec<u8>> {
+        use openssl::pkey::PKey;
+        use openssl::sign::Signer;
+
+        let digest_type = self.digest_type()?.to_openssl_digest()?;
+        let key = PKey::hmac(key)?;
+        let mut signer = Signer::new(digest_type, &key)?;
+        signer.update(message)?;
+        signer.sign_to_vec().map_err(|e| e.into())
+    }
+
+    /// Calculates the MAC of the message with the key
+    #[cfg(feature = "ring")]
+    pub fn mac_data(&self, key: &[u8], message: &[u8]) -> ProtoResult<Vec<u8>> {
+        use ring::hmac;
+
+        let digest_type = self.digest_type()?.to_ring_digest_alg()?;
+        let key = hmac::SigningKey::new(digest_type, key);
+        Ok(hmac::sign(&key, message).as_ref().to_vec())
+    }
+
+    /// Always fails, the openssl and ring features are both disabled
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    pub fn mac_data(&self, _key: &[u8], _message: &[u8]) -> ProtoResult<Vec<u8>> {
+        Err("The openssl and ring features are both disabled".into())
+    }
+
+    /// Verifies the MAC of the message with the key, in constant time
+    #[cfg(all(not(feature = "ring"), feature = "openssl"))]
+    pub fn verify_mac(&self, key: &[u8], message: &[u8], mac: &[u8]) -> ProtoResult<()> {
+        use openssl::memcmp;
+
+        let expected = self.mac_data(key, message)?;
+        if expected.len() == mac.len() && memcmp::eq(&expected, mac) {
+            Ok(())
+        } else {
+            Err("tsig mac does not match".into())
+        }
+    }
+
+    /// Verifies the MAC of the message with the key, in constant time
+    #[cfg(feature = "ring")]
+    pub fn verify_mac(&self, key: &[u8], message: &[u8], mac: &[u8]) -> ProtoResult<()> {
+        use ring::hmac;
+
+        let digest_type = self.digest_type()?.to_ring_digest_alg()?;
+        let key = hmac::VerificationKey::new(digest_type, key);
+        hmac::verify(&key, message, mac).map_err(|_| "tsig mac does not match".into())
+    }
+
+    /// Always fails, the openssl and ring features are both disabled
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    pub fn verify_mac(&self, _key: &[u8], _message: &[u8], _mac: &[u8]) -> ProtoResult<()> {
+        Err("The openssl and ring features are both disabled".into())
+    }
+}
+
+impl fmt::Display for TsigAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_name())
+    }
+}
+
+/// Returns the data covered by the MAC of a message, it is not yet signed
+///
+/// The message is emitted without its TSIG, its ID must already be the original ID of the TSIG.
+///
+/// # Arguments
+///
+/// * `previous_hash` - the MAC of the request, for a response, or of the previous message of a
+///                     TCP stream
+/// * `message` - the message to sign
+/// * `pre_tsig` - the TSIG which is to be signed, its MAC is not used
+/// * `key_name` - the name of the key, i.e. the owner of the TSIG record
+/// * `first_message` - false for the second and later messages of a TCP stream, e.g. an AXFR,
+///                     which only cover the timers of the TSIG variables
+pub fn message_tbs<M: BinEncodable>(
+    previous_hash: Option<&[u8]>,
+    message: &M,
+    pre_tsig: &TSIG,
+    key_name: &Name,
+    first_message: bool,
+) -> ProtoResult<Vec<u8>> {
+    let mut message_buf: Vec<u8> = Vec::with_capacity(512);
+
+    // the message needs its own encoder, name pointers are offsets from the start of the message
+    {
+        let mut encoder = BinEncoder::with_mode(&mut message_buf, EncodeMode::Signing);
+        message.emit(&mut encoder)?;
+    }
+
+    encoded_message_tbs(previous_hash, &message_buf, pre_tsig, key_name, first_message)
+}
+
+/// Returns the data covered by the MAC of an already encoded message, it is not yet signed
+///
+/// The message must not include the TSIG record, nor count it in the header.
+///
+/// # Arguments
+///
+/// * `previous_hash` - the MAC of the request, for a response, or of the previous message of a
+///                     TCP stream
+/// * `message` - the encoded message to sign
+/// * `pre_tsig` - the TSIG which is to be signed, its MAC is not used
+/// * `key_name` - the name of the key, i.e. the owner of the TSIG record
+/// * `first_message` - false for the second and later messages of a TCP stream
+pub fn encoded_message_tbs(
+    previous_hash: Option<&[u8]>,
+    message: &[u8],
+    pre_tsig: &TSIG,
+    key_name: &Name,
+    first_message: bool,
+) -> ProtoResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::with_capacity(message.len() + 256);
+    {
+        let mut encoder = BinEncoder::new(&mut buf);
+        emit_previous_hash(&mut encoder, previous_hash)?;
+        encoder.emit_vec(message)?;
+        emit_variables(&mut encoder, pre_tsig, key_name, first_message)?;
+    }
+
+    Ok(buf)
+}
+
+/// Returns the data covered by the MAC of a message as it was received, with its TSIG record
+///
+/// The TSIG record is removed, the ID is restored to the original ID of the TSIG and the
+///  additional count excludes the record. The bytes of the message are otherwise unchanged, as
+///  the MAC was calculated by the sender over the message it emitted.
+///
+/// # Arguments
+///
+/// * `previous_hash` - the MAC of the request, for a response, or of the previous message of a
+///                     TCP stream
+/// * `message` - the message as received
+/// * `first_message` - false for the second and later messages of a TCP stream, e.g. an AXFR
+///
+/// # Return
+///
+/// The data to verify the MAC of the TSIG against, and the TSIG record
+pub fn signed_bitmessage_to_buf(
+    previous_hash: Option<&[u8]>,
+    message: &[u8],
+    first_message: bool,
+) -> ProtoResult<(Vec<u8>, Record)> {
+    let mut decoder = BinDecoder::new(message);
+    let mut header = Header::read(&mut decoder)?;
+
+    for _ in 0..header.query_count() {
+        Query::read(&mut decoder)?;
+    }
+
+    // the TSIG must be the last record of the additional section
+    if header.additional_count() == 0 {
+        return Err("no tsig record in message".into());
+    }
+
+    let record_count = header.answer_count() as usize
+        + header.name_server_count() as usize
+        + header.additional_count() as usize;
+    for _ in 1..record_count {
+        Record::read(&mut decoder)?;
+    }
+
+    let tsig_start = decoder.index();
+    let record = Record::read(&mut decoder)?;
+    if !decoder.is_empty() {
+        return Err("data after the tsig record".into());
+    }
+
+    let oid = match *record.rdata() {
+        RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => tsig.oid(),
+        _ => return Err("the last record is not a tsig".into()),
+    };
+
+    let additional_count = header.additional_count() - 1;
+    header.set_id(oid).set_additional_count(additional_count);
+
+    let mut buf: Vec<u8> = Vec::with_capacity(message.len() + 256);
+    {
+        let mut encoder = BinEncoder::new(&mut buf);
+        emit_previous_hash(&mut encoder, previous_hash)?;
+        header.emit(&mut encoder)?;
+        encoder.emit_vec(&message[Header::len()..tsig_start])?;
+
+        if let RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) = *record.rdata() {
+            emit_variables(&mut encoder, tsig, record.name(), first_message)?;
+        }
+    }
+
+    Ok((buf, record))
+}
+
+/// Returns the TSIG record, owned by the name of the key
+pub fn make_tsig_record(key_name: Name, tsig: TSIG) -> Record {
+    let mut record = Record::from_rdata(
+        key_name,
+        0,
+        RecordType::DNSSEC(DNSSECRecordType::TSIG),
+        RData::DNSSEC(DNSSECRData::TSIG(tsig)),
+    );
+    record.set_dns_class(DNSClass::ANY);
+    record
+}
+
+fn emit_previous_hash(encoder: &mut BinEncoder, previous_hash: Option<&[u8]>) -> ProtoResult<()> {
+    if let Some(previous_hash) = previous_hash {
+        encoder.emit_u16(previous_hash.len() as u16)?;
+        encoder.emit_vec(previous_hash)?;
+    }
+
+    Ok(())
+}
+
+fn emit_variables(
+    encoder: &mut BinEncoder,
+    tsig: &TSIG,
+    key_name: &Name,
+    first_message: bool,
+) -> ProtoResult<()> {
+    if first_message {
+        tsig.emit_tsig_for_mac(encoder, key_name)
+    } else {
+        tsig.emit_timers_for_mac(encoder)
+    }
+}
+
+fn emit_time(encoder: &mut BinEncoder, time: u64) -> ProtoResult<()> {
+    encoder.emit_u16((time >> 32) as u16)?;
+    encoder.emit_u32(time as u32)
+}
+
+/// Returns the time as the 48 bits of the other data of a BADTIME error, i.e. the time of the
+///  server which rejected the message
+pub fn encode_time(time: u64) -> Vec<u8> {
+    let mut buf: Vec<u8> = Vec::with_capacity(6);
+    {
+        let mut encoder = BinEncoder::new(&mut buf);
+        // a Vec has no size limit
+        emit_time(&mut encoder, time).expect("could not encode time");
+    }
+
+    buf
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<TSIG> {
+    let start_idx = decoder.index();
+
+    let algorithm = TsigAlgorithm::from_name(Name::read(decoder)?);
+    let time_high = u64::from(decoder.read_u16()?.unverified(/*valid as any u16*/));
+    let time_low = u64::from(decoder.read_u32()?.unverified(/*valid as any u32*/));
+    let fudge = decoder.read_u16()?.unverified(/*valid as any u16*/);
+    let mac_len = decoder.read_u16()?.unverified(/*used only as length safely*/);
+    let mac = decoder
+        .read_vec(mac_len as usize)?
+        .unverified(/*will fail in verification if invalid*/);
+    let oid = decoder.read_u16()?.unverified(/*valid as any u16*/);
+    let error = decoder.read_u16()?.unverified(/*valid as any u16*/);
+    let other_len = decoder.read_u16()?.unverified(/*used only as length safely*/);
+    let other = decoder
+        .read_vec(other_len as usize)?
+        .unverified(/*only the server's time is defined*/);
+
+    rdata_length
+        .map(|u| u as usize)
+        .verify_unwrap(|u| *u == decoder.index() - start_idx)
+        .map_err(|_| ProtoError::from("invalid rdata length in TSIG"))?;
+
+    Ok(TSIG::new(
+        algorithm,
+        (time_high << 32) | time_low,
+        fudge,
+        mac,
+        oid,
+        error,
+        other,
+    ))
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder, tsig: &TSIG) -> ProtoResult<()> {
+    // the algorithm name is never compressed
+    tsig.algorithm.to_name().emit_as_canonical(encoder, true)?;
+    emit_time(encoder, tsig.time)?;
+    encoder.emit_u16(tsig.fudge)?;
+    encoder.emit_u16(tsig.mac.len() as u16)?;
+    encoder.emit_vec(&tsig.mac)?;
+    encoder.emit_u16(tsig.oid)?;
+    encoder.emit_u16(tsig.error)?;
+    encoder.emit_u16(tsig.other.len() as u16)?;
+    encoder.emit_vec(&tsig.other)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use op::{Message, MessageType, OpCode};
+
+    fn tsig() -> TSIG {
+        TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            0x0001_2345_6789,
+            300,
+            vec![0, 1, 2, 3, 4, 5, 6, 7],
+            42,
+            0,
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test() {
+        let rdata = tsig();
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+
+        let mut decoder: BinDecoder = BinDecoder::new(bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16));
+        assert!(
+            read_rdata.is_ok(),
+            format!("error decoding: {:?}", read_rdata.unwrap_err())
+        );
+        assert_eq!(rdata, read_rdata.unwrap());
+    }
+
+    #[test]
+    fn test_bad_length() {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+            emit(&mut encoder, &tsig()).unwrap();
+        }
+
+        let mut decoder: BinDecoder = BinDecoder::new(&bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16 + 1)).is_err());
+    }
+
+    #[test]
+    fn test_algorithm_names() {
+        for algorithm in &[
+            TsigAlgorithm::HmacSha256,
+            TsigAlgorithm::HmacSha384,
+            TsigAlgorithm::HmacSha512,
+        ] {
+            assert_eq!(&TsigAlgorithm::from_name(algorithm.to_name()), algorithm);
+        }
+
+        assert_eq!(
+            TsigAlgorithm::from_name(Name::from_str("HMAC-SHA256").unwrap()),
+            TsigAlgorithm::HmacSha256
+        );
+
+        let md5 = Name::from_str("hmac-md5.sig-alg.reg.int.").unwrap();
+        assert_eq!(
+            TsigAlgorithm::from_name(md5.clone()),
+            TsigAlgorithm::Unknown(md5)
+        );
+        assert!(!TsigAlgorithm::from_name(Name::from_str("hmac-md5.").unwrap()).is_supported());
+    }
+
+    #[test]
+    fn test_is_in_time() {
+        let tsig = tsig();
+        assert!(tsig.is_in_time(tsig.time()));
+        assert!(tsig.is_in_time(tsig.time() - 300));
+        assert!(tsig.is_in_time(tsig.time() + 300));
+        assert!(!tsig.is_in_time(tsig.time() - 301));
+        assert!(!tsig.is_in_time(tsig.time() + 301));
+    }
+
+    #[test]
+    fn test_encode_time() {
+        assert_eq!(
+            encode_time(0x0123_4567_89AB),
+            vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xAB]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            tsig().to_string(),
+            "hmac-sha256. 4886718345 300 8 AAECAwQFBgc= 42 0 0"
+        );
+    }
+
+    #[test]
+    fn test_tbs_of_received_message() {
+        let key_name = Name::from_str("key.example.com.").unwrap();
+
+        let mut message = Message::new();
+        message
+            .set_id(42)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .add_query(Query::query(
+                Name::from_str("www.example.com.").unwrap(),
+                RecordType::A,
+            ));
+
+        let pre_tsig = tsig();
+        let tbs = message_tbs(Some(&[9, 9]), &message, &pre_tsig, &key_name, true).unwrap();
+
+        // forwarding the message changes the ID, the original ID is in the TSIG
+        message.set_id(7);
+        message.add_tsig(make_tsig_record(key_name.clone(), pre_tsig.clone()));
+        let bytes = message.to_vec().unwrap();
+
+        let (received_tbs, record) = signed_bitmessage_to_buf(Some(&[9, 9]), &bytes, true).unwrap();
+        assert_eq!(received_tbs, tbs);
+        assert_eq!(record.name(), &key_name);
+        assert_eq!(record.dns_class(), DNSClass::ANY);
+        assert_eq!(
+            record.rdata(),
+            &RData::DNSSEC(DNSSECRData::TSIG(pre_tsig.clone()))
+        );
+
+        // the later messages of a stream only cover the timers
+        let (received_tbs, _) = signed_bitmessage_to_buf(None, &bytes, false).unwrap();
+        message.set_id(42);
+        let tbs = message_tbs(None, &message, &pre_tsig, &key_name, false).unwrap();
+        assert_eq!(received_tbs, tbs);
+    }
+
+    #[test]
+    fn test_tbs_without_tsig() {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+
+        let bytes = message.to_vec().unwrap();
+        assert!(signed_bitmessage_to_buf(None, &bytes, true).is_err());
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_mac() {
+        let key = b"a secret shared by the client and server";
+        let mac = TsigAlgorithm::HmacSha256
+            .mac_data(key, b"message")
+            .unwrap();
+        assert_eq!(mac.len(), 32);
+        assert_eq!(
+            TsigAlgorithm::HmacSha512.mac_data(key, b"message").unwrap().len(),
+            64
+        );
+
+        assert!(
+            TsigAlgorithm::HmacSha256
+                .verify_mac(key, b"message", &mac)
+                .is_ok()
+        );
+        assert!(
+            TsigAlgorithm::HmacSha256
+                .verify_mac(key, b"massage", &mac)
+                .is_err()
+        );
+        assert!(
+            TsigAlgorithm::HmacSha256
+                .verify_mac(b"another secret", b"message", &mac)
+                .is_err()
+        );
+        assert!(
+            TsigAlgorithm::HmacSha256
+                .verify_mac(key, b"message", &mac[..16])
+                .is_err()
+        );
+        assert!(
+            TsigAlgorithm::Unknown(Name::from_str("hmac-md5.").unwrap())
+                .mac_data(key, b"message")
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    fn test_rfc4231_vector() {
+        // test case 2 of RFC 4231
+        let mac = TsigAlgorithm::HmacSha256
+            .mac_data(b"Jefe", b"what do ya want for nothing?")
+            .unwrap();
+        assert_eq!(
+            mac,
+            vec![
+                0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+                0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+                0x64, 0xec, 0x38, 0x43,
+            ]
+        );
+    }
+}
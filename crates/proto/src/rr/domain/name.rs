@@ -349,6 +349,49 @@ impl Name {
         self_lower.zone_of_case(&name_lower)
     }
 
+    /// Substitutes the `owner` of a DNAME, an ancestor of this name, with its `target`
+    ///
+    /// This is the DNAME substitution of [RFC 6672](https://tools.ietf.org/html/rfc6672), the
+    ///  suffix of the name which matches the owner is replaced by the target.
+    ///
+    /// An error is returned if this name is not below the owner, or if the substituted name is
+    ///  longer than 255 bytes, which a server answers with YXDOMAIN.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::str::FromStr;
+    /// use trust_dns_proto::rr::domain::Name;
+    ///
+    /// let name = Name::from_str("www.old.example.com.").unwrap();
+    /// let owner = Name::from_str("old.example.com.").unwrap();
+    /// let target = Name::from_str("new.example.net.").unwrap();
+    /// assert_eq!(
+    ///     name.replace_domain(&owner, &target).unwrap(),
+    ///     Name::from_str("www.new.example.net.").unwrap()
+    /// );
+    /// assert!(owner.replace_domain(&owner, &target).is_err());
+    /// ```
+    pub fn replace_domain(&self, owner: &Self, target: &Self) -> ProtoResult<Self> {
+        if self.labels.len() <= owner.labels.len() || !owner.zone_of(self) {
+            return Err(format!("{} is not below {}", self, owner).into());
+        }
+
+        let prefix = &self.labels[..self.labels.len() - owner.labels.len()];
+        let name = Name {
+            is_fqdn: target.is_fqdn,
+            labels: prefix.iter().chain(target.labels.iter()).cloned().collect(),
+        };
+
+        // the length of the name on the wire, including the root label
+        let length = name.len() + 1;
+        if length > 255 {
+            return Err(ProtoErrorKind::DomainNameTooLong(length).into());
+        }
+
+        Ok(name)
+    }
+
     /// Returns the number of labels in the name, discounting `*`.
     ///
     /// # Examples
@@ -20,10 +20,12 @@
 //! record and rules for processing CAA records by certificate issuers.
 //! ```
 
+use std::fmt;
 use std::str;
 
 use error::*;
 use rr::domain::Name;
+use rr::rdata::txt::write_character_string;
 use serialize::binary::*;
 use url::Url;
 
@@ -665,6 +667,22 @@ impl KeyValue {
     }
 }
 
+/// ```text
+/// <FLAGS> <TAG> <VALUE>
+/// ```
+///
+/// The value is quoted, as it may contain spaces.
+impl fmt::Display for CAA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = if self.issuer_critical { 128 } else { 0 };
+        write!(f, "{} {} ", flags, self.tag.as_str())?;
+
+        let mut value = Vec::new();
+        emit_value(&mut BinEncoder::new(&mut value), &self.value).map_err(|_| fmt::Error)?;
+        write_character_string(f, &value)
+    }
+}
+
 /// Read the binary CAA format
 ///
 /// [RFC 6844, DNS Certification Authority Authorization, January 2013](https://tools.ietf.org/html/rfc6844#section-5.1)
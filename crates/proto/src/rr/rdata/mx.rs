@@ -16,6 +16,8 @@
 
 //! mail exchange, email, record
 
+use std::fmt;
+
 use error::*;
 use rr::domain::Name;
 use serialize::binary::*;
@@ -83,6 +85,15 @@ impl MX {
     }
 }
 
+/// ```text
+/// <PREFERENCE> <EXCHANGE>
+/// ```
+impl fmt::Display for MX {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.preference, self.exchange)
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder) -> ProtoResult<MX> {
     Ok(MX::new(
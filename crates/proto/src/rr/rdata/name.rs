@@ -72,6 +72,18 @@ pub fn emit(encoder: &mut BinEncoder, name_data: &Name) -> ProtoResult<()> {
     Ok(())
 }
 
+/// Write the RData of a name which may not be compressed, e.g. the target of a DNAME
+///
+/// Uppercase letters are still replaced by their lowercase counterparts in canonical form, see
+/// `emit`.
+pub fn emit_uncompressed(encoder: &mut BinEncoder, name_data: &Name) -> ProtoResult<()> {
+    if encoder.is_canonical_names() {
+        name_data.to_lowercase().emit_as_canonical(encoder, true)
+    } else {
+        name_data.emit_as_canonical(encoder, true)
+    }
+}
+
 #[test]
 pub fn test() {
     let rdata = Name::from_ascii("WWW.example.com.").unwrap();
@@ -91,3 +103,19 @@ pub fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_uncompressed() {
+    let rdata = Name::from_ascii("www.example.com.").unwrap();
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        assert!(emit_uncompressed(&mut encoder, &rdata).is_ok());
+    }
+
+    // the second name is written out in full, rather than as a pointer to the first
+    assert_eq!(bytes.len(), 2 * 17);
+    assert_eq!(bytes[..17], bytes[17..]);
+}
@@ -16,6 +16,9 @@
 
 //! null record type, generally not used except as an internal tool for representing null data
 
+use std::fmt;
+
+use data_encoding::HEXUPPER;
 use error::*;
 use serialize::binary::*;
 
@@ -60,6 +63,36 @@ impl NULL {
     }
 }
 
+/// [RFC 3597, Handling of Unknown DNS Resource Record (RR) Types, September 2003](https://tools.ietf.org/html/rfc3597#section-5)
+///
+/// ```text
+/// 5.  Text Representation
+///
+///    The RDATA section of an RR of unknown type is represented as a
+///    sequence of white space separated words as follows:
+///
+///       The special token \# (a backslash immediately followed by a hash
+///       sign), which identifies the RDATA as having the generic encoding
+///       defined herein rather than a traditional type-specific encoding.
+///
+///       An unsigned decimal integer specifying the RDATA length in octets.
+///
+///       Zero or more words of hexadecimal data encoding the actual RDATA
+///       field, each containing an even number of hexadecimal digits.
+/// ```
+///
+/// NULL records have no format of their own, and are represented in the generic encoding.
+impl fmt::Display for NULL {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.anything {
+            Some(ref anything) if !anything.is_empty() => {
+                write!(f, "\\# {} {}", anything.len(), HEXUPPER.encode(anything))
+            }
+            _ => f.write_str("\\# 0"),
+        }
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<NULL> {
     let rdata_length = rdata_length.map(|u| u as usize).unverified(/*any u16 is valid*/);
@@ -101,3 +134,9 @@ pub fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_display() {
+    assert_eq!(NULL::new().to_string(), "\\# 0");
+    assert_eq!(NULL::with(vec![0, 1, 0xAB]).to_string(), "\\# 3 0001AB");
+}
@@ -7,6 +7,9 @@
 
 //! OPENPGPKEY records for OpenPGP public keys
 
+use std::fmt;
+
+use data_encoding::BASE64;
 use error::*;
 use serialize::binary::*;
 
@@ -40,6 +43,13 @@ impl OPENPGPKEY {
     }
 }
 
+/// The public key is base64 encoded
+impl fmt::Display for OPENPGPKEY {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&BASE64.encode(&self.public_key))
+    }
+}
+
 /// Read the RData from the given decoder.
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<OPENPGPKEY> {
     let rdata_length = rdata_length.map(usize::from).unverified();
@@ -392,6 +392,9 @@ pub enum EdnsOption {
     #[cfg(feature = "dnssec")]
     N3U(SupportedAlgorithms),
 
+    /// [RFC 7873, Domain Name System (DNS) Cookies](https://tools.ietf.org/html/rfc7873)
+    Cookie(Cookie),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -404,6 +407,7 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.len(),
+            EdnsOption::Cookie(ref cookie) => cookie.len(),
             EdnsOption::Unknown(_, ref data) => data.len() as u16, // TODO: should we verify?
         }
     }
@@ -415,6 +419,7 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.is_empty(),
+            EdnsOption::Cookie(_) => false,
             EdnsOption::Unknown(_, ref data) => data.is_empty(),
         }
     }
@@ -427,6 +432,7 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.emit(encoder),
+            EdnsOption::Cookie(ref cookie) => cookie.emit(encoder),
             EdnsOption::Unknown(_, ref data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -442,6 +448,10 @@ impl<'a> From<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DHU => EdnsOption::DHU(value.1.into()),
             #[cfg(feature = "dnssec")]
             EdnsCode::N3U => EdnsOption::N3U(value.1.into()),
+            // a malformed cookie is kept as is, the server responds to it with FORMERR
+            EdnsCode::Cookie => Cookie::from_bytes(value.1)
+                .map(EdnsOption::Cookie)
+                .unwrap_or_else(|_| EdnsOption::Unknown(value.0.into(), value.1.to_vec())),
             _ => EdnsOption::Unknown(value.0.into(), value.1.to_vec()),
         }
     }
@@ -454,6 +464,7 @@ impl<'a> From<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.into(),
+            EdnsOption::Cookie(ref cookie) => cookie.to_bytes(),
             EdnsOption::Unknown(_, ref data) => data.clone(), // gah, clone needed or make a crazy api.
         }
     }
@@ -468,11 +479,151 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DHU(..) => EdnsCode::DHU,
             #[cfg(feature = "dnssec")]
             EdnsOption::N3U(..) => EdnsCode::N3U,
+            EdnsOption::Cookie(..) => EdnsCode::Cookie,
             EdnsOption::Unknown(code, _) => EdnsCode::Unknown(code),
         }
     }
 }
 
+/// A DNS Cookie, the client cookie and the server cookie, if the client has received one
+///
+/// [RFC 7873, Domain Name System (DNS) Cookies, May 2016](https://tools.ietf.org/html/rfc7873#section-4)
+///
+/// ```text
+/// 4.  DNS Cookie Option
+///
+///    The DNS Cookie Option is an OPT RR [RFC6891] option that can be
+///    included in the RDATA portion of an OPT RR in DNS requests and
+///    responses.  The option length varies, depending on the
+///    circumstances in which it is being used.  There are two cases, as
+///    described below.  Both use the same OPTION-CODE; they are
+///    distinguished by their length.
+///
+///    In a request sent by a client to a server when the client does not
+///    know the server's cookie, its length is 8, consisting of an 8-byte
+///    Client Cookie.
+///
+///    In a request sent by a client when a Server Cookie is known, and in
+///    all responses to such a request, the length is variable from 16 to
+///    40 bytes, consisting of an 8-byte Client Cookie followed by the
+///    variable-length (8 bytes to 32 bytes) Server Cookie.
+/// ```
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct Cookie {
+    client: [u8; 8],
+    server: Vec<u8>,
+}
+
+impl Cookie {
+    /// The length of the client cookie
+    pub const CLIENT_LEN: usize = 8;
+    /// The shortest server cookie
+    pub const MIN_SERVER_LEN: usize = 8;
+    /// The longest server cookie
+    pub const MAX_SERVER_LEN: usize = 32;
+
+    /// Returns a cookie of the client cookie and the server cookie, which is empty if the client
+    ///  has none
+    ///
+    /// # Errors
+    ///
+    /// If the server cookie is neither empty nor from 8 to 32 bytes long
+    pub fn new(client: [u8; 8], server: Vec<u8>) -> ProtoResult<Self> {
+        if !server.is_empty()
+            && (server.len() < Self::MIN_SERVER_LEN || server.len() > Self::MAX_SERVER_LEN)
+        {
+            return Err(format!("bad server cookie length: {}", server.len()).into());
+        }
+
+        Ok(Cookie { client, server })
+    }
+
+    /// Reads the cookie from the data of the option
+    pub fn from_bytes(bytes: &[u8]) -> ProtoResult<Self> {
+        if bytes.len() < Self::CLIENT_LEN {
+            return Err(format!("bad cookie length: {}", bytes.len()).into());
+        }
+
+        let mut client = [0u8; 8];
+        client.copy_from_slice(&bytes[..Self::CLIENT_LEN]);
+        Self::new(client, bytes[Self::CLIENT_LEN..].to_vec())
+    }
+
+    /// The client cookie
+    pub fn client(&self) -> &[u8; 8] {
+        &self.client
+    }
+
+    /// The server cookie, None if the client has none
+    pub fn server(&self) -> Option<&[u8]> {
+        if self.server.is_empty() {
+            None
+        } else {
+            Some(&self.server)
+        }
+    }
+
+    /// Returns the length in bytes of the option data
+    fn len(&self) -> u16 {
+        (Self::CLIENT_LEN + self.server.len()) as u16
+    }
+
+    /// Returns the option data, the client cookie followed by the server cookie
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len() as usize);
+        bytes.extend_from_slice(&self.client);
+        bytes.extend_from_slice(&self.server);
+        bytes
+    }
+}
+
+impl BinEncodable for Cookie {
+    fn emit(&self, encoder: &mut BinEncoder) -> ProtoResult<()> {
+        encoder.emit_vec(&self.client)?;
+        encoder.emit_vec(&self.server)
+    }
+}
+
+#[test]
+fn test_cookie() {
+    let client = [1, 2, 3, 4, 5, 6, 7, 8];
+    let mut rdata = OPT::default();
+    rdata.insert(EdnsOption::Cookie(
+        Cookie::new(client, vec![9; 16]).unwrap(),
+    ));
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder: BinEncoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).unwrap();
+    }
+    assert_eq!(&bytes[..4], &[0, 10, 0, 24]);
+
+    let mut decoder: BinDecoder = BinDecoder::new(&bytes);
+    let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+    assert_eq!(rdata, read_rdata);
+    match read_rdata.get(EdnsCode::Cookie) {
+        Some(&EdnsOption::Cookie(ref cookie)) => {
+            assert_eq!(cookie.client(), &client);
+            assert_eq!(cookie.server(), Some(&[9; 16][..]));
+        }
+        option => panic!("expected a cookie: {:?}", option),
+    }
+
+    // client cookie only
+    let cookie = Cookie::from_bytes(&client).unwrap();
+    assert_eq!(cookie.server(), None);
+    assert_eq!(cookie.len(), 8);
+
+    // malformed cookies are left unparsed
+    assert_eq!(
+        EdnsOption::from((EdnsCode::Cookie, &[1, 2, 3][..])),
+        EdnsOption::Unknown(10, vec![1, 2, 3])
+    );
+    assert!(Cookie::from_bytes(&[0; 12]).is_err());
+    assert!(Cookie::from_bytes(&[0; 41]).is_err());
+}
+
 #[test]
 #[cfg(feature = "dnssec")]
 pub fn test() {
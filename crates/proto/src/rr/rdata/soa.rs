@@ -16,6 +16,8 @@
 
 //! start of authority record defining ownership and defaults for the zone
 
+use std::fmt;
+
 use error::*;
 use rr::domain::Name;
 use serialize::binary::*;
@@ -209,6 +211,21 @@ impl SOA {
     }
 }
 
+/// [RFC 1035, DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION, November 1987](https://tools.ietf.org/html/rfc1035)
+///
+/// ```text
+/// <MNAME> <RNAME> <SERIAL> <REFRESH> <RETRY> <EXPIRE> <MINIMUM>
+/// ```
+impl fmt::Display for SOA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.mname, self.rname, self.serial, self.refresh, self.retry, self.expire, self.minimum
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder) -> ProtoResult<SOA> {
     Ok(SOA {
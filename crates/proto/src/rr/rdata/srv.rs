@@ -16,6 +16,8 @@
 
 //! service records for identify port mapping for specific services on a host
 
+use std::fmt;
+
 use error::*;
 use rr::domain::Name;
 use serialize::binary::*;
@@ -192,6 +194,19 @@ impl SRV {
     }
 }
 
+/// ```text
+/// <PRIORITY> <WEIGHT> <PORT> <TARGET>
+/// ```
+impl fmt::Display for SRV {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.priority, self.weight, self.port, self.target
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder) -> ProtoResult<SRV> {
     // SRV { priority: u16, weight: u16, port: u16, target: Name, },
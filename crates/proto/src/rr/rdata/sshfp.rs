@@ -7,6 +7,9 @@
 
 //! SSHFP records for SSH public key fingerprints
 
+use std::fmt;
+
+use data_encoding::HEXUPPER;
 use error::*;
 use serialize::binary::*;
 
@@ -202,6 +205,25 @@ impl Into<u8> for FingerprintType {
     }
 }
 
+/// ```text
+/// <ALGORITHM> <FINGERPRINT TYPE> <FINGERPRINT>
+/// ```
+///
+/// The fingerprint is hex encoded.
+impl fmt::Display for SSHFP {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let algorithm: u8 = self.algorithm.into();
+        let fingerprint_type: u8 = self.fingerprint_type.into();
+        write!(
+            f,
+            "{} {} {}",
+            algorithm,
+            fingerprint_type,
+            HEXUPPER.encode(&self.fingerprint)
+        )
+    }
+}
+
 /// Read the RData from the given decoder.
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<SSHFP> {
     let algorithm = decoder.read_u8()?.unverified().into();
@@ -7,6 +7,9 @@
 
 //! TLSA records for storing TLS certificate validation information
 
+use std::fmt;
+
+use data_encoding::HEXUPPER;
 use error::*;
 use serialize::binary::*;
 
@@ -330,6 +333,24 @@ impl TLSA {
     }
 }
 
+/// ```text
+/// <CERT USAGE> <SELECTOR> <MATCHING TYPE> <CERTIFICATE ASSOCIATION DATA>
+/// ```
+///
+/// The certificate association data is hex encoded.
+impl fmt::Display for TLSA {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            u8::from(self.cert_usage),
+            u8::from(self.selector),
+            u8::from(self.matching),
+            HEXUPPER.encode(&self.cert_data)
+        )
+    }
+}
+
 /// Read the RData from the given Decoder
 ///
 /// ```text
@@ -16,7 +16,9 @@
 
 //! text records for storing arbitrary data
 
+use std::fmt;
 use std::slice::Iter;
+use std::str;
 
 use error::*;
 use serialize::binary::*;
@@ -59,6 +61,25 @@ impl TXT {
         }
     }
 
+    /// Creates a new TXT record data from the raw octets of the strings, which need not be UTF-8.
+    ///
+    /// # Arguments
+    ///
+    /// * `txt_data` - the set of octet strings which make up the txt_data.
+    ///
+    /// # Return value
+    ///
+    /// The new TXT record data.
+    pub fn from_bytes(txt_data: Vec<&[u8]>) -> TXT {
+        TXT {
+            txt_data: txt_data
+                .into_iter()
+                .map(|s| s.to_vec().into_boxed_slice())
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+        }
+    }
+
     /// ```text
     /// TXT-DATA        One or more <character-string>s.
     /// ```
@@ -72,6 +93,52 @@ impl TXT {
     }
 }
 
+/// Each of the strings is quoted, see `write_character_string`
+impl fmt::Display for TXT {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut strings = self.txt_data.iter();
+        if let Some(string) = strings.next() {
+            write_character_string(f, string)?;
+        }
+        for string in strings {
+            f.write_str(" ")?;
+            write_character_string(f, string)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the <character-string> as a quoted string
+///
+/// `"` and `\` are escaped with a `\`, and the control characters, and any bytes which are not
+///  UTF-8, as `\DDD` decimal.
+pub(crate) fn write_character_string(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    f.write_str("\"")?;
+    match str::from_utf8(data) {
+        Ok(string) => for ch in string.chars() {
+            match ch {
+                '"' | '\\' => write!(f, "\\{}", ch)?,
+                ch if ch.is_control() => {
+                    let mut buf = [0; 4];
+                    for b in ch.encode_utf8(&mut buf).bytes() {
+                        write!(f, "\\{:03}", b)?;
+                    }
+                }
+                ch => write!(f, "{}", ch)?,
+            }
+        },
+        Err(_) => for b in data {
+            match *b {
+                b'"' | b'\\' => write!(f, "\\{}", *b as char)?,
+                0x20...0x7E => write!(f, "{}", *b as char)?,
+                b => write!(f, "\\{:03}", b)?,
+            }
+        },
+    }
+    f.write_str("\"")
+}
+
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder, rdata_length: Restrict<u16>) -> ProtoResult<TXT> {
     let data_len = decoder.len();
@@ -116,3 +183,22 @@ fn test() {
     );
     assert_eq!(rdata, read_rdata.unwrap());
 }
+
+#[test]
+fn test_display() {
+    let rdata = TXT::new(vec![
+        "a \"quoted\" \\ string".to_string(),
+        "tab\t".to_string(),
+        "rust-🦀".to_string(),
+    ]);
+    assert_eq!(
+        rdata.to_string(),
+        "\"a \\\"quoted\\\" \\\\ string\" \"tab\\009\" \"rust-🦀\""
+    );
+
+    // not UTF-8
+    let rdata = TXT {
+        txt_data: vec![vec![b'a', 0xFF].into_boxed_slice()].into_boxed_slice(),
+    };
+    assert_eq!(rdata.to_string(), "\"a\\255\"");
+}
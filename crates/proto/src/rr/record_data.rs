@@ -19,6 +19,7 @@
 use std::cmp::Ordering;
 #[cfg(test)]
 use std::convert::From;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use super::domain::Name;
@@ -150,6 +151,13 @@ pub enum RData {
     /// ```
     CNAME(Name),
 
+    /// [RFC 6672, DNAME Redirection in the DNS](https://tools.ietf.org/html/rfc6672#section-2.1)
+    ///
+    /// The RDATA of a DNAME has the same format as that of a CNAME, a single target
+    /// <domain-name>, which MUST be sent uncompressed. The DNAME redirects all names below its
+    /// owner, but not the owner itself, to the same names below the target.
+    DNAME(Name),
+
     /// ```text
     /// 3.3.9. MX RDATA format
     ///
@@ -509,6 +517,10 @@ impl RData {
                 debug!("reading CNAME");
                 rdata::name::read(decoder).map(RData::CNAME)
             }
+            RecordType::DNAME => {
+                debug!("reading DNAME");
+                rdata::name::read(decoder).map(RData::DNAME)
+            }
             RecordType::ZERO => {
                 debug!("reading EMPTY");
                 return Ok(RData::ZERO);
@@ -607,6 +619,8 @@ impl RData {
             RData::CNAME(ref name) | RData::NS(ref name) | RData::PTR(ref name) => {
                 rdata::name::emit(encoder, name)
             }
+            // to_lowercase for rfc4034 and rfc6840
+            RData::DNAME(ref name) => rdata::name::emit_uncompressed(encoder, name),
             RData::ZERO => Ok(()),
             // to_lowercase for rfc4034 and rfc6840
             RData::MX(ref mx) => rdata::mx::emit(encoder, mx),
@@ -633,6 +647,7 @@ impl RData {
             RData::AAAA(..) => RecordType::AAAA,
             RData::CAA(..) => RecordType::CAA,
             RData::CNAME(..) => RecordType::CNAME,
+            RData::DNAME(..) => RecordType::DNAME,
             RData::MX(..) => RecordType::MX,
             RData::NS(..) => RecordType::NS,
             RData::NULL(..) => RecordType::NULL,
@@ -661,6 +676,37 @@ impl RData {
     }
 }
 
+/// The presentation format of the record data, as in master files
+///
+/// Record data without a format of its own, i.e. of unknown types, NULL and OPT, is written in the
+///  generic encoding of [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5).
+impl fmt::Display for RData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RData::A(address) => write!(f, "{}", address),
+            RData::AAAA(ref address) => write!(f, "{}", address),
+            RData::CAA(ref caa) => write!(f, "{}", caa),
+            RData::CNAME(ref name)
+            | RData::DNAME(ref name)
+            | RData::NS(ref name)
+            | RData::PTR(ref name) => write!(f, "{}", name),
+            RData::MX(ref mx) => write!(f, "{}", mx),
+            RData::NULL(ref null) => write!(f, "{}", null),
+            RData::OPENPGPKEY(ref openpgpkey) => write!(f, "{}", openpgpkey),
+            RData::OPT(..) => write!(f, "{}", NULL::with(self.to_bytes())),
+            RData::SOA(ref soa) => write!(f, "{}", soa),
+            RData::SRV(ref srv) => write!(f, "{}", srv),
+            RData::SSHFP(ref sshfp) => write!(f, "{}", sshfp),
+            RData::TLSA(ref tlsa) => write!(f, "{}", tlsa),
+            RData::TXT(ref txt) => write!(f, "{}", txt),
+            #[cfg(feature = "dnssec")]
+            RData::DNSSEC(ref rdata) => write!(f, "{}", rdata),
+            RData::Unknown { ref rdata, .. } => write!(f, "{}", rdata),
+            RData::ZERO => write!(f, "{}", NULL::new()),
+        }
+    }
+}
+
 impl PartialOrd<RData> for RData {
     fn partial_cmp(&self, other: &RData) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -715,6 +761,13 @@ mod tests {
                     b'o', b'm', 0,
                 ],
             ),
+            (
+                RData::DNAME(Name::from_str("www.example.com").unwrap()),
+                vec![
+                    3, b'w', b'w', b'w', 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c',
+                    b'o', b'm', 0,
+                ],
+            ),
             (
                 RData::MX(MX::new(256, Name::from_str("n").unwrap())),
                 vec![1, 0, 1, b'n', 0],
@@ -876,6 +929,7 @@ mod tests {
             RData::AAAA(..) => RecordType::AAAA,
             RData::CAA(..) => RecordType::CAA,
             RData::CNAME(..) => RecordType::CNAME,
+            RData::DNAME(..) => RecordType::DNAME,
             RData::MX(..) => RecordType::MX,
             RData::NS(..) => RecordType::NS,
             RData::NULL(..) => RecordType::NULL,
@@ -54,7 +54,8 @@ pub enum RecordType {
     /// RFC 1035[1]	Canonical name record
     CNAME,
     //  DHCID,      //	49	RFC 4701	DHCP identifier
-    //  DNAME,      //	39	RFC 2672	Delegation Name
+    /// RFC 6672	Delegation name record
+    DNAME,
     //  HIP,        //	55	RFC 5205	Host Identity Protocol
     //  IPSECKEY,   //	45	RFC 4025	IPsec Key
     /// RFC 1996	Incremental Zone Transfer
@@ -85,11 +86,10 @@ pub enum RecordType {
     //  TKEY,       //	249	RFC 2930	Secret key record
     ///	RFC 6698	TLSA certificate association
     TLSA,
-    //  TSIG,       //	250	RFC 2845	Transaction Signature
     /// RFC 1035[1]	Text record
     TXT,
 
-    /// A DNSSEC- or SIG(0)- specific record type.
+    /// A DNSSEC-, SIG(0)- or TSIG- specific record type.
     ///
     /// These types are in `DNSSECRecordType` to make them easy to disable when
     /// crypto functionality isn't needed.
@@ -116,6 +116,12 @@ impl RecordType {
         self == RecordType::CNAME
     }
 
+    /// Returns true if this is a DNAME
+    #[inline]
+    pub fn is_dname(self) -> bool {
+        self == RecordType::DNAME
+    }
+
     /// Returns true if this is an SRV
     #[inline]
     pub fn is_srv(self) -> bool {
@@ -143,6 +149,10 @@ impl FromStr for RecordType {
     ///
     /// let var: RecordType = RecordType::from_str("A").unwrap();
     /// assert_eq!(RecordType::A, var);
+    ///
+    /// // the generic form of RFC 3597
+    /// let var: RecordType = RecordType::from_str("TYPE65280").unwrap();
+    /// assert_eq!(RecordType::Unknown(65280), var);
     /// ```
     fn from_str(str: &str) -> ProtoResult<Self> {
         // TODO missing stuff?
@@ -151,6 +161,7 @@ impl FromStr for RecordType {
             "AAAA" => Ok(RecordType::AAAA),
             "CAA" => Ok(RecordType::CAA),
             "CNAME" => Ok(RecordType::CNAME),
+            "DNAME" => Ok(RecordType::DNAME),
             "NULL" => Ok(RecordType::NULL),
             "MX" => Ok(RecordType::MX),
             "NS" => Ok(RecordType::NS),
@@ -163,6 +174,29 @@ impl FromStr for RecordType {
             "TXT" => Ok(RecordType::TXT),
             "ANY" | "*" => Ok(RecordType::ANY),
             "AXFR" => Ok(RecordType::AXFR),
+            "IXFR" => Ok(RecordType::IXFR),
+            #[cfg(feature = "dnssec")]
+            "DNSKEY" => Ok(RecordType::DNSSEC(DNSSECRecordType::DNSKEY)),
+            #[cfg(feature = "dnssec")]
+            "DS" => Ok(RecordType::DNSSEC(DNSSECRecordType::DS)),
+            #[cfg(feature = "dnssec")]
+            "KEY" => Ok(RecordType::DNSSEC(DNSSECRecordType::KEY)),
+            #[cfg(feature = "dnssec")]
+            "NSEC" => Ok(RecordType::DNSSEC(DNSSECRecordType::NSEC)),
+            #[cfg(feature = "dnssec")]
+            "NSEC3" => Ok(RecordType::DNSSEC(DNSSECRecordType::NSEC3)),
+            #[cfg(feature = "dnssec")]
+            "NSEC3PARAM" => Ok(RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM)),
+            #[cfg(feature = "dnssec")]
+            "RRSIG" => Ok(RecordType::DNSSEC(DNSSECRecordType::RRSIG)),
+            #[cfg(feature = "dnssec")]
+            "SIG" => Ok(RecordType::DNSSEC(DNSSECRecordType::SIG)),
+            #[cfg(feature = "dnssec")]
+            "TSIG" => Ok(RecordType::DNSSEC(DNSSECRecordType::TSIG)),
+            // RFC 3597, TYPE<code> for types without a mnemonic
+            _ if str.starts_with("TYPE") => u16::from_str(&str[4..])
+                .map(RecordType::from)
+                .map_err(|_| ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
             _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
         }
     }
@@ -183,8 +217,10 @@ impl From<u16> for RecordType {
             28 => RecordType::AAAA,
             255 => RecordType::ANY,
             252 => RecordType::AXFR,
+            251 => RecordType::IXFR,
             257 => RecordType::CAA,
             5 => RecordType::CNAME,
+            39 => RecordType::DNAME,
             0 => RecordType::ZERO,
             15 => RecordType::MX,
             2 => RecordType::NS,
@@ -205,7 +241,8 @@ impl From<u16> for RecordType {
             50/*NSEC3*/|
             51/*NSEC3PARAM*/|
             46/*RRSIG*/|
-            24/*SIG*/ => RecordType::DNSSEC(DNSSECRecordType::from(value)),
+            24/*SIG*/|
+            250/*TSIG*/ => RecordType::DNSSEC(DNSSECRecordType::from(value)),
             // all unknown record types
             _ => RecordType::Unknown(value),
         }
@@ -250,6 +287,7 @@ impl From<RecordType> for &'static str {
             RecordType::AXFR => "AXFR",
             RecordType::CAA => "CAA",
             RecordType::CNAME => "CNAME",
+            RecordType::DNAME => "DNAME",
             RecordType::ZERO => "",
             RecordType::IXFR => "IXFR",
             RecordType::MX => "MX",
@@ -288,6 +326,7 @@ impl From<RecordType> for u16 {
             RecordType::AXFR => 252,
             RecordType::CAA => 257,
             RecordType::CNAME => 5,
+            RecordType::DNAME => 39,
             RecordType::ZERO => 0,
             RecordType::IXFR => 251,
             RecordType::MX => 15,
@@ -324,7 +363,13 @@ impl Ord for RecordType {
 
 impl Display for RecordType {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-        f.write_str(Into::<&str>::into(*self))
+        match *self {
+            // RFC 3597, types without a mnemonic are TYPE<code>
+            RecordType::Unknown(code) => write!(f, "TYPE{}", code),
+            #[cfg(feature = "dnssec")]
+            RecordType::DNSSEC(DNSSECRecordType::Unknown(code)) => write!(f, "TYPE{}", code),
+            _ => f.write_str(Into::<&str>::into(*self)),
+        }
     }
 }
 
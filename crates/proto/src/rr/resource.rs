@@ -17,6 +17,7 @@
 //! resource record implementation
 
 use std::cmp::Ordering;
+use std::fmt;
 
 use error::*;
 use rr::dns_class::DNSClass;
@@ -330,6 +331,21 @@ impl<'r> BinDecodable<'r> for Record {
     }
 }
 
+/// The record as an entry of a master file
+///
+/// ```text
+/// <owner> <TTL> <class> <type> <RDATA>
+/// ```
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.name_labels, self.ttl, self.dns_class, self.rr_type, self.rdata
+        )
+    }
+}
+
 impl PartialEq for Record {
     /// Equality or records, as defined by
     ///  [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
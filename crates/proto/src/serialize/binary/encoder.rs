@@ -45,6 +45,11 @@ mod private {
             self.max_size = max as usize;
         }
 
+        /// Returns the maximum size which is enforced
+        pub fn max_size(&self) -> u16 {
+            self.max_size as u16
+        }
+
         /// returns an error if the maximum buffer size would be exceeded with the addition number of elements
         ///
         /// and reserves the additional space in the buffer
@@ -147,6 +152,11 @@ impl<'a> BinEncoder<'a> {
         self.buffer.set_max_size(max);
     }
 
+    /// Returns the maximum size of the buffer, see `set_max_size()`
+    pub fn max_size(&self) -> u16 {
+        self.buffer.max_size()
+    }
+
     /// Returns a reference to the internal buffer
     pub fn into_bytes(self) -> &'a Vec<u8> {
         self.buffer.into_bytes()
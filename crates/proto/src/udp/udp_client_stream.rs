@@ -17,7 +17,7 @@ use tokio_udp;
 
 use error::ProtoError;
 use op::message::NoopMessageFinalizer;
-use op::{Message, MessageFinalizer, OpCode};
+use op::{Message, MessageFinalizer, MessageVerifier};
 use udp::udp_stream::NextRandomUdpSocket;
 use xfer::{DnsRequest, DnsRequestSender, DnsResponse, SerialMessage};
 
@@ -128,8 +128,9 @@ impl<MF: MessageFinalizer> DnsRequestSender for UdpClientStream<MF> {
         let now = now as u32;
 
         // TODO: move this logic into Message::finalize?
-        if let OpCode::Update = message.op_code() {
-            if let Some(ref signer) = self.signer {
+        let mut verifier = None;
+        if let Some(ref signer) = self.signer {
+            if signer.should_finalize_message(&message) {
                 if let Err(e) = message.finalize::<MF>(signer.borrow(), now) {
                     debug!("could not sign message: {}", e);
                     return UdpResponse(Timeout::new(
@@ -137,6 +138,8 @@ impl<MF: MessageFinalizer> DnsRequestSender for UdpClientStream<MF> {
                         self.timeout,
                     ));
                 }
+
+                verifier = signer.response_verifier(&message);
             }
         }
 
@@ -153,7 +156,7 @@ impl<MF: MessageFinalizer> DnsRequestSender for UdpClientStream<MF> {
         let message_id = message.id();
         let message = SerialMessage::new(bytes, self.name_server);
 
-        UdpResponse::new(message, message_id, self.timeout)
+        UdpResponse::new(message, message_id, verifier, self.timeout)
     }
 
     fn error_response(err: ProtoError) -> Self::DnsResponseFuture {
@@ -197,9 +200,15 @@ impl UdpResponse {
     ///
     /// * `request` - Serialized message being sent
     /// * `message_id` - Id of the message that was encoded in the serial message
-    fn new(request: SerialMessage, message_id: u16, timeout: Duration) -> Self {
+    /// * `verifier` - verifies the response to a signed request
+    fn new(
+        request: SerialMessage,
+        message_id: u16,
+        verifier: Option<Box<MessageVerifier>>,
+        timeout: Duration,
+    ) -> Self {
         UdpResponse(Timeout::new(
-            SingleUseUdpSocket::StartSend(Some(request), message_id),
+            SingleUseUdpSocket::StartSend(Some(request), message_id, verifier),
             timeout,
         ))
     }
@@ -242,10 +251,25 @@ impl<MF: MessageFinalizer> Future for UdpClientConnect<MF> {
 }
 
 enum SingleUseUdpSocket {
-    StartSend(Option<SerialMessage>, u16),
-    Connect(Option<SerialMessage>, NextRandomUdpSocket, u16),
-    Send(Option<SerialMessage>, Option<tokio_udp::UdpSocket>, u16),
-    AwaitResponse(Option<SerialMessage>, tokio_udp::UdpSocket, u16),
+    StartSend(Option<SerialMessage>, u16, Option<Box<MessageVerifier>>),
+    Connect(
+        Option<SerialMessage>,
+        NextRandomUdpSocket,
+        u16,
+        Option<Box<MessageVerifier>>,
+    ),
+    Send(
+        Option<SerialMessage>,
+        Option<tokio_udp::UdpSocket>,
+        u16,
+        Option<Box<MessageVerifier>>,
+    ),
+    AwaitResponse(
+        Option<SerialMessage>,
+        tokio_udp::UdpSocket,
+        u16,
+        Option<Box<MessageVerifier>>,
+    ),
     Response(Option<Message>),
     Errored(Option<ProtoError>),
 }
@@ -257,23 +281,33 @@ impl Future for SingleUseUdpSocket {
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         loop {
             *self = match *self {
-                SingleUseUdpSocket::StartSend(ref mut msg, msg_id) => {
+                SingleUseUdpSocket::StartSend(ref mut msg, msg_id, ref mut verifier) => {
                     // get a new socket to use
                     let msg = msg.take();
                     let name_server = msg
                         .as_ref()
                         .expect("SingleUseUdpSocket::StartSend invalid state: msg")
                         .addr();
-                    SingleUseUdpSocket::Connect(msg, NextRandomUdpSocket::new(&name_server), msg_id)
+                    SingleUseUdpSocket::Connect(
+                        msg,
+                        NextRandomUdpSocket::new(&name_server),
+                        msg_id,
+                        verifier.take(),
+                    )
                 }
-                SingleUseUdpSocket::Connect(ref mut msg, ref mut future_socket, msg_id) => {
+                SingleUseUdpSocket::Connect(
+                    ref mut msg,
+                    ref mut future_socket,
+                    msg_id,
+                    ref mut verifier,
+                ) => {
                     let socket = try_ready!(future_socket.poll());
                     // TODO: connect the socket here on merge into master
 
                     // send the message, and then await the response
-                    SingleUseUdpSocket::Send(msg.take(), Some(socket), msg_id)
+                    SingleUseUdpSocket::Send(msg.take(), Some(socket), msg_id, verifier.take())
                 }
-                SingleUseUdpSocket::Send(ref mut msg, ref mut socket, msg_id) => {
+                SingleUseUdpSocket::Send(ref mut msg, ref mut socket, msg_id, ref mut verifier) => {
                     try_ready!(socket
                         .as_mut()
                         .expect("SingleUseUdpSocket::Send invalid state: socket1")
@@ -293,9 +327,15 @@ impl Future for SingleUseUdpSocket {
                             .take()
                             .expect("SingleUseUdpSocket::Send invalid state: socket2"),
                         msg_id,
+                        verifier.take(),
                     )
                 }
-                SingleUseUdpSocket::AwaitResponse(ref mut request, ref mut socket, msg_id) => {
+                SingleUseUdpSocket::AwaitResponse(
+                    ref mut request,
+                    ref mut socket,
+                    msg_id,
+                    ref mut verifier,
+                ) => {
                     // TODO: consider making this heap based? need to verify it matches EDNS settings
                     let mut buf = [0u8; 2048];
 
@@ -325,6 +365,19 @@ impl Future for SingleUseUdpSocket {
                         Ok(message) => {
                             if msg_id == message.id() {
                                 debug!("received message id: {}", message.id());
+
+                                // the response to a signed request must be signed as well, an
+                                //  unverified response may be spoofed, drop it like a wrong id
+                                if let Some(ref mut verifier) = *verifier {
+                                    if let Err(e) = verifier.verify_response(response.bytes()) {
+                                        warn!(
+                                            "response: {} did not verify, dropped: {}",
+                                            msg_id, e
+                                        );
+                                        continue;
+                                    }
+                                }
+
                                 SingleUseUdpSocket::Response(Some(message))
                             } else {
                                 // on wrong id, attempted poison?
@@ -509,3 +562,99 @@ fn udp_client_stream_test(server_addr: IpAddr) {
 
     assert!(worked_once);
 }
+
+#[test]
+fn test_udp_client_stream_drops_unverified() {
+    use error::ProtoResult;
+    use op::Query;
+    use rr::rdata::NULL;
+    use rr::{Name, RData, Record, RecordType};
+    use std;
+    use std::str::FromStr;
+    use tokio::runtime::current_thread::Runtime;
+
+    /// Signs nothing, the responses verify if they have an answer
+    struct TestFinalizer;
+
+    impl MessageFinalizer for TestFinalizer {
+        fn finalize_message(&self, _: &Message, _: u32) -> ProtoResult<Vec<Record>> {
+            Ok(vec![])
+        }
+
+        fn should_finalize_message(&self, _: &Message) -> bool {
+            true
+        }
+
+        fn response_verifier(&self, _: &Message) -> Option<Box<MessageVerifier>> {
+            Some(Box::new(TestVerifier))
+        }
+    }
+
+    struct TestVerifier;
+
+    impl MessageVerifier for TestVerifier {
+        fn verify_response(&mut self, response: &[u8]) -> ProtoResult<()> {
+            if Message::from_vec(response)?.answers().is_empty() {
+                Err("response is not signed".into())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
+    let server = std::net::UdpSocket::bind(server_addr).unwrap();
+    server
+        .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let server_addr = server.local_addr().unwrap();
+
+    let test_name = Name::from_str("dead.beef").unwrap();
+    let test_bytes: &'static [u8; 8] = b"DEADBEEF";
+    let test_name_server = test_name.clone();
+    let server_handle = std::thread::Builder::new()
+        .name("test_udp_client_stream_drops_unverified:server".to_string())
+        .spawn(move || {
+            let mut buffer = [0_u8; 512];
+            let (len, addr) = server.recv_from(&mut buffer).expect("receive failed");
+            let request = Message::from_vec(&buffer[0..len]).expect("failed parse of request");
+
+            // the unsigned response, e.g. spoofed, with the right id comes first
+            let mut message = Message::new();
+            message.set_id(request.id());
+            message.add_queries(request.queries().to_vec());
+            let bytes = message.to_vec().unwrap();
+            server.send_to(&bytes, addr).expect("send failed");
+
+            message.add_answer(Record::from_rdata(
+                test_name_server,
+                0,
+                RecordType::NULL,
+                RData::NULL(NULL::with(test_bytes.to_vec())),
+            ));
+            let bytes = message.to_vec().unwrap();
+            server.send_to(&bytes, addr).expect("send failed");
+        }).unwrap();
+
+    let mut io_loop = Runtime::new().unwrap();
+    let stream = UdpClientStream::with_timeout_and_signer(
+        server_addr,
+        Duration::from_secs(5),
+        Some(Arc::new(TestFinalizer)),
+    );
+    let mut stream = io_loop.block_on(stream).ok().unwrap();
+
+    let mut query = Message::new();
+    query.add_query(Query::query(test_name, RecordType::NULL));
+    let response = io_loop
+        .block_on(stream.send_message(DnsRequest::new(query, Default::default())))
+        .expect("the verified response was not received");
+    server_handle.join().expect("server thread failed");
+
+    let response = Message::from(response);
+    if let RData::NULL(null) = response.answers()[0].rdata() {
+        assert_eq!(null.anything().expect("no bytes in NULL"), test_bytes);
+    } else {
+        panic!("not a NULL response");
+    }
+}
@@ -23,7 +23,8 @@ use smallvec::SmallVec;
 use tokio_timer::Delay;
 
 use error::*;
-use op::{Message, MessageFinalizer, OpCode};
+use op::{Message, MessageFinalizer, MessageVerifier};
+use rr::{RData, Record, RecordType};
 use xfer::{
     ignore_send, DnsClientStream, DnsRequest, DnsRequestOptions, DnsRequestSender, DnsResponse,
     SerialMessage,
@@ -43,6 +44,10 @@ struct ActiveRequest {
     // TODO: change the completion above to a Stream, and don't hold messages...
     responses: SmallVec<[Message; 1]>,
     timeout: Delay,
+    // verifies the responses to a signed request, e.g. with TSIG
+    verifier: Option<Box<MessageVerifier>>,
+    // the responses to a zone transfer may span more than one message
+    is_zone_transfer: bool,
 }
 
 impl ActiveRequest {
@@ -51,6 +56,8 @@ impl ActiveRequest {
         request_id: u16,
         request_options: DnsRequestOptions,
         timeout: Delay,
+        verifier: Option<Box<MessageVerifier>>,
+        is_zone_transfer: bool,
     ) -> Self {
         ActiveRequest {
             completion,
@@ -59,6 +66,8 @@ impl ActiveRequest {
             // request,
             responses: SmallVec::new(),
             timeout,
+            verifier,
+            is_zone_transfer,
         }
     }
 
@@ -72,11 +81,56 @@ impl ActiveRequest {
         self.completion.is_canceled()
     }
 
+    /// Verifies the response, as it was received, if the request was signed
+    fn verify_response(&mut self, response: &[u8]) -> ProtoResult<()> {
+        match self.verifier {
+            Some(ref mut verifier) => verifier.verify_response(response),
+            None => Ok(()),
+        }
+    }
+
     /// Adds the response to the request such that it can be later sent to the client
     fn add_response(&mut self, message: Message) {
         self.responses.push(message);
     }
 
+    /// Returns true if the request is a zone transfer, whose last message is still to come
+    ///
+    /// The answers of an AXFR, or of an IXFR which is answered with the whole zone, start and end
+    ///  with the SOA of the zone. Those of an incremental IXFR start with the new SOA followed by
+    ///  an older one, the new SOA then also starts the additions of the last difference and ends
+    ///  the answers. A single SOA answers an IXFR of a zone which did not change.
+    fn is_transfer_pending(&self) -> bool {
+        if !self.is_zone_transfer {
+            return false;
+        }
+
+        let serial = |record: &Record| match *record.rdata() {
+            RData::SOA(ref soa) => Some(soa.serial()),
+            _ => None,
+        };
+
+        let answers = self
+            .responses
+            .iter()
+            .flat_map(Message::answers)
+            .collect::<Vec<_>>();
+        let first = match answers.first().and_then(|record| serial(record)) {
+            Some(first) => first,
+            None => return false,
+        };
+        if answers.len() == 1 {
+            return false;
+        }
+
+        let soas = answers
+            .iter()
+            .filter(|record| serial(record) == Some(first))
+            .count();
+        let is_incremental = serial(answers[1]).is_some();
+        soas < if is_incremental { 3 } else { 2 }
+    }
+
     /// the request id of the message that was sent
     fn request_id(&self) -> u16 {
         self.request_id
@@ -202,7 +256,7 @@ where
         // drop all the canceled requests
         for (id, error) in canceled {
             if let Some(active_request) = self.active_requests.remove(&id) {
-                if active_request.responses.is_empty() {
+                if active_request.responses.is_empty() || active_request.is_transfer_pending() {
                     // complete the request, it's failed...
                     active_request.complete_with_error(error);
                 } else {
@@ -242,7 +296,7 @@ where
         let error = ProtoError::from("stream closed before response received");
 
         for (_, mut active_request) in self.active_requests.drain() {
-            if active_request.responses.is_empty() {
+            if active_request.responses.is_empty() || active_request.is_transfer_pending() {
                 // complete the request, it's failed...
                 active_request.complete_with_error(error.clone());
             } else {
@@ -339,23 +393,37 @@ where
         // TODO: truncates u64 to u32, error on overflow?
         let now = now as u32;
 
-        // update messages need to be signed.
-        if let OpCode::Update = request.op_code() {
-            if let Some(ref signer) = self.signer {
+        // update messages need to be signed, as do all messages of a TSIG signer.
+        let mut verifier = None;
+        if let Some(ref signer) = self.signer {
+            if signer.should_finalize_message(&request) {
                 if let Err(e) = request.finalize::<MF>(signer.borrow(), now) {
                     debug!("could not sign message: {}", e);
                     return DnsMultiplexerSerialResponseInner::Err(Some(e)).into();
                 }
+
+                verifier = signer.response_verifier(&request);
             }
         }
 
+        let is_zone_transfer = request.queries().first().map_or(false, |query| {
+            query.query_type() == RecordType::AXFR || query.query_type() == RecordType::IXFR
+        });
+
         // store a Timeout for this message before sending
         let timeout = Delay::new(Instant::now() + self.timeout_duration);
 
         let (complete, receiver) = oneshot::channel();
 
         // send the message
-        let active_request = ActiveRequest::new(complete, request.id(), request_options, timeout);
+        let active_request = ActiveRequest::new(
+            complete,
+            request.id(),
+            request_options,
+            timeout,
+            verifier,
+            is_zone_transfer,
+        );
 
         match request.to_vec() {
             Ok(buffer) => {
@@ -428,6 +496,15 @@ where
                     match buffer.to_message() {
                         Ok(message) => match self.active_requests.entry(message.id()) {
                             Entry::Occupied(mut request_entry) => {
+                                // the responses to a signed request must be signed as well
+                                let verified =
+                                    request_entry.get_mut().verify_response(buffer.bytes());
+                                if let Err(e) = verified {
+                                    warn!("response: {} did not verify: {}", message.id(), e);
+                                    request_entry.remove().complete_with_error(e);
+                                    continue;
+                                }
+
                                 // first add the response to the active_requests responses
                                 let complete = {
                                     let mut active_request = request_entry.get_mut();
@@ -435,6 +512,7 @@ where
 
                                     // determine if this is complete
                                     !active_request.request_options().expects_multiple_responses
+                                        && !active_request.is_transfer_pending()
                                 };
 
                                 // now check if the request is complete
@@ -10,8 +10,8 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use proto::op::Query;
-use proto::rr::RData;
+use proto::op::{Query, ResponseCode};
+use proto::rr::Record;
 
 use config;
 use error::*;
@@ -139,23 +139,11 @@ impl DnsLru {
         }
     }
 
-    pub(crate) fn insert(
-        &mut self,
-        query: Query,
-        rdatas_and_ttl: Vec<(RData, u32)>,
-        now: Instant,
-    ) -> Lookup {
-        let len = rdatas_and_ttl.len();
+    pub(crate) fn insert(&mut self, query: Query, records: Vec<Record>, now: Instant) -> Lookup {
         // collapse the values, we're going to take the Minimum TTL as the correct one
-        let (rdatas, ttl): (Vec<RData>, Duration) = rdatas_and_ttl.into_iter().fold(
-            (Vec::with_capacity(len), self.positive_max_ttl),
-            |(mut rdatas, mut min_ttl), (rdata, ttl)| {
-                rdatas.push(rdata);
-                let ttl = Duration::from_secs(u64::from(ttl));
-                min_ttl = min_ttl.min(ttl);
-                (rdatas, min_ttl)
-            },
-        );
+        let ttl = records.iter().fold(self.positive_max_ttl, |min_ttl, record| {
+            min_ttl.min(Duration::from_secs(u64::from(record.ttl())))
+        });
 
         // If the cache was configured with a minimum TTL, and that value is higher
         // than the minimum TTL in the values, use it instead.
@@ -163,7 +151,7 @@ impl DnsLru {
         let valid_until = now + ttl;
 
         // insert into the LRU
-        let lookup = Lookup::new_with_deadline(query.clone(), Arc::new(rdatas), valid_until);
+        let lookup = Lookup::new_with_records(query.clone(), Arc::new(records), valid_until);
         self.cache.insert(
             query,
             LruValue {
@@ -197,11 +185,28 @@ impl DnsLru {
         lookup
     }
 
-    pub(crate) fn nx_error(query: Query, valid_until: Option<Instant>) -> ResolveError {
-        ResolveErrorKind::NoRecordsFound { query, valid_until }.into()
+    pub(crate) fn nx_error(
+        query: Query,
+        valid_until: Option<Instant>,
+        response_code: ResponseCode,
+        soa: Option<Record>,
+    ) -> ResolveError {
+        ResolveErrorKind::NoRecordsFound {
+            query,
+            valid_until,
+            response_code,
+            soa: soa.map(Box::new),
+        }.into()
     }
 
-    pub(crate) fn negative(&mut self, query: Query, ttl: u32, now: Instant) -> ResolveError {
+    pub(crate) fn negative(
+        &mut self,
+        query: Query,
+        ttl: u32,
+        now: Instant,
+        response_code: ResponseCode,
+        soa: Option<Record>,
+    ) -> ResolveError {
         // TODO: if we are getting a negative response, should we instead fallback to cache?
         //   this would cache indefinitely, probably not correct
 
@@ -220,7 +225,7 @@ impl DnsLru {
             },
         );
 
-        Self::nx_error(query, Some(valid_until))
+        Self::nx_error(query, Some(valid_until), response_code, soa)
     }
 
     /// This needs to be mut b/c it's an LRU, meaning the ordering of elements will potentially change on retrieval...
@@ -255,10 +260,18 @@ mod tests {
     use std::time::*;
 
     use proto::op::Query;
-    use proto::rr::{Name, RecordType};
+    use proto::rr::{Name, RData, RecordType};
 
     use super::*;
 
+    fn records(query: &Query, rdatas_and_ttl: Vec<(RData, u32)>) -> Vec<Record> {
+        rdatas_and_ttl
+            .into_iter()
+            .map(|(rdata, ttl)| {
+                Record::from_rdata(query.name().clone(), ttl, rdata.to_record_type(), rdata)
+            }).collect()
+    }
+
     #[test]
     fn test_is_current() {
         let now = Instant::now();
@@ -293,7 +306,7 @@ mod tests {
         };
         let mut lru = DnsLru::new(1, ttls);
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), records(&name, ips_ttl), now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the cache's min TTL, since the
         // query's TTL was below the minimum.
@@ -302,7 +315,7 @@ mod tests {
         // record should have TTL of 3 seconds.
         let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 3)];
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), records(&name, ips_ttl), now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the record's TTL, since it's
         // greater than the cache's minimum.
@@ -323,7 +336,7 @@ mod tests {
         let mut lru = DnsLru::new(1, ttls);
 
         // neg response should have TTL of 1 seconds.
-        let nx_error = lru.negative(name.clone(), 1, now);
+        let nx_error = lru.negative(name.clone(), 1, now, ResponseCode::NXDomain, None);
         match nx_error.kind() {
             &ResolveErrorKind::NoRecordsFound { valid_until, .. } => {
                 let valid_until = valid_until.expect("resolve error should have a deadline");
@@ -334,7 +347,7 @@ mod tests {
         }
 
         // neg response should have TTL of 3 seconds.
-        let nx_error = lru.negative(name.clone(), 3, now);
+        let nx_error = lru.negative(name.clone(), 3, now, ResponseCode::NXDomain, None);
         match nx_error.kind() {
             &ResolveErrorKind::NoRecordsFound { valid_until, .. } => {
                 let valid_until = valid_until.expect("ResolveError should have a deadline");
@@ -362,7 +375,7 @@ mod tests {
         };
         let mut lru = DnsLru::new(1, ttls);
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), records(&name, ips_ttl), now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the cache's min TTL, since the
         // query's TTL was above the maximum.
@@ -371,7 +384,7 @@ mod tests {
         // record should have TTL of 59 seconds.
         let ips_ttl = vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), 59)];
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), records(&name, ips_ttl), now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the record's TTL, since it's
         // below than the cache's maximum.
@@ -392,7 +405,7 @@ mod tests {
         let mut lru = DnsLru::new(1, ttls);
 
         // neg response should have TTL of 62 seconds.
-        let nx_error = lru.negative(name.clone(), 62, now);
+        let nx_error = lru.negative(name.clone(), 62, now, ResponseCode::NXDomain, None);
         match nx_error.kind() {
             &ResolveErrorKind::NoRecordsFound { valid_until, .. } => {
                 let valid_until = valid_until.expect("resolve error should have a deadline");
@@ -403,7 +416,7 @@ mod tests {
         }
 
         // neg response should have TTL of 59 seconds.
-        let nx_error = lru.negative(name.clone(), 59, now);
+        let nx_error = lru.negative(name.clone(), 59, now, ResponseCode::NXDomain, None);
         match nx_error.kind() {
             &ResolveErrorKind::NoRecordsFound { valid_until, .. } => {
                 let valid_until = valid_until.expect("resolve error should have a deadline");
@@ -423,7 +436,7 @@ mod tests {
         let ips = vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))];
         let mut lru = DnsLru::new(1, TtlConfig::default());
 
-        let rc_ips = lru.insert(name.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(name.clone(), records(&name, ips_ttl), now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
 
         let rc_ips = lru.get(&name, now).unwrap();
@@ -445,7 +458,7 @@ mod tests {
         ];
         let mut lru = DnsLru::new(1, TtlConfig::default());
 
-        lru.insert(name.clone(), ips_ttl, now);
+        lru.insert(name.clone(), records(&name, ips_ttl), now);
 
         // still valid
         let rc_ips = lru.get(&name, now + Duration::from_secs(1)).unwrap();
@@ -477,7 +490,7 @@ mod tests {
             ..Default::default()
         };
         let mut lru = DnsLru::new(1, ttls);
-        lru.insert(name.clone(), ips_ttl, now);
+        lru.insert(name.clone(), records(&name, ips_ttl), now);
 
         // still valid
         let rc_ips = lru.get(&name, now + Duration::from_secs(1)).unwrap();
@@ -521,7 +534,7 @@ mod tests {
             ..Default::default()
         };
         let mut lru = DnsLru::new(1, ttls);
-        lru.insert(name.clone(), ips_ttl, now);
+        lru.insert(name.clone(), records(&name, ips_ttl), now);
 
         // still valid
         let rc_ips = lru.get(&name, now + Duration::from_secs(1)).unwrap();
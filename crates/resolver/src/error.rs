@@ -10,7 +10,8 @@
 use failure::{Backtrace, Context, Fail};
 use std::{fmt, io, sync, time::Instant};
 use proto::error::{ProtoError, ProtoErrorKind};
-use proto::op::Query;
+use proto::op::{Query, ResponseCode};
+use proto::rr::Record;
 
 /// An alias for results returned by functions of this crate
 pub type ResolveResult<T> = ::std::result::Result<T, ResolveError>;
@@ -33,7 +34,11 @@ pub enum ResolveErrorKind {
         query: Query,
         /// A deadline after which the the `NXDOMAIN` response is no longer
         /// valid, and the nameserver should be queried again.
-        valid_until: Option<Instant>
+        valid_until: Option<Instant>,
+        /// `NXDomain` if the name does not exist, `NoError` if it has no records of the type
+        response_code: ResponseCode,
+        /// The SOA of the zone from the authority section of the response, if there was one
+        soa: Option<Box<Record>>,
     },
 
     // foreign
@@ -56,9 +61,16 @@ impl Clone for ResolveErrorKind {
         match *self {
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
-            NoRecordsFound { ref query, valid_until } => NoRecordsFound {
+            NoRecordsFound {
+                ref query,
+                valid_until,
+                response_code,
+                ref soa,
+            } => NoRecordsFound {
                 query: query.clone(),
                 valid_until,
+                response_code,
+                soa: soa.clone(),
             },
 
             // foreign
@@ -194,6 +194,7 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 extern crate lru_cache;
+extern crate rand;
 extern crate resolv_conf;
 #[cfg(feature = "serde-config")]
 #[macro_use]
@@ -227,6 +228,7 @@ pub mod lookup_ip;
 pub mod lookup_state;
 #[doc(hidden)]
 pub mod name_server_pool;
+mod recursor;
 #[cfg(any(feature = "tokio", test))]
 mod resolver;
 pub mod system_conf;
@@ -238,6 +240,7 @@ pub use self::proto::rr::{IntoName, Name, TryParseIp};
 
 pub use async_resolver::{AsyncResolver, Background, BackgroundLookup, BackgroundLookupIp};
 pub use hosts::Hosts;
+pub use recursor::Recursor;
 #[cfg(any(feature = "tokio", test))]
 pub use resolver::Resolver;
 
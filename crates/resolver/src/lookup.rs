@@ -18,7 +18,7 @@ use futures::{future, Async, Future, Poll};
 use proto::error::ProtoError;
 use proto::op::Query;
 use proto::rr::rdata;
-use proto::rr::{Name, RData, RecordType};
+use proto::rr::{Name, RData, Record, RecordType};
 use proto::xfer::{DnsRequest, DnsRequestOptions, DnsResponse};
 #[cfg(feature = "dnssec")]
 use proto::SecureDnsHandle;
@@ -36,7 +36,7 @@ use name_server_pool::{ConnectionHandle, ConnectionProvider, NameServerPool, Sta
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Lookup {
     query: Query,
-    rdatas: Arc<Vec<RData>>,
+    records: Arc<Vec<Record>>,
     valid_until: Instant,
 }
 
@@ -49,18 +49,45 @@ impl Lookup {
     /// Return new instance with given rdatas and the maximum TTL.
     pub fn new_with_max_ttl(query: Query, rdatas: Arc<Vec<RData>>) -> Self {
         let valid_until = Instant::now() + Duration::from_secs(u64::from(MAX_TTL));
-        Lookup {
-            query,
-            rdatas,
-            valid_until,
-        }
+        Self::new_with_deadline(query, rdatas, valid_until)
     }
 
     /// Return a new instance with the given rdatas and deadline.
+    ///
+    /// The records of the rdatas have the name and class of the query, and the TTL remaining
+    ///  until the deadline.
     pub fn new_with_deadline(query: Query, rdatas: Arc<Vec<RData>>, valid_until: Instant) -> Self {
+        let now = Instant::now();
+        let ttl = if valid_until > now {
+            (valid_until - now).as_secs() as u32
+        } else {
+            0
+        };
+
+        let records = rdatas
+            .iter()
+            .map(|rdata| {
+                let mut record = Record::from_rdata(
+                    query.name().clone(),
+                    ttl,
+                    rdata.to_record_type(),
+                    rdata.clone(),
+                );
+                record.set_dns_class(query.query_class());
+                record
+            }).collect();
+
+        Self::new_with_records(query, Arc::new(records), valid_until)
+    }
+
+    /// Return a new instance with the given records, as answered, and deadline.
+    ///
+    /// The records may start with the CNAMEs, or DNAMEs, which were followed to the records of
+    ///  the query, see `record_iter`.
+    pub fn new_with_records(query: Query, records: Arc<Vec<Record>>, valid_until: Instant) -> Self {
         Lookup {
             query,
-            rdatas,
+            records,
             valid_until,
         }
     }
@@ -71,8 +98,23 @@ impl Lookup {
     }
 
     /// Returns a borrowed iterator of the returned IPs
+    ///
+    /// This does not include the data of the CNAMEs, or DNAMEs, which were followed to the
+    ///  records of the query.
     pub fn iter(&self) -> LookupIter {
-        LookupIter(self.rdatas.iter())
+        LookupIter {
+            records: self.records.iter(),
+            query_type: self.query.query_type(),
+        }
+    }
+
+    /// Returns a borrowed iterator of the records, with their names and TTLs as answered
+    ///
+    /// The CNAMEs, or DNAMEs, followed to the records of the query come first, in the order of
+    ///  the chain. The TTLs are those of the answers, where cached they may be greater than the
+    ///  time remaining until `valid_until`.
+    pub fn record_iter(&self) -> Iter<Record> {
+        self.records.iter()
     }
 
     /// Returns the `Instant` at which this `Lookup` is no longer valid.
@@ -81,38 +123,62 @@ impl Lookup {
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.rdatas.is_empty()
+        self.iter().next().is_none()
     }
 
     pub(crate) fn len(&self) -> usize {
-        self.rdatas.len()
+        self.records.len()
     }
 
+    /// The data of the records of the query, see `iter`
     #[cfg(test)]
-    pub fn rdatas(&self) -> &[RData] {
-        self.rdatas.as_ref()
+    pub fn rdatas(&self) -> Vec<RData> {
+        self.iter().cloned().collect()
     }
 
     /// Clones the inner vec, appends the other vec
     pub(crate) fn append(&self, other: Lookup) -> Self {
-        let mut rdatas = Vec::with_capacity(self.len() + other.len());
-        rdatas.extend_from_slice(&*self.rdatas);
-        rdatas.extend_from_slice(&*other.rdatas);
+        let mut records = Vec::with_capacity(self.len() + other.len());
+        records.extend_from_slice(&self.records);
+        records.extend_from_slice(&other.records);
 
         // Choose the sooner deadline of the two lookups.
         let valid_until = min(self.valid_until(), other.valid_until());
-        Self::new_with_deadline(self.query.clone(), Arc::new(rdatas), valid_until)
+        Self::new_with_records(self.query.clone(), Arc::new(records), valid_until)
+    }
+
+    /// Returns a new instance with the records of the CNAMEs, or DNAMEs, which were followed to
+    ///  this lookup, prepended
+    pub(crate) fn with_chain(&self, chain: &[Record]) -> Self {
+        let mut records = Vec::with_capacity(chain.len() + self.len());
+        records.extend_from_slice(chain);
+        records.extend_from_slice(&self.records);
+
+        Self::new_with_records(self.query.clone(), Arc::new(records), self.valid_until)
     }
 }
 
 /// Borrowed view of set of RDatas returned from a Lookup
-pub struct LookupIter<'a>(Iter<'a, RData>);
+pub struct LookupIter<'a> {
+    records: Iter<'a, Record>,
+    query_type: RecordType,
+}
 
 impl<'a> Iterator for LookupIter<'a> {
     type Item = &'a RData;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        let query_type = self.query_type;
+
+        // skips the CNAMEs and DNAMEs of the chain to the records of the query
+        self.records
+            .by_ref()
+            .find(|r| match r.rr_type() {
+                RecordType::CNAME | RecordType::DNAME => {
+                    query_type == r.rr_type() || query_type.is_any()
+                }
+                _ => true,
+            }).map(Record::rdata)
     }
 }
 
@@ -210,7 +276,7 @@ impl<C: DnsHandle + 'static> Future for LookupFuture<C> {
                 // If the query returned a successful lookup, we will attempt
                 // to retry if the lookup is empty. Otherwise, we will return
                 // that lookup.
-                Ok(Async::Ready(ref lookup)) => lookup.rdatas.len() == 0,
+                Ok(Async::Ready(ref lookup)) => lookup.is_empty(),
                 // If the query failed, we will attempt to retry.
                 Err(_) => true,
             };
@@ -408,7 +474,7 @@ pub mod tests {
     use futures::{future, Future};
 
     use proto::error::{ProtoErrorKind, ProtoResult};
-    use proto::op::Message;
+    use proto::op::{Message, ResponseCode};
     use proto::rr::{Name, RData, Record, RecordType};
     use proto::xfer::{DnsRequest, DnsRequestOptions};
 
@@ -498,6 +564,8 @@ pub mod tests {
             ResolveErrorKind::NoRecordsFound {
                 query: Query::query(Name::root(), RecordType::A),
                 valid_until: None,
+                response_code: ResponseCode::NoError,
+                soa: None,
             }
         );
     }
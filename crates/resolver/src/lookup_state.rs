@@ -103,7 +103,14 @@ impl<C: DnsHandle + 'static> CachingClient<C> {
                     RecordType::PTR => {
                         return Box::new(future::ok(Lookup::from_rdata(query, LOCALHOST.clone())))
                     }
-                    _ => return Box::new(future::err(DnsLru::nx_error(query, None))), // Are there any other types we can use?
+                    _ => {
+                        return Box::new(future::err(DnsLru::nx_error(
+                            query,
+                            None,
+                            ResponseCode::NoError,
+                            None,
+                        )))
+                    } // Are there any other types we can use?
                 },
                 // when mdns is enabled we will follow a standard query path
                 #[cfg(feature = "mdns")]
@@ -113,7 +120,12 @@ impl<C: DnsHandle + 'static> CachingClient<C> {
                 #[cfg(not(feature = "mdns"))]
                 ResolverUsage::LinkLocal => (),
                 ResolverUsage::NxDomain => {
-                    return Box::new(future::err(DnsLru::nx_error(query, None)))
+                    return Box::new(future::err(DnsLru::nx_error(
+                        query,
+                        None,
+                        ResponseCode::NXDomain,
+                        None,
+                    )))
                 }
                 ResolverUsage::Normal => (),
             }
@@ -167,21 +179,36 @@ struct QueryFuture<C: DnsHandle + 'static> {
 }
 
 enum Records {
-    /// The records exists, a vec of rdata with ttl
-    Exists(Vec<(RData, u32)>),
-    /// Records do not exist, ttl for negative caching
-    NoData { ttl: Option<u32> },
-    /// Future lookup for recursive cname records
+    /// The records exists, as answered, preceded by the CNAMEs followed to them
+    Exists(Vec<Record>),
+    /// Records do not exist, ttl for negative caching, the response code and the SOA
+    NoData {
+        ttl: Option<u32>,
+        response_code: ResponseCode,
+        soa: Option<Record>,
+    },
+    /// Future lookup for recursive cname records, the chain is the CNAMEs followed so far
     CnameChain {
         next: Box<Future<Item = Lookup, Error = ResolveError> + Send>,
         min_ttl: u32,
+        chain: Vec<Record>,
     },
     /// Already cached, chained queries
-    Chained { cached: Lookup, min_ttl: u32 },
+    Chained {
+        cached: Lookup,
+        min_ttl: u32,
+        chain: Vec<Record>,
+    },
 }
 
 impl<C: DnsHandle + 'static> QueryFuture<C> {
-    fn next_query(&mut self, query: Query, cname_ttl: u32, message: DnsResponse) -> Records {
+    fn next_query(
+        &mut self,
+        query: Query,
+        cname_ttl: u32,
+        chain: Vec<Record>,
+        message: DnsResponse,
+    ) -> Records {
         if QUERY_DEPTH.with(|c| *c.borrow() >= MAX_QUERY_DEPTH) {
             // TODO: This should return an error
             self.handle_nxdomain(message, true)
@@ -192,6 +219,7 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
             Records::CnameChain {
                 next: self.client.lookup(query, self.options.clone()),
                 min_ttl: cname_ttl,
+                chain,
             }
         }
     }
@@ -201,11 +229,11 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
         const INITIAL_TTL: u32 = dns_lru::MAX_TTL;
 
         // seek out CNAMES, this is only performed if the query is not a CNAME, ANY, or SRV
-        let (search_name, cname_ttl, was_cname) = {
+        let (search_name, cname_ttl, was_cname, chain) = {
             // this will only search for CNAMEs if the request was not meant to be for one of the triggers for recursion
-            let (search_name, cname_ttl, was_cname) =
+            let (search_name, cname_ttl, was_cname, chain) =
                 if self.query.query_type().is_any() || self.query.query_type().is_cname() {
-                    (Cow::Borrowed(self.query.name()), INITIAL_TTL, false)
+                    (Cow::Borrowed(self.query.name()), INITIAL_TTL, false, vec![])
                 } else {
                     // Folds any cnames from the answers section, into the final cname in the answers section
                     //   this works by folding the last CNAME found into the final folded result.
@@ -213,16 +241,30 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                     // For SRV, the name added for the search becomes the target name.
                     //
                     // TODO: should this include the additionals?
+                    // The CNAMEs and DNAMEs followed are kept as the chain to the records.
                     response.messages().flat_map(Message::answers).fold(
-                        (Cow::Borrowed(self.query.name()), INITIAL_TTL, false),
-                        |(search_name, cname_ttl, was_cname), r| {
+                        (Cow::Borrowed(self.query.name()), INITIAL_TTL, false, vec![]),
+                        |(search_name, cname_ttl, was_cname, mut chain), r| {
                             match *r.rdata() {
                                 RData::CNAME(ref cname) => {
                                     // take the minimum TTL of the cname_ttl and the next record in the chain
                                     let ttl = cname_ttl.min(r.ttl());
                                     debug_assert_eq!(r.rr_type(), RecordType::CNAME);
                                     if search_name.as_ref() == r.name() {
-                                        return (Cow::Owned(cname.clone()), ttl, true);
+                                        chain.push(r.clone());
+                                        return (Cow::Owned(cname.clone()), ttl, true, chain);
+                                    }
+                                }
+                                RData::DNAME(ref target) => {
+                                    // take the minimum TTL of the cname_ttl and the next record in the chain
+                                    let ttl = cname_ttl.min(r.ttl());
+                                    debug_assert_eq!(r.rr_type(), RecordType::DNAME);
+
+                                    // a DNAME redirects the names below its owner, RFC 6672, the
+                                    //  server need not have synthesized the CNAME
+                                    if let Ok(name) = search_name.replace_domain(r.name(), target) {
+                                        chain.push(r.clone());
+                                        return (Cow::Owned(name), ttl, true, chain);
                                     }
                                 }
                                 RData::SRV(ref srv) => {
@@ -231,12 +273,12 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                                     debug_assert_eq!(r.rr_type(), RecordType::SRV);
 
                                     // the search name becomes the srv.target
-                                    return (Cow::Owned(srv.target().clone()), ttl, true);
+                                    return (Cow::Owned(srv.target().clone()), ttl, true, chain);
                                 }
                                 _ => (),
                             }
 
-                            (search_name, cname_ttl, was_cname)
+                            (search_name, cname_ttl, was_cname, chain)
                         },
                     )
                 };
@@ -252,14 +294,12 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                 .collect();
 
             // After following all the CNAMES to the last one, try and lookup the final name
+            //  the records keep their TTLs, the cache takes the minimum of the chain and them
             let records = answers
                 .into_iter()
                 // Chained records will generally exist in the additionals section
                 .chain(additionals.into_iter())
                 .filter_map(|r| {
-                    // because this resolved potentially recursively, we want the min TTL from the chain
-                    let ttl = cname_ttl.min(r.ttl());
-
                     // TODO: disable name validation with ResolverOpts? glibc feature...
                     // restrict to the RData type requested
                     if self.query.query_class() == r.dns_class() {
@@ -270,7 +310,7 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                         if ((self.query.query_type().is_any() || self.query.query_type() == r.rr_type()) &&
                             (search_name.as_ref() == r.name() || self.query.name() == r.name())) || 
                             (self.query.query_type().is_srv() && r.rr_type().is_ip_addr() && search_name.as_ref() == r.name()) {
-                            Some((r.unwrap_rdata(), ttl))
+                            Some(r)
                         } else {
                             None
                         }
@@ -281,10 +321,12 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                 .collect::<Vec<_>>();
 
             if !records.is_empty() {
-                return Ok(Async::Ready(Records::Exists(records)));
+                let mut chain = chain;
+                chain.extend(records);
+                return Ok(Async::Ready(Records::Exists(chain)));
             }
 
-            (search_name.into_owned(), cname_ttl, was_cname)
+            (search_name.into_owned(), cname_ttl, was_cname, chain)
         };
 
         // TODO: for SRV records we *could* do an implicit lookup, but, this requires knowing the type of IP desired
@@ -293,7 +335,7 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
         if was_cname {
             let next_query = Query::query(search_name, self.query.query_type());
             Ok(Async::Ready(
-                self.next_query(next_query, cname_ttl, response),
+                self.next_query(next_query, cname_ttl, chain, response),
             ))
         } else {
             // TODO: review See https://tools.ietf.org/html/rfc2308 for NoData section
@@ -317,6 +359,7 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
     /// * `message` - message to extract SOA, etc, from for caching failed requests
     /// * `valid_nsec` - species that in DNSSec mode, this request is safe to cache
     fn handle_nxdomain(&self, mut message: DnsResponse, valid_nsec: bool) -> Records {
+        let response_code = message.response_code();
         if valid_nsec || !self.dnssec {
             //  if there were validated NSEC records
             let soa = message
@@ -324,16 +367,24 @@ impl<C: DnsHandle + 'static> QueryFuture<C> {
                 .into_iter()
                 .find(|r| r.rr_type() == RecordType::SOA);
 
-            let ttl = if let Some(RData::SOA(soa)) = soa.map(|r| r.unwrap_rdata()) {
+            let ttl = if let Some(RData::SOA(ref soa)) = soa.as_ref().map(Record::rdata) {
                 Some(soa.minimum())
             } else {
                 // TODO: figure out a looping lookup to get SOA
                 None
             };
 
-            Records::NoData { ttl }
+            Records::NoData {
+                ttl,
+                response_code,
+                soa,
+            }
         } else {
-            Records::NoData { ttl: None }
+            Records::NoData {
+                ttl: None,
+                response_code,
+                soa: None,
+            }
         }
     }
 }
@@ -387,7 +438,14 @@ impl Future for InsertCache {
             Ok(mut lru) => {
                 // this will put this object into an inconsistent state, but no one should call poll again...
                 let query = mem::replace(&mut self.query, Query::new());
-                let rdata = mem::replace(&mut self.rdatas, Records::NoData { ttl: None });
+                let rdata = mem::replace(
+                    &mut self.rdatas,
+                    Records::NoData {
+                        ttl: None,
+                        response_code: ResponseCode::NoError,
+                        soa: None,
+                    },
+                );
 
                 match rdata {
                     Records::Exists(rdata) => {
@@ -396,17 +454,25 @@ impl Future for InsertCache {
                     Records::Chained {
                         cached: lookup,
                         min_ttl: ttl,
+                        chain,
                     } => Ok(Async::Ready(lru.duplicate(
                         query,
-                        lookup,
+                        lookup.with_chain(&chain),
                         ttl,
                         Instant::now(),
                     ))),
-                    Records::NoData { ttl: Some(ttl) } => {
-                        Err(lru.negative(query, ttl, Instant::now()))
-                    }
-                    Records::NoData { ttl: None } | Records::CnameChain { .. } => {
-                        Err(DnsLru::nx_error(query, None))
+                    Records::NoData {
+                        ttl: Some(ttl),
+                        response_code,
+                        soa,
+                    } => Err(lru.negative(query, ttl, Instant::now(), response_code, soa)),
+                    Records::NoData {
+                        ttl: None,
+                        response_code,
+                        soa,
+                    } => Err(DnsLru::nx_error(query, None, response_code, soa)),
+                    Records::CnameChain { .. } => {
+                        Err(DnsLru::nx_error(query, None, ResponseCode::NoError, None))
                     }
                 }
             }
@@ -424,6 +490,7 @@ enum QueryState<C: DnsHandle + 'static> {
         Box<Future<Item = Lookup, Error = ResolveError> + Send>,
         Query,
         u32,
+        Vec<Record>,
         Arc<Mutex<DnsLru>>,
     ),
     /// State of adding the item to the cache
@@ -484,6 +551,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
         &mut self,
         future: Box<Future<Item = Lookup, Error = ResolveError> + Send>,
         cname_ttl: u32,
+        chain: Vec<Record>,
     ) {
         // The error state, this query is complete...
         let query_state = mem::replace(self, QueryState::Error);
@@ -499,7 +567,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
             }) => {
                 mem::replace(
                     self,
-                    QueryState::CnameChain(future, query, cname_ttl, cache),
+                    QueryState::CnameChain(future, query, cname_ttl, chain, cache),
                 );
             }
             _ => panic!("bad state, expected Query"),
@@ -536,7 +604,7 @@ impl<C: DnsHandle + 'static> QueryState<C> {
                     }
                 }
             }
-            QueryState::CnameChain(_, query, _, cache) => {
+            QueryState::CnameChain(_, query, _, _, cache) => {
                 match rdatas {
                     // There are Cnames to lookup
                     Records::CnameChain { .. } => {
@@ -590,7 +658,7 @@ impl<C: DnsHandle + 'static> Future for QueryState<C> {
                     }
                 }
             }
-            QueryState::CnameChain(ref mut future, _, ttl, _) => {
+            QueryState::CnameChain(ref mut future, _, ttl, ref mut chain, _) => {
                 let poll = future.poll();
                 match poll {
                     Ok(Async::NotReady) => {
@@ -600,6 +668,7 @@ impl<C: DnsHandle + 'static> Future for QueryState<C> {
                         records = Some(Records::Chained {
                             cached: lookup,
                             min_ttl: ttl,
+                            chain: mem::replace(chain, vec![]),
                         });
                     }
                     Err(e) => {
@@ -620,7 +689,8 @@ impl<C: DnsHandle + 'static> Future for QueryState<C> {
                 Some(Records::CnameChain {
                     next: future,
                     min_ttl: ttl,
-                }) => self.cname(future, ttl),
+                    chain,
+                }) => self.cname(future, ttl, chain),
                 Some(records) => {
                     self.cache(records);
                 }
@@ -670,6 +740,8 @@ mod tests {
             ResolveErrorKind::NoRecordsFound {
                 query: Query::new(),
                 valid_until: None,
+                response_code: ResponseCode::NoError,
+                soa: None,
             }
         );
     }
@@ -679,7 +751,12 @@ mod tests {
         let cache = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
         cache.lock().unwrap().insert(
             Query::new(),
-            vec![(RData::A(Ipv4Addr::new(127, 0, 0, 1)), u32::max_value())],
+            vec![Record::from_rdata(
+                Name::root(),
+                u32::max_value(),
+                RecordType::A,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            )],
             Instant::now(),
         );
 
@@ -912,6 +989,92 @@ mod tests {
     //     );
     // }
 
+    fn dname_record() -> Record {
+        Record::from_rdata(
+            Name::from_str("old.example.com.").unwrap(),
+            86400,
+            RecordType::DNAME,
+            RData::DNAME(Name::from_str("example.net.").unwrap()),
+        )
+    }
+
+    fn redirected_message() -> ProtoResult<DnsResponse> {
+        let mut message = Message::new();
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("www.example.net.").unwrap(),
+            86400,
+            RecordType::A,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        )]);
+        Ok(message.into())
+    }
+
+    #[test]
+    fn test_dname_without_cname() {
+        let cache = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
+
+        // the DNAME is followed with a second query for the redirected name
+        let mut message = Message::new();
+        message.insert_answers(vec![dname_record()]);
+        let mut client = mock(vec![error(), redirected_message(), Ok(message.into())]);
+
+        let ips = QueryState::lookup(
+            Query::query(
+                Name::from_str("www.old.example.com.").unwrap(),
+                RecordType::A,
+            ),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        ).wait()
+        .expect("lookup failed");
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_dname_with_cname() {
+        let cache = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
+
+        // the synthesized CNAME and the answer for its target are in the response
+        let mut message = Message::new();
+        message.insert_answers(vec![
+            dname_record(),
+            Record::from_rdata(
+                Name::from_str("www.old.example.com.").unwrap(),
+                86400,
+                RecordType::CNAME,
+                RData::CNAME(Name::from_str("www.example.net.").unwrap()),
+            ),
+            Record::from_rdata(
+                Name::from_str("www.example.net.").unwrap(),
+                86400,
+                RecordType::A,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            ),
+        ]);
+        let mut client = mock(vec![error(), Ok(message.into())]);
+
+        let ips = QueryState::lookup(
+            Query::query(
+                Name::from_str("www.old.example.com.").unwrap(),
+                RecordType::A,
+            ),
+            Default::default(),
+            &mut client,
+            cache.clone(),
+        ).wait()
+        .expect("lookup failed");
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
     fn cname_ttl_test(first: u32, second: u32) {
         let lru = Arc::new(Mutex::new(DnsLru::new(1, dns_lru::TtlConfig::default())));
         // expecting no queries to be performed
@@ -948,7 +1111,13 @@ mod tests {
         assert!(poll.is_ready());
         if let Async::Ready(records) = poll {
             if let Records::Exists(records) = records {
-                assert!(records.iter().all(|&(_, ttl)| ttl == 1));
+                // the records are as answered, the CNAME followed first
+                assert_eq!(records.len(), 2);
+                assert_eq!(records[0].rr_type(), RecordType::CNAME);
+                assert_eq!(records[0].ttl(), first);
+                assert_eq!(*records[1].name(), Name::from_str("actual.example.com.").unwrap());
+                assert_eq!(records[1].ttl(), second);
+                assert_eq!(records.iter().map(Record::ttl).min(), Some(1));
             } else {
                 panic!("records don't exist");
             }
@@ -13,6 +13,7 @@ use std::time::{Duration, Instant};
 
 use futures::future::Loop;
 use futures::{future, task, Async, Future, IntoFuture, Poll};
+use rand;
 use smallvec::SmallVec;
 use tokio_executor::{DefaultExecutor, Executor};
 
@@ -20,6 +21,7 @@ use proto::error::{ProtoError, ProtoResult};
 #[cfg(feature = "mdns")]
 use proto::multicast::{MdnsClientStream, MdnsQueryType, MDNS_IPV4};
 use proto::op::{Edns, NoopMessageFinalizer, ResponseCode};
+use proto::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
 use proto::tcp::TcpClientStream;
 use proto::udp::{UdpResponse, UdpClientStream};
 use proto::xfer::{
@@ -184,6 +186,89 @@ impl PartialOrd for NameServerStats {
     }
 }
 
+/// The DNS cookies of a NameServer, see [RFC 7873](https://tools.ietf.org/html/rfc7873)
+///
+/// The client cookie is random for each name server, its responses return it along with a server
+///  cookie, which is sent in the later requests so that the server can tell them from requests
+///  with a forged source. Responses which do not return the client cookie were not sent by the
+///  server, e.g. they were forged by an attacker which did not see the request.
+#[derive(Debug)]
+struct NameServerCookies {
+    client: [u8; 8],
+    /// The server cookie, empty until the server responds with one
+    server: Mutex<Vec<u8>>,
+}
+
+impl NameServerCookies {
+    fn new() -> Self {
+        NameServerCookies {
+            client: rand::random(),
+            server: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Adds the cookies to the EDNS of the request
+    fn add_to(&self, request: &mut DnsRequest) {
+        let server = self.server.lock().expect("poisoned lock").clone();
+        let cookie = Cookie::new(self.client, server).expect("the server cookie was valid");
+        request.edns_mut().set_option(EdnsOption::Cookie(cookie));
+    }
+
+    /// Checks that the response returned the client cookie, and remembers its server cookie
+    ///
+    /// Once the server has responded with a server cookie, responses without a cookie are
+    ///  rejected too.
+    fn receive(&self, response: &DnsResponse) -> ProtoResult<()> {
+        let mut server = self.server.lock().expect("poisoned lock");
+        match response.edns().and_then(|edns| edns.option(EdnsCode::Cookie)) {
+            Some(&EdnsOption::Cookie(ref cookie)) if *cookie.client() == self.client => {
+                if let Some(cookie) = cookie.server() {
+                    *server = cookie.to_vec();
+                }
+                Ok(())
+            }
+            Some(_) => Err(ProtoError::from("response cookie does not match the request")),
+            None if !server.is_empty() => Err(ProtoError::from("response is missing the cookie")),
+            // the server does not support cookies
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sends the request with the DNS cookies, the request is sent again with the new server cookie
+///  if it was answered with BADCOOKIE, i.e. the server requires a valid server cookie
+fn send_with_cookies<C: DnsHandle>(
+    client: &mut C,
+    mut request: DnsRequest,
+    cookies: Arc<NameServerCookies>,
+) -> Box<Future<Item = DnsResponse, Error = ProtoError> + Send> {
+    cookies.add_to(&mut request);
+    let mut retry = Some((client.clone(), request.clone()));
+
+    Box::new(client.send(request).and_then(
+        move |response| -> Box<Future<Item = DnsResponse, Error = ProtoError> + Send> {
+            if let Err(error) = cookies.receive(&response) {
+                return Box::new(future::err(error));
+            }
+
+            match retry.take() {
+                Some((mut client, mut request))
+                    if response.response_code() == ResponseCode::BADCOOKIE =>
+                {
+                    debug!("retrying with the new server cookie");
+                    cookies.add_to(&mut request);
+                    Box::new(
+                        client
+                            .send(request)
+                            .and_then(move |response| cookies.receive(&response).map(|_| response)),
+                    )
+                }
+                _ => Box::new(future::ok(response)),
+            }
+        },
+    ))
+}
+
 /// A type to allow for custom ConnectionProviders. Needed mainly for mocking purposes.
 pub trait ConnectionProvider: 'static + Clone + Send + Sync {
     type ConnHandle;
@@ -515,6 +600,7 @@ pub struct NameServer<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> {
     client: C,
     // TODO: switch to FuturesMutex? (Mutex will have some undesireable locking)
     stats: Arc<Mutex<NameServerStats>>,
+    cookies: Arc<NameServerCookies>,
     conn_provider: P,
 }
 
@@ -544,6 +630,7 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
             options,
             client,
             stats: Arc::new(Mutex::new(NameServerStats::default())),
+            cookies: Arc::new(NameServerCookies::new()),
             conn_provider,
         }
     }
@@ -560,6 +647,7 @@ impl<C: DnsHandle, P: ConnectionProvider<ConnHandle = C>> NameServer<C, P> {
             options,
             client,
             stats: Arc::new(Mutex::new(NameServerStats::default())),
+            cookies: Arc::new(NameServerCookies::new()),
             conn_provider,
         }
     }
@@ -614,6 +702,19 @@ where
             return Box::new(future::err(error));
         }
 
+        // cookies are options of EDNS, and are not used for multicast
+        let request = request.into();
+        #[cfg(feature = "mdns")]
+        let use_cookies = request.edns().is_some() && self.config.protocol != Protocol::Mdns;
+        #[cfg(not(feature = "mdns"))]
+        let use_cookies = request.edns().is_some();
+
+        let response: Box<Future<Item = DnsResponse, Error = ProtoError> + Send> = if use_cookies {
+            send_with_cookies(&mut self.client, request, self.cookies.clone())
+        } else {
+            Box::new(self.client.send(request))
+        };
+
         let distrust_nx_responses = self.options.distrust_nx_responses;
 
         // Becuase a Poisoned lock error could have occured, make sure to create a new Mutex...
@@ -622,8 +723,7 @@ where
         let mutex1 = self.stats.clone();
         let mutex2 = self.stats.clone();
         Box::new(
-            self.client
-                .send(request)
+            response
                 .and_then(move |response| {
                     // first we'll evaluate if the message succeeded
                     //   see https://github.com/bluejekyll/trust-dns/issues/606
@@ -0,0 +1,525 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An iterative resolver, which follows referrals from the root name servers down to the
+//!  authoritative name servers of a zone.
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Loop};
+use futures::Future;
+use lru_cache::LruCache;
+
+use proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use proto::rr::{Name, RData, Record, RecordType};
+use proto::xfer::{DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse};
+
+use config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use error::*;
+use name_server_pool::{ConnectionHandle, ConnectionProvider, NameServerPool, StandardConnection};
+
+/// Port on which the name servers learned from referrals are queried
+const DNS_PORT: u16 = 53;
+/// Maximum number of referrals followed for a single query
+const MAX_REFERRALS: usize = 16;
+/// Maximum depth of nested resolutions, for CNAME targets and name servers without glue
+const MAX_QUERY_DEPTH: u8 = 8;
+/// Maximum payload requested with EDNS from the name servers
+const MAX_PAYLOAD_LEN: u16 = 1500 - 40 - 8;
+
+type RecordsFuture = Box<Future<Item = Vec<Record>, Error = ResolveError> + Send>;
+type LoopFuture<C, P> =
+    Box<Future<Item = Loop<Vec<Record>, (Delegation<C, P>, usize)>, Error = ResolveError> + Send>;
+type DelegationFuture<C, P> = Box<Future<Item = Delegation<C, P>, Error = ResolveError> + Send>;
+type DelegationCache<C, P> = LruCache<Name, (Delegation<C, P>, Instant)>;
+type AnswerCache = LruCache<Query, (ResolveResult<Vec<Record>>, Instant)>;
+
+/// The name servers to which queries for a zone are sent
+#[derive(Clone)]
+struct Delegation<C: DnsHandle + 'static, P: ConnectionProvider<ConnHandle = C> + 'static> {
+    zone: Name,
+    pool: NameServerPool<C, P>,
+}
+
+/// What a name server responded with
+enum Answer {
+    /// The records for the query, with any CNAMEs that lead to them
+    Records(Vec<Record>),
+    /// The CNAMEs leading out of the zone, with the name at which to continue
+    Cname(Vec<Record>, Name),
+    /// A referral to a zone closer to the name, with the name servers and glue
+    Referral {
+        zone: Name,
+        ttl: u32,
+        name_servers: Vec<Name>,
+        glue: Vec<IpAddr>,
+    },
+    /// The name or records do not exist, with the response code and the SOA of the zone
+    NoRecords(ResponseCode, Option<Record>),
+}
+
+/// A resolver which performs iterative resolution, starting from the root name servers
+///
+/// Unlike the `AsyncResolver`, which asks its configured name servers to recurse on its behalf,
+///  the `Recursor` asks the root name servers, follows the NS referrals and glue it is given and
+///  queries the authoritative name servers itself. Queries are sent without the RD flag set.
+///
+/// Delegations learned from referrals are cached for the TTL of their NS records, as are the
+///  final answers. The name servers are connected to on the default executor, no background
+///  future is needed.
+#[derive(Clone)]
+pub struct Recursor<
+    C: DnsHandle + 'static = ConnectionHandle,
+    P: ConnectionProvider<ConnHandle = C> + 'static = StandardConnection,
+> {
+    roots: Delegation<C, P>,
+    delegations: Arc<Mutex<DelegationCache<C, P>>>,
+    answers: Arc<Mutex<AnswerCache>>,
+    options: ResolverOpts,
+    conn_provider: P,
+}
+
+impl Recursor<ConnectionHandle, StandardConnection> {
+    /// Construct a new `Recursor`
+    ///
+    /// # Arguments
+    ///
+    /// * `roots` - the root name servers, generally from a root hints file
+    /// * `options` - options for the queries, only the timeouts, attempts and cache size are used
+    pub fn new(roots: NameServerConfigGroup, options: ResolverOpts) -> Self {
+        Self::with_provider(roots, options, StandardConnection)
+    }
+}
+
+impl<C, P> Recursor<C, P>
+where
+    C: DnsHandle + 'static,
+    P: ConnectionProvider<ConnHandle = C> + 'static,
+{
+    /// Construct a new `Recursor` with a custom `ConnectionProvider`
+    pub fn with_provider(
+        roots: NameServerConfigGroup,
+        options: ResolverOpts,
+        conn_provider: P,
+    ) -> Self {
+        let roots = Delegation {
+            zone: Name::root(),
+            pool: NameServerPool::from_config_with_provider(
+                &ResolverConfig::from_parts(None, vec![], roots),
+                &options,
+                conn_provider.clone(),
+            ),
+        };
+
+        Recursor {
+            roots,
+            delegations: Arc::new(Mutex::new(LruCache::new(options.cache_size))),
+            answers: Arc::new(Mutex::new(LruCache::new(options.cache_size))),
+            options,
+            conn_provider,
+        }
+    }
+
+    /// Resolve the query, starting from the closest known delegation for the name
+    ///
+    /// The returned records include any CNAMEs followed to reach the final records, in the order
+    ///  of the chain. If the name or records do not exist, `ResolveErrorKind::NoRecordsFound` is
+    ///  returned.
+    pub fn resolve(&self, query: Query) -> RecordsFuture {
+        self.resolve_with_depth(query, 0)
+    }
+
+    fn resolve_with_depth(&self, query: Query, depth: u8) -> RecordsFuture {
+        if depth > MAX_QUERY_DEPTH {
+            return Box::new(future::err(ResolveError::from(
+                "exceeded the maximum query depth",
+            )));
+        }
+
+        if let Some(cached) = self.cached(&query) {
+            return Box::new(future::result(cached));
+        }
+
+        let delegation = self.closest_delegation(query.name());
+        let answers = Arc::clone(&self.answers);
+        let cache_query = query.clone();
+        let recursor = self.clone();
+
+        Box::new(
+            future::loop_fn((delegation, 0), move |(delegation, referrals)| {
+                recursor.query_zone(query.clone(), depth, delegation, referrals)
+            }).then(move |result| {
+                cache_answer(&answers, cache_query, &result);
+                result
+            }),
+        )
+    }
+
+    /// Sends the query to the name servers of the zone, either finishing the resolution or
+    ///  continuing with the name servers of a referral.
+    fn query_zone(
+        &self,
+        query: Query,
+        depth: u8,
+        mut delegation: Delegation<C, P>,
+        referrals: usize,
+    ) -> LoopFuture<C, P> {
+        if referrals > MAX_REFERRALS {
+            return Box::new(future::err(ResolveError::from(
+                "exceeded the maximum number of referrals",
+            )));
+        }
+
+        debug!("querying {} for: {}", delegation.zone, query);
+        let recursor = self.clone();
+        let zone = delegation.zone.clone();
+
+        Box::new(
+            delegation
+                .pool
+                .send(iterative_request(query.clone()))
+                .map_err(ResolveError::from)
+                .and_then(move |response| -> LoopFuture<C, P> {
+                    let answer = match classify(&query, &zone, &response) {
+                        Ok(answer) => answer,
+                        Err(e) => return Box::new(future::err(e)),
+                    };
+
+                    match answer {
+                        Answer::Records(records) => Box::new(future::ok(Loop::Break(records))),
+                        Answer::NoRecords(response_code, soa) => {
+                            Box::new(future::err(no_records(query, response_code, soa)))
+                        }
+                        Answer::Cname(chain, target) => {
+                            // the target may be in any zone, so it is resolved from its closest
+                            //  known delegation
+                            let next = Query::query(target, query.query_type());
+                            Box::new(
+                                recursor
+                                    .resolve_with_depth(next, depth + 1)
+                                    .map(move |records| {
+                                        Loop::Break(chain.into_iter().chain(records).collect())
+                                    }),
+                            )
+                        }
+                        Answer::Referral {
+                            zone,
+                            ttl,
+                            name_servers,
+                            glue,
+                        } => Box::new(
+                            recursor
+                                .delegate(zone, ttl, name_servers, glue, depth)
+                                .map(move |delegation| Loop::Continue((delegation, referrals + 1))),
+                        ),
+                    }
+                }),
+        )
+    }
+
+    /// Builds, and caches, the delegation for a referral
+    ///
+    /// If the referral contained no glue within the zone which referred to it, the addresses of
+    ///  the name servers are resolved first.
+    fn delegate(
+        &self,
+        zone: Name,
+        ttl: u32,
+        name_servers: Vec<Name>,
+        glue: Vec<IpAddr>,
+        depth: u8,
+    ) -> DelegationFuture<C, P> {
+        let addresses: Box<Future<Item = Vec<IpAddr>, Error = ResolveError> + Send> =
+            if glue.is_empty() {
+                Box::new(self.resolve_name_servers(name_servers, depth))
+            } else {
+                Box::new(future::ok(glue))
+            };
+
+        let recursor = self.clone();
+        Box::new(addresses.map(move |addresses| {
+            debug!("delegated {} to: {:?}", zone, addresses);
+            let delegation = Delegation {
+                zone: zone.clone(),
+                pool: NameServerPool::from_config_with_provider(
+                    &ResolverConfig::from_parts(
+                        None,
+                        vec![],
+                        NameServerConfigGroup::from_ips_clear(&addresses, DNS_PORT),
+                    ),
+                    &recursor.options,
+                    recursor.conn_provider.clone(),
+                ),
+            };
+
+            let valid_until = Instant::now() + Duration::from_secs(u64::from(ttl));
+            if let Ok(mut delegations) = recursor.delegations.lock() {
+                delegations.insert(zone, (delegation.clone(), valid_until));
+            }
+
+            delegation
+        }))
+    }
+
+    /// Looks up the addresses of the first name server which resolves
+    fn resolve_name_servers(
+        &self,
+        name_servers: Vec<Name>,
+        depth: u8,
+    ) -> impl Future<Item = Vec<IpAddr>, Error = ResolveError> + Send {
+        let recursor = self.clone();
+        future::loop_fn(name_servers.into_iter(), move |mut name_servers| {
+            let name_server = match name_servers.next() {
+                Some(name_server) => name_server,
+                None => {
+                    return future::Either::A(future::err(ResolveError::from(
+                        "no addresses found for the name servers of the referral",
+                    )))
+                }
+            };
+
+            future::Either::B(
+                recursor
+                    .resolve_with_depth(Query::query(name_server, RecordType::A), depth + 1)
+                    .then(move |result| {
+                        let addresses = result
+                            .unwrap_or_default()
+                            .into_iter()
+                            .filter_map(|record| match *record.rdata() {
+                                RData::A(ip) => Some(IpAddr::V4(ip)),
+                                _ => None,
+                            }).collect::<Vec<_>>();
+
+                        if addresses.is_empty() {
+                            Ok(Loop::Continue(name_servers))
+                        } else {
+                            Ok(Loop::Break(addresses))
+                        }
+                    }),
+            )
+        })
+    }
+
+    /// Returns the cached delegation closest to the name, or the root name servers
+    fn closest_delegation(&self, name: &Name) -> Delegation<C, P> {
+        let now = Instant::now();
+        if let Ok(mut delegations) = self.delegations.lock() {
+            let mut zone = name.clone();
+            while !zone.is_root() {
+                let expired = match delegations.get_mut(&zone) {
+                    Some(&mut (ref delegation, valid_until)) if valid_until > now => {
+                        return delegation.clone()
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+
+                if expired {
+                    delegations.remove(&zone);
+                }
+
+                zone = zone.base_name();
+            }
+        }
+
+        self.roots.clone()
+    }
+
+    fn cached(&self, query: &Query) -> Option<ResolveResult<Vec<Record>>> {
+        let now = Instant::now();
+        let mut answers = self.answers.lock().ok()?;
+
+        let expired = match answers.get_mut(query) {
+            Some(&mut (ref result, valid_until)) if valid_until > now => {
+                return Some(result.clone())
+            }
+            Some(_) => true,
+            None => false,
+        };
+
+        if expired {
+            answers.remove(query);
+        }
+
+        None
+    }
+}
+
+/// Builds the query, without recursion desired
+fn iterative_request(query: Query) -> DnsRequest {
+    let mut message = Message::new();
+    message.add_query(query);
+    message
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(false);
+
+    {
+        let edns = message.edns_mut();
+        edns.set_max_payload(MAX_PAYLOAD_LEN);
+        edns.set_version(0);
+    }
+
+    DnsRequest::new(message, DnsRequestOptions::default())
+}
+
+/// Determines whether the response answers the query, refers to another zone, or is negative
+fn classify(query: &Query, zone: &Name, response: &DnsResponse) -> ResolveResult<Answer> {
+    match response.response_code() {
+        ResponseCode::NoError => (),
+        ResponseCode::NXDomain => {
+            return Ok(Answer::NoRecords(
+                ResponseCode::NXDomain,
+                negative_soa(zone, response),
+            ))
+        }
+        code => {
+            return Err(ResolveErrorKind::Msg(format!(
+                "name servers of {} responded with {}",
+                zone, code
+            )).into())
+        }
+    }
+
+    // the name servers are only trusted for the names of their zone, other records in the
+    //  response, including glue, are ignored and looked up from the zones they belong to
+    let in_zone = |records: &[Record]| -> Vec<Record> {
+        records
+            .iter()
+            .filter(|r| zone.zone_of(r.name()))
+            .cloned()
+            .collect()
+    };
+    let answers = in_zone(response.answers());
+    let additionals = in_zone(response.additionals());
+
+    // follow any CNAMEs and DNAMEs in the answers, these are expected in chained order
+    let query_type = query.query_type();
+    let mut name = query.name().clone();
+    let mut chain = Vec::new();
+    loop {
+        let records = answers
+            .iter()
+            .filter(|r| {
+                r.name() == &name && (query_type.is_any() || r.rr_type() == query_type)
+            }).cloned()
+            .collect::<Vec<_>>();
+
+        if !records.is_empty() {
+            chain.extend(records);
+            return Ok(Answer::Records(chain));
+        }
+
+        // a DNAME redirects the names below its owner, even without a synthesized CNAME
+        let redirect = answers
+            .iter()
+            .filter_map(|r| match *r.rdata() {
+                RData::CNAME(ref target) if r.name() == &name => Some((r, target.clone())),
+                RData::DNAME(ref target) => {
+                    name.replace_domain(r.name(), target).ok().map(|target| (r, target))
+                }
+                _ => None,
+            }).next();
+
+        match redirect {
+            Some((record, target)) if chain.len() < MAX_REFERRALS => {
+                name = target;
+                chain.push(record.clone());
+            }
+            _ => break,
+        }
+    }
+
+    if !chain.is_empty() {
+        return Ok(Answer::Cname(chain, name));
+    }
+
+    // only referrals to zones below the one asked, and containing the name, are followed
+    let referral = response
+        .name_servers()
+        .iter()
+        .filter(|r| r.rr_type() == RecordType::NS)
+        .filter(|r| r.name() != zone && zone.zone_of(r.name()) && r.name().zone_of(&name))
+        .collect::<Vec<_>>();
+
+    if let Some(first) = referral.first() {
+        let name_servers = referral
+            .iter()
+            .filter_map(|r| match *r.rdata() {
+                RData::NS(ref name_server) => Some(name_server.clone()),
+                _ => None,
+            }).collect::<Vec<_>>();
+
+        let glue = additionals
+            .iter()
+            .filter(|r| name_servers.contains(r.name()))
+            .filter_map(|r| match *r.rdata() {
+                RData::A(ip) => Some(IpAddr::V4(ip)),
+                RData::AAAA(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            }).collect::<Vec<_>>();
+
+        return Ok(Answer::Referral {
+            zone: first.name().clone(),
+            ttl: referral.iter().map(|r| r.ttl()).min().unwrap_or(0),
+            name_servers,
+            glue,
+        });
+    }
+
+    Ok(Answer::NoRecords(ResponseCode::NoError, negative_soa(zone, response)))
+}
+
+/// The SOA in the authority section of a negative response, see RFC 2308
+fn negative_soa(zone: &Name, response: &DnsResponse) -> Option<Record> {
+    response
+        .name_servers()
+        .iter()
+        .find(|r| r.rr_type() == RecordType::SOA && zone.zone_of(r.name()))
+        .cloned()
+}
+
+/// The negative caching TTL is the lesser of the TTL of the SOA and its minimum
+fn no_records(query: Query, response_code: ResponseCode, soa: Option<Record>) -> ResolveError {
+    let ttl = soa.as_ref().and_then(|r| match *r.rdata() {
+        RData::SOA(ref soa) => Some(soa.minimum().min(r.ttl())),
+        _ => None,
+    });
+
+    ResolveErrorKind::NoRecordsFound {
+        query,
+        valid_until: ttl.map(|ttl| Instant::now() + Duration::from_secs(u64::from(ttl))),
+        response_code,
+        soa: soa.map(Box::new),
+    }.into()
+}
+
+/// Caches the answer for the minimum TTL of the records, negative answers only when they are
+///  valid for a known time.
+fn cache_answer(
+    answers: &Mutex<AnswerCache>,
+    query: Query,
+    result: &ResolveResult<Vec<Record>>,
+) {
+    let valid_until = match *result {
+        Ok(ref records) => records
+            .iter()
+            .map(|r| r.ttl())
+            .min()
+            .map(|ttl| Instant::now() + Duration::from_secs(u64::from(ttl))),
+        Err(ref e) => match *e.kind() {
+            ResolveErrorKind::NoRecordsFound { valid_until, .. } => valid_until,
+            _ => None,
+        },
+    };
+
+    if let (Some(valid_until), Ok(mut answers)) = (valid_until, answers.lock()) {
+        answers.insert(query, (result.clone(), valid_until));
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Access control lists, which restrict the clients of the operations on the zones of a `Catalog`
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use trust_dns::rr::LowerName;
+
+/// The operations on a zone which are controlled by an `Acl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AclOperation {
+    /// Queries for the records of the zone
+    Query,
+    /// Dynamic updates of the zone
+    Update,
+    /// AXFR and IXFR transfers of the zone
+    Transfer,
+    /// NOTIFYs of changes to the zone
+    Notify,
+}
+
+/// The clients which an entry of an `Acl` applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AclMatch {
+    /// All clients
+    Any,
+    /// Clients with an address in the network, the address and the length of its prefix in bits
+    Network(IpAddr, u8),
+    /// Clients which signed the request with the TSIG key of the name
+    Key(LowerName),
+}
+
+impl AclMatch {
+    /// Returns the network of the prefix of the address, None if the prefix is longer than the
+    ///  address
+    ///
+    /// The bits of the address after the prefix are cleared, 192.0.2.1/24 is 192.0.2.0/24.
+    pub fn network(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        network_of(addr, prefix_len).map(|addr| AclMatch::Network(addr, prefix_len))
+    }
+
+    /// Returns true if a request from the address, verified with the TSIG key of the name, if
+    ///  any, is matched
+    pub fn matches(&self, src: IpAddr, key_name: Option<&LowerName>) -> bool {
+        match *self {
+            AclMatch::Any => true,
+            AclMatch::Network(IpAddr::V4(network), prefix_len) => match unmapped(src) {
+                IpAddr::V4(src) => u32::from(src) & v4_mask(prefix_len) == u32::from(network),
+                IpAddr::V6(_) => false,
+            },
+            AclMatch::Network(IpAddr::V6(network), prefix_len) => match src {
+                IpAddr::V6(src) => u128::from(src) & v6_mask(prefix_len) == u128::from(network),
+                IpAddr::V4(_) => false,
+            },
+            AclMatch::Key(ref name) => key_name == Some(name),
+        }
+    }
+}
+
+/// Returns the address with the bits after the prefix cleared, None if the prefix is longer than
+///  the address
+pub(crate) fn network_of(addr: IpAddr, prefix_len: u8) -> Option<IpAddr> {
+    match addr {
+        IpAddr::V4(addr) if prefix_len <= 32 => Some(IpAddr::V4(Ipv4Addr::from(
+            u32::from(addr) & v4_mask(prefix_len),
+        ))),
+        IpAddr::V6(addr) if prefix_len <= 128 => Some(IpAddr::V6(Ipv6Addr::from(
+            u128::from(addr) & v6_mask(prefix_len),
+        ))),
+        _ => None,
+    }
+}
+
+/// An IPv4 address which was mapped into IPv6, e.g. by a socket listening on both, is treated
+///  as the IPv4 address
+pub(crate) fn unmapped(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, high, low] => IpAddr::V4(Ipv4Addr::from(
+                (u32::from(high) << 16) | u32::from(low),
+            )),
+            _ => addr,
+        },
+        IpAddr::V4(_) => addr,
+    }
+}
+
+fn v4_mask(prefix_len: u8) -> u32 {
+    if prefix_len == 0 {
+        0
+    } else {
+        !0u32 << (32 - u32::from(prefix_len))
+    }
+}
+
+fn v6_mask(prefix_len: u8) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        !0u128 << (128 - u32::from(prefix_len))
+    }
+}
+
+/// An ordered list of the clients which are allowed or denied an operation
+///
+/// The first entry which matches the client decides, a client which matches no entry is denied.
+///  An empty list denies all clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Acl {
+    entries: Vec<(bool, AclMatch)>,
+}
+
+impl Acl {
+    /// Returns an empty list, which denies all clients
+    pub fn new() -> Self {
+        Acl {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Allows the clients which are matched, unless an earlier entry denies them
+    pub fn allow(&mut self, clients: AclMatch) -> &mut Self {
+        self.entries.push((true, clients));
+        self
+    }
+
+    /// Denies the clients which are matched, unless an earlier entry allows them
+    pub fn deny(&mut self, clients: AclMatch) -> &mut Self {
+        self.entries.push((false, clients));
+        self
+    }
+
+    /// Returns true if a request from the address, verified with the TSIG key of the name, if
+    ///  any, is allowed
+    pub fn is_allowed(&self, src: IpAddr, key_name: Option<&LowerName>) -> bool {
+        self.entries
+            .iter()
+            .find(|&&(_, ref clients)| clients.matches(src, key_name))
+            .map_or(false, |&(allow, _)| allow)
+    }
+}
+
+/// The `Acl`s of the operations on a zone, or of all zones, operations without an `Acl` are not
+///  restricted
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Acls {
+    query: Option<Acl>,
+    update: Option<Acl>,
+    transfer: Option<Acl>,
+    notify: Option<Acl>,
+}
+
+impl Acls {
+    /// Returns a set without any `Acl`s
+    pub fn new() -> Self {
+        Acls::default()
+    }
+
+    /// Restricts the operation to the clients allowed by the `Acl`
+    pub fn set(&mut self, operation: AclOperation, acl: Acl) -> &mut Self {
+        *self.acl_mut(operation) = Some(acl);
+        self
+    }
+
+    /// Returns the `Acl` of the operation, None if the operation is not restricted
+    pub fn get(&self, operation: AclOperation) -> Option<&Acl> {
+        match operation {
+            AclOperation::Query => self.query.as_ref(),
+            AclOperation::Update => self.update.as_ref(),
+            AclOperation::Transfer => self.transfer.as_ref(),
+            AclOperation::Notify => self.notify.as_ref(),
+        }
+    }
+
+    /// Returns true if no operation is restricted
+    pub fn is_empty(&self) -> bool {
+        self.query.is_none()
+            && self.update.is_none()
+            && self.transfer.is_none()
+            && self.notify.is_none()
+    }
+
+    fn acl_mut(&mut self, operation: AclOperation) -> &mut Option<Acl> {
+        match operation {
+            AclOperation::Query => &mut self.query,
+            AclOperation::Update => &mut self.update,
+            AclOperation::Transfer => &mut self.transfer,
+            AclOperation::Notify => &mut self.notify,
+        }
+    }
+}
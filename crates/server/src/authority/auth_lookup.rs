@@ -6,6 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::iter::Chain;
+use std::vec;
 
 use trust_dns::rr::Record;
 
@@ -33,6 +34,17 @@ pub enum AuthLookup<'r, 'q> {
     SOA(LookupRecords<'r, 'r>),
     /// An axfr starts with soa, chained to all the records, then another soa...
     AXFR(Chain<Chain<LookupRecords<'r, 'r>, LookupRecords<'r, 'q>>, LookupRecords<'r, 'r>>),
+    /// A portion of an AXFR, for transfers sent as a sequence of messages
+    AXFRChunk(vec::IntoIter<&'r Record>),
+    /// Records answered by the upstream name servers of a forward zone
+    Forward(vec::IntoIter<&'r Record>),
+    /// Records of the wildcard matching the query, these still have the wildcard as their owner
+    Wildcard(vec::IntoIter<&'r Record>),
+    /// The query is for a delegated zone, these are the NS records of the zone cut
+    Referral(LookupRecords<'r, 'q>),
+    /// The query is for a name below a DNAME, these are the DNAME records of its owner, from
+    ///  which the CNAME to the redirected name must be synthesized
+    Dname(LookupRecords<'r, 'q>),
 }
 
 impl<'r, 'q> AuthLookup<'r, 'q> {
@@ -40,7 +52,14 @@ impl<'r, 'q> AuthLookup<'r, 'q> {
     pub fn is_empty(&self) -> bool {
         match *self {
             AuthLookup::NameExists | AuthLookup::NxDomain | AuthLookup::Refused => true,
-            AuthLookup::Records(_) | AuthLookup::SOA(_) | AuthLookup::AXFR(_) => false,
+            AuthLookup::Records(_)
+            | AuthLookup::SOA(_)
+            | AuthLookup::AXFR(_)
+            | AuthLookup::AXFRChunk(_)
+            | AuthLookup::Forward(_)
+            | AuthLookup::Wildcard(_)
+            | AuthLookup::Referral(_)
+            | AuthLookup::Dname(_) => false,
         }
     }
 
@@ -52,6 +71,30 @@ impl<'r, 'q> AuthLookup<'r, 'q> {
         }
     }
 
+    /// These are the records of a wildcard, which must be synthesized for the query
+    pub fn is_wildcard(&self) -> bool {
+        match *self {
+            AuthLookup::Wildcard(_) => true,
+            _ => false,
+        }
+    }
+
+    /// This is a referral to the name servers of a delegated zone
+    pub fn is_referral(&self) -> bool {
+        match *self {
+            AuthLookup::Referral(_) => true,
+            _ => false,
+        }
+    }
+
+    /// These are the DNAME records which redirect the name of the query
+    pub fn is_dname(&self) -> bool {
+        match *self {
+            AuthLookup::Dname(_) => true,
+            _ => false,
+        }
+    }
+
     /// This is a non-existant domain name
     pub fn is_refused(&self) -> bool {
         match *self {
@@ -70,6 +113,11 @@ impl<'r, 'q> Iterator for AuthLookup<'r, 'q> {
             AuthLookup::Records(ref mut i) => i.next(),
             AuthLookup::SOA(ref mut i) => i.next(),
             AuthLookup::AXFR(ref mut i) => i.next(),
+            AuthLookup::AXFRChunk(ref mut i) => i.next(),
+            AuthLookup::Forward(ref mut i) => i.next(),
+            AuthLookup::Wildcard(ref mut i) => i.next(),
+            AuthLookup::Referral(ref mut i) => i.next(),
+            AuthLookup::Dname(ref mut i) => i.next(),
         }
     }
 }
@@ -10,21 +10,148 @@
 #[cfg(feature = "dnssec")]
 use std::borrow::Borrow;
 use std::collections::btree_map::Values;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::vec;
 
+#[cfg(feature = "dnssec")]
+use chrono::{DateTime, Duration, Utc};
 use proto::rr::RrsetRecords;
 #[cfg(feature = "dnssec")]
+use rand::{self, Rng};
+#[cfg(feature = "dnssec")]
 use trust_dns::error::*;
 use trust_dns::op::{LowerQuery, ResponseCode};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TSigner;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::NSEC3PARAM;
 use trust_dns::rr::dnssec::{Signer, SupportedAlgorithms};
 use trust_dns::rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
+use trust_dns::serialize::txt::Writer;
 
 #[cfg(feature = "dnssec")]
-use authority::UpdateRequest;
+use authority::{KeyState, KeyTiming, UpdateRequest};
 use authority::{AuthLookup, Journal, MessageRequest, UpdateResult, ZoneType};
 
 use error::{PersistenceErrorKind, PersistenceResult};
 
+/// The differences between an older version of a zone and the current zone, see
+///  `Authority::changes_since()`
+#[derive(Debug)]
+pub struct ZoneChanges {
+    /// The SOA of the older version of the zone
+    pub old_soa: Record,
+    /// Records in the older version which are not in the current zone, excluding the SOA
+    pub removed: Vec<Record>,
+    /// Records in the current zone which are not in the older version, excluding the SOA
+    pub added: Vec<Record>,
+}
+
+/// Maximum number of journal records replayed for `ZoneHistory::changes_since()`
+const MAX_HISTORY_REPLAY: usize = 100_000;
+
+/// A version of a zone with the journal of its history, see `Authority::history()`
+///
+/// The records are copied from the zone, so the journal may be replayed without the zone locked.
+pub struct ZoneHistory {
+    origin: Name,
+    class: DNSClass,
+    journal: Arc<Journal>,
+    soa: Record,
+    records: Vec<Record>,
+}
+
+impl ZoneHistory {
+    /// The SOA of this version of the zone
+    pub fn soa(&self) -> &Record {
+        &self.soa
+    }
+
+    /// Computes the changes between the version of the zone at `serial` and this version,
+    ///  see [RFC 1995](https://tools.ietf.org/html/rfc1995), IXFR.
+    ///
+    /// The journal is replayed until the SOA with the requested serial closes a version of the
+    ///  zone, that version is then compared to the records of this version. Returns `None` if the
+    ///  serial is not present in the journal, or is not found within `MAX_HISTORY_REPLAY` records
+    ///  of it; in which case a full zone transfer should be used instead.
+    pub fn changes_since(&self, serial: u32) -> PersistenceResult<Option<ZoneChanges>> {
+        let mut zone = Authority::new(
+            self.origin.clone(),
+            BTreeMap::new(),
+            ZoneType::Master,
+            false,
+            false,
+            false,
+        );
+        let mut old_records: Option<Vec<Record>> = None;
+
+        for (replayed, record) in self.journal.iter().enumerate() {
+            if replayed >= MAX_HISTORY_REPLAY {
+                warn!(
+                    "no version of {} at serial: {} within {} journal records",
+                    self.origin, serial, MAX_HISTORY_REPLAY
+                );
+                return Ok(None);
+            }
+
+            if record.rr_type() == RecordType::AXFR {
+                zone.records.clear();
+                continue;
+            }
+
+            let closes_version =
+                record.rr_type() == RecordType::SOA && record.dns_class() == self.class;
+            if let Err(error) = zone.update_records(&[record], false) {
+                return Err(PersistenceErrorKind::Recovery(error.to_str()).into());
+            }
+
+            // the rest of the journal is covered by the current records
+            if closes_version && zone.serial() == serial {
+                old_records = Some(zone.all_records());
+                break;
+            }
+        }
+
+        let old_records = match old_records {
+            Some(old_records) => old_records,
+            None => return Ok(None),
+        };
+
+        let old_soa = match old_records
+            .iter()
+            .find(|r| r.rr_type() == RecordType::SOA)
+            .cloned()
+        {
+            Some(old_soa) => old_soa,
+            None => return Ok(None),
+        };
+
+        let old_set = old_records.iter().collect::<BTreeSet<_>>();
+        let new_set = self.records.iter().collect::<BTreeSet<_>>();
+        let removed = old_set
+            .difference(&new_set)
+            .filter(|r| r.rr_type() != RecordType::SOA)
+            .map(|r| (*r).clone())
+            .collect();
+        let added = new_set
+            .difference(&old_set)
+            .filter(|r| r.rr_type() != RecordType::SOA)
+            .map(|r| (*r).clone())
+            .collect();
+
+        Ok(Some(ZoneChanges {
+            old_soa,
+            removed,
+            added,
+        }))
+    }
+}
+
 /// Authority is responsible for storing the resource records for a particular zone.
 ///
 /// Authorities default to DNSClass IN. The ZoneType specifies if this should be treated as the
@@ -32,11 +159,13 @@ use error::{PersistenceErrorKind, PersistenceResult};
 pub struct Authority {
     origin: LowerName,
     class: DNSClass,
-    journal: Option<Journal>,
+    // shared with the `ZoneHistory`, which is read without the zone locked
+    journal: Option<Arc<Journal>>,
     records: BTreeMap<RrKey, RecordSet>,
     zone_type: ZoneType,
     allow_update: bool,
     allow_axfr: bool,
+    also_notify: Vec<SocketAddr>,
     is_dnssec_enabled: bool,
     // Private key mapped to the Record of the DNSKey
     //  TODO: these private_keys should be stored securely. Ideally, we have keys only stored per
@@ -44,6 +173,18 @@ pub struct Authority {
     //   may not support dynamic updates to register the new key... Trust-DNS will provide support
     //   for this, in some form, perhaps alternate root zones...
     secure_keys: Vec<Signer>,
+    #[cfg(feature = "dnssec")]
+    tsig_keys: Vec<TSigner>,
+    #[cfg(feature = "dnssec")]
+    nsec3: Option<NSEC3PARAM>,
+    #[cfg(feature = "dnssec")]
+    sig_jitter: Duration,
+    // the timing of each of the secure_keys
+    #[cfg(feature = "dnssec")]
+    key_timings: Vec<KeyTiming>,
+    // the states of the secure_keys when the zone was last signed
+    #[cfg(feature = "dnssec")]
+    signed_key_states: Vec<KeyState>,
 }
 
 impl Authority {
@@ -78,8 +219,19 @@ impl Authority {
             zone_type,
             allow_update,
             allow_axfr,
+            also_notify: Vec::new(),
             is_dnssec_enabled,
             secure_keys: Vec::new(),
+            #[cfg(feature = "dnssec")]
+            tsig_keys: Vec::new(),
+            #[cfg(feature = "dnssec")]
+            nsec3: None,
+            #[cfg(feature = "dnssec")]
+            sig_jitter: Duration::zero(),
+            #[cfg(feature = "dnssec")]
+            key_timings: Vec::new(),
+            #[cfg(feature = "dnssec")]
+            signed_key_states: Vec::new(),
         }
     }
 
@@ -90,22 +242,110 @@ impl Authority {
     /// * `signer` - Signer with associated private key
     #[cfg(feature = "dnssec")]
     pub fn add_secure_key(&mut self, signer: Signer) -> DnsSecResult<()> {
+        self.add_secure_key_with_timing(signer, KeyTiming::default())
+    }
+
+    /// Adds a secure key which is published in, and signs, the zone only for part of its lifetime
+    ///
+    /// The DNSKEY of the key is added to or removed from the zone as the key changes state, and
+    ///  the zone is re-signed with the keys which are active, see `roll_keys()`. A DNSKEY with
+    ///  the secure entry point flag is a key signing key, which only signs the DNSKEY record set
+    ///  if there is also an active zone signing key.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - Signer with associated private key
+    /// * `timing` - when the key is published, activated, retired and removed
+    #[cfg(feature = "dnssec")]
+    pub fn add_secure_key_with_timing(
+        &mut self,
+        signer: Signer,
+        timing: KeyTiming,
+    ) -> DnsSecResult<()> {
+        self.secure_keys.push(signer);
+        self.key_timings.push(timing);
+        self.publish_dnskeys(Utc::now())
+    }
+
+    /// Returns the state of each of the secure keys at the time, in the order of `secure_keys()`
+    #[cfg(feature = "dnssec")]
+    pub fn key_states(&self, now: DateTime<Utc>) -> Vec<KeyState> {
+        self.key_timings
+            .iter()
+            .map(|timing| timing.state(now))
+            .collect()
+    }
+
+    /// Returns the next time at which one of the secure keys changes state, if any
+    #[cfg(feature = "dnssec")]
+    pub fn next_key_change(&self) -> Option<DateTime<Utc>> {
+        let now = Utc::now();
+        self.key_timings
+            .iter()
+            .filter_map(|timing| timing.next_change(now))
+            .min()
+    }
+
+    /// Re-signs the zone if any of the secure keys changed state since it was last signed
+    ///
+    /// Returns true if the zone was re-signed.
+    #[cfg(feature = "dnssec")]
+    pub fn roll_keys(&mut self) -> DnsSecResult<bool> {
+        if self.key_states(Utc::now()) == self.signed_key_states {
+            return Ok(false);
+        }
+
+        info!("keys of zone: {} changed state, re-signing", self.origin);
+        self.secure_zone()?;
+        Ok(true)
+    }
+
+    /// Adds the DNSKEYs of the secure keys which are published at the time to the zone, and
+    ///  removes the others
+    #[cfg(feature = "dnssec")]
+    fn publish_dnskeys(&mut self, now: DateTime<Utc>) -> DnsSecResult<()> {
         use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
 
-        // also add the key to the zone
         let zone_ttl = self.minimum_ttl();
-        let dnskey = signer.key().to_dnskey(signer.algorithm())?;
-        let dnskey = Record::from_rdata(
-            self.origin.clone().into(),
-            zone_ttl,
+        let serial = self.serial();
+        let rr_key = RrKey::new(
+            self.origin.clone(),
             RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
-            RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
         );
 
         // TODO: also generate the CDS and CDNSKEY
-        let serial = self.serial();
-        self.upsert(dnskey, serial);
-        self.secure_keys.push(signer);
+        let dnskeys = self
+            .secure_keys
+            .iter()
+            .zip(self.key_states(now))
+            .map(|(signer, state)| {
+                let dnskey = match *signer.key_rdata() {
+                    RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => dnskey.clone(),
+                    _ => signer.key().to_dnskey(signer.algorithm())?,
+                };
+                let dnskey = Record::from_rdata(
+                    self.origin.clone().into(),
+                    zone_ttl,
+                    RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+                    RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+                );
+
+                Ok((dnskey, state))
+            })
+            .collect::<DnsSecResult<Vec<_>>>()?;
+
+        for (dnskey, state) in dnskeys {
+            if state.is_published() {
+                self.upsert(dnskey, serial);
+            } else if let Some(rr_set) = self.records.get_mut(&rr_key) {
+                rr_set.remove(&dnskey, serial);
+            }
+        }
+
+        if self.records.get(&rr_key).map_or(false, |rr_set| rr_set.is_empty()) {
+            self.records.remove(&rr_key);
+        }
+
         Ok(())
     }
 
@@ -148,27 +388,133 @@ impl Authority {
             // TODO: THIS NEEDS TO BE IN A TRANSACTION!!!
             journal.insert_record(serial, Record::new().set_rr_type(RecordType::AXFR))?;
 
+            // the SOA is written last, it marks the end of this version of the zone in the journal
             for rr_set in self.records.values() {
+                if rr_set.record_type() == RecordType::SOA {
+                    continue;
+                }
+
                 // TODO: should we preserve rr_sets or not?
                 for record in rr_set.records_without_rrsigs() {
                     journal.insert_record(serial, record)?;
                 }
             }
 
+            for soa in self.soa() {
+                journal.insert_record(serial, soa)?;
+            }
+
             // TODO: COMMIT THE TRANSACTION!!!
         }
 
         Ok(())
     }
 
+    /// Persists the current state of the zone to the journal, and removes the history before the
+    ///  previously persisted state, does nothing if there is no associated Journal.
+    ///
+    /// The zone is persisted to the journal before the older entries are removed, so a recovery
+    ///  from the journal always yields a complete zone. The history since the previous compaction
+    ///  is kept, i.e. transfers from serials since then may still be incremental, IXFR, transfers
+    ///  from older serials will be full zone transfers.
+    pub fn compact_journal(&self) -> PersistenceResult<()> {
+        if let Some(journal) = self.journal.as_ref() {
+            let previous_row_id = journal.last_axfr_row_id()?;
+            self.persist_to_journal()?;
+
+            if let Some(previous_row_id) = previous_row_id {
+                let count = journal.delete_through(previous_row_id - 1)?;
+                info!("compacted journal, removed {} records", count);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the zone to the master file at `path`, replacing any existing file
+    ///
+    /// See `zone_file_records()` and `write_zone_file()`.
+    pub fn write_zone_file(&self, path: &Path) -> PersistenceResult<()> {
+        info!(
+            "writing zone at SOA.serial: {} to: {:?}",
+            self.serial(),
+            path
+        );
+
+        write_zone_file(&self.origin.clone().into(), &self.zone_file_records(), path)
+    }
+
+    /// The records of the zone as they are written to its master file
+    ///
+    /// The SOA is first and as is, the serial is preserved. RRSIGs are left out, a signed zone is
+    ///  resigned when loaded.
+    pub fn zone_file_records(&self) -> Vec<Record> {
+        let soa = self.soa().cloned();
+        let others = self
+            .records
+            .values()
+            .filter(|rr_set| rr_set.record_type() != RecordType::SOA)
+            .flat_map(|rr_set| rr_set.records_without_rrsigs())
+            .cloned();
+
+        soa.chain(others).collect()
+    }
+
+    /// Computes the changes between the version of the zone at `serial` and the current zone,
+    ///  see [RFC 1995](https://tools.ietf.org/html/rfc1995), IXFR.
+    ///
+    /// See `ZoneHistory::changes_since()`, this reads the journal with the zone borrowed, use
+    ///  `history()` to read it without.
+    pub fn changes_since(&self, serial: u32) -> PersistenceResult<Option<ZoneChanges>> {
+        match self.history() {
+            Some(history) => history.changes_since(serial),
+            None => Ok(None),
+        }
+    }
+
+    /// The current version of the zone along with its journal, from which the changes since older
+    ///  versions are computed
+    ///
+    /// Returns `None` if there is no journal, or the zone is signed; in which case a full zone
+    ///  transfer should be used instead.
+    pub fn history(&self) -> Option<ZoneHistory> {
+        let journal = match self.journal {
+            Some(ref journal) => Arc::clone(journal),
+            None => return None,
+        };
+
+        // RRSIGs are not stored in the journal, and resigning changes most of the zone
+        if !self.secure_keys.is_empty() {
+            return None;
+        }
+
+        let soa = self.soa().next().cloned()?;
+        Some(ZoneHistory {
+            origin: self.origin.clone().into(),
+            class: self.class,
+            journal,
+            soa,
+            records: self.all_records(),
+        })
+    }
+
+    /// All records in the zone, excluding RRSIGs
+    fn all_records(&self) -> Vec<Record> {
+        self.records
+            .values()
+            .flat_map(|rr_set| rr_set.records_without_rrsigs())
+            .cloned()
+            .collect()
+    }
+
     /// Associate a backing Journal with this Authority for Updatable zones
     pub fn set_journal(&mut self, journal: Journal) {
-        self.journal = Some(journal);
+        self.journal = Some(Arc::new(journal));
     }
 
     /// Returns the associated Journal
     pub fn journal(&self) -> Option<&Journal> {
-        self.journal.as_ref()
+        self.journal.as_ref().map(|journal| &**journal)
     }
 
     /// Enables the zone for dynamic DNS updates
@@ -181,11 +527,111 @@ impl Authority {
         self.allow_axfr = allow_axfr;
     }
 
+    /// Returns true if transfers of this zone are allowed
+    pub fn is_axfr_allowed(&self) -> bool {
+        self.allow_axfr
+    }
+
+    /// Returns true if the transfer of this zone is allowed to the request, either transfers are
+    ///  allowed to all, or the request is signed with one of the TSIG keys of the zone
+    pub fn is_transfer_allowed(&self, request: &MessageRequest) -> bool {
+        self.allow_axfr || self.verify_tsig(request)
+    }
+
+    /// Servers to be sent a NOTIFY when this zone changes
+    pub fn set_also_notify(&mut self, also_notify: Vec<SocketAddr>) {
+        self.also_notify = also_notify;
+    }
+
+    /// Returns the servers to be sent a NOTIFY when this zone changes
+    pub fn also_notify(&self) -> &[SocketAddr] {
+        &self.also_notify
+    }
+
     /// Retrieve the Signer, which contains the private keys, for this zone
     pub fn secure_keys(&self) -> &[Signer] {
         &self.secure_keys
     }
 
+    /// Sets the parameters of the NSEC3 records which the zone is signed with, instead of NSEC
+    ///  records, see `secure_zone()`
+    ///
+    /// The names of NSEC3 records are hashed, so that the names of the zone can't be listed by
+    ///  walking the chain. With opt-out, delegations to unsigned zones are left out of the chain.
+    #[cfg(feature = "dnssec")]
+    pub fn set_nsec3(&mut self, nsec3param: Option<NSEC3PARAM>) {
+        self.nsec3 = nsec3param;
+    }
+
+    /// The parameters of the NSEC3 records which the zone is signed with, None for NSEC records
+    #[cfg(feature = "dnssec")]
+    pub fn nsec3(&self) -> Option<&NSEC3PARAM> {
+        self.nsec3.as_ref()
+    }
+
+    /// Sets the maximum time by which the expiration of each RRSIG is brought forward, at random
+    ///
+    /// Signatures created at the same time would otherwise all expire together, and all need to
+    ///  be re-signed together, see `resign_expiring()`.
+    #[cfg(feature = "dnssec")]
+    pub fn set_sig_jitter(&mut self, sig_jitter: Duration) {
+        self.sig_jitter = sig_jitter;
+    }
+
+    /// Allows the updates and transfers of this zone which are signed with the TSIG key, updates
+    ///  must still be enabled, see `set_allow_update()`
+    #[cfg(feature = "dnssec")]
+    pub fn add_tsig_key(&mut self, signer: TSigner) {
+        self.tsig_keys.push(signer);
+    }
+
+    /// The TSIG keys which may sign updates and transfers of this zone
+    #[cfg(feature = "dnssec")]
+    pub fn tsig_keys(&self) -> &[TSigner] {
+        &self.tsig_keys
+    }
+
+    /// Returns true if the request is signed with one of the TSIG keys of this zone, within the
+    ///  fudge of the key
+    #[cfg(feature = "dnssec")]
+    fn verify_tsig(&self, request: &MessageRequest) -> bool {
+        use trust_dns::rr::rdata::DNSSECRecordType;
+
+        let key_name = match request.sig0().last() {
+            Some(record) if record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::TSIG) => {
+                record.name()
+            }
+            _ => return false,
+        };
+
+        let now = Utc::now().timestamp() as u64;
+        self.tsig_keys
+            .iter()
+            .filter(|signer| signer.signer_name() == key_name)
+            .any(|signer| {
+                match signer.verify_message_byte(None, request.as_bytes(), true) {
+                    Ok(ref tsig) if tsig.is_in_time(now) => {
+                        info!("verified tsig of: {} with key: {}", request.id(), key_name);
+                        true
+                    }
+                    Ok(_) => {
+                        debug!("tsig of: {} is outside of the fudge", request.id());
+                        false
+                    }
+                    Err(error) => {
+                        debug!("did not verify tsig of: {}: {}", request.id(), error);
+                        false
+                    }
+                }
+            })
+    }
+
+    /// Always false when DNSSEC is disabled.
+    #[cfg(not(feature = "dnssec"))]
+    fn verify_tsig(&self, _request: &MessageRequest) -> bool {
+        false
+    }
+
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     pub fn origin(&self) -> &LowerName {
         &self.origin
@@ -284,6 +730,21 @@ impl Authority {
         }
     }
 
+    /// Increments the serial of the SOA, and writes the new SOA to the journal, if there is one
+    ///
+    /// The new SOA closes the current version of the zone in the journal, see `changes_since()`.
+    fn increment_and_journal_soa(&mut self) -> PersistenceResult<u32> {
+        let serial = self.increment_soa_serial();
+
+        if let Some(ref journal) = self.journal {
+            for soa in self.soa() {
+                journal.insert_record(serial, soa)?;
+            }
+        }
+
+        Ok(serial)
+    }
+
     /// Get the NS, NameServer, record for the zone
     pub fn ns(&self, is_secure: bool, supported_algorithms: SupportedAlgorithms) -> LookupRecords {
         self.lookup(
@@ -526,7 +987,12 @@ impl Authority {
             return Err(ResponseCode::Refused);
         }
 
-        // verify sig0, currently the only authorization that is accepted.
+        // a TSIG of one of the keys of the zone
+        if self.verify_tsig(update_message) {
+            return Ok(());
+        }
+
+        // verify sig0
         let sig0s: &[Record] = update_message.sig0();
         debug!("authorizing with: {:?}", sig0s);
         if !sig0s.is_empty() && sig0s
@@ -847,7 +1313,10 @@ impl Authority {
             } else {
                 // the secure_zone() function increments the SOA during it's operation, if we're not
                 //  dnssec, then we need to do it here...
-                self.increment_soa_serial();
+                self.increment_and_journal_soa().map_err(|e| {
+                    error!("could not persist updated soa: {}", e);
+                    ResponseCode::ServFail
+                })?;
             }
         }
 
@@ -878,6 +1347,66 @@ impl Authority {
         records.insert(record, serial)
     }
 
+    /// Replaces all records in the zone, e.g. with the result of a zone transfer.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - The complete set of records for the zone, the serial of the zone's SOA
+    ///               in this set will be recorded against all records.
+    pub fn replace_records(&mut self, records: Vec<Record>) {
+        let serial = records
+            .iter()
+            .filter_map(|record| match *record.rdata() {
+                RData::SOA(ref soa) if record.rr_type() == RecordType::SOA => Some(soa.serial()),
+                _ => None,
+            }).next()
+            .unwrap_or(0);
+
+        self.records.clear();
+        for record in records {
+            self.upsert(record, serial);
+        }
+    }
+
+    /// Applies the differences of an incremental zone transfer, e.g. the result of an IXFR.
+    ///
+    /// # Arguments
+    ///
+    /// * `soa` - The SOA of the zone after the changes
+    /// * `changes` - The differences from the current version of the zone to that of the `soa`,
+    ///               in order
+    ///
+    /// # Return value
+    ///
+    /// false, with the zone unchanged, if the changes are not from the current version of the zone
+    pub fn apply_changes(&mut self, soa: Record, changes: Vec<ZoneChanges>) -> bool {
+        let serial = self.serial();
+        let is_current = changes.first().map_or(false, |change| match *change.old_soa.rdata() {
+            RData::SOA(ref old_soa) => old_soa.serial() == serial,
+            _ => false,
+        });
+        if !is_current {
+            return false;
+        }
+
+        let mut records = self
+            .all_records()
+            .into_iter()
+            .filter(|r| r.rr_type() != RecordType::SOA)
+            .collect::<BTreeSet<_>>();
+        for change in changes {
+            for record in change.removed {
+                if !records.remove(&record) {
+                    return false;
+                }
+            }
+            records.extend(change.added);
+        }
+
+        self.replace_records(Some(soa).into_iter().chain(records).collect());
+        true
+    }
+
     /// Takes the UpdateMessage, extracts the Records, and applies the changes to the record set.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -951,6 +1480,44 @@ impl Authority {
         Err(ResponseCode::NotImp)
     }
 
+    /// Returns all the records of the zone for a zone transfer, the first and last record are the
+    ///  SOA
+    ///
+    /// Whether the transfer is allowed is not checked, see `is_transfer_allowed()`.
+    pub fn axfr(&self, is_secure: bool, supported_algorithms: SupportedAlgorithms) -> AuthLookup {
+        self.transfer_lookup(&self.origin, is_secure, supported_algorithms)
+    }
+
+    fn transfer_lookup<'s, 'q>(
+        &'s self,
+        lookup_name: &'q LowerName,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> AuthLookup<'s, 'q> {
+        // verify that this is either the slave or master
+        match self.zone_type() {
+            ZoneType::Master | ZoneType::Slave => (),
+            // TODO: Forward?
+            _ => return AuthLookup::NxDomain, // TODO: this sould be an error.
+        }
+
+        // FIXME: shouldn't these SOA's be secure? at least the first, perhaps not the last?
+        let start_soa = self.soa();
+        let end_soa = self.soa();
+        let records = self.lookup(
+            lookup_name,
+            RecordType::AXFR,
+            is_secure,
+            supported_algorithms,
+        );
+
+        match start_soa {
+            LookupRecords::NxDomain => AuthLookup::NxDomain,
+            LookupRecords::NameExists => AuthLookup::NameExists,
+            start_soa => AuthLookup::AXFR(start_soa.chain(records).chain(end_soa)),
+        }
+    }
+
     /// Using the specified query, perform a lookup against this zone.
     ///
     /// # Arguments
@@ -971,21 +1538,6 @@ impl Authority {
         let lookup_name = query.name();
         let record_type: RecordType = query.query_type();
 
-        // if this is an AXFR zone transfer, verify that this is either the slave or master
-        //  for AXFR the first and last record must be the SOA
-        if RecordType::AXFR == record_type {
-            // TODO: support more advanced AXFR options
-            if !self.allow_axfr {
-                return AuthLookup::Refused;
-            }
-
-            match self.zone_type() {
-                ZoneType::Master | ZoneType::Slave => (),
-                // TODO: Forward?
-                _ => return AuthLookup::NxDomain, // TODO: this sould be an error.
-            }
-        }
-
         // perform the actual lookup
         match record_type {
             RecordType::SOA => {
@@ -999,38 +1551,316 @@ impl Authority {
                 }
             }
             RecordType::AXFR => {
-                // FIXME: shouldn't these SOA's be secure? at least the first, perhaps not the last?
-                let start_soa = self.soa();
-                let end_soa = self.soa();
-                let records =
-                    self.lookup(lookup_name, record_type, is_secure, supported_algorithms);
-
-                match start_soa {
-                    LookupRecords::NxDomain => AuthLookup::NxDomain,
-                    LookupRecords::NameExists => AuthLookup::NameExists,
-                    start_soa => AuthLookup::AXFR(start_soa.chain(records).chain(end_soa)),
+                // TODO: support more advanced AXFR options
+                if !self.allow_axfr {
+                    return AuthLookup::Refused;
                 }
+
+                self.transfer_lookup(lookup_name, is_secure, supported_algorithms)
             }
             _ => {
+                // names at or below a zone cut are answered by the delegated zone's name servers
+                if let Some(name_servers) = self.zone_cut(lookup_name, record_type) {
+                    return AuthLookup::Referral(LookupRecords::from(
+                        name_servers.records(false, supported_algorithms),
+                    ));
+                }
+
+                // names below a DNAME are redirected to the same names below its target
+                if let Some(dname) = self.dname(lookup_name) {
+                    return AuthLookup::Dname(LookupRecords::from(
+                        dname.records(is_secure, supported_algorithms),
+                    ));
+                }
+
+                // a name which does not exist may still be matched by a wildcard
+                if !self.name_exists(lookup_name) {
+                    return self.search_wildcard(
+                        lookup_name,
+                        record_type,
+                        is_secure,
+                        supported_algorithms,
+                    );
+                }
+
                 let lookup = self.lookup(lookup_name, record_type, is_secure, supported_algorithms);
 
                 match lookup {
-                    LookupRecords::NxDomain => AuthLookup::NxDomain,
-                    LookupRecords::NameExists => AuthLookup::NameExists,
+                    // this includes empty non-terminals, which have no records
+                    LookupRecords::NxDomain | LookupRecords::NameExists => {
+                        // a CNAME at the name answers queries of any type, RFC 1034 section 4.3.2
+                        let cname = self.lookup(
+                            lookup_name,
+                            RecordType::CNAME,
+                            is_secure,
+                            supported_algorithms,
+                        );
+
+                        match cname {
+                            LookupRecords::NxDomain | LookupRecords::NameExists => {
+                                AuthLookup::NameExists
+                            }
+                            cname => AuthLookup::Records(cname),
+                        }
+                    }
                     lookup => AuthLookup::Records(lookup),
                 }
             }
         }
     }
 
-    /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
+    /// Looks up the records of the wildcard which matches a name that does not exist in the zone,
+    ///  empty non-terminals exist, and nothing is synthesized below existing names
     ///
-    /// # Arguments
+    /// [RFC 4592](https://tools.ietf.org/html/rfc4592#section-3.3.1), Wildcards in the DNS, July 2006
     ///
-    /// * `name` - The `Name`, label, to lookup.
-    /// * `rtype` - The `RecordType`, to lookup. `RecordType::ANY` will return all records matching
-    ///             `name`. `RecordType::AXFR` will return all record types except `RecordType::SOA`
-    ///             due to the requirements that on zone transfers the `RecordType::SOA` must both
+    /// ```text
+    /// 3.3.1.  Step 2
+    ///
+    ///    Step 2 (of the RFC 1034 algorithm) is to find the closest encloser;
+    ///    if the domain name does not exist, i.e. there is no match, then
+    ///    "QNAME did not exist, look to see if a '*' label exists" at the
+    ///    closest encloser, the source of synthesis.
+    /// ```
+    ///
+    /// The records are returned with the wildcard as their owner, it is up to the caller to rewrite
+    ///  them to the queried name.
+    fn search_wildcard<'s, 'q>(
+        &'s self,
+        name: &LowerName,
+        rtype: RecordType,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> AuthLookup<'s, 'q> {
+        let wildcard = match self.wildcard(name) {
+            Some(wildcard) => wildcard,
+            None => return AuthLookup::NxDomain,
+        };
+
+        let wildcard_records = |rtype: RecordType| -> Vec<&Record> {
+            self.records
+                .range(RrKey::new(wildcard.clone(), RecordType::ZERO)..)
+                .take_while(|&(key, _)| *key.name() == wildcard)
+                .filter(|&(key, _)| rtype == RecordType::ANY || key.record_type == rtype)
+                .flat_map(|(_, rr_set)| rr_set.records(is_secure, supported_algorithms))
+                .collect()
+        };
+
+        let mut records = wildcard_records(rtype);
+
+        // a CNAME at the wildcard is synthesized for queries of any type
+        if records.is_empty() && rtype != RecordType::CNAME {
+            records = wildcard_records(RecordType::CNAME);
+        }
+
+        if records.is_empty() {
+            AuthLookup::NameExists
+        } else {
+            AuthLookup::Wildcard(records.into_iter())
+        }
+    }
+
+    /// Returns the NS records of the zone cut at or above the name, if it is in a delegated zone
+    ///
+    /// [RFC 4035](https://tools.ietf.org/html/rfc4035#section-3.1.4.1), DNSSEC Protocol Modifications, March 2005
+    ///
+    /// ```text
+    /// 3.1.4.1.  Responding to Queries for DS RRs
+    ///
+    ///    The DS resource record type is unusual in that it appears only on the
+    ///    parent zone's side of a zone cut.
+    /// ```
+    ///
+    /// A query for the DS records at the cut is answered by this zone, and is not delegated.
+    fn zone_cut(&self, name: &LowerName, rtype: RecordType) -> Option<&RecordSet> {
+        #[cfg(feature = "dnssec")]
+        fn is_ds(rtype: RecordType) -> bool {
+            use trust_dns::rr::rdata::DNSSECRecordType;
+
+            rtype == RecordType::DNSSEC(DNSSECRecordType::DS)
+        }
+
+        #[cfg(not(feature = "dnssec"))]
+        fn is_ds(_rtype: RecordType) -> bool {
+            false
+        }
+
+        // the cut closest to the apex, data below it belongs to the delegated zone
+        let mut zone_cut = None;
+        let mut ancestor = name.clone();
+        while ancestor != self.origin && self.origin.zone_of(&ancestor) {
+            if ancestor != *name || !is_ds(rtype) {
+                let key = RrKey::new(ancestor.clone(), RecordType::NS);
+                zone_cut = self.records.get(&key).or(zone_cut);
+            }
+
+            ancestor = ancestor.base_name();
+        }
+
+        zone_cut
+    }
+
+    /// Returns the DNAME of an ancestor of the name, which redirects it, if there is one
+    ///
+    /// [RFC 6672](https://tools.ietf.org/html/rfc6672), DNAME Redirection in the DNS, June 2012
+    ///
+    /// The DNAME only redirects the names below its owner, a query for the owner itself is
+    ///  answered from its own records. Like the zone cut, the DNAME closest to the apex is
+    ///  used, there should be no data below a DNAME.
+    fn dname(&self, name: &LowerName) -> Option<&RecordSet> {
+        let mut dname = None;
+        let mut ancestor = name.clone();
+        while ancestor != self.origin && self.origin.zone_of(&ancestor) {
+            ancestor = ancestor.base_name();
+
+            let key = RrKey::new(ancestor.clone(), RecordType::DNAME);
+            dname = self.records.get(&key).or(dname);
+        }
+
+        dname
+    }
+
+    /// Returns the addresses in this zone of the names to which the records refer, i.e. the targets
+    ///  of MX, SRV, NS and CNAME records, for the additional section
+    ///
+    /// [RFC 1034](https://tools.ietf.org/html/rfc1034#section-4.3.2), Domain Concepts and Facilities, November 1987
+    ///
+    /// ```text
+    ///    6. Using local data only, attempt to add other RRs which may be
+    ///       useful to the additional section of the query.  Exit.
+    /// ```
+    ///
+    /// Addresses which are already among the records, e.g. of a CNAME target, are not repeated.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - the answer, or the name servers of a referral, whose glue is returned
+    /// * `is_secure` - if true then it will return RRSIG records as well
+    pub fn additionals<'s>(
+        &'s self,
+        records: &[&Record],
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> Vec<&'s Record> {
+        let mut targets: Vec<LowerName> = records
+            .iter()
+            .filter_map(|record| match *record.rdata() {
+                RData::MX(ref mx) => Some(mx.exchange()),
+                RData::SRV(ref srv) => Some(srv.target()),
+                RData::NS(ref name_server) => Some(name_server),
+                RData::CNAME(ref cname) => Some(cname),
+                _ => None,
+            })
+            .map(LowerName::from)
+            .filter(|target| self.origin.zone_of(target))
+            .collect();
+        targets.sort();
+        targets.dedup();
+
+        targets
+            .into_iter()
+            .flat_map(|target| {
+                vec![
+                    RrKey::new(target.clone(), RecordType::A),
+                    RrKey::new(target, RecordType::AAAA),
+                ]
+            })
+            .filter(|key| {
+                !records.iter().any(|record| {
+                    record.rr_type() == key.record_type
+                        && LowerName::from(record.name()) == key.name
+                })
+            })
+            .filter_map(|key| self.records.get(&key))
+            .flat_map(|rr_set| rr_set.records(is_secure, supported_algorithms))
+            .collect()
+    }
+
+    /// Returns the DS records of a delegation, or the NSEC record proving that there are none, the
+    ///  delegated zone is then unsigned
+    ///
+    /// # Arguments
+    ///
+    /// * `zone_cut` - the name of the delegated zone
+    /// * `is_secure` - if true then it will return RRSIG records as well
+    #[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
+    pub fn delegation_signer<'s, 'q>(
+        &'s self,
+        zone_cut: &LowerName,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> LookupRecords<'s, 'q> {
+        #[cfg(feature = "dnssec")]
+        {
+            use trust_dns::rr::rdata::DNSSECRecordType;
+
+            let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+            if let Some(rr_set) = self.records.get(&RrKey::new(zone_cut.clone(), ds)) {
+                return LookupRecords::from(rr_set.records(is_secure, supported_algorithms));
+            }
+
+            // the NSEC at the zone cut, or with NSEC3 opt-out the closest encloser proof
+            self.get_nsec_proof(zone_cut, ds, is_secure, supported_algorithms)
+        }
+
+        #[cfg(not(feature = "dnssec"))]
+        LookupRecords::NxDomain
+    }
+
+    /// Returns true if there are records at the name, or below it, i.e. it is an empty non-terminal
+    ///
+    /// The hashed owner names of NSEC3 records are not names of the zone.
+    fn name_exists(&self, name: &LowerName) -> bool {
+        // names below the name sort directly after it in canonical order
+        self.records
+            .range(RrKey::new(name.clone(), RecordType::ZERO)..)
+            .find(|&(key, _)| !is_nsec3(key.record_type))
+            .map_or(false, |(key, _)| name.zone_of(key.name()))
+    }
+
+    /// Returns the closest encloser of a name which does not exist, its nearest existing ancestor
+    fn closest_encloser(&self, name: &LowerName) -> Option<LowerName> {
+        let mut encloser = name.base_name();
+
+        loop {
+            if !self.origin.zone_of(&encloser) {
+                return None;
+            }
+
+            if self.name_exists(&encloser) {
+                return Some(encloser);
+            }
+
+            if encloser == self.origin {
+                return None;
+            }
+
+            encloser = encloser.base_name();
+        }
+    }
+
+    /// Returns the wildcard at the closest encloser of a name which does not exist, the source of
+    ///  synthesis
+    ///
+    /// None if there is no such wildcard.
+    fn wildcard(&self, name: &LowerName) -> Option<LowerName> {
+        let wildcard = wildcard_at(self.closest_encloser(name)?);
+
+        if self.name_exists(&wildcard) {
+            Some(wildcard)
+        } else {
+            None
+        }
+    }
+
+    /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The `Name`, label, to lookup.
+    /// * `rtype` - The `RecordType`, to lookup. `RecordType::ANY` will return all records matching
+    ///             `name`. `RecordType::AXFR` will return all record types except `RecordType::SOA`
+    ///             due to the requirements that on zone transfers the `RecordType::SOA` must both
     ///             preceed and follow all other records.
     /// * `is_secure` - If the DO bit is set on the EDNS OPT record, then return RRSIGs as well.
     ///
@@ -1085,15 +1915,285 @@ impl Authority {
     ///
     /// # Arguments
     ///
-    /// * `name` - given this name (i.e. the lookup name), return the NSEC record that is less than
-    ///            this
+    /// * `name` - given this name (i.e. the lookup name), return the NSEC record which matches or
+    ///            covers it, i.e. the last one with an owner less than or equal to the name
     /// * `is_secure` - if true then it will return RRSIG records as well
     pub fn get_nsec_records<'s, 'q>(
         &'s self,
         name: &'q LowerName,
         is_secure: bool,
         supported_algorithms: SupportedAlgorithms,
+    ) -> LookupRecords<'s, 'q> {
+        self.nsec_covering(name)
+            .map_or(LookupRecords::NxDomain, |rr_set| {
+                LookupRecords::from(rr_set.records(is_secure, supported_algorithms))
+            })
+    }
+
+    /// Return the NSEC records which prove a negative, or wildcard, answer to a query
+    ///
+    /// [RFC 4035](https://tools.ietf.org/html/rfc4035#section-3.1.3), DNSSEC Protocol Modifications, March 2005
+    ///
+    /// ```text
+    /// 3.1.3.  Including NSEC RRs in a Response
+    ///
+    ///    When responding to a query that has the DO bit set, a security-aware
+    ///    authoritative name server for a signed zone MUST include NSEC RRs in
+    ///    each of the following cases:
+    ///
+    ///    No Data: The zone contains RRsets that exactly match <SNAME, SCLASS>
+    ///       but does not contain any RRsets that exactly match <SNAME, SCLASS,
+    ///       STYPE>.
+    ///
+    ///    Name Error: The zone does not contain any RRsets that match <SNAME,
+    ///       SCLASS> either exactly or via wildcard name expansion.
+    ///
+    ///    Wildcard Answer: The zone does not contain any RRsets that exactly
+    ///       match <SNAME, SCLASS> but does contain an RRset that matches
+    ///       <SNAME, SCLASS, STYPE> via wildcard name expansion.
+    ///
+    ///    Wildcard No Data: The zone does not contain any RRsets that exactly
+    ///       match <SNAME, SCLASS> and does contain one or more RRsets that
+    ///       match <SNAME, SCLASS> via wildcard name expansion, but does not
+    ///       contain any RRsets that match <SNAME, SCLASS, STYPE> via wildcard
+    ///       name expansion.
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the query
+    /// * `rtype` - the type of the query, this determines if a wildcard answers it
+    /// * `is_secure` - if true then it will return RRSIG records as well
+    pub fn get_nsec_proof<'s, 'q>(
+        &'s self,
+        name: &LowerName,
+        rtype: RecordType,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
     ) -> LookupRecords<'s, 'q> {
+        #[cfg(feature = "dnssec")]
+        {
+            if let Some(nsec3param) = self.nsec3param() {
+                return self.get_nsec3_proof(
+                    nsec3param,
+                    name,
+                    rtype,
+                    is_secure,
+                    supported_algorithms,
+                );
+            }
+        }
+
+        let mut nsecs: Vec<&RecordSet> = Vec::with_capacity(2);
+
+        // No Data, the NSEC at the name, or covering the empty non-terminal
+        // otherwise an NSEC covering the name proves that it does not exist
+        nsecs.extend(self.nsec_covering(name));
+
+        if !self.name_exists(name) {
+            if let Some(wildcard) = self.wildcard(name) {
+                // Wildcard No Data, the NSEC at the wildcard proves the type does not exist
+                if !self.is_wildcard_answer(&wildcard, rtype) {
+                    nsecs.extend(self.nsec_covering(&wildcard));
+                }
+            } else if let Some(encloser) = self.closest_encloser(name) {
+                // Name Error, an NSEC must also prove there is no wildcard at the closest encloser
+                nsecs.extend(self.nsec_covering(&wildcard_at(encloser)));
+            }
+        }
+
+        // the same NSEC may cover both the name and the wildcard
+        nsecs.dedup_by(|a, b| a.name() == b.name());
+
+        let records: Vec<&Record> = nsecs
+            .into_iter()
+            .flat_map(|rr_set| rr_set.records(is_secure, supported_algorithms))
+            .collect();
+
+        if records.is_empty() {
+            LookupRecords::NxDomain
+        } else {
+            LookupRecords::ManyRecords(records.into_iter())
+        }
+    }
+
+    /// Return the NSEC3 records which prove a negative, or wildcard, answer to a query, in a zone
+    ///  signed with NSEC3
+    ///
+    /// [RFC 5155](https://tools.ietf.org/html/rfc5155#section-7.2), NSEC3, March 2008
+    ///
+    /// The cases are those of `get_nsec_proof()`, but a name which does not exist is proven by the
+    ///  closest encloser proof, the NSEC3 which matches the closest encloser of the name and the
+    ///  NSEC3 which covers the next closer name. The same proof is given for a name without an
+    ///  NSEC3 of its own, i.e. a delegation to an unsigned zone which was opted out of the chain.
+    #[cfg(feature = "dnssec")]
+    fn get_nsec3_proof<'s, 'q>(
+        &'s self,
+        nsec3param: &NSEC3PARAM,
+        name: &LowerName,
+        rtype: RecordType,
+        is_secure: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> LookupRecords<'s, 'q> {
+        let mut nsec3s: Vec<&RecordSet> = Vec::with_capacity(3);
+
+        if let Some(nsec3) = self.nsec3_matching(nsec3param, name) {
+            // No Data, the NSEC3 matching the name proves the type does not exist
+            nsec3s.push(nsec3);
+        } else if let Some((encloser, matching, next_closer)) =
+            self.nsec3_closest_encloser(nsec3param, name)
+        {
+            let wildcard = if self.name_exists(name) {
+                None
+            } else {
+                self.wildcard(name)
+            };
+
+            match wildcard {
+                // Wildcard Answer, the next closer name does not exist, so the wildcard answered
+                Some(ref wildcard) if self.is_wildcard_answer(wildcard, rtype) => {
+                    nsec3s.push(next_closer)
+                }
+                // Wildcard No Data, the NSEC3 matching the wildcard proves the type does not exist
+                Some(ref wildcard) => {
+                    nsec3s.push(matching);
+                    nsec3s.push(next_closer);
+                    nsec3s.extend(self.nsec3_matching(nsec3param, wildcard));
+                }
+                None => {
+                    nsec3s.push(matching);
+                    nsec3s.push(next_closer);
+
+                    // Name Error, there is also no wildcard at the closest encloser
+                    if !self.name_exists(name) {
+                        nsec3s.extend(self.nsec3_covering(nsec3param, &wildcard_at(encloser)));
+                    }
+                }
+            }
+        }
+
+        // the same NSEC3 may match, or cover, more than one of the names
+        nsec3s.sort_by(|a, b| a.name().cmp(b.name()));
+        nsec3s.dedup_by(|a, b| a.name() == b.name());
+
+        let records: Vec<&Record> = nsec3s
+            .into_iter()
+            .flat_map(|rr_set| rr_set.records(is_secure, supported_algorithms))
+            .collect();
+
+        if records.is_empty() {
+            LookupRecords::NxDomain
+        } else {
+            LookupRecords::ManyRecords(records.into_iter())
+        }
+    }
+
+    /// Returns true if the wildcard has records which answer a query of the type, otherwise it is
+    ///  a Wildcard No Data
+    fn is_wildcard_answer(&self, wildcard: &LowerName, rtype: RecordType) -> bool {
+        rtype == RecordType::ANY
+            || self
+                .records
+                .contains_key(&RrKey::new(wildcard.clone(), rtype))
+            || self
+                .records
+                .contains_key(&RrKey::new(wildcard.clone(), RecordType::CNAME))
+    }
+
+    /// Returns the NSEC3PARAM at the apex, if the zone is signed with NSEC3
+    ///
+    /// The records are used rather than `nsec3()`, a slave zone is signed by its master.
+    #[cfg(feature = "dnssec")]
+    fn nsec3param(&self) -> Option<&NSEC3PARAM> {
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
+
+        let key = RrKey::new(
+            self.origin.clone(),
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+        );
+
+        self.records
+            .get(&key)?
+            .records_without_rrsigs()
+            .filter_map(|record| match *record.rdata() {
+                RData::DNSSEC(DNSSECRData::NSEC3PARAM(ref nsec3param)) => Some(nsec3param),
+                _ => None,
+            }).next()
+    }
+
+    /// Returns the hashed owner name of the NSEC3 record of the name
+    #[cfg(feature = "dnssec")]
+    fn nsec3_owner(&self, nsec3param: &NSEC3PARAM, name: &LowerName) -> Option<LowerName> {
+        use trust_dns::rr::dnssec::Nsec3HashAlgorithm;
+
+        let zone: Name = self.origin.clone().into();
+        nsec3param
+            .hash_algorithm()
+            .hash(nsec3param.salt(), &name.clone().into(), nsec3param.iterations())
+            .and_then(|hash| Nsec3HashAlgorithm::hashed_owner_name(hash.as_ref(), &zone))
+            .map(LowerName::from)
+            .map_err(|e| warn!("could not hash name for nsec3: {}: {}", name, e))
+            .ok()
+    }
+
+    /// Returns the NSEC3 record set whose owner is the hash of the name
+    #[cfg(feature = "dnssec")]
+    fn nsec3_matching(&self, nsec3param: &NSEC3PARAM, name: &LowerName) -> Option<&RecordSet> {
+        use trust_dns::rr::rdata::DNSSECRecordType;
+
+        let owner = self.nsec3_owner(nsec3param, name)?;
+        self.records
+            .get(&RrKey::new(owner, RecordType::DNSSEC(DNSSECRecordType::NSEC3)))
+    }
+
+    /// Returns the NSEC3 record set which matches the hash of the name, or the one which covers it
+    #[cfg(feature = "dnssec")]
+    fn nsec3_covering(&self, nsec3param: &NSEC3PARAM, name: &LowerName) -> Option<&RecordSet> {
+        let owner = self.nsec3_owner(nsec3param, name)?;
+        let nsec3s = || {
+            self.records
+                .values()
+                .filter(|rr_set| is_nsec3(rr_set.record_type()))
+        };
+
+        // the NSEC3 records are in the order of the hashes, the last one in the chain wraps around
+        //  and covers the hashes before the first
+        nsec3s()
+            .take_while(|rr_set| LowerName::from(rr_set.name()) <= owner)
+            .last()
+            .or_else(|| nsec3s().next_back())
+    }
+
+    /// Returns the closest provable encloser of a name without an NSEC3, along with the NSEC3 which
+    ///  matches it and the NSEC3 which covers the next closer name
+    ///
+    /// [RFC 5155](https://tools.ietf.org/html/rfc5155#section-7.2.1), NSEC3, March 2008
+    ///
+    /// The closest provable encloser is the nearest ancestor of the name which has an NSEC3, the
+    ///  next closer name is the ancestor one label below it, possibly the name itself.
+    #[cfg(feature = "dnssec")]
+    fn nsec3_closest_encloser(
+        &self,
+        nsec3param: &NSEC3PARAM,
+        name: &LowerName,
+    ) -> Option<(LowerName, &RecordSet, &RecordSet)> {
+        let mut next_closer = name.clone();
+        while next_closer != self.origin && self.origin.zone_of(&next_closer) {
+            let encloser = next_closer.base_name();
+
+            if let Some(matching) = self.nsec3_matching(nsec3param, &encloser) {
+                let covering = self.nsec3_covering(nsec3param, &next_closer)?;
+                return Some((encloser, matching, covering));
+            }
+
+            next_closer = encloser;
+        }
+
+        None
+    }
+
+    /// Returns the NSEC record set which matches the name, or the one which covers it
+    fn nsec_covering(&self, name: &LowerName) -> Option<&RecordSet> {
         #[cfg(feature = "dnssec")]
         fn is_nsec_rrset(rr_set: &RecordSet) -> bool {
             use trust_dns::rr::rdata::DNSSECRecordType;
@@ -1108,29 +2208,36 @@ impl Authority {
             false
         }
 
+        // the last NSEC at or before the name in canonical order, the NSEC chain wraps around to
+        //  the apex, so the last NSEC in the zone covers every name after it
         self.records
             .values()
             .filter(|rr_set| is_nsec_rrset(rr_set))
-            .skip_while(|rr_set| *name < rr_set.name().into())
-            .next()
-            .map_or(LookupRecords::NxDomain, |rr_set| {
-                LookupRecords::from(rr_set.records(is_secure, supported_algorithms))
-            })
+            .take_while(|rr_set| LowerName::from(rr_set.name()) <= *name)
+            .last()
     }
 
     /// (Re)generates the nsec records, increments the serial number nad signs the zone
     #[cfg(feature = "dnssec")]
     pub fn secure_zone(&mut self) -> DnsSecResult<()> {
+        let now = Utc::now();
+        self.publish_dnskeys(now)?;
+
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sur IXFR works properly
-        self.nsec_zone();
+        match self.nsec3.clone() {
+            Some(nsec3param) => self.nsec3_zone(&nsec3param)?,
+            None => self.nsec_zone(),
+        }
 
         // need to resign any records at the current serial number and bump the number.
         // first bump the serial number on the SOA, so that it is resigned with the new serial.
-        self.increment_soa_serial();
+        self
+            .increment_and_journal_soa()
+            .map_err(|e| format!("could not persist the secured soa: {}", e))?;
 
         // TODO: should we auto sign here? or maybe up a level...
-        self.sign_zone()
+        self.sign_zone(now)
     }
 
     /// (Re)generates the nsec records, increments the serial number nad signs the zone
@@ -1151,16 +2258,7 @@ impl Authority {
         debug!("generating nsec records: {}", self.origin);
 
         // first remove all existing nsec records
-        let delete_keys: Vec<RrKey> = self
-            .records
-            .keys()
-            .filter(|k| k.record_type == RecordType::DNSSEC(DNSSECRecordType::NSEC))
-            .cloned()
-            .collect();
-
-        for key in delete_keys {
-            self.records.remove(&key);
-        }
+        self.remove_denial_records();
 
         // now go through and generate the nsec records
         let ttl = self.minimum_ttl();
@@ -1212,108 +2310,447 @@ impl Authority {
         }
     }
 
-    /// Signs any records in the zone that have serial numbers greater than or equal to `serial`
+    /// (Re)generates the NSEC3 records of the zone, and the NSEC3PARAM at the apex
+    ///
+    /// [RFC 5155](https://tools.ietf.org/html/rfc5155#section-7.1), NSEC3, March 2008
+    ///
+    /// Every name of the zone has an NSEC3 record, including empty non-terminals, but not the glue
+    ///  below zone cuts. With opt-out, delegations without DS records are left out of the chain,
+    ///  along with the empty non-terminals which are only above them.
     #[cfg(feature = "dnssec")]
-    fn sign_zone(&mut self) -> DnsSecResult<()> {
-        use chrono::Utc;
-        use trust_dns::rr::dnssec::tbs;
-        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, SIG};
+    fn nsec3_zone(&mut self, nsec3param: &NSEC3PARAM) -> DnsSecResult<()> {
+        use trust_dns::rr::dnssec::Nsec3HashAlgorithm;
+        use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, NSEC3};
+
+        // only create nsec3 records for secure zones
+        if self.secure_keys.is_empty() {
+            return Ok(());
+        }
+        debug!("generating nsec3 records: {}", self.origin);
+
+        // first remove all existing nsec and nsec3 records
+        self.remove_denial_records();
+
+        let ttl = self.minimum_ttl();
+        let serial = self.serial();
+        let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+
+        // the NSEC3PARAM tells the servers of the zone how the names are hashed, it has no flags
+        let mut record = Record::with(
+            self.origin.clone().into(),
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+            ttl,
+        );
+        record.set_rdata(RData::DNSSEC(DNSSECRData::NSEC3PARAM(NSEC3PARAM::new(
+            nsec3param.hash_algorithm(),
+            false,
+            nsec3param.iterations(),
+            nsec3param.salt().to_vec(),
+        ))));
+        self.upsert(record, serial);
+
+        // the types at each authoritative name of the zone, and if any of them are signed
+        let mut names: BTreeMap<LowerName, (Vec<RecordType>, bool)> = BTreeMap::new();
+        for key in self.records.keys() {
+            if self.zone_cut(&key.name, ds).is_none() {
+                let entry = names
+                    .entry(key.name.clone())
+                    .or_insert_with(|| (Vec::new(), false));
+                entry.0.push(key.record_type);
+                entry.1 |= self.is_signed(key);
+            }
+        }
+
+        // the names of the chain, with the empty non-terminals above them
+        let mut chain: BTreeMap<LowerName, Vec<RecordType>> = BTreeMap::new();
+        for (name, (mut types, is_signed)) in names {
+            let is_opted_out = nsec3param.opt_out()
+                && name != self.origin
+                && types.contains(&RecordType::NS)
+                && !types.contains(&ds);
+            if is_opted_out {
+                continue;
+            }
+
+            let mut ancestor = name.base_name();
+            while ancestor != self.origin && self.origin.zone_of(&ancestor) {
+                chain.entry(ancestor.clone()).or_insert_with(Vec::new);
+                ancestor = ancestor.base_name();
+            }
+
+            // the NS records of an insecure delegation are not signed, it has no RRSIGs
+            if is_signed {
+                types.push(RecordType::DNSSEC(DNSSECRecordType::RRSIG));
+            }
+            chain.insert(name, types);
+        }
+
+        let mut hashes: Vec<(Vec<u8>, Vec<RecordType>)> = Vec::with_capacity(chain.len());
+        for (name, types) in chain {
+            let hash = nsec3param.hash_algorithm().hash(
+                nsec3param.salt(),
+                &name.into(),
+                nsec3param.iterations(),
+            )?;
+            hashes.push((hash.as_ref().to_vec(), types));
+        }
+        hashes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // each NSEC3 refers to the next hash, and the last to the first
+        let zone: Name = self.origin.clone().into();
+        let mut records: Vec<Record> = Vec::with_capacity(hashes.len());
+        for (i, &(ref hash, ref types)) in hashes.iter().enumerate() {
+            let next_hash = &hashes[(i + 1) % hashes.len()].0;
+
+            let mut record = Record::with(
+                Nsec3HashAlgorithm::hashed_owner_name(hash, &zone)?,
+                RecordType::DNSSEC(DNSSECRecordType::NSEC3),
+                ttl,
+            );
+            record.set_rdata(RData::DNSSEC(DNSSECRData::NSEC3(NSEC3::new(
+                nsec3param.hash_algorithm(),
+                nsec3param.opt_out(),
+                nsec3param.iterations(),
+                nsec3param.salt().to_vec(),
+                next_hash.clone(),
+                types.clone(),
+            ))));
+            records.push(record);
+        }
+
+        // insert all the nsec3 records
+        for record in records {
+            self.upsert(record, serial);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the record set is authoritative data of the zone, which is signed
+    ///
+    /// The NS records of a delegation and the glue below it belong to the delegated zone.
+    #[cfg(feature = "dnssec")]
+    fn is_signed(&self, key: &RrKey) -> bool {
+        use trust_dns::rr::rdata::DNSSECRecordType;
+
+        let ds = RecordType::DNSSEC(DNSSECRecordType::DS);
+        (key.record_type != RecordType::NS || key.name == self.origin)
+            && self.zone_cut(&key.name, ds).is_none()
+    }
+
+    /// Removes the NSEC, NSEC3 and NSEC3PARAM records, before they are regenerated
+    #[cfg(feature = "dnssec")]
+    fn remove_denial_records(&mut self) {
+        use trust_dns::rr::rdata::DNSSECRecordType;
+
+        let delete_keys: Vec<RrKey> = self
+            .records
+            .keys()
+            .filter(|k| match k.record_type {
+                RecordType::DNSSEC(DNSSECRecordType::NSEC)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+                | RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM) => true,
+                _ => false,
+            }).cloned()
+            .collect();
 
+        for key in delete_keys {
+            self.records.remove(&key);
+        }
+    }
+
+    /// Signs all the record sets in the zone with the keys of the zone which are active
+    #[cfg(feature = "dnssec")]
+    fn sign_zone(&mut self, inception: DateTime<Utc>) -> DnsSecResult<()> {
         debug!("signing zone: {}", self.origin);
-        let inception = Utc::now();
         let zone_ttl = self.minimum_ttl();
+        let key_states = self.key_states(inception);
 
         // TODO: should this be an error?
-        if self.secure_keys.is_empty() {
+        if !key_states.iter().any(|state| state.is_active()) {
             warn!("attempt to sign_zone for dnssec, but no keys available!")
         }
 
-        // sign all record_sets, as of 0.12.1 this includes DNSKEY
-        for rr_set in self.records.values_mut() {
-            rr_set.clear_rrsigs();
-            let rrsig_temp = Record::with(
-                rr_set.name().clone(),
-                RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+        // the NS records of delegations and the glue below them are not authoritative data of
+        //  the zone, which is not signed
+        let unsigned: Vec<RrKey> = self
+            .records
+            .keys()
+            .filter(|key| !self.is_signed(key))
+            .cloned()
+            .collect();
+
+        // sign all other record_sets, as of 0.12.1 this includes DNSKEY
+        for (key, rr_set) in self.records.iter_mut() {
+            if unsigned.contains(key) {
+                rr_set.clear_rrsigs();
+                continue;
+            }
+
+            sign_rrset(
+                rr_set,
+                &rrset_signers(&self.secure_keys, &key_states, rr_set.record_type()),
+                self.class,
                 zone_ttl,
-            );
+                inception,
+                self.sig_jitter,
+            )?;
+        }
 
-            for signer in &self.secure_keys {
-                debug!(
-                    "signing rr_set: {}, {} with: {}",
-                    rr_set.name(),
-                    rr_set.record_type(),
-                    signer.algorithm(),
-                );
+        self.signed_key_states = key_states;
+        Ok(())
+    }
+
+    /// Returns the earliest expiration of the RRSIGs in the zone, in seconds since the epoch, None
+    ///  if the zone is not signed
+    #[cfg(feature = "dnssec")]
+    pub fn next_sig_expiration(&self) -> Option<u32> {
+        self.records
+            .values()
+            .filter_map(rrsig_expiration)
+            .min()
+    }
 
-                let expiration = inception + signer.sig_duration();
+    /// Re-signs the record sets whose RRSIGs expire within `refresh`, and increments the serial
+    ///  of the zone if any were re-signed
+    ///
+    /// Returns the number of record sets which were re-signed. Nothing is re-signed if the zone
+    ///  has no keys, e.g. a slave zone which is signed by its master.
+    ///
+    /// # Arguments
+    ///
+    /// * `refresh` - the time before the expiration of an RRSIG at which it is replaced
+    #[cfg(feature = "dnssec")]
+    pub fn resign_expiring(&mut self, refresh: Duration) -> DnsSecResult<usize> {
+        let inception = Utc::now();
+        let key_states = self.key_states(inception);
+        if !key_states.iter().any(|state| state.is_active()) {
+            return Ok(0);
+        }
 
-                let tbs = tbs::rrset_tbs(
-                    rr_set.name(),
-                    self.class,
-                    rr_set.name().num_labels(),
-                    rr_set.record_type(),
-                    signer.algorithm(),
-                    rr_set.ttl(),
-                    expiration.timestamp() as u32,
-                    inception.timestamp() as u32,
-                    signer.calculate_key_tag()?,
-                    signer.signer_name(),
-                    // TODO: this is a nasty clone... the issue is that the vec
-                    //  from records is of Vec<&R>, but we really want &[R]
-                    &rr_set
-                        .records_without_rrsigs()
-                        .cloned()
-                        .collect::<Vec<Record>>(),
-                );
+        let due = (inception + refresh).timestamp() as u32;
+        let mut expiring: Vec<RrKey> = self
+            .records
+            .iter()
+            .filter(|&(_, rr_set)| rrsig_expiration(rr_set).map_or(false, |e| e <= due))
+            .map(|(key, _)| key.clone())
+            .collect();
 
-                // TODO, maybe chain these with some ETL operations instead?
-                let tbs = match tbs {
-                    Ok(tbs) => tbs,
-                    Err(err) => {
-                        error!("could not serialize rrset to sign: {}", err);
-                        continue;
-                    }
-                };
+        if expiring.is_empty() {
+            return Ok(0);
+        }
 
-                let signature = signer.sign(&tbs);
-                let signature = match signature {
-                    Ok(signature) => signature,
-                    Err(err) => {
-                        error!("could not sign rrset: {}", err);
-                        continue;
-                    }
-                };
+        // the changed SOA must be re-signed as well, whether or not it was expiring
+        let serial = self
+            .increment_and_journal_soa()
+            .map_err(|e| format!("could not persist the re-signed soa: {}", e))?;
+        let soa_key = RrKey::new(self.origin.clone(), RecordType::SOA);
+        if !expiring.contains(&soa_key) {
+            expiring.push(soa_key);
+        }
+
+        info!(
+            "re-signing {} record sets of zone: {} at serial: {}",
+            expiring.len(),
+            self.origin,
+            serial
+        );
 
-                let mut rrsig = rrsig_temp.clone();
-                rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
-                    // type_covered: RecordType,
-                    rr_set.record_type(),
-                    // algorithm: Algorithm,
-                    signer.algorithm(),
-                    // num_labels: u8,
-                    rr_set.name().num_labels(),
-                    // original_ttl: u32,
-                    rr_set.ttl(),
-                    // sig_expiration: u32,
-                    expiration.timestamp() as u32,
-                    // sig_inception: u32,
-                    inception.timestamp() as u32,
-                    // key_tag: u16,
-                    signer.calculate_key_tag()?,
-                    // signer_name: Name,
-                    signer.signer_name().clone(),
-                    // sig: Vec<u8>
-                    signature,
-                ))));
-
-                rr_set.insert_rrsig(rrsig);
+        let zone_ttl = self.minimum_ttl();
+        for key in &expiring {
+            if let Some(rr_set) = self.records.get_mut(key) {
+                sign_rrset(
+                    rr_set,
+                    &rrset_signers(&self.secure_keys, &key_states, rr_set.record_type()),
+                    self.class,
+                    zone_ttl,
+                    inception,
+                    self.sig_jitter,
+                )?;
             }
         }
 
-        Ok(())
+        Ok(expiring.len())
+    }
+}
+
+/// Writes the records, e.g. of `Authority::zone_file_records()`, to the master file at `path`,
+///  replacing any existing file
+///
+/// The records are written to a temporary file beside `path`, which is then renamed over it, such
+///  that the file is never left partially written.
+pub(crate) fn write_zone_file(
+    origin: &Name,
+    records: &[Record],
+    path: &Path,
+) -> PersistenceResult<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    {
+        let mut writer = Writer::new(BufWriter::new(File::create(&tmp_path)?));
+        writer.write_origin(origin)?;
+
+        for record in records {
+            writer.write_record(record)?;
+        }
+
+        let file = writer.into_inner().into_inner().map_err(io::Error::from)?;
+        file.sync_all()?;
+    }
+
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Returns the earliest expiration of the RRSIGs of the record set
+#[cfg(feature = "dnssec")]
+fn rrsig_expiration(rr_set: &RecordSet) -> Option<u32> {
+    use trust_dns::rr::rdata::DNSSECRData;
+
+    rr_set
+        .rrsigs()
+        .iter()
+        .filter_map(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(sig.sig_expiration()),
+            _ => None,
+        })
+        .min()
+}
+
+/// Returns the active keys which sign record sets of the type
+///
+/// A key signing key, i.e. with the secure entry point flag, only signs the DNSKEY record set and
+///  a zone signing key only signs the others, unless there is no active key of the other kind.
+#[cfg(feature = "dnssec")]
+fn rrset_signers<'k>(
+    secure_keys: &'k [Signer],
+    key_states: &[KeyState],
+    record_type: RecordType,
+) -> Vec<&'k Signer> {
+    use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType};
+
+    let is_key_signing_key = |signer: &Signer| match *signer.key_rdata() {
+        RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => dnskey.secure_entry_point(),
+        _ => false,
+    };
+
+    let (key_signing, zone_signing): (Vec<&Signer>, Vec<&Signer>) = secure_keys
+        .iter()
+        .zip(key_states)
+        .filter(|&(_, state)| state.is_active())
+        .map(|(signer, _)| signer)
+        .partition(|signer| is_key_signing_key(signer));
+
+    let is_dnskey = record_type == RecordType::DNSSEC(DNSSECRecordType::DNSKEY);
+    match (is_dnskey, key_signing.is_empty(), zone_signing.is_empty()) {
+        (true, false, _) | (false, false, true) => key_signing,
+        _ => zone_signing,
     }
 }
 
+/// Replaces the RRSIGs of the record set with new ones from each of the keys
+///
+/// The expiration of each RRSIG is brought forward by a random amount, up to `sig_jitter`.
+#[cfg(feature = "dnssec")]
+fn sign_rrset(
+    rr_set: &mut RecordSet,
+    secure_keys: &[&Signer],
+    zone_class: DNSClass,
+    zone_ttl: u32,
+    inception: DateTime<Utc>,
+    sig_jitter: Duration,
+) -> DnsSecResult<()> {
+    use trust_dns::rr::dnssec::tbs;
+    use trust_dns::rr::rdata::{DNSSECRData, DNSSECRecordType, SIG};
+
+    rr_set.clear_rrsigs();
+    let rrsig_temp = Record::with(
+        rr_set.name().clone(),
+        RecordType::DNSSEC(DNSSECRecordType::RRSIG),
+        zone_ttl,
+    );
+
+    for signer in secure_keys {
+        debug!(
+            "signing rr_set: {}, {} with: {}",
+            rr_set.name(),
+            rr_set.record_type(),
+            signer.algorithm(),
+        );
+
+        let jitter = match sig_jitter.num_seconds() {
+            jitter if jitter > 0 => rand::thread_rng().gen_range(0, jitter + 1),
+            _ => 0,
+        };
+        let expiration = inception + signer.sig_duration() - Duration::seconds(jitter);
+
+        let tbs = tbs::rrset_tbs(
+            rr_set.name(),
+            zone_class,
+            rr_set.name().num_labels(),
+            rr_set.record_type(),
+            signer.algorithm(),
+            rr_set.ttl(),
+            expiration.timestamp() as u32,
+            inception.timestamp() as u32,
+            signer.calculate_key_tag()?,
+            signer.signer_name(),
+            // TODO: this is a nasty clone... the issue is that the vec
+            //  from records is of Vec<&R>, but we really want &[R]
+            &rr_set
+                .records_without_rrsigs()
+                .cloned()
+                .collect::<Vec<Record>>(),
+        );
+
+        // TODO, maybe chain these with some ETL operations instead?
+        let tbs = match tbs {
+            Ok(tbs) => tbs,
+            Err(err) => {
+                error!("could not serialize rrset to sign: {}", err);
+                continue;
+            }
+        };
+
+        let signature = signer.sign(&tbs);
+        let signature = match signature {
+            Ok(signature) => signature,
+            Err(err) => {
+                error!("could not sign rrset: {}", err);
+                continue;
+            }
+        };
+
+        let mut rrsig = rrsig_temp.clone();
+        rrsig.set_rdata(RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+            // type_covered: RecordType,
+            rr_set.record_type(),
+            // algorithm: Algorithm,
+            signer.algorithm(),
+            // num_labels: u8,
+            rr_set.name().num_labels(),
+            // original_ttl: u32,
+            rr_set.ttl(),
+            // sig_expiration: u32,
+            expiration.timestamp() as u32,
+            // sig_inception: u32,
+            inception.timestamp() as u32,
+            // key_tag: u16,
+            signer.calculate_key_tag()?,
+            // signer_name: Name,
+            signer.signer_name().clone(),
+            // sig: Vec<u8>
+            signature,
+        ))));
+
+        rr_set.insert_rrsig(rrsig);
+    }
+
+    Ok(())
+}
+
 /// An iterator over an ANY query for Records.
 ///
 /// The length of this result cannot be known without consuming the iterator.
@@ -1391,6 +2828,29 @@ impl<'r, 'q> Iterator for AnyRecordsIter<'r, 'q> {
     }
 }
 
+/// Returns the wildcard name, `*`, below the encloser
+fn wildcard_at(encloser: LowerName) -> LowerName {
+    let encloser: Name = encloser.into();
+
+    Name::from_ascii("*")
+        .expect("* is a valid label")
+        .append_domain(&encloser)
+        .into()
+}
+
+/// Returns true for NSEC3 records, their hashed owner names are not names of the zone
+#[cfg(feature = "dnssec")]
+fn is_nsec3(rtype: RecordType) -> bool {
+    use trust_dns::rr::rdata::DNSSECRecordType;
+
+    rtype == RecordType::DNSSEC(DNSSECRecordType::NSEC3)
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn is_nsec3(_rtype: RecordType) -> bool {
+    false
+}
+
 /// The result of a lookup
 #[derive(Debug)]
 pub enum LookupRecords<'r, 'q> {
@@ -1402,6 +2862,8 @@ pub enum LookupRecords<'r, 'q> {
     RecordsIter(RrsetRecords<'r>),
     /// A generic lookup response where anything is desired
     AnyRecordsIter(AnyRecordsIter<'r, 'q>),
+    /// Records from a number of record sets, e.g. the NSEC records proving a negative answer
+    ManyRecords(vec::IntoIter<&'r Record>),
 }
 
 impl<'r, 'q> LookupRecords<'r, 'q> {
@@ -1437,6 +2899,7 @@ impl<'r, 'q> Iterator for LookupRecords<'r, 'q> {
             LookupRecords::NxDomain | LookupRecords::NameExists => None,
             LookupRecords::RecordsIter(ref mut i) => i.next(),
             LookupRecords::AnyRecordsIter(ref mut i) => i.next(),
+            LookupRecords::ManyRecords(ref mut i) => i.next(),
         }
     }
 }
@@ -16,32 +16,271 @@
 // TODO, I've implemented this as a seperate entity from the cache, but I wonder if the cache
 //  should be the only "front-end" for lookups, where if that misses, then we go to the catalog
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::sync::RwLock;
+use std::mem;
+use std::sync::{Arc, RwLock};
 
-use server::{Request, RequestHandler, ResponseHandler};
-use trust_dns::op::{Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode};
+#[cfg(feature = "dnssec")]
+use chrono::Utc;
+#[cfg(feature = "resolver")]
+use futures::Future;
+use tokio_executor::{DefaultExecutor, Executor};
+#[cfg(feature = "resolver")]
+use trust_dns_resolver::error::ResolveErrorKind;
+
+#[cfg(feature = "dnssec")]
+use server::TsigResponseHandle;
+use server::{Protocol, Request, RequestHandler, ResponseHandler};
+use trust_dns::op::{Edns, Header, LowerQuery, MessageType, OpCode, Query, ResponseCode};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TSigner;
 use trust_dns::rr::dnssec::{Algorithm, SupportedAlgorithms};
 use trust_dns::rr::rdata::opt::{EdnsCode, EdnsOption};
-use trust_dns::rr::{LowerName, RecordType};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::tsig::{self, TSIG};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::DNSSECRData;
+use trust_dns::rr::{LowerName, Name, RData, Record, RecordType};
+#[cfg(feature = "resolver")]
+use trust_dns::serialize::binary::BinDecoder;
+use trust_dns::serialize::binary::{BinEncodable, BinEncoder};
 
+use authority::cookie::unix_time;
+use authority::slave::is_serial_newer;
+#[cfg(feature = "dnssec")]
+use authority::TsigResponseSigner;
+#[cfg(feature = "resolver")]
+use authority::{ForwardAuthority, Queries};
 use authority::{
-    AuthLookup, Authority, LookupRecords, MessageRequest, MessageResponse, MessageResponseBuilder,
-    ZoneType,
+    notify_slaves, Acl, AclOperation, Acls, AuthLookup, Authority, LookupRecords, MessageRequest,
+    MessageResponse, MessageResponseBuilder, RefreshHandle, ServerCookies, ZoneType,
 };
 
 /// Set of authorities, zones, available to this server.
 #[derive(Default)]
 pub struct Catalog {
-    authorities: HashMap<LowerName, RwLock<Authority>>,
+    authorities: HashMap<LowerName, Arc<RwLock<Authority>>>,
+    refresh_handles: HashMap<LowerName, RefreshHandle>,
+    #[cfg(feature = "resolver")]
+    forwarders: HashMap<LowerName, ForwardAuthority>,
+    #[cfg(feature = "dnssec")]
+    tsig_keys: HashMap<LowerName, TSigner>,
+    acls: Acls,
+    zone_acls: HashMap<LowerName, Acls>,
+    cookies: Option<ServerCookies>,
 }
 
-fn send_response<R: ResponseHandler + 'static>(
+/// NOTIFY the slaves of the zone, logs an error if this is not running within an executor
+fn spawn_notify(authority: &Authority) {
+    if let Err(error) = DefaultExecutor::current().spawn(notify_slaves(authority)) {
+        warn!(
+            "could not send NOTIFY for {}: {:?}",
+            authority.origin(),
+            error
+        );
+    }
+}
+
+/// Answers the query from the upstream name servers of the forward zone
+///
+/// The response is sent once the upstream lookup completes, which requires an executor. Queries
+///  for which the upstream has no records are answered with its response code, NXDomain or
+///  NoError, and the SOA of its authority section, so that they can be cached negatively.
+#[cfg(feature = "resolver")]
+fn forward<R: ResponseHandler + 'static>(
+    forwarder: &ForwardAuthority,
+    request: &MessageRequest,
+    query: &LowerQuery,
     response_edns: Option<Edns>,
-    mut response: MessageResponse,
     response_handle: R,
 ) -> io::Result<()> {
+    let mut response_header = Header::new();
+    response_header.set_id(request.id());
+    response_header.set_op_code(OpCode::Query);
+    response_header.set_message_type(MessageType::Response);
+    response_header.set_recursion_desired(request.recursion_desired());
+    response_header.set_recursion_available(true);
+
+    if let Err(error) = DefaultExecutor::current().status() {
+        warn!("request: {} can not be forwarded: {:?}", request.id(), error);
+        let response = MessageResponseBuilder::new(Some(request.raw_queries()));
+        return send_response(
+            response_edns,
+            response.error_msg(request.id(), request.op_code(), ResponseCode::ServFail),
+            response_handle,
+        );
+    }
+
+    // the response outlives the request, so the queries are read again from a copy of their bytes
+    let query_count = request.raw_queries().len();
+    let query_bytes = request.raw_queries().as_bytes().to_vec();
+
+    let lookup = forwarder.lookup(query).then(move |result| {
+        let mut decoder = BinDecoder::new(&query_bytes);
+        let queries = Queries::read(&mut decoder, query_count)
+            .map_err(|e| warn!("could not read forwarded queries: {}", e))?;
+
+        let mut soa = None;
+        let records = match result {
+            Ok(records) => records,
+            Err(error) => {
+                match *error.kind() {
+                    ResolveErrorKind::NoRecordsFound {
+                        response_code,
+                        soa: ref negative_soa,
+                        ..
+                    } => {
+                        response_header.set_response_code(response_code);
+                        soa = negative_soa.clone();
+                    }
+                    _ => {
+                        warn!("forward lookup failed: {}", error);
+                        response_header.set_response_code(ResponseCode::ServFail);
+                    }
+                }
+                Vec::new()
+            }
+        };
+
+        let mut response = MessageResponseBuilder::new(Some(&queries));
+        response.answers(AuthLookup::Forward(
+            records.iter().collect::<Vec<_>>().into_iter(),
+        ));
+        let soa = soa.iter().map(|soa| &**soa).collect::<Vec<_>>();
+        response.name_servers(
+            LookupRecords::ManyRecords(soa.into_iter()).chain(LookupRecords::NxDomain),
+        );
+
+        send_response(
+            response_edns,
+            response.build(response_header),
+            response_handle,
+        ).map_err(|e| warn!("could not send forwarded response: {}", e))
+    });
+
+    DefaultExecutor::current()
+        .spawn(Box::new(lookup))
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))
+}
+
+/// Returns the records of a wildcard, synthesized with the name as their owner
+fn synthesize<'r, I: Iterator<Item = &'r Record>>(name: &LowerName, wildcard: I) -> Vec<Record> {
+    let name = Name::from(name.clone());
+
+    wildcard
+        .map(|record| {
+            let mut record = record.clone();
+            record.set_name(name.clone());
+            record
+        })
+        .collect()
+}
+
+/// Returns the records of a DNAME, followed by the CNAME synthesized from it for the name below its
+///  owner
+///
+/// [RFC 6672](https://tools.ietf.org/html/rfc6672), DNAME Redirection in the DNS, June 2012
+///
+/// The CNAME has the TTL of the DNAME, and is not signed, the DNAME is the signed proof of the
+///  redirection. If the redirected name would be too long there is no CNAME, and the query is
+///  answered with YXDOMAIN.
+fn redirect<'r, I: Iterator<Item = &'r Record>>(name: &LowerName, dname: I) -> Vec<Record> {
+    let mut records: Vec<Record> = dname.cloned().collect();
+    let name = Name::from(name.clone());
+
+    let cname = records
+        .iter()
+        .filter_map(|record| match *record.rdata() {
+            RData::DNAME(ref target) => Some((record, target)),
+            _ => None,
+        })
+        .next()
+        .and_then(
+            |(dname, target)| match name.replace_domain(dname.name(), target) {
+                Ok(cname) => {
+                    let mut record = Record::from_rdata(
+                        name.clone(),
+                        dname.ttl(),
+                        RecordType::CNAME,
+                        RData::CNAME(cname),
+                    );
+                    record.set_dns_class(dname.dns_class());
+                    Some(record)
+                }
+                Err(e) => {
+                    warn!("DNAME substitution of {} failed: {}", name, e);
+                    None
+                }
+            },
+        );
+
+    records.extend(cname);
+    records
+}
+
+/// Returns true if the answer to a query of the type is followed, i.e. to the target of a CNAME,
+///  and to the addresses of its targets for the additional section
+fn is_answer_followed(query_type: RecordType) -> bool {
+    match query_type {
+        RecordType::ANY | RecordType::AXFR | RecordType::IXFR => false,
+        _ => true,
+    }
+}
+
+/// Returns the target of the CNAME at the name, if there is one in the records
+fn cname_target<'r, I: Iterator<Item = &'r Record>>(
+    name: &LowerName,
+    records: I,
+) -> Option<LowerName> {
+    records
+        .filter(|record| LowerName::from(record.name()) == *name)
+        .filter_map(|record| match *record.rdata() {
+            RData::CNAME(ref target) => Some(LowerName::from(target)),
+            _ => None,
+        })
+        .next()
+}
+
+/// Searches the zone for the records of the name, as owned records, e.g. the target of a CNAME
+fn search_owned(
+    authority: &Authority,
+    name: &LowerName,
+    query_type: RecordType,
+    is_dnssec: bool,
+    supported_algorithms: SupportedAlgorithms,
+) -> Vec<Record> {
+    let query = LowerQuery::from(Query::query(name.clone().into(), query_type));
+
+    match authority.search(&query, is_dnssec, supported_algorithms) {
+        AuthLookup::Wildcard(wildcard) => synthesize(name, wildcard),
+        AuthLookup::Dname(dname) => redirect(name, dname),
+        // the delegated zone has the answer
+        AuthLookup::Referral(_) => Vec::new(),
+        lookup => lookup.cloned().collect(),
+    }
+}
+
+/// Returns the target of a CNAME which answers the query in the zone, if there is one
+fn answer_cname_target(
+    authority: &Authority,
+    query: &LowerQuery,
+    is_dnssec: bool,
+    supported_algorithms: SupportedAlgorithms,
+) -> Option<LowerName> {
+    let name = query.name();
+    match authority.search(query, is_dnssec, supported_algorithms) {
+        AuthLookup::Wildcard(wildcard) => cname_target(name, synthesize(name, wildcard).iter()),
+        AuthLookup::Dname(dname) => cname_target(name, redirect(name, dname).iter()),
+        AuthLookup::Referral(_) => None,
+        lookup => cname_target(name, lookup),
+    }
+}
+
+/// Maximum size of the records in each message of a zone transfer
+const TRANSFER_MESSAGE_RECORDS_SIZE: usize = 16 * 1024;
+
+fn set_response_edns(response_edns: Option<Edns>, response: &mut MessageResponse) {
     if let Some(mut resp_edns) = response_edns {
         // set edns DAU and DHU
         // send along the algorithms which are supported by this authority
@@ -59,10 +298,144 @@ fn send_response<R: ResponseHandler + 'static>(
 
         response.set_edns(resp_edns);
     }
+}
 
+fn send_response<R: ResponseHandler + 'static>(
+    response_edns: Option<Edns>,
+    mut response: MessageResponse,
+    response_handle: R,
+) -> io::Result<()> {
+    set_response_edns(response_edns, &mut response);
     response_handle.send_response(response)
 }
 
+/// Answers a request whose TSIG could not be verified, the error is in the TSIG of the response
+///  which is not signed, i.e. its MAC is empty
+#[cfg(feature = "dnssec")]
+fn send_tsig_error<R: ResponseHandler + 'static>(
+    request: &MessageRequest,
+    key_name: &Name,
+    tsig: &TSIG,
+    error: ResponseCode,
+    response_handle: R,
+) -> io::Result<()> {
+    let unsigned = TSIG::new(
+        tsig.algorithm().clone(),
+        tsig.time(),
+        tsig.fudge(),
+        Vec::new(),
+        tsig.oid(),
+        u16::from(error),
+        Vec::new(),
+    );
+
+    let mut response = MessageResponseBuilder::new(Some(request.raw_queries()));
+    response.sig0(vec![tsig::make_tsig_record(key_name.clone(), unsigned)]);
+    response_handle.send_response(response.error_msg(
+        request.id(),
+        request.op_code(),
+        ResponseCode::NotAuth,
+    ))
+}
+
+fn send_responses<R: ResponseHandler + 'static>(
+    response_edns: Option<Edns>,
+    mut responses: Vec<MessageResponse>,
+    response_handle: R,
+) -> io::Result<()> {
+    // EDNS is only necessary on the first message of the sequence
+    if let Some(response) = responses.first_mut() {
+        set_response_edns(response_edns, response);
+    }
+
+    response_handle.send_responses(responses)
+}
+
+/// Returns true if the request is for an AXFR or IXFR
+fn is_transfer(request: &MessageRequest) -> bool {
+    request
+        .queries()
+        .iter()
+        .any(|query| match query.query_type() {
+            RecordType::AXFR | RecordType::IXFR => true,
+            _ => false,
+        })
+}
+
+/// The operation of the request which is controlled by the `Acl`s, None for requests which are
+///  not answered, e.g. unimplemented op codes
+fn acl_operation(request: &MessageRequest) -> Option<AclOperation> {
+    if request.message_type() != MessageType::Query {
+        return None;
+    }
+
+    match request.op_code() {
+        OpCode::Query if is_transfer(request) => Some(AclOperation::Transfer),
+        OpCode::Query => Some(AclOperation::Query),
+        OpCode::Update => Some(AclOperation::Update),
+        OpCode::Notify => Some(AclOperation::Notify),
+        _ => None,
+    }
+}
+
+/// Returns if the DO bit is set, and the algorithms supported by the requestor
+fn dnssec_options(request: &MessageRequest) -> (bool, SupportedAlgorithms) {
+    let (is_dnssec, supported_algorithms) =
+        request
+            .edns()
+            .map_or((false, SupportedAlgorithms::new()), |edns| {
+                let supported_algorithms =
+                    if let Some(&EdnsOption::DAU(algs)) = edns.option(EdnsCode::DAU) {
+                        algs
+                    } else {
+                        debug!("no DAU in request, used default SupportAlgorithms");
+                        Default::default()
+                    };
+
+                (edns.dnssec_ok(), supported_algorithms)
+            });
+
+    // log algorithms being requested
+    if is_dnssec {
+        info!(
+            "request: {} supported_algs: {}",
+            request.id(),
+            supported_algorithms
+        );
+    }
+
+    (is_dnssec, supported_algorithms)
+}
+
+/// Splits the records of a zone transfer into groups which each fit in a single message
+fn chunk_transfer(records: Vec<&Record>) -> Vec<Vec<&Record>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = 0;
+    let mut buf = Vec::with_capacity(512);
+
+    for record in records {
+        // the record is encoded without the other names of the message to compress against, so
+        //  this is never smaller than the record will be in the message
+        buf.clear();
+        let size = {
+            let mut encoder = BinEncoder::new(&mut buf);
+            record.emit(&mut encoder).map(|_| encoder.len()).unwrap_or(0)
+        };
+
+        if !chunk.is_empty() && chunk_size + size > TRANSFER_MESSAGE_RECORDS_SIZE {
+            chunks.push(mem::replace(&mut chunk, Vec::new()));
+            chunk_size = 0;
+        }
+
+        chunk.push(record);
+        chunk_size += size;
+    }
+
+    chunks.push(chunk);
+    chunks
+}
+
 impl RequestHandler for Catalog {
     /// Determine's what needs to happen given the type of request, i.e. Query or Update.
     ///
@@ -74,9 +447,37 @@ impl RequestHandler for Catalog {
         &'a self,
         request: &'q Request,
         response_handle: R,
+    ) -> io::Result<()> {
+        trace!("request: {:?}", request.message);
+
+        #[cfg(feature = "dnssec")]
+        {
+            let tsig = request.message.sig0().last().and_then(|record| {
+                match *record.rdata() {
+                    RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => Some((record.name(), tsig)),
+                    _ => None,
+                }
+            });
+
+            if let Some((key_name, tsig)) = tsig {
+                return self.handle_signed_request(request, key_name, tsig, response_handle);
+            }
+        }
+
+        self.dispatch(request, None, response_handle)
+    }
+}
+
+impl Catalog {
+    /// Determine's what needs to happen given the type of request, i.e. Query or Update, once any
+    ///  TSIG of the request is verified, `key_name` is the name of the key it was verified with
+    fn dispatch<R: ResponseHandler + 'static>(
+        &self,
+        request: &Request,
+        key_name: Option<&LowerName>,
+        response_handle: R,
     ) -> io::Result<()> {
         let request_message = &request.message;
-        trace!("request: {:?}", request_message);
 
         let response_edns: Option<Edns>;
 
@@ -108,17 +509,75 @@ impl RequestHandler for Catalog {
                 return response_handle.send_response(response.build(response_header));
             }
 
+            if let Some(ref cookies) = self.cookies {
+                match req_edns.option(EdnsCode::Cookie) {
+                    Some(&EdnsOption::Cookie(ref cookie)) => {
+                        let src = request.src.ip();
+                        let now = unix_time();
+                        let is_valid = cookies.is_valid(cookie, src, now);
+                        resp_edns.set_option(EdnsOption::Cookie(cookies.respond(cookie, src, now)));
+
+                        // over TCP the client has already proven its address
+                        if !is_valid && cookies.is_required() && request.protocol == Protocol::Udp {
+                            debug!(
+                                "request: {} has no valid server cookie",
+                                request_message.id()
+                            );
+                            resp_edns.set_rcode_high(ResponseCode::BADCOOKIE.high() as u8);
+                            response.edns(resp_edns);
+                            return response_handle.send_response(response.error_msg(
+                                request_message.id(),
+                                request_message.op_code(),
+                                ResponseCode::BADCOOKIE,
+                            ));
+                        }
+                    }
+                    Some(option) => {
+                        warn!("request: {} malformed cookie: {:?}", request_message.id(), option);
+                        response.edns(resp_edns);
+                        return response_handle.send_response(response.error_msg(
+                            request_message.id(),
+                            request_message.op_code(),
+                            ResponseCode::FormErr,
+                        ));
+                    }
+                    None => (),
+                }
+            }
+
             response_edns = Some(resp_edns);
         } else {
             response_edns = None;
         }
 
+        if let Some(operation) = acl_operation(request_message) {
+            if !self.is_allowed(request, key_name, operation) {
+                let response = MessageResponseBuilder::new(Some(request_message.raw_queries()));
+                return send_response(
+                    response_edns,
+                    response.error_msg(
+                        request_message.id(),
+                        request_message.op_code(),
+                        ResponseCode::Refused,
+                    ),
+                    response_handle,
+                );
+            }
+        }
+
         match request_message.message_type() {
             // TODO think about threading query lookups for multiple lookups, this could be a huge improvement
             //  especially for recursive lookups
             MessageType::Query => match request_message.op_code() {
-                OpCode::Query => self.lookup(request_message, response_edns, response_handle),
+                OpCode::Query => {
+                    if is_transfer(request_message) {
+                        self.transfer(request, response_edns, response_handle)
+                    } else {
+                        self.lookup(request_message, response_edns, response_handle)
+                    }
+                }
                 OpCode::Update => self.update(request_message, response_edns, response_handle),
+                OpCode::Notify => self.notify(request, response_edns, response_handle),
                 c => {
                     error!("unimplemented op_code: {:?}", c);
                     let response = MessageResponseBuilder::new(Some(request_message.raw_queries()));
@@ -144,16 +603,38 @@ impl RequestHandler for Catalog {
             }
         }
     }
-}
 
-impl Catalog {
     /// Constructs a new Catalog
     pub fn new() -> Self {
         Catalog {
             authorities: HashMap::new(),
+            refresh_handles: HashMap::new(),
+            #[cfg(feature = "resolver")]
+            forwarders: HashMap::new(),
+            #[cfg(feature = "dnssec")]
+            tsig_keys: HashMap::new(),
+            acls: Acls::new(),
+            zone_acls: HashMap::new(),
+            cookies: None,
         }
     }
 
+    /// Adds a key which requests may be signed with by TSIG, the key is known by its name
+    ///
+    /// The responses to a signed request are signed with the same key. Requests which are
+    ///  signed with an unknown key, or do not verify, are answered with NotAuth.
+    #[cfg(feature = "dnssec")]
+    pub fn add_tsig_key(&mut self, signer: TSigner) {
+        self.tsig_keys
+            .insert(LowerName::from(signer.signer_name()), signer);
+    }
+
+    /// Returns the TSIG key of the name, if it was added to the catalog
+    #[cfg(feature = "dnssec")]
+    pub fn tsig_key(&self, name: &LowerName) -> Option<&TSigner> {
+        self.tsig_keys.get(name)
+    }
+
     /// Insert or update a zone authority
     ///
     /// # Arguments
@@ -161,14 +642,210 @@ impl Catalog {
     /// * `name` - zone name, e.g. example.com.
     /// * `authority` - the zone data
     pub fn upsert(&mut self, name: LowerName, authority: Authority) {
-        self.authorities.insert(name, RwLock::new(authority));
+        self.upsert_shared(name, Arc::new(RwLock::new(authority)));
+    }
+
+    /// Insert or update a zone authority which is shared with another owner
+    ///
+    /// This is used for zones which are modified outside of request handling, e.g. a slave zone
+    ///  which is periodically refreshed from its master.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `authority` - the shared zone data
+    pub fn upsert_shared(&mut self, name: LowerName, authority: Arc<RwLock<Authority>>) {
+        self.authorities.insert(name, authority);
+    }
+
+    /// Insert or update a forward zone, queries for names in the zone are answered by its upstream
+    ///  name servers, unless a more specific authority is in the catalog
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `forwarder` - the forward zone
+    #[cfg(feature = "resolver")]
+    pub fn upsert_forward(&mut self, name: LowerName, forwarder: ForwardAuthority) {
+        self.forwarders.insert(name, forwarder);
+    }
+
+    /// Restricts the operations on all zones to the clients allowed by the `Acl`s, unless the zone
+    ///  has its own `Acl` for the operation, see `set_zone_acls()`
+    ///
+    /// Requests which are not allowed are answered with Refused. Operations without an `Acl` are
+    ///  allowed to all clients, as far as the zone itself allows them, e.g. updates must still be
+    ///  enabled on the `Authority`.
+    pub fn set_acls(&mut self, acls: Acls) {
+        self.acls = acls;
+    }
+
+    /// Restricts the operations on the zone, and on names below it which are not in another zone,
+    ///  to the clients allowed by the `Acl`s, in place of the `Acl`s of all zones
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `acls` - the `Acl`s of the operations on the zone
+    pub fn set_zone_acls(&mut self, name: LowerName, acls: Acls) {
+        self.zone_acls.insert(name, acls);
+    }
+
+    /// Answers requests which have a DNS cookie with a server cookie, which the client returns in
+    ///  its later requests to prove that it received the response, see `ServerCookies`
+    ///
+    /// Requests with a malformed cookie are answered with FormErr.
+    pub fn set_server_cookies(&mut self, cookies: ServerCookies) {
+        self.cookies = Some(cookies);
+    }
+
+    /// Returns true if the request, verified with the TSIG key of the name, if any, is allowed the
+    ///  operation on the zones of all of its queries
+    fn is_allowed(
+        &self,
+        request: &Request,
+        key_name: Option<&LowerName>,
+        operation: AclOperation,
+    ) -> bool {
+        let src = request.src.ip();
+        request.message.queries().iter().all(|query| {
+            let allowed = self
+                .find_acl(query.name(), operation)
+                .map_or(true, |acl| acl.is_allowed(src, key_name));
+            if !allowed {
+                warn!(
+                    "request: {} {:?} of {} refused to {}",
+                    request.message.id(),
+                    operation,
+                    query.name(),
+                    src
+                );
+            }
+            allowed
+        })
+    }
+
+    /// Searches for the `Acl` of the operation on the zone of the name, falling back to the `Acl`
+    ///  of all zones
+    fn find_acl(&self, name: &LowerName, operation: AclOperation) -> Option<&Acl> {
+        if let Some(acl) = self.zone_acls.get(name).and_then(|acls| acls.get(operation)) {
+            return Some(acl);
+        }
+
+        if name.is_root() || self.is_zone(name) {
+            self.acls.get(operation)
+        } else {
+            self.find_acl(&name.base_name(), operation)
+        }
+    }
+
+    /// Returns true if the name is the origin of an authority, or of a forward zone
+    fn is_zone(&self, name: &LowerName) -> bool {
+        #[cfg(feature = "resolver")]
+        {
+            if self.forwarders.contains_key(name) {
+                return true;
+            }
+        }
+
+        self.authorities.contains_key(name)
     }
 
     /// Remove a zone from the catalog
-    pub fn remove(&mut self, name: &LowerName) -> Option<RwLock<Authority>> {
+    pub fn remove(&mut self, name: &LowerName) -> Option<Arc<RwLock<Authority>>> {
+        self.refresh_handles.remove(name);
+        self.zone_acls.remove(name);
         self.authorities.remove(name)
     }
 
+    /// Associate the refresh of a slave zone, NOTIFYs received for the zone will trigger it
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `handle` - handle to the `SlaveRefresh` of the zone
+    pub fn set_refresh_handle(&mut self, name: LowerName, handle: RefreshHandle) {
+        self.refresh_handles.insert(name, handle);
+    }
+
+    /// Verifies the TSIG of the request, which is only handled if it verifies, the responses are
+    ///  then signed with the same key
+    ///
+    /// [RFC 8945](https://tools.ietf.org/html/rfc8945), Secret Key Transaction Authentication for DNS (TSIG), November 2020
+    ///
+    /// A request signed with a key which is not known is answered with BADKEY, and one which does
+    ///  not verify with BADSIG, those responses are not signed. A request which was signed outside
+    ///  of the fudge of the key is answered with BADTIME, which is signed so that the client may
+    ///  trust the time of the server. The error is in the TSIG, the response code is NotAuth.
+    #[cfg(feature = "dnssec")]
+    fn handle_signed_request<R: ResponseHandler + 'static>(
+        &self,
+        request: &Request,
+        key_name: &Name,
+        tsig: &TSIG,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let message = &request.message;
+
+        let signer = match self.tsig_keys.get(&LowerName::from(key_name)) {
+            Some(signer) if signer.algorithm() == tsig.algorithm() => signer,
+            _ => {
+                warn!(
+                    "request: {} signed with unknown tsig key: {} {}",
+                    message.id(),
+                    key_name,
+                    tsig.algorithm()
+                );
+                return send_tsig_error(
+                    message,
+                    key_name,
+                    tsig,
+                    ResponseCode::BADKEY,
+                    response_handle,
+                );
+            }
+        };
+
+        let tsig = match signer.verify_message_byte(None, message.as_bytes(), true) {
+            Ok(tsig) => tsig,
+            Err(error) => {
+                warn!("request: {} tsig did not verify: {}", message.id(), error);
+                return send_tsig_error(
+                    message,
+                    key_name,
+                    tsig,
+                    ResponseCode::BADSIG,
+                    response_handle,
+                );
+            }
+        };
+
+        let now = Utc::now().timestamp() as u64;
+        if !tsig.is_in_time(now) {
+            warn!(
+                "request: {} tsig time: {} is outside of the fudge: {}",
+                message.id(),
+                tsig.time(),
+                tsig.fudge()
+            );
+            let response = MessageResponseBuilder::new(Some(message.raw_queries())).error_msg(
+                message.id(),
+                message.op_code(),
+                ResponseCode::NotAuth,
+            );
+            let signer = TsigResponseSigner::bad_time(signer.clone(), &tsig, now);
+            return TsigResponseHandle::new(response_handle, signer).send_response(response);
+        }
+
+        debug!("request: {} verified with tsig key: {}", message.id(), key_name);
+        let signer = TsigResponseSigner::new(signer.clone(), tsig.mac().to_vec());
+        self.dispatch(
+            request,
+            Some(&LowerName::from(key_name)),
+            TsigResponseHandle::new(response_handle, signer),
+        )
+    }
+
     /// Update the zone given the Update request.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -265,8 +942,12 @@ impl Catalog {
                     let update_result = authority.update(update);
                     match update_result {
                         // successful update
-                        Ok(..) => {
+                        Ok(updated) => {
                             response_header.set_response_code(ResponseCode::NoError);
+
+                            if updated && !authority.also_notify().is_empty() {
+                                spawn_notify(&authority);
+                            }
                         }
                         Err(response_code) => {
                             response_header.set_response_code(response_code);
@@ -300,6 +981,84 @@ impl Catalog {
         }
     }
 
+    /// Handles a NOTIFY, triggering a refresh of the slave zone
+    ///
+    /// [RFC 1996](https://tools.ietf.org/html/rfc1996), NOTIFY, August 1996
+    ///
+    /// ```text
+    /// 3.7. A NOTIFY request has QDCOUNT>0, ANCOUNT>=0, AUCOUNT>=0,
+    ///    ADCOUNT>=0.  If ANCOUNT>0, then the answer section represents an
+    ///    unsecure hint at the new RRset for this <QNAME,QCLASS,QTYPE>.  A
+    ///    slave receiving such a hint is free to treat equivalence of this
+    ///    answer section with its local data as a "no further work needs to be
+    ///    done" indication.  If ANCOUNT=0, or ANCOUNT>0 and the answer section
+    ///    differs from the slave's local data, then the slave should query its
+    ///    known masters to retrieve the new data.
+    ///
+    /// 3.10. If a slave receives a NOTIFY request from a host that is not a
+    ///    known master for the zone containing the QNAME, it should ignore the
+    ///    request and produce an error message in its operations log.
+    /// ```
+    ///
+    /// The answer section is not trusted, the refresh always checks the SOA with the masters.
+    ///  NOTIFYs from hosts which are not masters of the zone are Refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the NOTIFY request
+    /// * `response_edns` - the EDNS to associate with the response
+    /// * `response_handle` - sink for the response message to be sent
+    pub fn notify<R: ResponseHandler + 'static>(
+        &self,
+        request: &Request,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let message = &request.message;
+        let response = MessageResponseBuilder::new(Some(message.raw_queries()));
+        let mut response_header = Header::default();
+        response_header.set_id(message.id());
+        response_header.set_op_code(OpCode::Notify);
+        response_header.set_message_type(MessageType::Response);
+
+        let queries = message.queries();
+        let response_code = if queries.len() != 1 {
+            ResponseCode::FormErr
+        } else if queries[0].query_type() != RecordType::SOA {
+            ResponseCode::NotImp
+        } else {
+            match self.refresh_handles.get(queries[0].name()) {
+                Some(handle) if handle.is_master(&request.src) => {
+                    info!(
+                        "NOTIFY for {} from {}, refreshing",
+                        queries[0].name(),
+                        request.src
+                    );
+                    if !handle.refresh() {
+                        warn!("refresh of {} is not running", queries[0].name());
+                    }
+                    ResponseCode::NoError
+                }
+                Some(_) => {
+                    warn!(
+                        "NOTIFY for {} from {} which is not a master, ignoring",
+                        queries[0].name(),
+                        request.src
+                    );
+                    ResponseCode::Refused
+                }
+                None => ResponseCode::NotAuth,
+            }
+        };
+
+        response_header.set_response_code(response_code);
+        send_response(
+            response_edns,
+            response.build(response_header),
+            response_handle,
+        )
+    }
+
     /// Checks whether the `Catalog` contains DNS records for `name`
     ///
     /// Use this when you know the exact `LowerName` that was used when
@@ -328,7 +1087,36 @@ impl Catalog {
         // TODO: the spec is very unclear on what to do with multiple queries
         //  we will search for each, in the future, maybe make this threaded to respond even faster.
         for query in request.queries() {
+            #[cfg(feature = "resolver")]
+            {
+                if let Some(forwarder) = self.find_forward(query.name()) {
+                    info!(
+                        "request: {} forwarding to zone: {}",
+                        request.id(),
+                        forwarder.origin()
+                    );
+                    return forward(forwarder, request, query, response_edns, response_handle);
+                }
+            }
+
             if let Some(ref_authority) = self.find(query.name()) {
+                let (is_dnssec, supported_algorithms) = dnssec_options(request);
+
+                // a CNAME in the answer is followed to its target within the zones of the catalog,
+                //  which are each locked in turn, not while the lock of this zone is held
+                let mut chased: Vec<Record> = Vec::new();
+                let chased_target = if is_answer_followed(query.query_type())
+                    && query.query_type() != RecordType::CNAME
+                {
+                    let authority = ref_authority.read().unwrap(); // poison errors should panic
+                    answer_cname_target(&authority, query, is_dnssec, supported_algorithms)
+                } else {
+                    None
+                };
+                if let Some(ref target) = chased_target {
+                    chased = self.chase_cname(query, target, is_dnssec, supported_algorithms);
+                }
+
                 let authority = &ref_authority.read().unwrap(); // poison errors should panic
                 info!(
                     "request: {} found authority: {}",
@@ -336,49 +1124,119 @@ impl Catalog {
                     authority.origin()
                 );
 
+                // a slave zone which has not been transferred yet, or has expired, has nothing to
+                //  serve
+                if authority.zone_type() == ZoneType::Slave && authority.records().is_empty() {
+                    warn!(
+                        "request: {} slave zone not loaded: {}",
+                        request.id(),
+                        authority.origin()
+                    );
+
+                    let response = MessageResponseBuilder::new(Some(request.raw_queries()));
+                    return send_response(
+                        response_edns,
+                        response.error_msg(request.id(), request.op_code(), ResponseCode::ServFail),
+                        response_handle,
+                    );
+                }
+
+                let mut records = authority.search(query, is_dnssec, supported_algorithms);
+                let is_wildcard = records.is_wildcard();
+                let is_dname = records.is_dname();
+
+                // records of a wildcard are synthesized with the name of the query as their owner,
+                //  and a name below a DNAME is redirected by a synthesized CNAME
+                let synthesized: Vec<Record> = if is_wildcard {
+                    synthesize(query.name(), records.by_ref())
+                } else if is_dname {
+                    redirect(query.name(), records.by_ref())
+                } else {
+                    Vec::new()
+                };
+
+                // the redirected name is too long, RFC 6672 section 2.2
+                let is_yx_domain =
+                    is_dname && cname_target(query.name(), synthesized.iter()).is_none();
+
+                if is_wildcard || is_dname {
+                    records = AuthLookup::Records(LookupRecords::ManyRecords(
+                        synthesized.iter().collect::<Vec<_>>().into_iter(),
+                    ));
+                }
+
+                // the addresses of the targets of the answer are added as additional records
+                let mut additionals: Vec<&Record> = Vec::new();
+                if !records.is_empty()
+                    && !records.is_referral()
+                    && is_answer_followed(query.query_type())
+                {
+                    let mut answers: Vec<&Record> = records.collect();
+
+                    // the zone may have changed since the CNAME was chased
+                    if cname_target(query.name(), answers.iter().cloned()) == chased_target {
+                        answers.extend(chased.iter());
+                    }
+
+                    additionals = authority.additionals(&answers, is_dnssec, supported_algorithms);
+                    records = AuthLookup::Records(LookupRecords::ManyRecords(answers.into_iter()));
+                }
+
                 let mut response = MessageResponseBuilder::new(Some(request.raw_queries()));
                 let mut response_header = Header::new();
                 response_header.set_id(request.id());
                 response_header.set_op_code(OpCode::Query);
                 response_header.set_message_type(MessageType::Response);
 
-                let (is_dnssec, supported_algorithms) =
-                    request
-                        .edns()
-                        .map_or((false, SupportedAlgorithms::new()), |edns| {
-                            let supported_algorithms =
-                                if let Some(&EdnsOption::DAU(algs)) = edns.option(EdnsCode::DAU) {
-                                    algs
-                                } else {
-                                    debug!("no DAU in request, used default SupportAlgorithms");
-                                    Default::default()
-                                };
-
-                            (edns.dnssec_ok(), supported_algorithms)
-                        });
-
-                // log algorithms being requested
-                if is_dnssec {
-                    info!(
-                        "request: {} supported_algs: {}",
-                        request.id(),
-                        supported_algorithms
-                    );
-                }
-
-                let records = authority.search(query, is_dnssec, supported_algorithms);
-
                 // setup headers
                 //  and add records
-                if !records.is_empty() {
+                if records.is_referral() {
+                    // the name servers of the delegated zone, with their glue, this server is not
+                    //  authoritative for the answer, RFC 1034 section 4.3.2 step 3.b
+                    let name_servers: Vec<&Record> = records.collect();
+                    let zone_cut = name_servers.first().map(|ns| LowerName::from(ns.name()));
+
+                    // glue is not signed, it is not authoritative data
+                    let glue = authority.additionals(&name_servers, false, supported_algorithms);
+                    let delegation_signer = match zone_cut {
+                        Some(ref zone_cut) if is_dnssec => authority.delegation_signer(
+                            zone_cut,
+                            is_dnssec,
+                            supported_algorithms,
+                        ),
+                        _ => LookupRecords::NxDomain,
+                    };
+
                     response_header.set_response_code(ResponseCode::NoError);
+                    let name_servers = LookupRecords::ManyRecords(name_servers.into_iter());
+                    response.name_servers(name_servers.chain(delegation_signer));
+                    response.additionals(glue);
+                } else if !records.is_empty() {
+                    response_header.set_response_code(if is_yx_domain {
+                        ResponseCode::YXDomain
+                    } else {
+                        ResponseCode::NoError
+                    });
                     response_header.set_authoritative(true);
                     response.answers(records);
+                    response.additionals(additionals);
 
                     // get the NS records
                     let ns = authority.ns(is_dnssec, supported_algorithms);
-                    // chain here to match type below...
-                    response.name_servers(ns.chain(LookupRecords::NxDomain));
+
+                    // a wildcard answer must be proven, the query name does not exist in the zone
+                    let nsecs = if is_dnssec && is_wildcard {
+                        authority.get_nsec_proof(
+                            query.name(),
+                            query.query_type(),
+                            is_dnssec,
+                            supported_algorithms,
+                        )
+                    } else {
+                        LookupRecords::NxDomain
+                    };
+
+                    response.name_servers(ns.chain(nsecs));
                 } else if records.is_refused() {
                     response_header.set_response_code(ResponseCode::Refused);
                 } else {
@@ -395,35 +1253,39 @@ impl Catalog {
                         AuthLookup::Refused => {
                             panic!("programming error, should have return Refused above")
                         }
-                        AuthLookup::Records(_) | AuthLookup::SOA(_) | AuthLookup::AXFR(_) => {
+                        AuthLookup::Records(_)
+                        | AuthLookup::SOA(_)
+                        | AuthLookup::AXFR(_)
+                        | AuthLookup::AXFRChunk(_)
+                        | AuthLookup::Forward(_)
+                        | AuthLookup::Wildcard(_)
+                        | AuthLookup::Referral(_)
+                        | AuthLookup::Dname(_) => {
                             panic!(
                                 "programming error, should have return NoError with records above"
                             )
                         }
                     };
 
-                    // in the dnssec case, nsec records should exist, we return NoError + NoData + NSec...
-                    let ns = if is_dnssec {
-                        // get NSEC records
-                        let mut nsecs = authority.get_nsec_records(
+                    response_header.set_authoritative(true);
+
+                    // in the dnssec case the denial of existence is proven by the signed SOA and
+                    //  the NSEC records, RFC 4035 section 3.1.3, the response code is unchanged
+                    let nsecs = if is_dnssec {
+                        debug!("request: {} non-existent adding nsecs", request.id());
+                        authority.get_nsec_proof(
                             query.name(),
+                            query.query_type(),
                             is_dnssec,
                             supported_algorithms,
-                        );
-                        debug!("request: {} non-existent adding nsecs", request.id(),);
-
-                        response_header.set_response_code(ResponseCode::NoError);
-                        nsecs
+                        )
                     } else {
-                        // place holder...
                         debug!("request: {} non-existent", request.id());
                         LookupRecords::NxDomain
                     };
 
                     let soa = authority.soa_secure(is_dnssec, supported_algorithms);
-                    let ns = ns.chain(soa);
-
-                    response.name_servers(ns);
+                    response.name_servers(soa.chain(nsecs));
                 }
 
                 return send_response(
@@ -442,9 +1304,323 @@ impl Catalog {
         )
     }
 
+    /// Follows a CNAME in the answer to its target, and then any CNAME of the target in turn,
+    ///  through the zones of this catalog
+    ///
+    /// [RFC 1034](https://tools.ietf.org/html/rfc1034#section-4.3.2), Domain Concepts and Facilities, November 1987
+    ///
+    /// ```text
+    ///      3. Start matching down, label by label, in the zone.  The
+    ///         matching process can terminate several ways:
+    ///
+    ///         a. If the whole of QNAME is matched, we have found the
+    ///            node.
+    ///
+    ///            If the data at the node is a CNAME, and QTYPE doesn't
+    ///            match CNAME, copy the CNAME RR into the answer section
+    ///            of the response, change QNAME to the canonical name in
+    ///            the CNAME RR, and go back to step 1.
+    /// ```
+    ///
+    /// Returns the records of the targets, which are appended to the answer. The chain ends at a
+    ///  target which is not in a zone of this catalog, or has no records of the type, and a loop
+    ///  ends at the first repeated name.
+    ///
+    /// Each zone is only locked while its records are searched, no other lock of a zone may be
+    ///  held, the target may be in any of the zones.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - the query which was answered with the CNAME
+    /// * `target` - the target of the CNAME in the answer
+    fn chase_cname(
+        &self,
+        query: &LowerQuery,
+        target: &LowerName,
+        is_dnssec: bool,
+        supported_algorithms: SupportedAlgorithms,
+    ) -> Vec<Record> {
+        let mut chased: Vec<Record> = Vec::new();
+        let mut names: HashSet<LowerName> = HashSet::new();
+        names.insert(query.name().clone());
+
+        let mut target = Some(target.clone());
+        while let Some(name) = target.take() {
+            if !names.insert(name.clone()) {
+                warn!("CNAME loop at: {}", name);
+                break;
+            }
+
+            let records = match self.find(&name) {
+                Some(zone) => search_owned(
+                    &zone.read().unwrap(), // poison errors should panic
+                    &name,
+                    query.query_type(),
+                    is_dnssec,
+                    supported_algorithms,
+                ),
+                None => break,
+            };
+
+            target = cname_target(&name, records.iter());
+            chased.extend(records);
+        }
+
+        chased
+    }
+
+    /// Answers a zone transfer, AXFR or IXFR, request
+    ///
+    /// [RFC 5936](https://tools.ietf.org/html/rfc5936), DNS Zone Transfer Protocol (AXFR), June 2010
+    ///
+    /// ```text
+    /// 2.2.  AXFR Response
+    ///
+    ///    The AXFR response will consist of one or more messages.  The special
+    ///    case of a server closing the TCP connection without sending an AXFR
+    ///    response is covered in Section 2.3.
+    ///
+    ///    An AXFR response that is transferring the zone's contents will
+    ///    consist of a series (which could be a series of length 1) of DNS
+    ///    messages.  In such a series, the first message MUST begin with the
+    ///    SOA resource record of the zone, and the last message MUST conclude
+    ///    with the same SOA resource record.  Intermediate messages MUST NOT
+    ///    contain the SOA resource record.
+    /// ```
+    ///
+    /// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS, August 1996
+    ///
+    /// ```text
+    /// 4. Response Format
+    ///
+    ///    If incremental zone transfer is not available, the entire zone is
+    ///    returned.  The first and the last RR of the response is the SOA
+    ///    record of the zone.  I.e. the behavior is the same as an AXFR
+    ///    response except the query type is IXFR.
+    ///
+    ///    If incremental zone transfer is available, one or more difference
+    ///    sequences is returned.  The list of difference sequences is preceded
+    ///    and followed by a copy of the server's current version of the SOA.
+    ///
+    ///    Each difference sequence represents one update to the zone (one SOA
+    ///    serial change) consisting of deleted RRs and added RRs.  The first RR
+    ///    of the deleted RRs is the older SOA RR and the first RR of the added
+    ///    RRs is the newer SOA RR.
+    /// ```
+    ///
+    /// AXFR is only sent over stream protocols, requests received over UDP are refused. IXFR
+    ///  requests over UDP, or from a client which already has the current version of the zone, are
+    ///  answered with only the current SOA. Otherwise the changes since the client's serial are
+    ///  sent as a single difference sequence, computed from the journal, falling back to the
+    ///  entire zone when the journal does not have that version of the zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the AXFR or IXFR request
+    /// * `response_edns` - the EDNS to associate with the response
+    /// * `response_handle` - sink for the response messages to be sent
+    pub fn transfer<R: ResponseHandler + 'static>(
+        &self,
+        request: &Request,
+        response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> io::Result<()> {
+        let message = &request.message;
+        let error_msg = |response_code| {
+            MessageResponseBuilder::new(Some(message.raw_queries())).error_msg(
+                message.id(),
+                message.op_code(),
+                response_code,
+            )
+        };
+
+        if message.queries().len() != 1 {
+            return send_response(
+                response_edns,
+                error_msg(ResponseCode::FormErr),
+                response_handle,
+            );
+        }
+
+        let query = &message.queries()[0];
+        let is_incremental = query.query_type() == RecordType::IXFR;
+
+        if !is_incremental && !request.protocol.is_stream() {
+            warn!(
+                "request: {} AXFR refused over {}",
+                message.id(),
+                request.protocol
+            );
+            return send_response(
+                response_edns,
+                error_msg(ResponseCode::Refused),
+                response_handle,
+            );
+        }
+
+        let zone = match self.authorities.get(query.name()) {
+            Some(zone) => zone,
+            None => {
+                return send_response(
+                    response_edns,
+                    error_msg(ResponseCode::NotAuth),
+                    response_handle,
+                )
+            }
+        };
+        let mut authority = zone.read().unwrap(); // poison errors should panic
+
+        // the IXFR request carries the SOA of the client's version of the zone
+        let mut soa_only = false;
+        let mut history = None;
+        let mut changes = None;
+        if is_incremental {
+            let client_serial = message
+                .name_servers()
+                .iter()
+                .filter_map(|record| match *record.rdata() {
+                    RData::SOA(ref soa) => Some(soa.serial()),
+                    _ => None,
+                }).next();
+
+            let client_serial = match client_serial {
+                Some(client_serial) => client_serial,
+                None => {
+                    return send_response(
+                        response_edns,
+                        error_msg(ResponseCode::FormErr),
+                        response_handle,
+                    )
+                }
+            };
+
+            if !authority.is_transfer_allowed(message) {
+                return send_response(
+                    response_edns,
+                    error_msg(ResponseCode::Refused),
+                    response_handle,
+                );
+            }
+
+            if !request.protocol.is_stream()
+                || !is_serial_newer(authority.serial(), client_serial)
+            {
+                soa_only = true;
+            } else if let Some(zone_history) = authority.history() {
+                // the journal is replayed without the zone locked, updates are not held up
+                drop(authority);
+                changes = zone_history
+                    .changes_since(client_serial)
+                    .unwrap_or_else(|error| {
+                        warn!("could not read changes from journal: {}", error);
+                        None
+                    });
+                history = Some(zone_history);
+                authority = zone.read().unwrap();
+            }
+        }
+
+        let (is_dnssec, supported_algorithms) = dnssec_options(message);
+        let soa = authority.soa().next();
+
+        let records: Vec<&Record> = match (soa, history.as_ref(), changes.as_ref()) {
+            (Some(soa), _, _) if soa_only => vec![soa],
+            (Some(_), Some(history), Some(changes)) => {
+                // condensed to a single difference sequence, old SOA, removed, new SOA, added
+                //  the version of the changes is that of the history, the zone may be newer
+                let soa = history.soa();
+                let mut records =
+                    Vec::with_capacity(changes.removed.len() + changes.added.len() + 4);
+                records.push(soa);
+                records.push(&changes.old_soa);
+                records.extend(changes.removed.iter());
+                records.push(soa);
+                records.extend(changes.added.iter());
+                records.push(soa);
+                records
+            }
+            (Some(_), _, _) if !authority.is_transfer_allowed(message) => {
+                return send_response(
+                    response_edns,
+                    error_msg(ResponseCode::Refused),
+                    response_handle,
+                )
+            }
+            (Some(_), _, _) => {
+                match authority.axfr(is_dnssec, supported_algorithms) {
+                    AuthLookup::AXFR(records) => records.collect(),
+                    _ => {
+                        return send_response(
+                            response_edns,
+                            error_msg(ResponseCode::ServFail),
+                            response_handle,
+                        )
+                    }
+                }
+            }
+            // there is no SOA, e.g. the slave zone has not been transferred
+            (None, _, _) => {
+                return send_response(
+                    response_edns,
+                    error_msg(ResponseCode::ServFail),
+                    response_handle,
+                )
+            }
+        };
+
+        info!(
+            "request: {} {} of {} records for: {}",
+            message.id(),
+            query.query_type(),
+            records.len(),
+            authority.origin()
+        );
+
+        let responses: Vec<MessageResponse> = chunk_transfer(records)
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                // the question is only required in the first message
+                let queries = if i == 0 {
+                    Some(message.raw_queries())
+                } else {
+                    None
+                };
+
+                let mut header = Header::new();
+                header.set_id(message.id());
+                header.set_op_code(OpCode::Query);
+                header.set_message_type(MessageType::Response);
+                header.set_authoritative(true);
+
+                let mut response = MessageResponseBuilder::new(queries);
+                response.answers(AuthLookup::AXFRChunk(chunk.into_iter()));
+                response.build(header)
+            }).collect();
+
+        send_responses(response_edns, responses, response_handle)
+    }
+
+    /// Searches the catalog for the forward zone of the name, unless an authority for the name, or
+    ///  one of its parents, is more specific
+    #[cfg(feature = "resolver")]
+    fn find_forward(&self, name: &LowerName) -> Option<&ForwardAuthority> {
+        if self.authorities.contains_key(name) {
+            return None;
+        }
+
+        self.forwarders.get(name).or_else(|| {
+            if name.is_root() {
+                None
+            } else {
+                self.find_forward(&name.base_name())
+            }
+        })
+    }
+
     /// Recursively searches the catalog for a matching authority
     pub fn find(&self, name: &LowerName) -> Option<&RwLock<Authority>> {
-        self.authorities.get(name).or_else(|| {
+        self.authorities.get(name).map(|a| &**a).or_else(|| {
             let name = name.base_name();
             if !name.is_root() {
                 self.find(&name)
@@ -0,0 +1,378 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Server cookies, which prove to the server that a client received its earlier responses
+//!
+//! [RFC 7873](https://tools.ietf.org/html/rfc7873), Domain Name System (DNS) Cookies, May 2016
+//! [RFC 9018](https://tools.ietf.org/html/rfc9018), Interoperable Domain Name System (DNS)
+//!  Server Cookies, April 2021
+
+use std::net::IpAddr;
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rand;
+use trust_dns::rr::rdata::opt::Cookie;
+
+use authority::acl::unmapped;
+
+/// The version of the server cookies of RFC 9018
+const VERSION: u8 = 1;
+/// The length of the server cookies, the version, 3 reserved bytes, the timestamp and the hash
+const SERVER_COOKIE_LEN: usize = 16;
+/// Server cookies are valid for an hour after they were generated
+const MAX_AGE: u32 = 60 * 60;
+/// A valid cookie is replaced once it is half an hour old
+const REFRESH_AGE: u32 = 30 * 60;
+/// Allowance for clocks of servers which share the secret which are behind the clock of this one
+const MAX_FUTURE: u32 = 5 * 60;
+
+/// The secrets which the server cookies are generated with
+///
+/// The server cookies are SipHash-2-4 MACs of the client cookie, the time and the address of the
+///  client, in the format of RFC 9018, so that all servers of an anycast address with the same
+///  secret accept each other's cookies. Cookies of the previous secret are still valid after the
+///  secret is rotated.
+pub struct ServerCookies {
+    secrets: RwLock<Secrets>,
+    rotation: Option<Duration>,
+    required: bool,
+}
+
+struct Secrets {
+    current: [u8; 16],
+    previous: Option<[u8; 16]>,
+    rotated: Instant,
+}
+
+impl ServerCookies {
+    /// Returns cookies of a random secret
+    ///
+    /// # Arguments
+    ///
+    /// * `rotation` - the interval at which the secret is replaced by a random one, if any
+    pub fn new(rotation: Option<Duration>) -> Self {
+        let mut cookies = Self::with_secret(rand::random());
+        cookies.rotation = rotation;
+        cookies
+    }
+
+    /// Returns cookies of the secret, e.g. one which is shared with the other servers of an
+    ///  anycast address, see `new()`
+    ///
+    /// The secret is never replaced by a random one, which the other servers would not share, it
+    ///  is only rotated with `rotate()`.
+    pub fn with_secret(secret: [u8; 16]) -> Self {
+        ServerCookies {
+            secrets: RwLock::new(Secrets {
+                current: secret,
+                previous: None,
+                rotated: Instant::now(),
+            }),
+            rotation: None,
+            required: false,
+        }
+    }
+
+    /// Replaces the secret, the cookies of the replaced secret are still valid until the next
+    ///  rotation, e.g. with a new secret which is shared with the other servers
+    pub fn rotate(&self, secret: [u8; 16]) {
+        let mut secrets = self.secrets.write().expect("poisoned lock");
+        secrets.previous = Some(secrets.current);
+        secrets.current = secret;
+        secrets.rotated = Instant::now();
+    }
+
+    /// Requires a valid server cookie of the requests with a cookie over UDP, which are otherwise
+    ///  answered with BADCOOKIE, and a new server cookie to retry with
+    ///
+    /// Requests without a cookie, and requests over TCP, are answered as usual.
+    pub fn set_required(&mut self, required: bool) {
+        self.required = required;
+    }
+
+    /// Returns true if a valid server cookie is required over UDP, see `set_required()`
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Returns true if the server cookie of the cookie was generated, by this server or one with
+    ///  the same secret, for the client within the last hour
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie` - the cookie of the request
+    /// * `src` - the address of the client
+    /// * `now` - the time, in seconds since the Unix epoch
+    pub fn is_valid(&self, cookie: &Cookie, src: IpAddr, now: u32) -> bool {
+        self.is_fresh(cookie, src, now, MAX_AGE)
+    }
+
+    /// Returns the cookie of the response, the cookie of the request if it is valid and recent,
+    ///  otherwise a new one
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie` - the cookie of the request
+    /// * `src` - the address of the client
+    /// * `now` - the time, in seconds since the Unix epoch
+    pub fn respond(&self, cookie: &Cookie, src: IpAddr, now: u32) -> Cookie {
+        self.rotate_if_due();
+        if self.is_fresh(cookie, src, now, REFRESH_AGE) {
+            return cookie.clone();
+        }
+
+        let secret = self.secrets.read().expect("poisoned lock").current;
+        let server = server_cookie(&secret, cookie.client(), src, now);
+        Cookie::new(*cookie.client(), server.to_vec()).expect("server cookie has a valid length")
+    }
+
+    /// Returns true if the server cookie is valid and no older than max_age seconds
+    fn is_fresh(&self, cookie: &Cookie, src: IpAddr, now: u32, max_age: u32) -> bool {
+        let server = match cookie.server() {
+            Some(server) if server.len() == SERVER_COOKIE_LEN && server[0] == VERSION => server,
+            _ => return false,
+        };
+
+        let timestamp = (u32::from(server[4]) << 24)
+            | (u32::from(server[5]) << 16)
+            | (u32::from(server[6]) << 8)
+            | u32::from(server[7]);
+
+        // serial number arithmetic, the timestamps wrap in 2106
+        let age = now.wrapping_sub(timestamp);
+        if age > max_age && timestamp.wrapping_sub(now) > MAX_FUTURE {
+            return false;
+        }
+
+        let secrets = self.secrets.read().expect("poisoned lock");
+        let matches = |secret: &[u8; 16]| {
+            constant_time_eq(&server_cookie(secret, cookie.client(), src, timestamp), server)
+        };
+
+        matches(&secrets.current) || secrets.previous.as_ref().map_or(false, matches)
+    }
+
+    /// Replaces a random secret with a new random one, a shared secret is not rotated
+    fn rotate_if_due(&self) {
+        let rotation = match self.rotation {
+            Some(rotation) => rotation,
+            None => return,
+        };
+
+        let is_due = |secrets: &Secrets| secrets.rotated.elapsed() >= rotation;
+        if !is_due(&self.secrets.read().expect("poisoned lock")) {
+            return;
+        }
+
+        let mut secrets = self.secrets.write().expect("poisoned lock");
+        // another request may have rotated it in the meantime
+        if is_due(&secrets) {
+            debug!("rotating the server cookie secret");
+            secrets.previous = Some(secrets.current);
+            secrets.current = rand::random();
+            secrets.rotated = Instant::now();
+        }
+    }
+}
+
+/// The current time, in seconds since the Unix epoch, truncated to 32 bits, as in server cookies
+pub(crate) fn unix_time() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+/// Compares the server cookies in constant time, the time taken does not reveal the matching bytes
+#[cfg(all(not(feature = "ring"), feature = "openssl"))]
+fn constant_time_eq(expected: &[u8], cookie: &[u8]) -> bool {
+    use openssl::memcmp;
+
+    expected.len() == cookie.len() && memcmp::eq(expected, cookie)
+}
+
+/// Compares the server cookies in constant time, the time taken does not reveal the matching bytes
+#[cfg(feature = "ring")]
+fn constant_time_eq(expected: &[u8], cookie: &[u8]) -> bool {
+    use ring::constant_time;
+
+    constant_time::verify_slices_are_equal(expected, cookie).is_ok()
+}
+
+/// Compares the server cookies in constant time, the time taken does not reveal the matching bytes
+///
+/// The openssl and ring features are both disabled, the difference of every byte is accumulated
+#[cfg(not(any(feature = "openssl", feature = "ring")))]
+fn constant_time_eq(expected: &[u8], cookie: &[u8]) -> bool {
+    expected.len() == cookie.len()
+        && expected
+            .iter()
+            .zip(cookie)
+            .fold(0, |difference, (e, c)| difference | (e ^ c))
+            == 0
+}
+
+/// Returns the server cookie of RFC 9018 for the client at the time
+fn server_cookie(
+    secret: &[u8; 16],
+    client: &[u8; 8],
+    src: IpAddr,
+    timestamp: u32,
+) -> [u8; SERVER_COOKIE_LEN] {
+    let mut cookie = [0u8; SERVER_COOKIE_LEN];
+    cookie[0] = VERSION;
+    cookie[4] = (timestamp >> 24) as u8;
+    cookie[5] = (timestamp >> 16) as u8;
+    cookie[6] = (timestamp >> 8) as u8;
+    cookie[7] = timestamp as u8;
+
+    // the client cookie, the version, reserved bytes and timestamp, and the address of the client
+    let mut input = Vec::with_capacity(8 + 8 + 16);
+    input.extend_from_slice(client);
+    input.extend_from_slice(&cookie[..8]);
+    match unmapped(src) {
+        IpAddr::V4(src) => input.extend_from_slice(&src.octets()),
+        IpAddr::V6(src) => input.extend_from_slice(&src.octets()),
+    }
+
+    let hash = siphash24(secret, &input);
+    for (i, byte) in cookie[8..].iter_mut().enumerate() {
+        *byte = (hash >> (8 * i)) as u8;
+    }
+
+    cookie
+}
+
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .enumerate()
+        .fold(0, |word, (i, byte)| word | (u64::from(*byte) << (8 * i)))
+}
+
+/// SipHash-2-4 of the data with the key, see https://131002.net/siphash/
+fn siphash24(key: &[u8; 16], data: &[u8]) -> u64 {
+    let k0 = read_u64_le(&key[..8]);
+    let k1 = read_u64_le(&key[8..]);
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    // the final block holds the remaining bytes, and the length of the data in the last byte
+    let tail = data.len() - data.len() % 8;
+    let blocks = data[..tail]
+        .chunks(8)
+        .map(read_u64_le)
+        .chain(Some(read_u64_le(&data[tail..]) | (data.len() as u64) << 56));
+    for m in blocks {
+        v[3] ^= m;
+        sip_round(&mut v);
+        sip_round(&mut v);
+        v[0] ^= m;
+    }
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: [u8; 8] = [0x24, 0x64, 0xc4, 0xab, 0xcf, 0x10, 0xc9, 0x57];
+    const SECRET: [u8; 16] = [
+        0xe5, 0xe9, 0x73, 0xe5, 0xa6, 0xb2, 0xa4, 0x3f, 0x48, 0xe7, 0xdc, 0x84, 0x9e, 0x37, 0xbf,
+        0xcf,
+    ];
+
+    #[test]
+    fn test_siphash24() {
+        // the test vectors of the reference implementation
+        let key = [
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+        ];
+        let data: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(&key, &[]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash24(&key, &data[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(siphash24(&key, &data), 0xa129_ca61_49be_45e5);
+    }
+
+    #[test]
+    fn test_server_cookie() {
+        // RFC 9018, Appendix A.1
+        let src = IpAddr::from([198, 51, 100, 100]);
+        assert_eq!(
+            server_cookie(&SECRET, &CLIENT, src, 1_559_731_985),
+            [
+                0x01, 0x00, 0x00, 0x00, 0x5c, 0xf7, 0x9f, 0x11, 0x1f, 0x81, 0x30, 0xc3, 0xee,
+                0xe2, 0x94, 0x80,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(&SECRET, &SECRET));
+        assert!(!constant_time_eq(&SECRET, &SECRET[..15]));
+        assert!(!constant_time_eq(&SECRET[..8], &CLIENT));
+        assert!(constant_time_eq(&[], &[]));
+    }
+
+    #[test]
+    fn test_respond() {
+        let cookies = ServerCookies::with_secret(SECRET);
+        let src = IpAddr::from([198, 51, 100, 100]);
+        let now = 1_559_731_985;
+
+        let request = Cookie::new(CLIENT, Vec::new()).unwrap();
+        assert!(!cookies.is_valid(&request, src, now));
+
+        let response = cookies.respond(&request, src, now);
+        assert_eq!(response.client(), &CLIENT);
+        assert!(cookies.is_valid(&response, src, now));
+        assert!(cookies.is_valid(&response, src, now + MAX_AGE));
+        assert!(!cookies.is_valid(&response, src, now + MAX_AGE + 1));
+        assert!(!cookies.is_valid(&response, IpAddr::from([198, 51, 100, 101]), now));
+
+        // recent cookies are kept, older ones replaced
+        assert_eq!(cookies.respond(&response, src, now + 10), response);
+        let refreshed = cookies.respond(&response, src, now + REFRESH_AGE + 1);
+        assert_ne!(refreshed, response);
+        assert!(cookies.is_valid(&refreshed, src, now + REFRESH_AGE + 1));
+
+        // cookies of the previous secret are valid until it is rotated again
+        cookies.rotate([1; 16]);
+        assert!(cookies.is_valid(&response, src, now));
+        cookies.rotate([2; 16]);
+        assert!(!cookies.is_valid(&response, src, now));
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Writes zones which have changed, e.g. through dynamic updates, back to their zone files
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use futures::{Future, Stream};
+use tokio_timer::Interval;
+
+use trust_dns::rr::Name;
+use trust_dns::serialize::txt::{Lexer, Token};
+
+use authority::authority::write_zone_file;
+use authority::Authority;
+use error::PersistenceResult;
+
+/// Writes a zone back to its zone file, and compacts its journal, whenever the zone has changed.
+///
+/// The zone is compared by SOA serial with the version last written. Nothing has been written
+///  when the `ZoneFlush` is created, so the first flush always writes the zone file; the file may
+///  be older than a zone recovered from its journal.
+#[derive(Clone)]
+pub struct ZoneFlush {
+    authority: Arc<RwLock<Authority>>,
+    zone_path: PathBuf,
+    // also serializes flushes, i.e. periodic and on shutdown
+    flushed_serial: Arc<Mutex<Option<u32>>>,
+}
+
+impl ZoneFlush {
+    /// Returns a new flush of the zone
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - the zone to write, this is shared with the `Catalog`
+    /// * `zone_path` - path to the zone file, which is replaced on each flush
+    pub fn new(authority: Arc<RwLock<Authority>>, zone_path: PathBuf) -> Self {
+        ZoneFlush {
+            authority,
+            zone_path,
+            flushed_serial: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The zone file to which the zone is written
+    pub fn zone_path(&self) -> &Path {
+        &self.zone_path
+    }
+
+    /// Returns true if the zone file has `$INCLUDE` or `$GENERATE` entries
+    ///
+    /// The zone is written as the records it consists of, so these entries, and the files they
+    ///  include, would be replaced by their records; such zone files should not be flushed.
+    pub fn has_include_or_generate(&self) -> io::Result<bool> {
+        let mut buf = String::new();
+        File::open(&self.zone_path)?.read_to_string(&mut buf)?;
+
+        let mut lexer = Lexer::new(&buf);
+        loop {
+            match lexer.next_token() {
+                Ok(Some(Token::Include)) | Ok(Some(Token::Generate)) => return Ok(true),
+                Ok(Some(_)) => (),
+                Ok(None) => return Ok(false),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            }
+        }
+    }
+
+    /// Writes the zone file and compacts the journal if the zone changed since the last flush
+    ///
+    /// Returns true if the zone was written.
+    ///
+    /// The records are copied under the read lock of the zone, which is released while the file
+    ///  is written, so that updates and queries are not held up by the write.
+    pub fn flush(&self) -> PersistenceResult<bool> {
+        let mut flushed_serial = self.flushed_serial.lock().expect("flushed_serial poisoned");
+
+        let (serial, origin, records) = {
+            let authority = self.authority.read().expect("authority poisoned");
+
+            let serial = authority.serial();
+            if *flushed_serial == Some(serial) {
+                return Ok(false);
+            }
+
+            let origin: Name = authority.origin().clone().into();
+            (serial, origin, authority.zone_file_records())
+        };
+
+        info!("writing zone at SOA.serial: {} to: {:?}", serial, self.zone_path);
+        write_zone_file(&origin, &records, &self.zone_path)?;
+        *flushed_serial = Some(serial);
+
+        self.authority
+            .read()
+            .expect("authority poisoned")
+            .compact_journal()?;
+        Ok(true)
+    }
+
+    /// Returns a never ending future which flushes the zone every `interval`
+    ///
+    /// Errors are logged, and the zone is flushed again after the next interval.
+    pub fn every(&self, interval: Duration) -> Box<Future<Item = (), Error = ()> + Send> {
+        let zone_flush = self.clone();
+
+        Box::new(
+            Interval::new(Instant::now() + interval, interval)
+                .map_err(|e| error!("zone flush timer failed: {}", e))
+                .for_each(move |_| {
+                    if let Err(e) = zone_flush.flush() {
+                        error!(
+                            "failed to write zone file {:?}: {}",
+                            zone_flush.zone_path, e
+                        );
+                    }
+
+                    Ok(())
+                }),
+        )
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Forward zones, all queries for the zone are answered by upstream name servers, and Hint zones,
+//!  for which the queries are resolved iteratively from the root name servers
+
+use std::net::IpAddr;
+use std::time::Instant;
+
+use futures::Future;
+
+use trust_dns::op::LowerQuery;
+use trust_dns::rr::{LowerName, Name, RData, Record, RecordType};
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::lookup::Lookup;
+use trust_dns_resolver::{AsyncResolver, Recursor};
+
+/// A zone for which all queries are forwarded to a set of upstream name servers, or resolved
+///  iteratively starting from the root name servers
+///
+/// Lookups are performed with the `trust-dns-resolver`, and so are cached for the TTL of the
+///  upstream answers.
+pub struct ForwardAuthority {
+    origin: LowerName,
+    upstream: Upstream,
+}
+
+enum Upstream {
+    /// Upstream name servers which recurse on behalf of the zone
+    Resolver(AsyncResolver),
+    /// Iterative resolution from the root name servers
+    Recursor(Box<Recursor>),
+}
+
+impl ForwardAuthority {
+    /// Creates a new forward zone
+    ///
+    /// The returned background future drives the resolver, and must be spawned on the executor
+    ///  before any lookups will complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the zone being forwarded, e.g. `example.com.` or `.` for all names
+    /// * `name_servers` - the upstream name servers to which queries are forwarded
+    pub fn new(
+        origin: Name,
+        name_servers: NameServerConfigGroup,
+    ) -> (Self, impl Future<Item = (), Error = ()>) {
+        let config = ResolverConfig::from_parts(None, Vec::new(), name_servers);
+
+        // answers come only from the upstream name servers
+        let mut options = ResolverOpts::default();
+        options.use_hosts_file = false;
+
+        let (resolver, background) = AsyncResolver::new(config, options);
+        let authority = ForwardAuthority {
+            origin: LowerName::new(&origin),
+            upstream: Upstream::Resolver(resolver),
+        };
+
+        (authority, background)
+    }
+
+    /// Creates a new Hint zone, which resolves queries itself by following the referrals from the
+    ///  root name servers
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the zone being resolved, generally `.` for all names
+    /// * `roots` - the root name servers, see `root_hints`
+    pub fn recursive(origin: Name, roots: NameServerConfigGroup) -> Self {
+        ForwardAuthority {
+            origin: LowerName::new(&origin),
+            upstream: Upstream::Recursor(Box::new(Recursor::new(roots, ResolverOpts::default()))),
+        }
+    }
+
+    /// Get the origin of this zone
+    pub fn origin(&self) -> &LowerName {
+        &self.origin
+    }
+
+    /// Forwards the query to the upstream name servers, or resolves it from the root name servers
+    ///
+    /// The records are returned as answered, including any CNAMEs followed. The TTLs of the
+    ///  records of forwarded queries are at most the time remaining in the cache.
+    pub fn lookup(
+        &self,
+        query: &LowerQuery,
+    ) -> Box<Future<Item = Vec<Record>, Error = ResolveError> + Send> {
+        match self.upstream {
+            Upstream::Resolver(ref resolver) => {
+                let name = query.original().name().clone();
+                let query_type = query.query_type();
+
+                Box::new(
+                    resolver
+                        .lookup(name, query_type)
+                        .map(|lookup| lookup_records(&lookup)),
+                )
+            }
+            Upstream::Recursor(ref recursor) => recursor.resolve(query.original().clone()),
+        }
+    }
+}
+
+/// Returns the root name servers from the records of a root hints file
+///
+/// These are the addresses of the name servers in the NS records of the root zone.
+pub fn root_hints<'r, I: IntoIterator<Item = &'r Record>>(hints: I) -> NameServerConfigGroup {
+    let hints = hints.into_iter().collect::<Vec<_>>();
+    let name_servers = hints
+        .iter()
+        .filter(|r| r.rr_type() == RecordType::NS && r.name().is_root())
+        .filter_map(|r| match *r.rdata() {
+            RData::NS(ref name) => Some(name),
+            _ => None,
+        }).collect::<Vec<_>>();
+
+    let addresses = hints
+        .iter()
+        .filter(|r| name_servers.contains(&r.name()))
+        .filter_map(|r| match *r.rdata() {
+            RData::A(ip) => Some(IpAddr::V4(ip)),
+            RData::AAAA(ip) => Some(IpAddr::V6(ip)),
+            _ => None,
+        }).collect::<Vec<_>>();
+
+    NameServerConfigGroup::from_ips_clear(&addresses, 53)
+}
+
+fn lookup_records(lookup: &Lookup) -> Vec<Record> {
+    let now = Instant::now();
+    let remaining = if lookup.valid_until() > now {
+        (lookup.valid_until() - now).as_secs() as u32
+    } else {
+        0
+    };
+
+    lookup
+        .record_iter()
+        .map(|record| {
+            let mut record = record.clone();
+            let ttl = record.ttl().min(remaining);
+            record.set_ttl(ttl);
+            record
+        }).collect()
+}
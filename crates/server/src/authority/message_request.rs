@@ -22,6 +22,7 @@ pub struct MessageRequest<'r> {
     additionals: Vec<Record>,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    original: &'r [u8],
 }
 
 impl<'r> MessageRequest<'r> {
@@ -145,7 +146,7 @@ impl<'r> MessageRequest<'r> {
         self.edns.as_ref()
     }
 
-    /// Any SIG0 records for signed messages
+    /// Any SIG0 or TSIG records for signed messages
     pub fn sig0(&self) -> &[Record] {
         &self.sig0
     }
@@ -173,12 +174,18 @@ impl<'r> MessageRequest<'r> {
     pub fn raw_queries(&self) -> &Queries {
         &self.queries
     }
+
+    /// Returns the bytes of the message as they were seen from the Client, e.g. to verify a TSIG
+    pub fn as_bytes(&self) -> &'r [u8] {
+        self.original
+    }
 }
 
 impl<'r> BinDecodable<'r> for MessageRequest<'r> {
     // TODO: generify this with Message?
     /// Reads a MessageRequest from the decoder
     fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let start = decoder.index();
         let header = Header::read(decoder)?;
 
         // TODO/FIXME: return just header, and in the case of the rest of message getting an error.
@@ -196,6 +203,7 @@ impl<'r> BinDecodable<'r> for MessageRequest<'r> {
         let (answers, _, _) = Message::read_records(decoder, answer_count, false)?;
         let (name_servers, _, _) = Message::read_records(decoder, name_server_count, false)?;
         let (additionals, edns, sig0) = Message::read_records(decoder, additional_count, true)?;
+        let original = decoder.slice_from(start)?;
 
         Ok(MessageRequest {
             header,
@@ -205,6 +213,7 @@ impl<'r> BinDecodable<'r> for MessageRequest<'r> {
             additionals,
             sig0,
             edns,
+            original,
         })
     }
 }
@@ -303,7 +312,7 @@ pub trait UpdateRequest {
     /// Additional records
     fn additionals(&self) -> &[Record];
 
-    /// SIG0 records for verifying the Message
+    /// SIG0 and TSIG records for verifying the Message
     fn sig0(&self) -> &[Record];
 }
 
@@ -5,13 +5,26 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::cell::Cell;
 use std::iter::Chain;
+#[cfg(feature = "dnssec")]
+use std::sync::{Arc, Mutex};
 
+#[cfg(feature = "dnssec")]
+use chrono::Utc;
 use proto::error::*;
 use proto::op::message::EmitAndCount;
 use proto::op::{message, Edns, Header, MessageType, OpCode, ResponseCode};
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::rdata::tsig::{self, TSIG};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TSigner;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::DNSSECRecordType;
 use trust_dns::rr::Record;
-use trust_dns::serialize::binary::BinEncoder;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::RecordType;
+use trust_dns::serialize::binary::{BinEncodable, BinEncoder};
 
 use authority::message_request::QueriesEmitAndCount;
 use authority::{AuthLookup, LookupRecords, Queries};
@@ -34,6 +47,149 @@ pub struct MessageResponse<
     additionals: Vec<&'a Record>,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    #[cfg(feature = "dnssec")]
+    tsig: Option<TsigResponseSigner>,
+}
+
+/// Signs the responses to a request which was signed with a TSIG
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS (TSIG), November 2020](https://tools.ietf.org/html/rfc8945)
+///
+/// The MAC of the first response covers the MAC of the request, and each later response of a
+///  zone transfer covers the MAC of the response before it, only with the timers of its TSIG.
+///  Clones share this chain, so the responses must be emitted in the order they are sent.
+#[cfg(feature = "dnssec")]
+#[derive(Clone, Debug)]
+pub struct TsigResponseSigner {
+    state: Arc<Mutex<TsigResponseState>>,
+}
+
+#[cfg(feature = "dnssec")]
+#[derive(Debug)]
+struct TsigResponseState {
+    signer: TSigner,
+    previous_mac: Vec<u8>,
+    first_message: bool,
+    time: Option<u64>,
+    error: u16,
+    other: Vec<u8>,
+}
+
+#[cfg(feature = "dnssec")]
+impl TsigResponseSigner {
+    /// Returns a signer for the responses to a request which was verified with the key
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - the key which the request was signed with
+    /// * `request_mac` - the MAC of the TSIG of the request
+    pub fn new(signer: TSigner, request_mac: Vec<u8>) -> Self {
+        Self::with_state(TsigResponseState {
+            signer,
+            previous_mac: request_mac,
+            first_message: true,
+            time: None,
+            error: 0,
+            other: Vec::new(),
+        })
+    }
+
+    /// Returns a signer for the BADTIME response to a request, which was signed outside of the
+    ///  fudge of the key
+    ///
+    /// The response has the time of the request, the time of the server is in the other data of
+    ///  its TSIG, so that the client may learn of the difference of their clocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - the key which the request was signed with
+    /// * `request_tsig` - the TSIG of the request
+    /// * `now` - the current time of the server
+    pub fn bad_time(signer: TSigner, request_tsig: &TSIG, now: u64) -> Self {
+        Self::with_state(TsigResponseState {
+            signer,
+            previous_mac: request_tsig.mac().to_vec(),
+            first_message: true,
+            time: Some(request_tsig.time()),
+            error: u16::from(ResponseCode::BADTIME),
+            other: tsig::encode_time(now),
+        })
+    }
+
+    fn with_state(state: TsigResponseState) -> Self {
+        TsigResponseSigner {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns the length of the TSIG record which is appended to a response, at most
+    fn record_len(&self) -> ProtoResult<usize> {
+        let state = self.state.lock().expect("poisoned tsig state");
+
+        let mac = vec![0; state.signer.algorithm().output_len()];
+        let record = tsig::make_tsig_record(
+            state.signer.signer_name().clone(),
+            state.pre_tsig(0).set_mac(mac),
+        );
+        Ok(record.to_bytes()?.len())
+    }
+
+    /// Signs the message which was emitted to the encoder from `start`, the TSIG record is
+    ///  appended and counted in the header of the message
+    fn sign_emitted(&self, id: u16, encoder: &mut BinEncoder, start: usize) -> ProtoResult<()> {
+        let mut state = self.state.lock().expect("poisoned tsig state");
+
+        let pre_tsig = state.pre_tsig(id);
+
+        let end = encoder.offset();
+        let tbs = tsig::encoded_message_tbs(
+            Some(&state.previous_mac),
+            encoder.slice_of(start, end),
+            &pre_tsig,
+            state.signer.signer_name(),
+            state.first_message,
+        )?;
+        let mac = state.signer.sign(&tbs)?;
+        let record = tsig::make_tsig_record(
+            state.signer.signer_name().clone(),
+            pre_tsig.set_mac(mac.clone()),
+        );
+        record.emit(encoder)?;
+
+        // the additional count is the last of the counts in the header
+        let count_offset = start + 10;
+        let additional_count = {
+            let count = encoder.slice_of(count_offset, count_offset + 2);
+            (u16::from(count[0]) << 8 | u16::from(count[1])) + 1
+        };
+        let tsig_end = encoder.offset();
+        encoder.set_offset(count_offset);
+        encoder.emit_u16(additional_count)?;
+        encoder.set_offset(tsig_end);
+
+        state.previous_mac = mac;
+        state.first_message = false;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "dnssec")]
+impl TsigResponseState {
+    /// Returns the TSIG of the next response, without a MAC
+    fn pre_tsig(&self, id: u16) -> TSIG {
+        let time = self
+            .time
+            .unwrap_or_else(|| Utc::now().timestamp() as u64);
+        TSIG::new(
+            self.signer.algorithm().clone(),
+            time,
+            self.signer.fudge(),
+            Vec::new(),
+            id,
+            self.error,
+            self.other.clone(),
+        )
+    }
 }
 
 enum EmptyOrQueries<'q> {
@@ -58,6 +214,135 @@ impl<'q> EmitAndCount for EmptyOrQueries<'q> {
     }
 }
 
+/// The additional section of a response
+///
+/// [RFC 2181](https://tools.ietf.org/html/rfc2181#section-9), Clarifications to the DNS Specification, July 1997
+///
+/// ```text
+///    The TC bit should not be set merely because some extra information
+///    could have been included, but there was insufficient room.
+/// ```
+///
+/// Additional records which do not fit in the message are left out, without truncating it. The
+///  EDNS record is always emitted, in the room which was kept for it.
+struct AdditionalsEmitAndCount<'a, 'r, 't> {
+    edns: Option<Record>,
+    additionals: &'r [&'a Record],
+    truncated: &'t Cell<bool>,
+    max_size: u16,
+}
+
+impl<'a, 'r, 't> EmitAndCount for AdditionalsEmitAndCount<'a, 'r, 't> {
+    fn emit(&mut self, encoder: &mut BinEncoder) -> ProtoResult<usize> {
+        encoder.set_max_size(self.max_size);
+        let edns_count = encoder.emit_all(self.edns.iter())?;
+        if self.truncated.get() {
+            return Ok(edns_count);
+        }
+
+        let (count, _) = emit_records(self.additionals.iter().cloned(), false, encoder)?;
+        Ok(edns_count + count)
+    }
+}
+
+/// The answer or authority section of a response
+///
+/// [RFC 2181](https://tools.ietf.org/html/rfc2181#section-9), Clarifications to the DNS Specification, July 1997
+///
+/// ```text
+///    Where TC is set, the partial RRSet that would not completely fit may
+///    be left in the response.  When a DNS client receives a reply with TC
+///    set, it should ignore that response, and query again, using a
+///    mechanism, such as a TCP connection, that will permit larger replies.
+/// ```
+///
+/// A record set which does not fit in the message is left out whole, and the message is
+///  truncated. The sections after a truncated section are left empty.
+struct SectionEmitAndCount<'t, I> {
+    records: I,
+    truncated: &'t Cell<bool>,
+}
+
+impl<'a, 't, I: Iterator<Item = &'a Record>> EmitAndCount for SectionEmitAndCount<'t, I> {
+    fn emit(&mut self, encoder: &mut BinEncoder) -> ProtoResult<usize> {
+        if self.truncated.get() {
+            return Ok(0);
+        }
+
+        let (count, truncated) = emit_records(&mut self.records, true, encoder)?;
+        if truncated {
+            self.truncated.set(true);
+            return Err(ProtoErrorKind::NotAllRecordsWritten { count }.into());
+        }
+
+        Ok(count)
+    }
+}
+
+/// Emits the records in order, up to the first record which does not fit in the message
+///
+/// Returns the count of the records which were emitted, and true if a record was left out.
+///
+/// # Arguments
+///
+/// * `records` - the records to emit, the RRSIGs of a record set follow its records
+/// * `whole_rrsets` - if true, the records of the record set of the first record which does not
+///                    fit are left out as well, with their RRSIGs
+/// * `encoder` - the encoder of the message
+fn emit_records<'a, I>(
+    records: I,
+    whole_rrsets: bool,
+    encoder: &mut BinEncoder,
+) -> ProtoResult<(usize, bool)>
+where
+    I: Iterator<Item = &'a Record>,
+{
+    let mut count = 0;
+    let mut rrset_start = (encoder.offset(), count);
+    let mut previous: Option<&Record> = None;
+
+    for record in records {
+        let is_same_rrset =
+            whole_rrsets && previous.map_or(false, |previous| is_same_rrset(previous, record));
+        if !is_same_rrset {
+            rrset_start = (encoder.offset(), count);
+        }
+
+        if let Err(e) = record.emit(encoder) {
+            if let ProtoErrorKind::MaxBufferSizeExceeded(_) = *e.kind() {
+                let (offset, count) = rrset_start;
+                encoder.set_offset(offset);
+                // also drops the label pointers into the records which were left out
+                encoder.trim();
+                return Ok((count, true));
+            }
+
+            return Err(e);
+        }
+
+        count += 1;
+        previous = Some(record);
+    }
+
+    Ok((count, false))
+}
+
+fn is_same_rrset(previous: &Record, record: &Record) -> bool {
+    record.name() == previous.name()
+        && record.dns_class() == previous.dns_class()
+        && (record.rr_type() == previous.rr_type() || is_rrsig(record))
+}
+
+#[cfg(feature = "dnssec")]
+fn is_rrsig(record: &Record) -> bool {
+    record.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG)
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn is_rrsig(_record: &Record) -> bool {
+    false
+}
+
 impl<'q, 'a, A, N> MessageResponse<'q, 'a, A, N>
 where
     A: 'q + 'a + Iterator<Item = &'a Record>,
@@ -74,18 +359,73 @@ where
         self
     }
 
+    /// Sign the Response with TSIG, as it is emitted
+    #[cfg(feature = "dnssec")]
+    pub fn set_tsig_signer(&mut self, signer: TsigResponseSigner) -> &mut Self {
+        self.tsig = Some(signer);
+        self
+    }
+
     /// Consumes self, and emits to the encoder.
+    ///
+    /// The response is truncated to the maximum size of the encoder, see `SectionEmitAndCount`.
     pub fn destructive_emit(mut self, encoder: &mut BinEncoder) -> ProtoResult<()> {
-        message::emit_message_parts(
+        #[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
+        let start = encoder.offset();
+        let max_size = encoder.max_size();
+
+        // room is kept for the EDNS and TSIG records, they must be in a truncated response
+        let edns = self.edns.as_ref().map(Record::from);
+        let edns_len = match edns {
+            Some(ref edns) => edns.to_bytes()?.len(),
+            None => 0,
+        };
+        #[cfg(feature = "dnssec")]
+        let tsig_len = match self.tsig {
+            Some(ref signer) => signer.record_len()?,
+            None => 0,
+        };
+        #[cfg(not(feature = "dnssec"))]
+        let tsig_len = 0;
+        let less = |len: usize| (max_size as usize).saturating_sub(len) as u16;
+
+        // the EDNS record is emitted with the additional records, ahead of them
+        let truncated = Cell::new(false);
+        let mut additionals = AdditionalsEmitAndCount {
+            edns,
+            additionals: &self.additionals,
+            truncated: &truncated,
+            max_size: less(tsig_len),
+        };
+
+        encoder.set_max_size(less(tsig_len + edns_len));
+        let emitted = message::emit_message_parts(
             &self.header,
             &mut EmptyOrQueries::from(self.queries),
-            &mut self.answers,
-            &mut self.name_servers,
-            &mut self.additionals.iter().cloned(),
-            self.edns.as_ref(),
+            &mut SectionEmitAndCount {
+                records: &mut self.answers,
+                truncated: &truncated,
+            },
+            &mut SectionEmitAndCount {
+                records: &mut self.name_servers,
+                truncated: &truncated,
+            },
+            &mut additionals,
+            None,
             &self.sig0,
             encoder,
-        )
+        );
+        encoder.set_max_size(max_size);
+        emitted?;
+
+        #[cfg(feature = "dnssec")]
+        {
+            if let Some(ref signer) = self.tsig {
+                signer.sign_emitted(self.header.id(), encoder, start)?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -131,12 +471,25 @@ impl<'q, 'a> MessageResponseBuilder<'q, 'a> {
         self
     }
 
+    /// Associate a set of additional records with the response, e.g. the glue of a referral
+    pub fn additionals(&mut self, records: Vec<&'a Record>) -> &mut Self {
+        self.additionals = Some(records);
+        self
+    }
+
     /// Associate EDNS with the Response
     pub fn edns(&mut self, edns: Edns) -> &mut Self {
         self.edns = Some(edns);
         self
     }
 
+    /// Associate SIG0 or TSIG records with the Response, which are emitted last, e.g. the unsigned
+    ///  TSIG of an error which prevented the response from being signed
+    pub fn sig0(&mut self, records: Vec<Record>) -> &mut Self {
+        self.sig0 = Some(records);
+        self
+    }
+
     /// Constructs the new MessageResponse with associated Header
     ///
     /// # Arguments
@@ -153,6 +506,8 @@ impl<'q, 'a> MessageResponseBuilder<'q, 'a> {
             additionals: self.additionals.unwrap_or_default(),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 
@@ -185,6 +540,8 @@ impl<'q, 'a> MessageResponseBuilder<'q, 'a> {
             additionals: self.additionals.unwrap_or_default(),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 }
@@ -222,6 +579,8 @@ mod tests {
                 additionals: vec![],
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -231,7 +590,8 @@ mod tests {
 
         let response = Message::from_vec(&buf).expect("failed to decode");
         assert!(response.header().truncated());
-        assert!(response.answer_count() > 1);
+        // the record set did not fit, none of it is written
+        assert_eq!(response.answer_count(), 0);
         // should never have written the name server field...
         assert_eq!(response.name_server_count(), 0);
     }
@@ -257,6 +617,8 @@ mod tests {
                 additionals: vec![],
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -267,6 +629,136 @@ mod tests {
         let response = Message::from_vec(&buf).expect("failed to decode");
         assert!(response.header().truncated());
         assert_eq!(response.answer_count(), 0);
-        assert!(response.name_server_count() > 1);
+        assert_eq!(response.name_server_count(), 0);
+    }
+
+    #[test]
+    fn test_truncation_drops_partial_rrset() {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            encoder.set_max_size(512);
+
+            let answer = answer_record();
+            let other = Record::new()
+                .set_name(Name::from_str("other.example.com.").unwrap())
+                .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 35)))
+                .set_dns_class(DNSClass::NONE)
+                .clone();
+
+            let message = MessageResponse {
+                header: Header::new(),
+                queries: None,
+                answers: iter::once(&answer).chain(iter::repeat(&other).take(100)),
+                name_servers: iter::once(&answer),
+                additionals: vec![&answer],
+                sig0: vec![],
+                edns: Some(Edns::new()),
+                #[cfg(feature = "dnssec")]
+                tsig: None,
+            };
+
+            message
+                .destructive_emit(&mut encoder)
+                .expect("failed to encode");
+        }
+
+        assert!(buf.len() <= 512);
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert!(response.header().truncated());
+        assert_eq!(response.answers(), &[answer_record()]);
+        // the sections after the truncated one are empty, other than the EDNS
+        assert_eq!(response.name_server_count(), 0);
+        assert!(response.additionals().is_empty());
+        assert!(response.edns().is_some());
+    }
+
+    fn answer_record() -> Record {
+        Record::new()
+            .set_name(Name::from_str("www.example.com.").unwrap())
+            .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+            .set_dns_class(DNSClass::NONE)
+            .clone()
+    }
+
+    #[cfg(any(feature = "dnssec-openssl", feature = "dnssec-ring"))]
+    #[test]
+    fn test_truncation_keeps_tsig() {
+        use proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+
+        let signer = TSigner::new(
+            b"some secret".to_vec(),
+            TsigAlgorithm::HmacSha512,
+            Name::from_str("key.example.com.").unwrap(),
+            300,
+        ).unwrap();
+
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            encoder.set_max_size(512);
+
+            let answer = answer_record();
+            let mut message = MessageResponse {
+                header: Header::new(),
+                queries: None,
+                answers: iter::repeat(&answer),
+                name_servers: iter::empty(),
+                additionals: vec![],
+                sig0: vec![],
+                edns: Some(Edns::new()),
+                tsig: None,
+            };
+            message.set_tsig_signer(TsigResponseSigner::new(signer, vec![]));
+
+            message
+                .destructive_emit(&mut encoder)
+                .expect("failed to encode");
+        }
+
+        assert!(buf.len() <= 512);
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert!(response.header().truncated());
+        assert_eq!(response.answer_count(), 0);
+        assert!(response.edns().is_some());
+        assert_eq!(response.sig0().len(), 1);
+    }
+
+    #[test]
+    fn test_additionals_do_not_truncate() {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            encoder.set_max_size(512);
+
+            let answer = Record::new()
+                .set_name(Name::from_str("www.example.com.").unwrap())
+                .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+                .set_dns_class(DNSClass::NONE)
+                .clone();
+
+            let message = MessageResponse {
+                header: Header::new(),
+                queries: None,
+                answers: iter::once(&answer),
+                name_servers: iter::empty(),
+                additionals: vec![&answer; 100],
+                sig0: vec![],
+                edns: Some(Edns::new()),
+                #[cfg(feature = "dnssec")]
+                tsig: None,
+            };
+
+            message
+                .destructive_emit(&mut encoder)
+                .expect("failed to encode");
+        }
+
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert!(!response.header().truncated());
+        assert_eq!(response.answer_count(), 1);
+        assert!(response.edns().is_some());
+        assert!(response.additionals().len() > 1);
+        assert!(response.additionals().len() < 100);
     }
 }
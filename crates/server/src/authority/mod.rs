@@ -28,23 +28,47 @@ pub enum ZoneType {
     Master,
     /// A secondary, i.e. replicated from the Master
     Slave,
-    /// A cached zone with recursive resolver abilities
+    /// A cached zone with recursive resolver abilities, starting from the root hints in its file
     Hint,
     /// A cached zone where all requests are forwarded to another Resolver
     Forward,
 }
 
+pub(crate) mod acl;
 mod auth_lookup;
 pub mod authority;
 mod catalog;
+mod cookie;
+mod flush;
+#[cfg(feature = "resolver")]
+mod forward;
 pub(crate) mod message_request;
 mod message_response;
+mod notify;
 pub mod persistence;
+#[cfg(feature = "dnssec")]
+mod resign;
+#[cfg(feature = "dnssec")]
+mod rollover;
+mod slave;
 
+pub use self::acl::{Acl, AclMatch, AclOperation, Acls};
 pub use self::auth_lookup::AuthLookup;
-pub use self::authority::Authority;
+pub use self::authority::{Authority, ZoneChanges, ZoneHistory};
 pub use self::authority::LookupRecords;
 pub use self::catalog::Catalog;
+pub use self::cookie::ServerCookies;
+pub use self::flush::ZoneFlush;
+#[cfg(feature = "resolver")]
+pub use self::forward::{root_hints, ForwardAuthority};
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
+#[cfg(feature = "dnssec")]
+pub use self::message_response::TsigResponseSigner;
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
 pub use self::persistence::Journal;
+pub use self::notify::notify_slaves;
+#[cfg(feature = "dnssec")]
+pub use self::resign::ZoneResign;
+#[cfg(feature = "dnssec")]
+pub use self::rollover::{KeyState, KeyTiming};
+pub use self::slave::{RefreshHandle, SlaveRefresh, ZoneTransfer};
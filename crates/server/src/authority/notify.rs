@@ -0,0 +1,72 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! NOTIFY support for master zones, informs the slaves of a zone that it has changed
+
+use futures::{future, Future};
+use tokio_executor;
+
+use proto::udp::UdpClientStream;
+use trust_dns::client::{ClientFuture, ClientHandle};
+use trust_dns::rr::{DNSClass, Name, Record, RecordType};
+
+use authority::Authority;
+
+/// Returns a future which sends a NOTIFY for the zone to each of its also-notify servers
+///
+/// [RFC 1996](https://tools.ietf.org/html/rfc1996), NOTIFY, August 1996
+///
+/// ```text
+/// 3.7. A NOTIFY request has QDCOUNT>0, ANCOUNT>=0, AUCOUNT>=0,
+///    ADCOUNT>=0.  If ANCOUNT>0, then the answer section represents an
+///    unsecure hint at the new RRset for this <QNAME,QCLASS,QTYPE>.  A
+///    slave receiving such a hint is free to treat equivalence of this
+///    answer section with its local data as a "no further work needs to be
+///    done" indication.  If ANCOUNT=0, or ANCOUNT>0 and the answer section
+///    differs from the slave's local data, then the slave should query its
+///    known masters to retrieve the new data.
+/// ```
+///
+/// The current SOA is sent in the answer section. Each NOTIFY is sent once over UDP, failures are
+///  only logged, the slaves will still pick up the change at their next SOA refresh.
+pub fn notify_slaves(authority: &Authority) -> Box<Future<Item = (), Error = ()> + Send> {
+    let origin: Name = authority.origin().clone().into();
+    let soa: Option<Record> = authority.soa().next().cloned();
+    let targets = authority.also_notify().to_vec();
+
+    Box::new(future::lazy(move || {
+        let notifies = targets
+            .into_iter()
+            .map(|target| {
+                info!("sending NOTIFY for {} to {}", origin, target);
+
+                let (bg, mut client) = ClientFuture::connect(UdpClientStream::new(target));
+                tokio_executor::spawn(bg);
+
+                let origin = origin.clone();
+                client
+                    .notify(origin.clone(), DNSClass::IN, RecordType::SOA, soa.clone())
+                    .then(move |result| {
+                        match result {
+                            Ok(response) => debug!(
+                                "NOTIFY for {} acknowledged by {}: {:?}",
+                                origin,
+                                target,
+                                response.response_code()
+                            ),
+                            Err(error) => {
+                                warn!("NOTIFY for {} to {} failed: {}", origin, target, error)
+                            }
+                        }
+
+                        Ok(())
+                    })
+            }).collect::<Vec<_>>();
+
+        future::join_all(notifies).map(|_| ())
+    }))
+}
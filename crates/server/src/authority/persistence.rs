@@ -8,12 +8,14 @@
 //! All zone persistence related types
 
 use std::iter::Iterator;
+use std::ops::Deref;
 use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
 
 use rusqlite::{self, types::ToSql, Connection};
 use time;
 
-use trust_dns::rr::Record;
+use trust_dns::rr::{Record, RecordType};
 use trust_dns::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
 
 use error::{PersistenceErrorKind, PersistenceResult};
@@ -23,7 +25,7 @@ pub const CURRENT_VERSION: i64 = 1;
 
 /// The Journal is the audit log of all changes to a zone after initial creation.
 pub struct Journal {
-    conn: Connection,
+    conn: Mutex<Connection>,
     version: i64,
 }
 
@@ -31,7 +33,10 @@ impl Journal {
     /// Constructs a new Journal, attaching to the specified Sqlite Connection
     pub fn new(conn: Connection) -> PersistenceResult<Journal> {
         let version = Self::select_schema_version(&conn)?;
-        Ok(Journal { conn, version })
+        Ok(Journal {
+            conn: Mutex::new(conn),
+            version,
+        })
     }
 
     /// Constructs a new Journal opening a Sqlite connection to the file at the specified path
@@ -46,9 +51,12 @@ impl Journal {
         }
     }
 
-    /// Returns a reference to the Sqlite Connection
-    pub fn conn(&self) -> &Connection {
-        &self.conn
+    /// Returns a guard for the Sqlite Connection
+    ///
+    /// The connection is locked such that the `Journal`, and the `Authority` which it is attached
+    ///  to, may be shared between threads, e.g. with the refresh of a slave zone.
+    pub fn conn(&self) -> MutexGuard<Connection> {
+        self.conn.lock().expect("conn poisoned")
     }
 
     /// Returns the current schema version of the journal
@@ -86,7 +94,7 @@ impl Journal {
         let client_id: i64 = 0; // TODO: we need better id information about the client, like pub_key
         let soa_serial: i64 = i64::from(soa_serial);
 
-        let count = self.conn.execute(
+        let count = self.conn().execute(
             "INSERT
                                           \
                                             INTO records (client_id, soa_serial, timestamp, \
@@ -136,7 +144,8 @@ impl Journal {
             "schema version mismatch, schema_up() resolves this"
         );
 
-        let mut stmt = self.conn.prepare(
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
             "SELECT _rowid_, record
                                             \
                                                FROM records
@@ -173,13 +182,74 @@ impl Journal {
         }
     }
 
+    /// Returns the row_id of the last record in the journal, 0 if the journal is empty
+    pub fn last_row_id(&self) -> PersistenceResult<i64> {
+        assert!(
+            self.version == CURRENT_VERSION,
+            "schema version mismatch, schema_up() resolves this"
+        );
+
+        let row_id: i64 = self.conn().query_row(
+            "SELECT IFNULL(MAX(_rowid_), 0) FROM records",
+            None::<&dyn ToSql>,
+            |row| row.get(0),
+        )?;
+
+        Ok(row_id)
+    }
+
+    /// Returns the row_id of the AXFR which starts the last version of the entire zone in the
+    ///  journal, see `Authority::persist_to_journal()`, None if there is none
+    pub fn last_axfr_row_id(&self) -> PersistenceResult<Option<i64>> {
+        assert!(
+            self.version == CURRENT_VERSION,
+            "schema version mismatch, schema_up() resolves this"
+        );
+
+        // the AXFR has no name or data, so its serialized form is always the same
+        let mut axfr: Vec<u8> = Vec::with_capacity(16);
+        {
+            let mut encoder = BinEncoder::new(&mut axfr);
+            Record::new().set_rr_type(RecordType::AXFR).emit(&mut encoder)?;
+        }
+
+        let row_id: Option<i64> = self.conn().query_row(
+            "SELECT MAX(_rowid_) FROM records WHERE record = $1",
+            &[&axfr],
+            |row| row.get(0),
+        )?;
+
+        Ok(row_id)
+    }
+
+    /// Deletes all records up to and including `row_id`, returns the number of records deleted.
+    ///
+    /// This is used to compact the journal once a newer version of the entire zone has been
+    ///  inserted, the older history is then no longer needed to reconstruct the zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `row_id` - the last row to delete, see `last_row_id()`
+    pub fn delete_through(&self, row_id: i64) -> PersistenceResult<usize> {
+        assert!(
+            self.version == CURRENT_VERSION,
+            "schema version mismatch, schema_up() resolves this"
+        );
+
+        let count = self
+            .conn()
+            .execute("DELETE FROM records WHERE _rowid_ <= $1", &[&row_id])?;
+
+        Ok(count)
+    }
+
     /// selects the current schema version of the journal DB, returns -1 if there is no schema
     ///
     ///
     /// # Arguments
     ///
-    /// * `conn` - db connection to use
-    pub fn select_schema_version(conn: &Connection) -> PersistenceResult<i64> {
+    /// * `conn` - db connection to use, e.g. `&Connection` or the guard of `conn()`
+    pub fn select_schema_version<C: Deref<Target = Connection>>(conn: C) -> PersistenceResult<i64> {
         // first see if our schema is there
         let mut stmt = conn.prepare(
             "SELECT name
@@ -219,7 +289,7 @@ impl Journal {
         assert!(new_version <= CURRENT_VERSION);
 
         let count = self
-            .conn
+            .conn()
             .execute("UPDATE tdns_schema SET version = $1", &[&new_version])?;
 
         //
@@ -244,7 +314,7 @@ impl Journal {
 
     /// initial schema, include the tdns_schema table for tracking the Journal version
     fn init_up(&self) -> PersistenceResult<i64> {
-        let count = self.conn.execute(
+        let count = self.conn().execute(
             "CREATE TABLE tdns_schema (
                                           \
                                             version INTEGER NOT NULL
@@ -255,7 +325,7 @@ impl Journal {
         //
         assert_eq!(count, 0);
 
-        let count = self.conn.execute(
+        let count = self.conn().execute(
             "INSERT INTO tdns_schema (version) VALUES (0)",
             None::<&dyn ToSql>,
         )?;
@@ -269,7 +339,7 @@ impl Journal {
     ///  authority. Each record is expected to be in the format of an update record
     fn records_up(&self) -> PersistenceResult<i64> {
         // we'll be using rowid for our primary key, basically: `rowid INTEGER PRIMARY KEY ASC`
-        let count = self.conn.execute(
+        let count = self.conn().execute(
             "CREATE TABLE records (
                                           \
                                             client_id      INTEGER NOT NULL,
@@ -0,0 +1,116 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Re-signs the records of signed zones before their signatures expire, and as their keys roll
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration as StdDuration, Instant};
+
+use chrono::{Duration, Utc};
+use futures::future::{self, Loop};
+use futures::Future;
+use tokio_executor::{DefaultExecutor, Executor};
+use tokio_timer::Delay;
+
+use trust_dns::error::DnsSecResult;
+
+use authority::{notify_slaves, Authority};
+
+/// Minimum seconds between checks for expiring signatures, this also limits the retries of a
+///  zone which could not be signed
+const MIN_CHECK_SECS: i64 = 60;
+/// Maximum seconds between checks for expiring signatures, in case the zone was re-signed e.g.
+///  by an update in between
+const MAX_CHECK_SECS: i64 = 3600;
+
+/// Re-signs the record sets of a zone whose RRSIGs are about to expire.
+///
+/// Each record set is re-signed once its earliest RRSIG expires within the refresh window, and
+///  the whole zone is re-signed when one of its keys changes state, see `Authority::roll_keys()`.
+///  The serial of the zone is incremented with each re-signing, and the slaves are sent a NOTIFY.
+#[derive(Clone)]
+pub struct ZoneResign {
+    authority: Arc<RwLock<Authority>>,
+    refresh: Duration,
+}
+
+impl ZoneResign {
+    /// Returns a new re-signing of the zone
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - the signed zone, this is shared with the `Catalog`
+    /// * `refresh` - the time before the expiration of an RRSIG at which it is replaced, this
+    ///               should be well below the duration of the signatures of the keys
+    pub fn new(authority: Arc<RwLock<Authority>>, refresh: Duration) -> Self {
+        ZoneResign { authority, refresh }
+    }
+
+    /// Re-signs the zone if its keys changed state, otherwise the record sets of the zone which
+    ///  expire within the refresh window
+    ///
+    /// Returns the number of record sets which were re-signed.
+    pub fn resign(&self) -> DnsSecResult<usize> {
+        let mut authority = self.authority.write().expect("authority poisoned");
+        let count = if authority.roll_keys()? {
+            authority.records().len()
+        } else {
+            authority.resign_expiring(self.refresh)?
+        };
+
+        if count > 0 && !authority.also_notify().is_empty() {
+            if let Err(error) = DefaultExecutor::current().spawn(notify_slaves(&authority)) {
+                warn!(
+                    "could not send NOTIFY for {}: {:?}",
+                    authority.origin(),
+                    error
+                );
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Returns the time until the next record set of the zone is due to be re-signed, or one of
+    ///  its keys changes state
+    pub fn next_resign(&self) -> StdDuration {
+        let authority = self.authority.read().expect("authority poisoned");
+        let now = Utc::now().timestamp();
+        let until_expiring = authority
+            .next_sig_expiration()
+            .map_or(MAX_CHECK_SECS, |expiration| {
+                i64::from(expiration) - self.refresh.num_seconds() - now
+            });
+        let until_key_change = authority
+            .next_key_change()
+            .map_or(MAX_CHECK_SECS, |change| change.timestamp() - now);
+        let until_due = until_expiring.min(until_key_change);
+
+        StdDuration::from_secs(until_due.max(MIN_CHECK_SECS).min(MAX_CHECK_SECS) as u64)
+    }
+
+    /// Returns a never ending future which re-signs the zone whenever record sets are due
+    ///
+    /// Errors are logged, and the zone is checked again after a while.
+    pub fn schedule(&self) -> Box<Future<Item = (), Error = ()> + Send> {
+        Box::new(future::loop_fn(self.clone(), |zone_resign| {
+            Delay::new(Instant::now() + zone_resign.next_resign())
+                .map_err(|e| error!("zone re-sign timer failed: {}", e))
+                .map(move |()| {
+                    if let Err(e) = zone_resign.resign() {
+                        error!(
+                            "failed to re-sign zone {}: {}",
+                            zone_resign.authority.read().expect("authority poisoned").origin(),
+                            e
+                        );
+                    }
+
+                    Loop::Continue(zone_resign)
+                })
+        }))
+    }
+}
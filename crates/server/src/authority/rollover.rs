@@ -0,0 +1,122 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The lifecycle of the DNSSEC keys of a zone, for rolling over from one key to another
+
+use chrono::{DateTime, Utc};
+
+/// The state of a key of a zone at some point in time, see `KeyTiming`
+///
+/// [RFC 7583](https://tools.ietf.org/html/rfc7583#section-3.1), DNSSEC Key Rollover Timing Considerations, October 2015
+///
+/// A zone signing key is rolled over by pre-publication: the new key is published before it is
+///  activated, so that it is in the caches of resolvers before any signatures are made with it,
+///  and the old key remains published for a while after it is retired, until the signatures made
+///  with it have expired from the caches. A key signing key is rolled over by double signature:
+///  the old and new keys are both active for a while, and both sign the DNSKEY record set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyState {
+    /// The key is not yet published in the zone
+    Generated,
+    /// The DNSKEY of the key is in the zone, but the key does not sign yet
+    Published,
+    /// The DNSKEY of the key is in the zone, and the key signs the zone
+    Active,
+    /// The DNSKEY of the key is still in the zone, but the key no longer signs
+    Retired,
+    /// The DNSKEY of the key has been removed from the zone
+    Removed,
+}
+
+impl KeyState {
+    /// Returns true if the DNSKEY of the key is in the zone in this state
+    pub fn is_published(self) -> bool {
+        match self {
+            KeyState::Published | KeyState::Active | KeyState::Retired => true,
+            KeyState::Generated | KeyState::Removed => false,
+        }
+    }
+
+    /// Returns true if the key signs the zone in this state
+    pub fn is_active(self) -> bool {
+        self == KeyState::Active
+    }
+}
+
+/// The times at which a key of a zone changes state, see `KeyState`
+///
+/// A key without any times is active forever. The DNSKEY of a key without a publish time is
+///  published when the key is activated, and a key with a publish time but no activation time
+///  is published without ever signing the zone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyTiming {
+    publish: Option<DateTime<Utc>>,
+    activate: Option<DateTime<Utc>>,
+    inactive: Option<DateTime<Utc>>,
+    delete: Option<DateTime<Utc>>,
+}
+
+impl KeyTiming {
+    /// Returns the timing of a key, None if the times are not in order
+    ///
+    /// # Arguments
+    ///
+    /// * `publish` - when the DNSKEY is added to the zone
+    /// * `activate` - when the key starts to sign the zone
+    /// * `inactive` - when the key stops signing the zone
+    /// * `delete` - when the DNSKEY is removed from the zone
+    pub fn new(
+        publish: Option<DateTime<Utc>>,
+        activate: Option<DateTime<Utc>>,
+        inactive: Option<DateTime<Utc>>,
+        delete: Option<DateTime<Utc>>,
+    ) -> Option<Self> {
+        let times: Vec<DateTime<Utc>> = [publish, activate, inactive, delete]
+            .iter()
+            .filter_map(|time| *time)
+            .collect();
+        if times.windows(2).any(|pair| pair[1] < pair[0]) {
+            return None;
+        }
+
+        Some(KeyTiming {
+            publish,
+            activate,
+            inactive,
+            delete,
+        })
+    }
+
+    /// Returns the state of the key at the time
+    pub fn state(&self, now: DateTime<Utc>) -> KeyState {
+        let has_passed = |time: Option<DateTime<Utc>>| time.map_or(false, |time| time <= now);
+
+        if has_passed(self.delete) {
+            KeyState::Removed
+        } else if has_passed(self.inactive) {
+            KeyState::Retired
+        } else if self
+            .activate
+            .map_or(self.publish.is_none(), |activate| activate <= now)
+        {
+            KeyState::Active
+        } else if has_passed(self.publish) {
+            KeyState::Published
+        } else {
+            KeyState::Generated
+        }
+    }
+
+    /// Returns the next time after `now` at which the key changes state, if any
+    pub fn next_change(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        [self.publish, self.activate, self.inactive, self.delete]
+            .iter()
+            .filter_map(|time| *time)
+            .filter(|time| *time > now)
+            .min()
+    }
+}
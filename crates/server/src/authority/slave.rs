@@ -0,0 +1,778 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Secondary, aka slave, zone support: transfers zones from a master and keeps them refreshed
+//!
+//! Zones are transferred incrementally, with IXFR, once they have been transferred in full, with
+//!  AXFR.
+
+use std::mem;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use futures::sync::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::{Async, Future, Poll, Stream};
+use rand;
+use tokio_executor;
+use tokio_tcp::TcpStream as TokioTcpStream;
+use tokio_timer::{Delay, Timeout};
+
+use proto::error::{ProtoError, ProtoResult};
+use proto::tcp::{TcpClientConnect, TcpClientStream};
+use proto::udp::UdpClientStream;
+use proto::xfer::{DnsResponse, SerialMessage};
+use proto::DnsStreamHandle;
+use trust_dns::client::{ClientFuture, ClientHandle};
+use trust_dns::error::ClientError;
+use trust_dns::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns::rr::{DNSClass, Name, RData, Record, RecordType};
+
+use authority::{Authority, ZoneChanges};
+
+/// Default seconds before a refresh is attempted, used when the zone has no SOA yet
+const DEFAULT_REFRESH_SECS: u64 = 3600;
+/// Default seconds before retrying a failed refresh, used when the zone has no SOA yet
+const DEFAULT_RETRY_SECS: u64 = 60;
+/// Maximum seconds allowed for an entire zone transfer
+const TRANSFER_TIMEOUT_SECS: u64 = 120;
+
+/// Returns true if `remote` is a newer serial number than `local`
+///
+/// [RFC 1982](https://tools.ietf.org/html/rfc1982), Serial Number Arithmetic, August 1996
+pub(crate) fn is_serial_newer(remote: u32, local: u32) -> bool {
+    remote != local && remote.wrapping_sub(local) < 0x8000_0000
+}
+
+/// The records received in a zone transfer
+#[derive(Debug)]
+pub enum Transfer {
+    /// All of the records in the zone, with the SOA first, the response to an AXFR or to an IXFR
+    ///  which the master could not answer incrementally
+    Full(Vec<Record>),
+    /// The current SOA of the zone and the differences leading to it, in order, from the version
+    ///  of the zone requested by an IXFR
+    Incremental(Record, Vec<ZoneChanges>),
+    /// The version of the zone requested by an IXFR is the current version
+    Current,
+}
+
+/// A future which performs a zone transfer, AXFR or IXFR, of a zone over TCP.
+#[must_use = "futures do nothing unless polled"]
+pub struct ZoneTransfer {
+    receiver: TransferReceiver,
+    state: TransferState,
+    // the stream closes when all handles are dropped, so this must be held until completion
+    _stream_handle: Box<DnsStreamHandle + Send>,
+}
+
+enum TransferState {
+    Connecting(TcpClientConnect),
+    Receiving(TcpClientStream<TokioTcpStream>),
+    Failed(Option<ProtoError>),
+}
+
+impl ZoneTransfer {
+    /// Starts an AXFR of the zone from the master, this resolves to `Transfer::Full`
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - name of the zone to transfer
+    /// * `master` - address of the master server, the transfer is always performed over TCP
+    pub fn new(origin: Name, master: SocketAddr) -> Self {
+        Self::with_query(origin, master, RecordType::AXFR, None)
+    }
+
+    /// Starts an IXFR of the zone from the master, for the changes since the version of the SOA
+    ///
+    /// [RFC 1995](https://tools.ietf.org/html/rfc1995), Incremental Zone Transfer in DNS, August 1996
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - name of the zone to transfer
+    /// * `master` - address of the master server, the transfer is always performed over TCP
+    /// * `soa` - the SOA of the version of the zone held, it is sent in the authority section
+    pub fn incremental(origin: Name, master: SocketAddr, soa: Record) -> Self {
+        Self::with_query(origin, master, RecordType::IXFR, Some(soa))
+    }
+
+    fn with_query(
+        origin: Name,
+        master: SocketAddr,
+        query_type: RecordType,
+        soa: Option<Record>,
+    ) -> Self {
+        let (connect, mut stream_handle) = TcpClientStream::new(master);
+        let query_id: u16 = rand::random();
+
+        let mut message = Message::new();
+        message
+            .set_id(query_id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false)
+            .add_query(Query::query(origin.clone(), query_type));
+        if let Some(soa) = soa {
+            message.add_name_server(soa);
+        }
+
+        // the request is buffered until the connection is established
+        let state = match message
+            .to_vec()
+            .and_then(|bytes| stream_handle.send(SerialMessage::new(bytes, master)))
+        {
+            Ok(()) => TransferState::Connecting(connect),
+            Err(e) => TransferState::Failed(Some(e)),
+        };
+
+        ZoneTransfer {
+            receiver: TransferReceiver::new(origin, query_id, query_type),
+            state,
+            _stream_handle: stream_handle,
+        }
+    }
+}
+
+/// Collects the records of the response messages to a zone transfer request
+struct TransferReceiver {
+    origin: Name,
+    query_id: u16,
+    query_type: RecordType,
+    records: Vec<Record>,
+    soa_count: usize,
+}
+
+impl TransferReceiver {
+    fn new(origin: Name, query_id: u16, query_type: RecordType) -> Self {
+        TransferReceiver {
+            origin,
+            query_id,
+            query_type,
+            records: Vec::new(),
+            soa_count: 0,
+        }
+    }
+
+    /// Collects the answers from one message of the transfer, returns true once the final SOA is
+    ///  seen
+    ///
+    /// The SOAs of an incremental response alternate between the older and the newer version of
+    ///  each difference, the final SOA is the one in the place of an older version with the serial
+    ///  of the first. A response to an IXFR of only the first SOA is the current version.
+    fn receive(&mut self, mut message: Message) -> ProtoResult<bool> {
+        if message.id() != self.query_id {
+            return Err(format!(
+                "zone transfer of {} received mismatched id: {}",
+                self.origin,
+                message.id()
+            ).into());
+        }
+
+        if message.response_code() != ResponseCode::NoError {
+            return Err(format!(
+                "zone transfer of {} failed: {:?}",
+                self.origin,
+                message.response_code()
+            ).into());
+        }
+
+        for record in message.take_answers() {
+            let serial = match *record.rdata() {
+                RData::SOA(ref soa) if record.rr_type() == RecordType::SOA => Some(soa.serial()),
+                _ => None,
+            };
+
+            if self.records.is_empty() {
+                if serial.is_none() {
+                    return Err(
+                        format!("zone transfer of {} did not start with SOA", self.origin).into(),
+                    );
+                }
+            } else if let Some(serial) = serial {
+                let is_incremental = self.query_type == RecordType::IXFR
+                    && self.records.get(1).map_or(true, |r| r.rr_type() == RecordType::SOA);
+                let is_final = !is_incremental
+                    || (self.soa_count % 2 == 0 && Some(serial) == self.serial());
+
+                if is_final {
+                    return Ok(true);
+                }
+                self.soa_count += 1;
+            }
+
+            self.records.push(record);
+        }
+
+        Ok(self.query_type == RecordType::IXFR && self.records.len() == 1)
+    }
+
+    /// The serial of the first SOA of the transfer
+    fn serial(&self) -> Option<u32> {
+        self.records.first().and_then(|soa| match *soa.rdata() {
+            RData::SOA(ref soa) => Some(soa.serial()),
+            _ => None,
+        })
+    }
+
+    /// Returns the transfer of the received records
+    fn transfer(&mut self) -> Transfer {
+        let mut records = mem::replace(&mut self.records, Vec::new());
+        let is_incremental = self.query_type == RecordType::IXFR
+            && records.get(1).map_or(true, |r| r.rr_type() == RecordType::SOA);
+
+        if !is_incremental {
+            return Transfer::Full(records);
+        }
+
+        if records.len() == 1 {
+            return Transfer::Current;
+        }
+
+        let mut changes: Vec<ZoneChanges> = Vec::new();
+        let mut adding = false;
+        for record in records.drain(1..) {
+            if record.rr_type() == RecordType::SOA {
+                // an older SOA starts each difference, the newer SOA starts the added records
+                if changes.is_empty() || adding {
+                    changes.push(ZoneChanges {
+                        old_soa: record,
+                        removed: Vec::new(),
+                        added: Vec::new(),
+                    });
+                    adding = false;
+                } else {
+                    adding = true;
+                }
+                continue;
+            }
+
+            // the first record is an SOA, so there is always a difference
+            let change = changes.last_mut().expect("difference not started");
+            if adding {
+                change.added.push(record);
+            } else {
+                change.removed.push(record);
+            }
+        }
+
+        let soa = records.pop().expect("the SOA is the first record");
+        Transfer::Incremental(soa, changes)
+    }
+}
+
+impl Future for ZoneTransfer {
+    type Item = Transfer;
+    type Error = ProtoError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let stream = match self.state {
+                TransferState::Connecting(ref mut connect) => match connect.poll()? {
+                    Async::Ready(stream) => stream,
+                    Async::NotReady => return Ok(Async::NotReady),
+                },
+                TransferState::Receiving(ref mut stream) => match stream.poll()? {
+                    Async::NotReady => return Ok(Async::NotReady),
+                    Async::Ready(Some(message)) => {
+                        if self.receiver.receive(message.to_message()?)? {
+                            return Ok(Async::Ready(self.receiver.transfer()));
+                        }
+                        continue;
+                    }
+                    Async::Ready(None) => {
+                        return Err(format!(
+                            "zone transfer of {} closed before the final SOA",
+                            self.receiver.origin
+                        ).into())
+                    }
+                },
+                TransferState::Failed(ref mut error) => {
+                    return Err(error
+                        .take()
+                        .expect("ZoneTransfer polled after completion"))
+                }
+            };
+
+            self.state = TransferState::Receiving(stream);
+        }
+    }
+}
+
+enum RefreshState {
+    Waiting(Delay),
+    CheckingSoa(Box<Future<Item = DnsResponse, Error = ClientError> + Send>),
+    /// the transfer, and whether it is incremental
+    Transferring(Timeout<ZoneTransfer>, bool),
+}
+
+enum Transition {
+    CheckSoa,
+    SoaResponse(DnsResponse),
+    Transferred(Transfer),
+    IncrementalFailed(String),
+    Failed(String),
+}
+
+/// A handle to a `SlaveRefresh`, used to trigger an immediate SOA check on receipt of a NOTIFY
+#[derive(Clone)]
+pub struct RefreshHandle {
+    masters: Vec<SocketAddr>,
+    sender: UnboundedSender<()>,
+}
+
+impl RefreshHandle {
+    /// Returns true if `src` is one of the masters of the zone
+    ///
+    /// [RFC 1996](https://tools.ietf.org/html/rfc1996), NOTIFY, August 1996
+    ///
+    /// ```text
+    /// 3.10. If a slave receives a NOTIFY request from a host that is not a
+    ///    known master for the zone containing the QNAME, it should ignore the
+    ///    request and produce an error message in its operations log.
+    /// ```
+    pub fn is_master(&self, src: &SocketAddr) -> bool {
+        self.masters.iter().any(|master| master.ip() == src.ip())
+    }
+
+    /// Triggers an SOA check of the zone, returns false if the refresh is no longer running
+    pub fn refresh(&self) -> bool {
+        self.sender.unbounded_send(()).is_ok()
+    }
+}
+
+/// A never ending future which keeps a slave zone up to date with its masters.
+///
+/// The zone's SOA is checked against the master's every SOA refresh interval, and the zone is
+///  transferred when the master's serial is newer. Once the zone has been transferred in full,
+///  with AXFR, the changes since its serial are requested with IXFR; a full transfer is performed
+///  instead should the incremental transfer fail. On failure each master is tried in turn, after
+///  which the SOA retry interval is used. If the zone can not be refreshed before the SOA expire
+///  interval elapses, all records are removed and the zone will answer with ServFail.
+///
+/// The SOA is checked immediately when triggered through the `RefreshHandle`, i.e. when a NOTIFY
+///  is received from a master.
+#[must_use = "futures do nothing unless polled"]
+pub struct SlaveRefresh {
+    authority: Arc<RwLock<Authority>>,
+    origin: Name,
+    masters: Vec<SocketAddr>,
+    next_master: usize,
+    failures: usize,
+    expires_at: Option<Instant>,
+    handle: RefreshHandle,
+    notifications: UnboundedReceiver<()>,
+    notified: bool,
+    state: RefreshState,
+}
+
+impl SlaveRefresh {
+    /// Creates a new refresh task for the zone, the first SOA check is performed immediately
+    ///
+    /// # Arguments
+    ///
+    /// * `authority` - the slave zone to keep updated, this is shared with the `Catalog`
+    /// * `masters` - the master servers from which the zone is transferred, must not be empty
+    pub fn new(authority: Arc<RwLock<Authority>>, masters: Vec<SocketAddr>) -> Self {
+        assert!(!masters.is_empty(), "at least one master is required");
+
+        let (origin, expires_at) = {
+            let authority = authority.read().expect("authority poisoned");
+            let expires_at = if authority.records().is_empty() {
+                None
+            } else {
+                Some(Instant::now() + Self::timers(&authority).2)
+            };
+
+            (authority.origin().clone().into(), expires_at)
+        };
+
+        let (sender, notifications) = unbounded();
+        let handle = RefreshHandle {
+            masters: masters.clone(),
+            sender,
+        };
+
+        SlaveRefresh {
+            authority,
+            origin,
+            masters,
+            next_master: 0,
+            failures: 0,
+            expires_at,
+            handle,
+            notifications,
+            notified: false,
+            state: RefreshState::Waiting(Delay::new(Instant::now())),
+        }
+    }
+
+    /// Returns a handle with which an immediate refresh of the zone can be triggered
+    pub fn handle(&self) -> RefreshHandle {
+        self.handle.clone()
+    }
+
+    /// Returns the refresh, retry and expire intervals from the zone's SOA
+    fn timers(authority: &Authority) -> (Duration, Duration, Duration) {
+        fn secs(secs: i32) -> Duration {
+            Duration::from_secs(secs.max(0) as u64)
+        }
+
+        authority
+            .soa()
+            .next()
+            .and_then(|soa| match *soa.rdata() {
+                RData::SOA(ref soa) => Some((
+                    secs(soa.refresh()),
+                    secs(soa.retry()),
+                    secs(soa.expire()),
+                )),
+                _ => None,
+            }).unwrap_or((
+                Duration::from_secs(DEFAULT_REFRESH_SECS),
+                Duration::from_secs(DEFAULT_RETRY_SECS),
+                Duration::from_secs(DEFAULT_REFRESH_SECS),
+            ))
+    }
+
+    fn master(&self) -> SocketAddr {
+        self.masters[self.next_master]
+    }
+
+    fn check_soa(&mut self) -> RefreshState {
+        if self.expires_at.map_or(false, |expires_at| expires_at <= Instant::now()) {
+            warn!("slave zone expired, removing all records: {}", self.origin);
+            self.authority
+                .write()
+                .expect("authority poisoned")
+                .replace_records(Vec::new());
+            self.expires_at = None;
+        }
+
+        let master = self.master();
+        debug!("checking SOA of {} with master: {}", self.origin, master);
+
+        let (bg, mut client) = ClientFuture::connect(UdpClientStream::new(master));
+        tokio_executor::spawn(bg);
+
+        RefreshState::CheckingSoa(Box::new(client.query(
+            self.origin.clone(),
+            DNSClass::IN,
+            RecordType::SOA,
+        )))
+    }
+
+    fn soa_response(&mut self, response: &DnsResponse) -> RefreshState {
+        let remote_serial = response
+            .answers()
+            .iter()
+            .filter_map(|record| match *record.rdata() {
+                RData::SOA(ref soa) => Some(soa.serial()),
+                _ => None,
+            }).next();
+
+        let remote_serial = match (response.response_code(), remote_serial) {
+            (ResponseCode::NoError, Some(remote_serial)) => remote_serial,
+            (code, _) => {
+                return self.failed(format!("bad SOA response: {:?}", code));
+            }
+        };
+
+        let (local_soa, local_serial) = {
+            let authority = self.authority.read().expect("authority poisoned");
+            (authority.soa().next().cloned(), authority.serial())
+        };
+
+        if local_soa.is_none() || is_serial_newer(remote_serial, local_serial) {
+            info!(
+                "transferring {} from {}, serial: {} -> {}",
+                self.origin,
+                self.master(),
+                local_serial,
+                remote_serial
+            );
+
+            self.transfer(local_soa)
+        } else {
+            debug!("slave zone is up to date: {} serial: {}", self.origin, local_serial);
+            self.refreshed()
+        }
+    }
+
+    /// Starts an IXFR from the version of the SOA, or an AXFR if there is none
+    fn transfer(&self, soa: Option<Record>) -> RefreshState {
+        let incremental = soa.is_some();
+        let transfer = match soa {
+            Some(soa) => ZoneTransfer::incremental(self.origin.clone(), self.master(), soa),
+            None => ZoneTransfer::new(self.origin.clone(), self.master()),
+        };
+
+        RefreshState::Transferring(
+            Timeout::new(transfer, Duration::from_secs(TRANSFER_TIMEOUT_SECS)),
+            incremental,
+        )
+    }
+
+    fn transferred(&mut self, transfer: Transfer) -> RefreshState {
+        match transfer {
+            Transfer::Full(records) => {
+                info!(
+                    "transferred {} records for {} from {}",
+                    records.len(),
+                    self.origin,
+                    self.master()
+                );
+
+                self.authority
+                    .write()
+                    .expect("authority poisoned")
+                    .replace_records(records);
+            }
+            Transfer::Incremental(soa, changes) => {
+                info!(
+                    "transferred {} changes for {} from {}",
+                    changes.len(),
+                    self.origin,
+                    self.master()
+                );
+
+                let applied = self
+                    .authority
+                    .write()
+                    .expect("authority poisoned")
+                    .apply_changes(soa, changes);
+                if !applied {
+                    return self.incremental_failed(
+                        "the changes are not from the version of the zone".to_string(),
+                    );
+                }
+            }
+            Transfer::Current => {
+                debug!("slave zone is current with: {}", self.master());
+            }
+        }
+
+        self.refreshed()
+    }
+
+    /// Falls back to a full transfer from the same master
+    fn incremental_failed(&mut self, error: String) -> RefreshState {
+        warn!(
+            "incremental transfer of {} from {} failed, transferring in full: {}",
+            self.origin,
+            self.master(),
+            error
+        );
+
+        self.transfer(None)
+    }
+
+    fn refreshed(&mut self) -> RefreshState {
+        let (refresh, _, expire) =
+            Self::timers(&self.authority.read().expect("authority poisoned"));
+
+        self.failures = 0;
+        self.expires_at = Some(Instant::now() + expire);
+
+        // a NOTIFY received during the refresh may be for a newer version of the zone
+        if self.notified {
+            self.notified = false;
+            return RefreshState::Waiting(Delay::new(Instant::now()));
+        }
+
+        RefreshState::Waiting(Delay::new(Instant::now() + refresh))
+    }
+
+    fn failed(&mut self, error: String) -> RefreshState {
+        warn!(
+            "failed to refresh {} from {}: {}",
+            self.origin,
+            self.master(),
+            error
+        );
+
+        self.failures += 1;
+        self.next_master = (self.next_master + 1) % self.masters.len();
+
+        // try the remaining masters before backing off
+        if self.failures < self.masters.len() {
+            return self.check_soa();
+        }
+
+        let (_, retry, _) = Self::timers(&self.authority.read().expect("authority poisoned"));
+
+        self.failures = 0;
+        RefreshState::Waiting(Delay::new(Instant::now() + retry))
+    }
+}
+
+impl Future for SlaveRefresh {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        // the sender is held by the handle, so the stream never ends
+        while let Ok(Async::Ready(Some(()))) = self.notifications.poll() {
+            debug!("NOTIFY received for slave zone: {}", self.origin);
+            self.notified = true;
+        }
+
+        loop {
+            let transition = match self.state {
+                RefreshState::Waiting(_) if self.notified => {
+                    self.notified = false;
+                    Transition::CheckSoa
+                }
+                RefreshState::Waiting(ref mut delay) => match delay.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(())) => Transition::CheckSoa,
+                    Err(e) => {
+                        error!("slave refresh timer failed for {}: {}", self.origin, e);
+                        return Err(());
+                    }
+                },
+                RefreshState::CheckingSoa(ref mut response) => match response.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(response)) => Transition::SoaResponse(response),
+                    Err(e) => Transition::Failed(e.to_string()),
+                },
+                RefreshState::Transferring(ref mut transfer, incremental) => match transfer.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(transfer)) => Transition::Transferred(transfer),
+                    Err(e) if incremental => {
+                        Transition::IncrementalFailed(ProtoError::from(e).to_string())
+                    }
+                    Err(e) => Transition::Failed(ProtoError::from(e).to_string()),
+                },
+            };
+
+            self.state = match transition {
+                Transition::CheckSoa => self.check_soa(),
+                Transition::SoaResponse(response) => self.soa_response(&response),
+                Transition::Transferred(transfer) => self.transferred(transfer),
+                Transition::IncrementalFailed(error) => self.incremental_failed(error),
+                Transition::Failed(error) => self.failed(error),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_serial_newer() {
+        assert!(is_serial_newer(2, 1));
+        assert!(!is_serial_newer(1, 1));
+        assert!(!is_serial_newer(1, 2));
+
+        // wrapping
+        assert!(is_serial_newer(0, u32::max_value()));
+        assert!(is_serial_newer(10, u32::max_value() - 10));
+        assert!(!is_serial_newer(u32::max_value(), 0));
+    }
+
+    fn soa(serial: u32) -> Record {
+        use std::str::FromStr;
+        use trust_dns::rr::rdata::SOA;
+
+        Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RecordType::SOA,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("root.example.com.").unwrap(),
+                serial,
+                3600,
+                60,
+                86400,
+                3600,
+            )),
+        )
+    }
+
+    fn a(octet: u8) -> Record {
+        use std::str::FromStr;
+
+        Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RecordType::A,
+            RData::A([127, 0, 0, octet].into()),
+        )
+    }
+
+    fn message(id: u16, answers: Vec<Record>) -> Message {
+        let mut message = Message::new();
+        message.set_id(id).add_answers(answers);
+        message
+    }
+
+    fn receiver(query_type: RecordType) -> TransferReceiver {
+        use std::str::FromStr;
+
+        TransferReceiver::new(Name::from_str("example.com.").unwrap(), 10, query_type)
+    }
+
+    #[test]
+    fn test_receive_axfr() {
+        let mut axfr = receiver(RecordType::AXFR);
+        assert!(!axfr.receive(message(10, vec![soa(1), a(1)])).unwrap());
+        assert!(axfr.receive(message(10, vec![a(2), soa(1)])).unwrap());
+
+        match axfr.transfer() {
+            Transfer::Full(records) => assert_eq!(records, vec![soa(1), a(1), a(2)]),
+            transfer => panic!("unexpected transfer: {:?}", transfer),
+        }
+
+        // must start with the SOA
+        assert!(receiver(RecordType::AXFR).receive(message(10, vec![a(1)])).is_err());
+
+        // must match the query
+        assert!(receiver(RecordType::AXFR).receive(message(11, vec![soa(1)])).is_err());
+    }
+
+    #[test]
+    fn test_receive_ixfr() {
+        // two differences, 1 -> 2 removes 1 and adds 2, 2 -> 3 adds 3
+        let mut ixfr = receiver(RecordType::IXFR);
+        assert!(!ixfr.receive(message(10, vec![soa(3), soa(1), a(1)])).unwrap());
+        assert!(!ixfr.receive(message(10, vec![soa(2), a(2), soa(2)])).unwrap());
+        assert!(ixfr.receive(message(10, vec![soa(3), a(3), soa(3)])).unwrap());
+
+        match ixfr.transfer() {
+            Transfer::Incremental(soa3, changes) => {
+                assert_eq!(soa3, soa(3));
+                assert_eq!(changes.len(), 2);
+                assert_eq!(changes[0].old_soa, soa(1));
+                assert_eq!(changes[0].removed, vec![a(1)]);
+                assert_eq!(changes[0].added, vec![a(2)]);
+                assert_eq!(changes[1].old_soa, soa(2));
+                assert!(changes[1].removed.is_empty());
+                assert_eq!(changes[1].added, vec![a(3)]);
+            }
+            transfer => panic!("unexpected transfer: {:?}", transfer),
+        }
+
+        // the zone in full, as for AXFR
+        let mut ixfr = receiver(RecordType::IXFR);
+        assert!(ixfr.receive(message(10, vec![soa(3), a(3), soa(3)])).unwrap());
+        match ixfr.transfer() {
+            Transfer::Full(records) => assert_eq!(records, vec![soa(3), a(3)]),
+            transfer => panic!("unexpected transfer: {:?}", transfer),
+        }
+
+        // the requested version is current
+        let mut ixfr = receiver(RecordType::IXFR);
+        assert!(ixfr.receive(message(10, vec![soa(3)])).unwrap());
+        match ixfr.transfer() {
+            Transfer::Current => (),
+            transfer => panic!("unexpected transfer: {:?}", transfer),
+        }
+    }
+}
@@ -9,29 +9,46 @@
 
 use std::fs::File;
 use std::io::Read;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
+#[cfg(feature = "dnssec")]
+use chrono::{DateTime, Utc};
 use log;
 use toml;
 
 use proto::error::ProtoResult;
-#[cfg(feature = "dnssec")]
 use trust_dns::error::*;
 #[cfg(feature = "dnssec")]
 use trust_dns::rr::dnssec::{Algorithm, KeyFormat};
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::Nsec3HashAlgorithm;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::tsig::TsigAlgorithm;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::rdata::NSEC3PARAM;
 use trust_dns::rr::Name;
+#[cfg(feature = "resolver")]
+use trust_dns_resolver::config::NameServerConfigGroup;
 
-use authority::ZoneType;
+#[cfg(feature = "dnssec")]
+use authority::KeyTiming;
+use authority::{Acl, AclMatch, AclOperation, Acls, ServerCookies, ZoneType};
 use error::{ConfigError, ConfigResult};
+use server::RateLimits;
 
 static DEFAULT_PATH: &'static str = "/var/named"; // TODO what about windows (do I care? ;)
 static DEFAULT_PORT: u16 = 53;
 static DEFAULT_TLS_PORT: u16 = 853;
 static DEFAULT_HTTPS_PORT: u16 = 443;
 static DEFAULT_TCP_REQUEST_TIMEOUT: u64 = 5;
+static DEFAULT_SIG_VALIDITY: u64 = 52 * 7 * 24 * 60 * 60;
+static DEFAULT_COOKIE_ROTATION: u64 = 24 * 60 * 60;
+// RFC 9276, validators may treat the answers of zones with more iterations as insecure
+#[cfg(feature = "dnssec")]
+static MAX_NSEC3_ITERATIONS: u16 = 100;
 
 /// Server configuration
 #[derive(Deserialize, Debug)]
@@ -59,6 +76,16 @@ pub struct Config {
     zones: Vec<ZoneConfig>,
     /// Certificate to associate to TLS connections (currently the same is used for HTTPS and TLS)
     tls_cert: Option<TlsCertConfig>,
+    /// Keys shared with clients, which may sign their requests with TSIG
+    #[serde(default)]
+    tsig_keys: Vec<TsigKeyConfig>,
+    /// Clients allowed the operations on all zones, unless a zone has its own
+    #[serde(default)]
+    acl: AclConfig,
+    /// Limits of the rate of responses over UDP, which are not limited without it
+    rate_limit: Option<RateLimitConfig>,
+    /// DNS cookies, requests with a cookie are answered with a server cookie
+    cookies: Option<CookieConfig>,
 }
 
 impl Config {
@@ -141,6 +168,27 @@ impl Config {
     pub fn get_tls_cert(&self) -> Option<&TlsCertConfig> {
         self.tls_cert.as_ref()
     }
+
+    /// the keys which requests may be signed with by TSIG, zones allow updates and transfers
+    ///  signed by the keys which they name
+    pub fn get_tsig_keys(&self) -> &[TsigKeyConfig] {
+        &self.tsig_keys
+    }
+
+    /// the clients allowed the operations on all zones, the ACLs of a zone take their place
+    pub fn get_acls(&self) -> ParseResult<Acls> {
+        self.acl.to_acls()
+    }
+
+    /// the limits of the rate of responses over UDP, None if they are not limited
+    pub fn get_rate_limit(&self) -> Option<&RateLimitConfig> {
+        self.rate_limit.as_ref()
+    }
+
+    /// the configuration of DNS cookies, None if requests with a cookie are answered without one
+    pub fn get_cookies(&self) -> Option<&CookieConfig> {
+        self.cookies.as_ref()
+    }
 }
 
 impl FromStr for Config {
@@ -156,12 +204,43 @@ impl FromStr for Config {
 pub struct ZoneConfig {
     zone: String, // TODO: make Domain::Name decodable
     zone_type: ZoneType,
+    /// Forward zones have no zone file
+    #[serde(default)]
     file: String,
     allow_update: Option<bool>,
     allow_axfr: Option<bool>,
     enable_dnssec: Option<bool>,
     #[serde(default)]
     keys: Vec<KeyConfig>,
+    /// Sign the zone with NSEC3 records, rather than NSEC records
+    nsec3: Option<Nsec3Config>,
+    /// Seconds for which the RRSIGs of the zone are valid
+    sig_validity: Option<u64>,
+    /// Seconds before the expiration of an RRSIG at which the record set is re-signed
+    sig_refresh: Option<u64>,
+    /// Maximum seconds by which the expiration of each RRSIG is brought forward, at random
+    sig_jitter: Option<u64>,
+    /// Masters from which a Slave zone is transferred, e.g. "192.0.2.1" or "192.0.2.1:5353"
+    #[serde(default)]
+    masters: Vec<String>,
+    /// Servers sent a NOTIFY when a Master zone changes, e.g. "192.0.2.2" or "192.0.2.2:5353"
+    #[serde(default)]
+    also_notify: Vec<String>,
+    /// Seconds between writes of the zone back to its file, the file is only written if the zone
+    ///  has changed, e.g. through dynamic updates. Zone files with `$INCLUDE` or `$GENERATE`
+    ///  entries are not written back, the entries would be replaced by their records.
+    flush_interval: Option<u64>,
+    /// Write the zone back to its file on shutdown, if the zone has changed, as `flush_interval`
+    flush_on_shutdown: Option<bool>,
+    /// Upstream name servers to which the queries of a Forward zone are sent
+    #[cfg(feature = "resolver")]
+    forwarders: Option<NameServerConfigGroup>,
+    /// Names of the TSIG keys which may sign updates and transfers of the zone, "key.example.com."
+    #[serde(default)]
+    tsig_keys: Vec<String>,
+    /// Clients allowed the operations on the zone, in place of those of all zones
+    #[serde(default)]
+    acl: AclConfig,
 }
 
 impl ZoneConfig {
@@ -193,6 +272,18 @@ impl ZoneConfig {
             allow_axfr,
             enable_dnssec,
             keys,
+            nsec3: None,
+            sig_validity: None,
+            sig_refresh: None,
+            sig_jitter: None,
+            masters: Vec::new(),
+            also_notify: Vec::new(),
+            flush_interval: None,
+            flush_on_shutdown: None,
+            #[cfg(feature = "resolver")]
+            forwarders: None,
+            tsig_keys: Vec::new(),
+            acl: AclConfig::default(),
         }
     }
 
@@ -234,6 +325,299 @@ impl ZoneConfig {
     pub fn get_keys(&self) -> &[KeyConfig] {
         &self.keys
     }
+
+    /// the configuration of NSEC3 for a signed zone, None if the zone is signed with NSEC
+    pub fn get_nsec3(&self) -> Option<&Nsec3Config> {
+        self.nsec3.as_ref()
+    }
+
+    /// the duration for which the RRSIGs of the zone are valid, defaults to 52 weeks
+    pub fn get_sig_validity(&self) -> Duration {
+        Duration::from_secs(self.sig_validity.unwrap_or(DEFAULT_SIG_VALIDITY))
+    }
+
+    /// the time before the expiration of an RRSIG at which the record set is re-signed, defaults
+    ///  to a quarter of the validity
+    pub fn get_sig_refresh(&self) -> Duration {
+        self.sig_refresh
+            .map_or_else(|| self.get_sig_validity() / 4, Duration::from_secs)
+    }
+
+    /// the maximum time by which the expiration of each RRSIG is brought forward, so that the
+    ///  record sets are not all due to be re-signed at once, defaults to a quarter of the refresh
+    pub fn get_sig_jitter(&self) -> Duration {
+        self.sig_jitter
+            .map_or_else(|| self.get_sig_refresh() / 4, Duration::from_secs)
+    }
+
+    /// the masters from which a Slave zone is transferred, the port defaults to 53
+    pub fn get_masters(&self) -> ParseResult<Vec<SocketAddr>> {
+        parse_servers(&self.masters, "master")
+    }
+
+    /// the servers to NOTIFY when a Master zone changes, the port defaults to 53
+    pub fn get_also_notify(&self) -> ParseResult<Vec<SocketAddr>> {
+        parse_servers(&self.also_notify, "also_notify")
+    }
+
+    /// the interval at which the zone is written back to its file, None if it is never written
+    ///
+    /// Zone files with `$INCLUDE` or `$GENERATE` entries are never written, the entries would be
+    ///  replaced by their records. Once written, the journal of a dynamically updated zone is
+    ///  compacted, the history since the previous write is kept for incremental transfers.
+    pub fn get_flush_interval(&self) -> Option<Duration> {
+        self.flush_interval.map(Duration::from_secs)
+    }
+
+    /// write the zone back to its file on shutdown
+    pub fn is_flush_on_shutdown(&self) -> bool {
+        self.flush_on_shutdown.unwrap_or(false)
+    }
+
+    /// the upstream name servers of a Forward zone, e.g. UDP, TCP, TLS or HTTPS name servers
+    #[cfg(feature = "resolver")]
+    pub fn get_forwarders(&self) -> Option<&NameServerConfigGroup> {
+        self.forwarders.as_ref()
+    }
+
+    /// the names of the TSIG keys which may sign updates and transfers of the zone, updates must
+    ///  still be allowed
+    pub fn get_tsig_keys(&self) -> ParseResult<Vec<Name>> {
+        self.tsig_keys
+            .iter()
+            .map(|name| Name::parse(name, Some(&Name::new())).map_err(Into::into))
+            .collect()
+    }
+
+    /// the clients allowed the operations on the zone, in place of the ACLs of all zones
+    pub fn get_acls(&self) -> ParseResult<Acls> {
+        self.acl.to_acls()
+    }
+}
+
+/// Access control lists of the operations on zones, operations without a list are not restricted
+///
+/// Each list is of the clients allowed the operation, checked in order, the first entry which
+///  matches the client decides: a network "192.0.2.0/24", an address "2001:db8::1", the name of
+///  a TSIG key "key:key.example.com", or "any". An entry prefixed with "!" denies the clients.
+#[derive(Deserialize, PartialEq, Debug, Default)]
+pub struct AclConfig {
+    /// Clients allowed to query
+    query: Option<Vec<String>>,
+    /// Clients allowed to update, updates must still be allowed by the zone
+    update: Option<Vec<String>>,
+    /// Clients allowed AXFR and IXFR, transfers must still be allowed by the zone
+    transfer: Option<Vec<String>>,
+    /// Clients allowed to NOTIFY, which must still be masters of the zone
+    notify: Option<Vec<String>>,
+}
+
+impl AclConfig {
+    /// the ACLs of the operations which have a list
+    pub fn to_acls(&self) -> ParseResult<Acls> {
+        let mut acls = Acls::new();
+        for &(operation, entries) in &[
+            (AclOperation::Query, &self.query),
+            (AclOperation::Update, &self.update),
+            (AclOperation::Transfer, &self.transfer),
+            (AclOperation::Notify, &self.notify),
+        ] {
+            if let Some(ref entries) = *entries {
+                acls.set(operation, parse_acl(entries)?);
+            }
+        }
+
+        Ok(acls)
+    }
+}
+
+fn parse_acl(entries: &[String]) -> ParseResult<Acl> {
+    let mut acl = Acl::new();
+    for entry in entries {
+        let (allow, clients) = if entry.starts_with('!') {
+            (false, &entry[1..])
+        } else {
+            (true, &entry[..])
+        };
+
+        let clients = if clients == "any" {
+            AclMatch::Any
+        } else if clients.starts_with("key:") {
+            AclMatch::Key(Name::parse(&clients[4..], Some(&Name::new()))?.into())
+        } else {
+            parse_network(clients)
+                .ok_or_else(|| ParseErrorKind::Msg(format!("bad acl entry: {}", entry)))?
+        };
+
+        if allow {
+            acl.allow(clients);
+        } else {
+            acl.deny(clients);
+        }
+    }
+
+    Ok(acl)
+}
+
+/// a network "192.0.2.0/24", or a single address
+fn parse_network(network: &str) -> Option<AclMatch> {
+    let mut parts = network.splitn(2, '/');
+    let addr = parts.next()?.parse::<IpAddr>().ok()?;
+    let prefix_len = match parts.next() {
+        Some(prefix_len) => prefix_len.parse::<u8>().ok()?,
+        None if addr.is_ipv4() => 32,
+        None => 128,
+    };
+
+    AclMatch::network(addr, prefix_len)
+}
+
+/// Response rate limiting, see `RateLimits` for the defaults
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct RateLimitConfig {
+    /// Identical responses per second to a network, 0 for unlimited
+    responses_per_second: Option<u32>,
+    /// NXDOMAIN responses per second to a network, defaults to responses_per_second
+    nxdomains_per_second: Option<u32>,
+    /// Error responses per second to a network, defaults to responses_per_second
+    errors_per_second: Option<u32>,
+    /// Seconds over which the rates are averaged
+    window: Option<u64>,
+    /// Every nth limited response is sent truncated rather than dropped, 0 drops them all
+    slip: Option<u32>,
+    /// Length of the prefix of IPv4 addresses which are counted together, at most 32
+    ipv4_prefix_len: Option<u8>,
+    /// Length of the prefix of IPv6 addresses which are counted together, at most 128
+    ipv6_prefix_len: Option<u8>,
+    /// The most networks and responses which are counted, the least recently counted are evicted
+    max_accounts: Option<usize>,
+}
+
+impl RateLimitConfig {
+    /// the limits of the rate of responses
+    pub fn to_rate_limits(&self) -> ParseResult<RateLimits> {
+        let defaults = RateLimits::default();
+        let responses_per_second = self
+            .responses_per_second
+            .unwrap_or(defaults.responses_per_second);
+
+        let ipv4_prefix_len = self.ipv4_prefix_len.unwrap_or(defaults.ipv4_prefix_len);
+        let ipv6_prefix_len = self.ipv6_prefix_len.unwrap_or(defaults.ipv6_prefix_len);
+        if ipv4_prefix_len > 32 || ipv6_prefix_len > 128 {
+            return Err(ParseErrorKind::Msg(format!(
+                "bad prefix length: {} {}",
+                ipv4_prefix_len, ipv6_prefix_len
+            )).into());
+        }
+
+        let max_accounts = self.max_accounts.unwrap_or(defaults.max_accounts);
+        if max_accounts == 0 {
+            return Err(ParseErrorKind::Msg("max_accounts must be at least 1".into()).into());
+        }
+
+        Ok(RateLimits {
+            responses_per_second,
+            nxdomains_per_second: self.nxdomains_per_second.unwrap_or(responses_per_second),
+            errors_per_second: self.errors_per_second.unwrap_or(responses_per_second),
+            window: self.window.map_or(defaults.window, Duration::from_secs),
+            slip: self.slip.unwrap_or(defaults.slip),
+            ipv4_prefix_len,
+            ipv6_prefix_len,
+            max_accounts,
+        })
+    }
+}
+
+/// DNS cookies, see `ServerCookies`
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct CookieConfig {
+    /// Require a valid server cookie of requests with a cookie over UDP
+    require: Option<bool>,
+    /// Seconds after which the secret is replaced by a random one, 0 never rotates it, not with
+    ///  a configured secret
+    rotation: Option<u64>,
+    /// The secret shared with the other servers of an anycast address, 16 bytes hex encoded
+    secret: Option<String>,
+}
+
+impl CookieConfig {
+    /// requests with a cookie but without a valid server cookie are answered with BADCOOKIE
+    pub fn is_required(&self) -> bool {
+        self.require.unwrap_or(false)
+    }
+
+    /// the interval at which a random secret is rotated, by default a day
+    ///
+    /// A configured secret is never rotated, it must be replaced on all the servers which share
+    ///  it, a rotation along with it is an error.
+    pub fn get_rotation(&self) -> ParseResult<Option<Duration>> {
+        let rotation = match (self.secret.is_some(), self.rotation) {
+            (_, Some(0)) | (true, None) => return Ok(None),
+            (true, Some(_)) => {
+                return Err(ParseErrorKind::Msg(
+                    "cookie rotation can not be set with a shared secret".into(),
+                ).into())
+            }
+            (false, rotation) => rotation.unwrap_or(DEFAULT_COOKIE_ROTATION),
+        };
+
+        Ok(Some(Duration::from_secs(rotation)))
+    }
+
+    /// the configured secret, None if a random one is used
+    pub fn get_secret(&self) -> ParseResult<Option<[u8; 16]>> {
+        let secret = match self.secret {
+            Some(ref secret) => secret,
+            None => return Ok(None),
+        };
+
+        match parse_hex(secret) {
+            Some(ref bytes) if bytes.len() == 16 => {
+                let mut key = [0u8; 16];
+                key.copy_from_slice(bytes);
+                Ok(Some(key))
+            }
+            // the secret is not logged
+            _ => Err(ParseErrorKind::Msg("cookie secret is not 16 bytes of hex".into()).into()),
+        }
+    }
+
+    /// the server cookies of the configuration
+    pub fn to_server_cookies(&self) -> ParseResult<ServerCookies> {
+        let rotation = self.get_rotation()?;
+        let mut cookies = match self.get_secret()? {
+            Some(secret) => ServerCookies::with_secret(secret),
+            None => ServerCookies::new(rotation),
+        };
+
+        cookies.set_required(self.is_required());
+        Ok(cookies)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|digits| {
+            ::std::str::from_utf8(digits)
+                .ok()
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        }).collect()
+}
+
+fn parse_servers(servers: &[String], kind: &str) -> ParseResult<Vec<SocketAddr>> {
+    servers
+        .iter()
+        .map(|server| {
+            server
+                .parse::<SocketAddr>()
+                .or_else(|_| {
+                    server
+                        .parse::<IpAddr>()
+                        .map(|ip| SocketAddr::new(ip, DEFAULT_PORT))
+                }).map_err(|_| ParseErrorKind::Msg(format!("bad {}: {}", kind, server)).into())
+        }).collect()
 }
 
 /// Key pair configuration for DNSSec keys for signing a zone
@@ -246,6 +630,15 @@ pub struct KeyConfig {
     signer_name: Option<String>,
     is_zone_signing_key: Option<bool>,
     is_zone_update_auth: Option<bool>,
+    is_key_signing_key: Option<bool>,
+    /// When the DNSKEY is added to the zone, e.g. "2019-01-01T00:00:00Z"
+    publish: Option<String>,
+    /// When the key starts to sign the zone
+    activate: Option<String>,
+    /// When the key stops signing the zone
+    inactive: Option<String>,
+    /// When the DNSKEY is removed from the zone
+    delete: Option<String>,
 }
 
 #[cfg(feature = "dnssec")]
@@ -275,6 +668,11 @@ impl KeyConfig {
             signer_name: Some(signer_name),
             is_zone_signing_key: Some(is_zone_signing_key),
             is_zone_update_auth: Some(is_zone_update_auth),
+            is_key_signing_key: None,
+            publish: None,
+            activate: None,
+            inactive: None,
+            delete: None,
         }
     }
 
@@ -348,6 +746,37 @@ impl KeyConfig {
     pub fn is_zone_update_auth(&self) -> bool {
         self.is_zone_update_auth.unwrap_or(false)
     }
+
+    /// specifies that this is a key signing key, the DNSKEY has the secure entry point flag and
+    ///  it only signs the DNSKEY record set, if the zone also has a zone signing key
+    pub fn is_key_signing_key(&self) -> bool {
+        self.is_key_signing_key.unwrap_or(false)
+    }
+
+    /// when the key is published, activated, retired and removed from the zone, the times are
+    ///  RFC 3339, a key without any times is always active
+    pub fn timing(&self) -> ParseResult<KeyTiming> {
+        let parse = |time: &Option<String>| -> ParseResult<Option<DateTime<Utc>>> {
+            match *time {
+                Some(ref time) => DateTime::parse_from_rfc3339(time)
+                    .map(|time| Some(time.with_timezone(&Utc)))
+                    .map_err(|e| format!("bad key time {}: {}", time, e).into()),
+                None => Ok(None),
+            }
+        };
+
+        KeyTiming::new(
+            parse(&self.publish)?,
+            parse(&self.activate)?,
+            parse(&self.inactive)?,
+            parse(&self.delete)?,
+        ).ok_or_else(|| {
+            format!(
+                "key times are not in order: {:?}",
+                self.key_path()
+            ).into()
+        })
+    }
 }
 
 #[cfg(not(feature = "dnssec"))]
@@ -355,6 +784,120 @@ impl KeyConfig {
 #[derive(Deserialize, PartialEq, Debug)]
 pub struct KeyConfig {}
 
+/// Configuration of NSEC3, the hashed denial of existence of a signed zone
+#[cfg(feature = "dnssec")]
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Nsec3Config {
+    salt: Option<String>,
+    iterations: Option<u16>,
+    opt_out: Option<bool>,
+}
+
+#[cfg(feature = "dnssec")]
+impl Nsec3Config {
+    /// the salt which is hashed along with the names, hex encoded, e.g. "aabbccdd", or none
+    pub fn salt(&self) -> ParseResult<Vec<u8>> {
+        let salt = match self.salt {
+            Some(ref salt) if salt != "-" => salt,
+            _ => return Ok(Vec::new()),
+        };
+
+        parse_hex(salt).ok_or_else(|| format!("nsec3 salt is not hex: {}", salt).into())
+    }
+
+    /// the number of additional times the names are hashed, 0 is recommended and the default
+    pub fn iterations(&self) -> u16 {
+        self.iterations.unwrap_or(0)
+    }
+
+    /// leave delegations to unsigned zones out of the NSEC3 chain
+    pub fn is_opt_out(&self) -> bool {
+        self.opt_out.unwrap_or(false)
+    }
+
+    /// the parameters of the NSEC3 records, with SHA-1, the only hash algorithm of NSEC3
+    ///
+    /// More than 100 iterations are rejected, see
+    ///  [RFC 9276](https://tools.ietf.org/html/rfc9276#section-3.2).
+    pub fn to_nsec3param(&self) -> ParseResult<NSEC3PARAM> {
+        if self.iterations() > MAX_NSEC3_ITERATIONS {
+            return Err(format!(
+                "nsec3 iterations: {} is more than: {}",
+                self.iterations(),
+                MAX_NSEC3_ITERATIONS
+            ).into());
+        }
+
+        Ok(NSEC3PARAM::new(
+            Nsec3HashAlgorithm::SHA1,
+            self.is_opt_out(),
+            self.iterations(),
+            self.salt()?,
+        ))
+    }
+}
+
+#[cfg(not(feature = "dnssec"))]
+#[allow(missing_docs)]
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct Nsec3Config {}
+
+/// Configuration of a key for TSIG, a secret shared with clients
+#[cfg(feature = "dnssec")]
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct TsigKeyConfig {
+    name: String,
+    algorithm: String,
+    key_path: String,
+}
+
+#[cfg(feature = "dnssec")]
+impl TsigKeyConfig {
+    /// Return a new TsigKeyConfig
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the key, which clients sign with, e.g. key.example.com
+    /// * `algorithm` - the MAC algorithm, e.g. hmac-sha256
+    /// * `key_path` - file path to the secret
+    pub fn new(name: String, algorithm: TsigAlgorithm, key_path: String) -> Self {
+        TsigKeyConfig {
+            name,
+            algorithm: algorithm.to_name().to_ascii(),
+            key_path,
+        }
+    }
+
+    /// the name of the key, the owner of the TSIG records which are signed with it
+    pub fn name(&self) -> ParseResult<Name> {
+        Name::parse(&self.name, Some(&Name::new())).map_err(Into::into)
+    }
+
+    /// algorithm of the key, one of hmac-sha256, hmac-sha384 or hmac-sha512
+    pub fn algorithm(&self) -> ParseResult<TsigAlgorithm> {
+        let name = Name::parse(&self.algorithm, Some(&Name::new()))?;
+        match TsigAlgorithm::from_name(name) {
+            TsigAlgorithm::Unknown(_) => {
+                Err(format!("unrecognized tsig algorithm {}", self.algorithm).into())
+            }
+            algorithm => Ok(algorithm),
+        }
+    }
+
+    /// path to the file of the secret, either relative to the zone directory, or explicit from
+    ///  the root
+    ///
+    /// The file holds the bytes of the secret as they are, e.g. 32 random bytes for hmac-sha256.
+    pub fn key_path(&self) -> &Path {
+        Path::new(&self.key_path)
+    }
+}
+
+#[cfg(not(feature = "dnssec"))]
+#[allow(missing_docs)]
+#[derive(Deserialize, PartialEq, Debug)]
+pub struct TsigKeyConfig {}
+
 /// Certificate format of the file being read
 #[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
@@ -8,6 +8,7 @@ use rusqlite;
 
 use failure::{Backtrace, Context, Fail};
 use std::fmt;
+use std::io;
 
 use proto::error::*;
 
@@ -31,6 +32,10 @@ pub enum ErrorKind {
     },
 
     // foreign
+    /// An error occurred while reading or writing a file
+    #[fail(display = "io error")]
+    Io,
+
     /// An error got returned by the trust-dns-proto crate
     #[fail(display = "proto error")]
     Proto,
@@ -87,6 +92,12 @@ impl From<Context<ErrorKind>> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        e.context(ErrorKind::Io).into()
+    }
+}
+
 impl From<ProtoError> for Error {
     fn from(e: ProtoError) -> Error {
         match *e.kind() {
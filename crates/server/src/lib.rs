@@ -34,6 +34,11 @@ extern crate failure;
 extern crate futures;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "openssl")]
+extern crate openssl;
+extern crate rand;
+#[cfg(feature = "ring")]
+extern crate ring;
 extern crate rusqlite;
 extern crate serde;
 #[macro_use]
@@ -61,6 +66,8 @@ extern crate trust_dns_https;
 #[cfg(feature = "dns-over-openssl")]
 extern crate trust_dns_openssl;
 pub extern crate trust_dns_proto as proto;
+#[cfg(feature = "resolver")]
+extern crate trust_dns_resolver;
 #[cfg(feature = "dns-over-rustls")]
 extern crate trust_dns_rustls;
 
@@ -31,6 +31,7 @@ extern crate log;
 #[cfg(feature = "dns-over-rustls")]
 extern crate rustls;
 extern crate tokio;
+extern crate tokio_signal;
 extern crate tokio_tcp;
 extern crate tokio_udp;
 extern crate trust_dns;
@@ -45,11 +46,12 @@ use std::fs::File;
 use std::io::{self, Read};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "dnssec")]
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use clap::{Arg, ArgMatches};
-use futures::{future, Future};
+use futures::{future, Future, Stream};
 #[cfg(feature = "dns-over-rustls")]
 use rustls::{Certificate, PrivateKey};
 use tokio::runtime::current_thread::Runtime;
@@ -58,18 +60,26 @@ use tokio_udp::UdpSocket;
 
 use trust_dns::error::ParseResult;
 #[cfg(feature = "dnssec")]
-use trust_dns::rr::dnssec::{KeyPair, Private, Signer};
+use trust_dns::rr::rdata::DNSKEY;
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::{KeyPair, Private, Signer, TSigner};
 use trust_dns::rr::Name;
-use trust_dns::serialize::txt::{Lexer, Parser};
+use trust_dns::serialize::txt::Parser;
 
 #[cfg(all(
     feature = "dns-over-openssl",
     not(feature = "dns-over-rustls")
 ))]
 use trust_dns_openssl::tls_server::*;
-use trust_dns_server::authority::{Authority, Catalog, Journal, ZoneType};
+#[cfg(feature = "resolver")]
+use trust_dns_server::authority::{root_hints, ForwardAuthority};
+use trust_dns_server::authority::{
+    notify_slaves, Authority, Catalog, Journal, SlaveRefresh, ZoneFlush, ZoneType,
+};
+#[cfg(feature = "dnssec")]
+use trust_dns_server::authority::ZoneResign;
 #[cfg(feature = "dnssec")]
-use trust_dns_server::config::KeyConfig;
+use trust_dns_server::config::{KeyConfig, TsigKeyConfig};
 #[cfg(feature = "dns-over-tls")]
 use trust_dns_server::config::TlsCertConfig;
 use trust_dns_server::config::{Config, ZoneConfig};
@@ -77,21 +87,14 @@ use trust_dns_server::logger;
 use trust_dns_server::server::ServerFuture;
 
 fn parse_zone_file(
-    file: File,
+    zone_path: &Path,
     origin: Option<Name>,
     zone_type: ZoneType,
     allow_update: bool,
     allow_axfr: bool,
     is_dnssec_enabled: bool,
 ) -> ParseResult<Authority> {
-    let mut file = file;
-    let mut buf = String::new();
-
-    // TODO, this should really use something to read line by line or some other method to
-    //  keep the usage down. and be a custom lexer...
-    file.read_to_string(&mut buf)?;
-    let lexer = Lexer::new(&buf);
-    let (origin, records) = Parser::new().parse(lexer, origin)?;
+    let (origin, records) = Parser::new().parse_file(zone_path, origin)?;
 
     Ok(Authority::new(
         origin,
@@ -104,7 +107,11 @@ fn parse_zone_file(
 }
 
 #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
-fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, String> {
+fn load_zone(
+    zone_dir: &Path,
+    zone_config: &ZoneConfig,
+    catalog: &Catalog,
+) -> Result<Authority, String> {
     debug!("loading zone with config: {:#?}", zone_config);
 
     let zone_name: Name = zone_config.get_zone().expect("bad zone name");
@@ -136,11 +143,8 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
     } else if zone_path.exists() {
         info!("loading zone file: {:?}", zone_path);
 
-        let zone_file = File::open(&zone_path)
-            .map_err(|e| format!("error opening zone file: {:?}: {}", zone_path, e))?;
-
         let mut authority = parse_zone_file(
-            zone_file,
+            &zone_path,
             Some(zone_name.clone()),
             zone_config.get_zone_type(),
             zone_config.is_update_allowed(),
@@ -164,6 +168,17 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
 
         info!("zone file loaded: {}", zone_name);
         authority
+    } else if zone_config.get_zone_type() == ZoneType::Slave {
+        // the zone will be populated on the first transfer from the master
+        info!("no zone file for slave, starting empty: {}", zone_name);
+        Authority::new(
+            zone_name.clone(),
+            BTreeMap::new(),
+            zone_config.get_zone_type(),
+            zone_config.is_update_allowed(),
+            zone_config.is_axfr_allowed(),
+            zone_config.is_dnssec_enabled(),
+        )
     } else {
         return Err(format!("no zone file defined at: {:?}", zone_path));
     };
@@ -173,26 +188,62 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         authority: &mut Authority,
         zone_name: Name,
         zone_config: &ZoneConfig,
+        catalog: &Catalog,
     ) -> Result<(), String> {
         if zone_config.is_dnssec_enabled() {
+            let sig_validity = zone_config.get_sig_validity();
+            if zone_config.get_sig_refresh() + zone_config.get_sig_jitter() >= sig_validity {
+                return Err("sig_refresh and sig_jitter must be less than sig_validity".to_string());
+            }
+
+            let sig_validity = Duration::from_std(sig_validity)
+                .map_err(|e| format!("bad sig_validity: {}", e))?;
+            let sig_jitter = Duration::from_std(zone_config.get_sig_jitter())
+                .map_err(|e| format!("bad sig_jitter: {}", e))?;
+
             for key_config in zone_config.get_keys() {
-                let signer = load_key(zone_name.clone(), key_config).map_err(|e| {
+                let signer = load_key(zone_name.clone(), key_config, sig_validity).map_err(|e| {
                     format!("failed to load key: {:?} msg: {}", key_config.key_path(), e)
                 })?;
+                let timing = key_config.timing().map_err(|e| e.to_string())?;
                 info!(
-                    "adding key to zone: {:?}, is_zsk: {}, is_auth: {}",
+                    "adding key to zone: {:?}, is_zsk: {}, is_ksk: {}, is_auth: {}, state: {:?}",
                     key_config.key_path(),
                     key_config.is_zone_signing_key(),
-                    key_config.is_zone_update_auth()
+                    key_config.is_key_signing_key(),
+                    key_config.is_zone_update_auth(),
+                    timing.state(Utc::now())
                 );
                 authority
-                    .add_secure_key(signer)
+                    .add_secure_key_with_timing(signer, timing)
                     .expect("failed to add key to authority");
             }
 
+            if let Some(nsec3) = zone_config.get_nsec3() {
+                let nsec3param = nsec3
+                    .to_nsec3param()
+                    .map_err(|e| format!("bad nsec3 config: {}", e))?;
+
+                info!("signing zone with nsec3: {}", nsec3param);
+                authority.set_nsec3(Some(nsec3param));
+            }
+
             info!("signing zone: {}", zone_config.get_zone().unwrap());
+            authority.set_sig_jitter(sig_jitter);
             authority.secure_zone().expect("failed to sign zone");
         }
+
+        // the TSIG keys are shared by the zones, they were read into the catalog
+        let tsig_keys = zone_config.get_tsig_keys().map_err(|e| e.to_string())?;
+        for name in tsig_keys {
+            let signer = catalog
+                .tsig_key(&name.clone().into())
+                .ok_or_else(|| format!("tsig key not configured: {}", name))?;
+
+            info!("adding tsig key to zone: {}", name);
+            authority.add_tsig_key(signer.clone());
+        }
+
         Ok(())
     }
 
@@ -201,12 +252,16 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
         _authority: &mut Authority,
         _zone_name: Name,
         _zone_config: &ZoneConfig,
+        _catalog: &Catalog,
     ) -> Result<(), String> {
         Ok(())
     }
 
     // load any keys for the Zone, if it is a dynamic update zone, then keys are required
-    load_keys(&mut authority, zone_name, zone_config)?;
+    load_keys(&mut authority, zone_name, zone_config, catalog)?;
+
+    let also_notify = zone_config.get_also_notify().map_err(|e| e.to_string())?;
+    authority.set_also_notify(also_notify);
 
     info!(
         "zone successfully loaded: {}",
@@ -215,6 +270,197 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
     Ok(authority)
 }
 
+/// Writes the zone back to its file, periodically and/or on shutdown as configured
+fn flush_zone(
+    zone_dir: &Path,
+    zone_config: &ZoneConfig,
+    authority: &Arc<RwLock<Authority>>,
+    flushes: &mut Vec<Box<Future<Item = (), Error = ()> + Send>>,
+    shutdown_flushes: &mut Vec<ZoneFlush>,
+) {
+    let flush_interval = zone_config.get_flush_interval();
+    if flush_interval.is_none() && !zone_config.is_flush_on_shutdown() {
+        return;
+    }
+
+    let zone_path: PathBuf = zone_dir.to_owned().join(zone_config.get_file());
+    let zone_flush = ZoneFlush::new(authority.clone(), zone_path);
+
+    // the zone would be written back without these entries, flattened to their records
+    match zone_flush.has_include_or_generate() {
+        Ok(false) => (),
+        Ok(true) => {
+            warn!(
+                "zone file {:?} has $INCLUDE or $GENERATE entries, it is not written back",
+                zone_flush.zone_path()
+            );
+            return;
+        }
+        Err(e) => {
+            warn!(
+                "could not read zone file {:?}, it is not written back: {}",
+                zone_flush.zone_path(),
+                e
+            );
+            return;
+        }
+    }
+
+    if let Some(flush_interval) = flush_interval {
+        info!(
+            "writing zone file {:?} every {}s",
+            zone_flush.zone_path(),
+            flush_interval.as_secs()
+        );
+        flushes.push(zone_flush.every(flush_interval));
+    }
+
+    if zone_config.is_flush_on_shutdown() {
+        shutdown_flushes.push(zone_flush);
+    }
+}
+
+/// Re-signs the record sets of a signed zone before their RRSIGs expire
+#[cfg(feature = "dnssec")]
+fn resign_zone(
+    zone_config: &ZoneConfig,
+    authority: &Arc<RwLock<Authority>>,
+    resigns: &mut Vec<Box<Future<Item = (), Error = ()> + Send>>,
+) -> Result<(), String> {
+    if !zone_config.is_dnssec_enabled() {
+        return Ok(());
+    }
+
+    let sig_refresh = zone_config.get_sig_refresh();
+    info!(
+        "re-signing zone {} {}s before its signatures expire",
+        authority.read().expect("authority poisoned").origin(),
+        sig_refresh.as_secs()
+    );
+
+    let sig_refresh =
+        Duration::from_std(sig_refresh).map_err(|e| format!("bad sig_refresh: {}", e))?;
+    resigns.push(ZoneResign::new(authority.clone(), sig_refresh).schedule());
+    Ok(())
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn resign_zone(
+    _zone_config: &ZoneConfig,
+    _authority: &Arc<RwLock<Authority>>,
+    _resigns: &mut Vec<Box<Future<Item = (), Error = ()> + Send>>,
+) -> Result<(), String> {
+    Ok(())
+}
+
+/// Resolves when the server is asked to stop, i.e. on SIGINT, or on SIGTERM on unix
+///
+/// A signal which can not be listened for is logged, the server still runs without it.
+fn shutdown_signal() -> Box<Future<Item = (), Error = ()> + Send> {
+    let ctrl_c = on_signal(tokio_signal::ctrl_c(), "SIGINT");
+
+    #[cfg(unix)]
+    {
+        use tokio_signal::unix::{Signal, SIGTERM};
+
+        let sigterm = on_signal(Signal::new(SIGTERM), "SIGTERM");
+        Box::new(ctrl_c.select(sigterm).map(|_| ()).map_err(|_| ()))
+    }
+
+    #[cfg(not(unix))]
+    Box::new(ctrl_c)
+}
+
+/// Resolves on the first signal of the stream, or never if the signal can not be listened for
+fn on_signal<S>(
+    signal: tokio_signal::IoFuture<S>,
+    name: &'static str,
+) -> Box<Future<Item = (), Error = ()> + Send>
+where
+    S: Stream<Error = io::Error> + Send + 'static,
+{
+    Box::new(signal.then(move |stream| -> Box<Future<Item = (), Error = ()> + Send> {
+        match stream {
+            Ok(stream) => Box::new(
+                stream
+                    .into_future()
+                    .map(move |_| info!("received {}", name))
+                    .map_err(move |(e, _)| error!("failed to listen for {}: {}", name, e)),
+            ),
+            Err(e) => {
+                warn!("could not listen for {}, it will not stop the server: {}", name, e);
+                Box::new(future::empty())
+            }
+        }
+    }))
+}
+
+/// Registers the forward zone with the catalog, returning the background future of its resolver
+#[cfg(feature = "resolver")]
+fn load_forward(
+    catalog: &mut Catalog,
+    zone_config: &ZoneConfig,
+) -> Result<Box<Future<Item = (), Error = ()> + Send>, String> {
+    let zone_name: Name = zone_config.get_zone().map_err(|e| e.to_string())?;
+    let name_servers = match zone_config.get_forwarders() {
+        Some(name_servers) if !name_servers.is_empty() => name_servers.clone(),
+        _ => return Err("no forwarders for forward zone".to_string()),
+    };
+
+    info!("forwarding zone {} to: {:?}", zone_name, name_servers);
+    let (forwarder, background) = ForwardAuthority::new(zone_name.clone(), name_servers);
+    catalog.upsert_forward(zone_name.into(), forwarder);
+
+    Ok(Box::new(background))
+}
+
+#[cfg(not(feature = "resolver"))]
+fn load_forward(
+    _catalog: &mut Catalog,
+    _zone_config: &ZoneConfig,
+) -> Result<Box<Future<Item = (), Error = ()> + Send>, String> {
+    Err("forward zones require the resolver feature".to_string())
+}
+
+/// Registers the Hint zone with the catalog, its file holds the root hints
+#[cfg(feature = "resolver")]
+fn load_hint(
+    catalog: &mut Catalog,
+    zone_dir: &Path,
+    zone_config: &ZoneConfig,
+) -> Result<(), String> {
+    use trust_dns::rr::DNSClass;
+
+    let zone_name: Name = zone_config.get_zone().map_err(|e| e.to_string())?;
+    let hints_path: PathBuf = zone_dir.to_owned().join(zone_config.get_file());
+
+    // root hints files state no class
+    let (_, records) = Parser::new()
+        .set_default_class(DNSClass::IN)
+        .parse_file(&hints_path, Some(Name::root()))
+        .map_err(|e| e.to_string())?;
+
+    let roots = root_hints(records.values().flat_map(|rrset| rrset.records_without_rrsigs()));
+    if roots.is_empty() {
+        return Err(format!("no root name servers in {}", hints_path.display()));
+    }
+
+    info!("resolving zone {} from the root name servers: {:?}", zone_name, roots);
+    let hint = ForwardAuthority::recursive(zone_name.clone(), roots);
+    catalog.upsert_forward(zone_name.into(), hint);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "resolver"))]
+fn load_hint(
+    _catalog: &mut Catalog,
+    _zone_dir: &Path,
+    _zone_config: &ZoneConfig,
+) -> Result<(), String> {
+    Err("hint zones require the resolver feature".to_string())
+}
+
 /// set of DNSSEC algorithms to use to sign the zone. enable_dnssec must be true.
 /// these will be lookedup by $file.{key_name}.pem, for backward compatability
 /// with previous versions of TRust-DNS, if enable_dnssec is enabled but
@@ -230,7 +476,11 @@ fn load_zone(zone_dir: &Path, zone_config: &ZoneConfig) -> Result<Authority, Str
 /// same directory has the zone $file:
 ///  keys = [ "my_rsa_2048|RSASHA256", "/path/to/my_ed25519|ED25519" ]
 #[cfg(feature = "dnssec")]
-fn load_key(zone_name: Name, key_config: &KeyConfig) -> Result<Signer, String> {
+fn load_key(
+    zone_name: Name,
+    key_config: &KeyConfig,
+    sig_validity: Duration,
+) -> Result<Signer, String> {
     let key_path = key_config.key_path();
     let algorithm = key_config
         .algorithm()
@@ -261,18 +511,45 @@ fn load_key(zone_name: Name, key_config: &KeyConfig) -> Result<Signer, String> {
         .unwrap_or(zone_name);
 
     // add the key to the zone
-    // TODO: allow the duration of signatutes to be customized
-    let dnskey = key
+    let mut dnskey = key
         .to_dnskey(algorithm)
         .map_err(|e| format!("error converting to dnskey: {}", e))?;
+    if key_config.is_key_signing_key() {
+        dnskey = DNSKEY::new(true, true, false, algorithm, dnskey.public_key().to_vec());
+    }
     Ok(Signer::dnssec(
         dnskey.clone(),
         key,
         name,
-        Duration::weeks(52),
+        sig_validity,
     ))
 }
 
+/// Load a TSIG key, a secret shared with clients
+///
+/// the file of the secret is relative to the zone directory
+#[cfg(feature = "dnssec")]
+fn load_tsig_key(zone_dir: &Path, key_config: &TsigKeyConfig) -> Result<TSigner, String> {
+    let key_path = zone_dir.to_owned().join(key_config.key_path());
+    let name = key_config
+        .name()
+        .map_err(|e| format!("bad tsig key name: {}", e))?;
+    let algorithm = key_config
+        .algorithm()
+        .map_err(|e| format!("bad algorithm: {}", e))?;
+
+    info!("reading tsig key: {:?}", key_path);
+    let mut file = File::open(&key_path)
+        .map_err(|e| format!("error opening tsig key file: {:?}: {}", key_path, e))?;
+
+    let mut key = Vec::with_capacity(64);
+    file.read_to_end(&mut key)
+        .map_err(|e| format!("could not read tsig key from: {:?}: {}", key_path, e))?;
+
+    // TODO: allow the fudge to be configured
+    TSigner::new(key, algorithm, name, 300).map_err(|e| format!("bad tsig key: {}", e))
+}
+
 #[cfg(all(
     feature = "dns-over-openssl",
     not(feature = "dns-over-rustls")
@@ -496,14 +773,122 @@ pub fn main() {
         .unwrap_or_else(|| &directory_config);
 
     let mut catalog: Catalog = Catalog::new();
+
+    #[cfg(feature = "dnssec")]
+    for key_config in config.get_tsig_keys() {
+        let signer = load_tsig_key(zone_dir, key_config)
+            .unwrap_or_else(|e| panic!("could not load tsig key {:?}: {}", key_config, e));
+        catalog.add_tsig_key(signer);
+    }
+
+    let acls = config
+        .get_acls()
+        .unwrap_or_else(|e| panic!("bad acl in {:?}: {}", config_path, e));
+    catalog.set_acls(acls);
+
+    if let Some(cookies) = config.get_cookies() {
+        let cookies = cookies
+            .to_server_cookies()
+            .unwrap_or_else(|e| panic!("bad cookies in {:?}: {}", config_path, e));
+        catalog.set_server_cookies(cookies);
+    }
+
+    let mut slave_refreshes: Vec<SlaveRefresh> = Vec::new();
+    let mut notifies: Vec<Box<Future<Item = (), Error = ()> + Send>> = Vec::new();
+    let mut forward_backgrounds: Vec<Box<Future<Item = (), Error = ()> + Send>> = Vec::new();
+    let mut flushes: Vec<Box<Future<Item = (), Error = ()> + Send>> = Vec::new();
+    let mut shutdown_flushes: Vec<ZoneFlush> = Vec::new();
+    let mut resigns: Vec<Box<Future<Item = (), Error = ()> + Send>> = Vec::new();
     // configure our server based on the config_path
     for zone in config.get_zones() {
         let zone_name = zone
             .get_zone()
             .unwrap_or_else(|_| panic!("bad zone name in {:?}", config_path));
 
-        match load_zone(zone_dir, zone) {
-            Ok(authority) => catalog.upsert(zone_name.into(), authority),
+        // a zone is not served without the ACLs it is configured with
+        match zone.get_acls() {
+            Ok(ref acls) if acls.is_empty() => (),
+            Ok(acls) => catalog.set_zone_acls(zone_name.clone().into(), acls),
+            Err(error) => {
+                error!("could not load zone {}: {}", zone_name, error);
+                continue;
+            }
+        }
+
+        if zone.get_zone_type() == ZoneType::Forward {
+            match load_forward(&mut catalog, zone) {
+                Ok(background) => forward_backgrounds.push(background),
+                Err(error) => error!("could not load zone {}: {}", zone_name, error),
+            }
+
+            continue;
+        }
+
+        if zone.get_zone_type() == ZoneType::Hint {
+            if let Err(error) = load_hint(&mut catalog, zone_dir, zone) {
+                error!("could not load zone {}: {}", zone_name, error);
+            }
+
+            continue;
+        }
+
+        if zone.get_zone_type() == ZoneType::Slave {
+            let masters = match zone.get_masters() {
+                Ok(ref masters) if masters.is_empty() => {
+                    error!("could not load zone {}: no masters for slave", zone_name);
+                    continue;
+                }
+                Ok(masters) => masters,
+                Err(error) => {
+                    error!("could not load zone {}: {}", zone_name, error);
+                    continue;
+                }
+            };
+
+            match load_zone(zone_dir, zone, &catalog) {
+                Ok(authority) => {
+                    let authority = Arc::new(RwLock::new(authority));
+                    flush_zone(
+                        zone_dir,
+                        zone,
+                        &authority,
+                        &mut flushes,
+                        &mut shutdown_flushes,
+                    );
+
+                    let slave_refresh = SlaveRefresh::new(authority.clone(), masters);
+                    catalog.upsert_shared(zone_name.clone().into(), authority);
+                    catalog.set_refresh_handle(zone_name.into(), slave_refresh.handle());
+                    slave_refreshes.push(slave_refresh);
+                }
+                Err(error) => error!("could not load zone {}: {}", zone_name, error),
+            }
+
+            continue;
+        }
+
+        match load_zone(zone_dir, zone, &catalog) {
+            Ok(authority) => {
+                // the slaves may have an older version of the zone than the one just loaded
+                if !authority.also_notify().is_empty() {
+                    notifies.push(notify_slaves(&authority));
+                }
+
+                let authority = Arc::new(RwLock::new(authority));
+                flush_zone(
+                    zone_dir,
+                    zone,
+                    &authority,
+                    &mut flushes,
+                    &mut shutdown_flushes,
+                );
+
+                if let Err(error) = resign_zone(zone, &authority, &mut resigns) {
+                    error!("could not re-sign zone {}: {}", zone_name, error);
+                }
+
+                catalog.upsert_shared(zone_name.into(), authority)
+            }
             Err(error) => error!("could not load zone {}: {}", zone_name, error),
         }
     }
@@ -539,8 +924,14 @@ pub fn main() {
     let mut io_loop = Runtime::new().expect("error when creating tokio Runtime");
 
     // now, run the server, based on the config
-    #[cfg_attr(not(feature = "dns-over-tls"), allow(unused_mut))]
     let mut server = ServerFuture::new(catalog);
+    if let Some(rate_limit) = config.get_rate_limit() {
+        let limits = rate_limit
+            .to_rate_limits()
+            .unwrap_or_else(|e| panic!("bad rate_limit in {:?}: {}", config_path, e));
+        info!("limiting the rate of UDP responses: {:?}", limits);
+        server.set_rate_limits(limits);
+    }
 
     let server_future: Box<Future<Item = (), Error = ()> + Send> =
         Box::new(future::lazy(move || {
@@ -558,6 +949,31 @@ pub fn main() {
                     .expect("could not register TCP listener");
             }
 
+            // keep all the slave zones up to date with their masters
+            for slave_refresh in slave_refreshes {
+                tokio::spawn(slave_refresh);
+            }
+
+            // the resolvers of the forward zones
+            for background in forward_backgrounds {
+                tokio::spawn(background);
+            }
+
+            // and let the slaves of the master zones know they were loaded
+            for notify in notifies {
+                tokio::spawn(notify);
+            }
+
+            // write the zones back to their files
+            for flush in flushes {
+                tokio::spawn(flush);
+            }
+
+            // and keep the signatures of the signed zones from expiring
+            for resign in resigns {
+                tokio::spawn(resign);
+            }
+
             let tls_cert_config = config.get_tls_cert();
 
             // and TLS as necessary
@@ -595,7 +1011,7 @@ pub fn main() {
             /// Ideally the processing would be n-threads for recieving, which hand off to m-threads for
             ///  request handling. It would generally be the case that n <= m.
             info!("Server starting up");
-            future::empty()
+            shutdown_signal()
         }));
 
     if let Err(e) = io_loop.block_on(server_future.map_err(|_| {
@@ -607,6 +1023,16 @@ pub fn main() {
         error!("failed to listen: {}", e);
     }
 
+    for zone_flush in shutdown_flushes {
+        if let Err(e) = zone_flush.flush() {
+            error!(
+                "failed to write zone file {:?}: {}",
+                zone_flush.zone_path(),
+                e
+            );
+        }
+    }
+
     // we're exiting for some reason...
     info!("Trust-DNS {} stopping", trust_dns::version());
 }
@@ -16,7 +16,8 @@ use tokio_io::{AsyncRead, AsyncWrite};
 use trust_dns_https::https_server;
 
 use authority::MessageResponse;
-use server::request_handler::RequestHandler;
+use server::protocol::Protocol;
+use server::request_handler::{Request, RequestHandler, TlsInfo};
 use server::response_handler::ResponseHandler;
 use server::server_future;
 
@@ -24,6 +25,8 @@ pub fn h2_handler<T, I>(
     handler: Arc<Mutex<T>>,
     io: I,
     src_addr: SocketAddr,
+    dst_addr: SocketAddr,
+    tls_info: Arc<TlsInfo>,
     dns_hostname: Arc<String>,
 ) -> impl Future<Item = (), Error = io::Error>
 where
@@ -42,6 +45,7 @@ where
                     debug!("Received request: {:#?}", request);
                     let dns_hostname = dns_hostname.clone();
                     let handler = handler.clone();
+                    let tls_info = tls_info.clone();
                     let responder = HttpsResponseHandle(respond);
 
                     https_server::message_from(dns_hostname, request)
@@ -50,12 +54,14 @@ where
                             let message = BinDecodable::from_bytes(&bytes)?;
                             debug!("reieved message: {:?}", message);
 
-                            server_future::handle_request(
+                            let request = Request {
                                 message,
-                                src_addr,
-                                handler.clone(),
-                                responder,
-                            )
+                                src: src_addr,
+                                dst: dst_addr,
+                                protocol: Protocol::Https,
+                                tls: Some(tls_info),
+                            };
+                            server_future::handle_request(request, handler.clone(), responder)
                         })
                 })
         }).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("error in h2 handler: {}", e)))
@@ -9,12 +9,18 @@
 
 #[cfg(feature = "dns-over-https")]
 mod https_handler;
+mod protocol;
+mod rate_limit;
 mod request_handler;
 mod response_handler;
 mod server_future;
 mod timeout_stream;
 
-pub use self::request_handler::{Request, RequestHandler};
+pub use self::protocol::Protocol;
+pub use self::rate_limit::{RateLimitAction, RateLimiter, RateLimits, ResponseIdentity};
+pub use self::request_handler::{Request, RequestHandler, TlsInfo};
+#[cfg(feature = "dnssec")]
+pub use self::response_handler::TsigResponseHandle;
 pub use self::response_handler::{ResponseHandle, ResponseHandler};
 pub use self::server_future::ServerFuture;
 pub use self::timeout_stream::TimeoutStream;
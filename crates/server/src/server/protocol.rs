@@ -0,0 +1,45 @@
+// Copyright 2015-2018 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::fmt;
+
+/// The protocol on which a Request was received
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// UDP, responses are limited to a single message
+    Udp,
+    /// TCP
+    Tcp,
+    /// TLS over TCP, aka DNS-over-TLS
+    Tls,
+    /// HTTPS over HTTP/2, aka DNS-over-HTTPS
+    Https,
+}
+
+impl Protocol {
+    /// Returns true if this is a stream based protocol, i.e. multiple response messages may be
+    ///  sent for a single request
+    pub fn is_stream(self) -> bool {
+        match self {
+            Protocol::Tcp | Protocol::Tls => true,
+            Protocol::Udp | Protocol::Https => false,
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let s = match *self {
+            Protocol::Udp => "UDP",
+            Protocol::Tcp => "TCP",
+            Protocol::Tls => "TLS",
+            Protocol::Https => "HTTPS",
+        };
+
+        f.write_str(s)
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright 2015-2019 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Response rate limiting, which keeps the server from being used to reflect amplified responses
+//!  over UDP at the forged source of the requests
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use trust_dns::op::{Query, ResponseCode};
+use trust_dns::rr::{LowerName, RecordType};
+
+use authority::acl::{network_of, unmapped};
+
+/// The limits of the `RateLimiter`, a rate of 0 does not limit those responses
+///
+/// The responses to the clients in a network, the prefix of their address, are counted together.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimits {
+    /// Identical responses, for the same name and type, per second to a network
+    pub responses_per_second: u32,
+    /// NXDOMAIN responses per second to a network, for any name, as the names of a random
+    ///  subdomain attack are all different
+    pub nxdomains_per_second: u32,
+    /// Error responses, e.g. ServFail or Refused, per second to a network
+    pub errors_per_second: u32,
+    /// The interval over which the rates are averaged, a network which exceeded a rate for the
+    ///  whole window is limited for as long again
+    pub window: Duration,
+    /// Every nth limited response is sent truncated, with no records, rather than dropped, so that
+    ///  real clients in the network retry over TCP, 0 drops them all
+    pub slip: u32,
+    /// The length of the prefix of IPv4 addresses which are counted together
+    pub ipv4_prefix_len: u8,
+    /// The length of the prefix of IPv6 addresses which are counted together
+    pub ipv6_prefix_len: u8,
+    /// The most accounts, of a network and identity, which are kept, the least recently charged
+    ///  are evicted to make room for new ones, so that forged sources can't exhaust the memory
+    pub max_accounts: usize,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            responses_per_second: 10,
+            nxdomains_per_second: 10,
+            errors_per_second: 10,
+            window: Duration::from_secs(15),
+            slip: 2,
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 56,
+            max_accounts: 20_000,
+        }
+    }
+}
+
+/// What is identical about responses which are counted together, besides the network of the
+///  client
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResponseIdentity {
+    /// A response with the records of the name and type, or without any
+    Answer(LowerName, RecordType),
+    /// An NXDOMAIN response
+    NxDomain,
+    /// An error response
+    Error,
+}
+
+impl ResponseIdentity {
+    /// Returns the identity of the response with the code, to the query
+    pub fn new(response_code: ResponseCode, query: Option<&Query>) -> Self {
+        match (response_code, query) {
+            (ResponseCode::NoError, Some(query)) => {
+                ResponseIdentity::Answer(LowerName::new(query.name()), query.query_type())
+            }
+            (ResponseCode::NXDomain, _) => ResponseIdentity::NxDomain,
+            _ => ResponseIdentity::Error,
+        }
+    }
+}
+
+/// What to do with a response, see `RateLimiter::check()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitAction {
+    /// Send the response
+    Send,
+    /// Send a truncated response, without any records, in place of the response
+    Slip,
+    /// Do not respond
+    Drop,
+}
+
+/// The responses of an identity to a network
+struct Account {
+    /// Responses which may still be sent, negative once the rate is exceeded
+    balance: f64,
+    updated: Instant,
+    limited: u32,
+}
+
+/// Limits the rate of responses to the networks of clients, as BIND does
+///
+/// Each account earns the rate of responses every second, up to one second's worth, and each
+///  response is charged to it. Responses are limited while the account is overdrawn, which it
+///  may be by up to the window's worth of responses.
+pub struct RateLimiter {
+    limits: RateLimits,
+    accounts: Mutex<Accounts>,
+}
+
+struct Accounts {
+    accounts: HashMap<(IpAddr, ResponseIdentity), Account>,
+    pruned: Instant,
+}
+
+impl Accounts {
+    /// Removes the least recently charged quarter of the accounts, at least one, so that the cost
+    ///  of finding them is shared by the accounts which take their place
+    fn evict_oldest(&mut self) {
+        let mut updated: Vec<Instant> = self
+            .accounts
+            .values()
+            .map(|account| account.updated)
+            .collect();
+        if updated.is_empty() {
+            return;
+        }
+        updated.sort_unstable();
+
+        let evicted = (updated.len() / 4).max(1);
+        let newest_evicted = updated[evicted - 1];
+        let mut ties = evicted - updated.iter().take_while(|u| **u < newest_evicted).count();
+        self.accounts.retain(|_, account| {
+            if account.updated == newest_evicted && ties > 0 {
+                ties -= 1;
+                return false;
+            }
+
+            account.updated > newest_evicted
+        });
+    }
+}
+
+impl RateLimiter {
+    /// Returns a new `RateLimiter` with the limits
+    pub fn new(limits: RateLimits) -> Self {
+        RateLimiter {
+            limits,
+            accounts: Mutex::new(Accounts {
+                accounts: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+        }
+    }
+
+    /// The limits of the responses
+    pub fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    /// Charges the response to the client, returns whether it should be sent, slipped or dropped
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - the address of the client
+    /// * `identity` - the identity of the response
+    /// * `now` - the time at which the response is sent
+    pub fn check(&self, src: IpAddr, identity: ResponseIdentity, now: Instant) -> RateLimitAction {
+        let rate = self.rate(&identity);
+        if rate == 0 {
+            return RateLimitAction::Send;
+        }
+
+        let src = unmapped(src);
+        let prefix_len = match src {
+            IpAddr::V4(_) => self.limits.ipv4_prefix_len,
+            IpAddr::V6(_) => self.limits.ipv6_prefix_len,
+        };
+        let network = network_of(src, prefix_len).unwrap_or(src);
+
+        let rate = f64::from(rate);
+        let window = secs(self.limits.window);
+        let mut accounts = self.accounts.lock().expect("poisoned lock");
+
+        // accounts which are back to a full balance are the same as new ones
+        if now > accounts.pruned + self.limits.window {
+            accounts.accounts.retain(|&(_, ref identity), account| {
+                let rate = f64::from(self.rate(identity));
+                account.balance + elapsed_secs(now, account.updated) * rate < rate
+            });
+            accounts.pruned = now;
+        }
+
+        let key = (network, identity);
+        if accounts.accounts.len() >= self.limits.max_accounts
+            && !accounts.accounts.contains_key(&key)
+        {
+            accounts.evict_oldest();
+        }
+
+        let account = accounts
+            .accounts
+            .entry(key)
+            .or_insert_with(|| Account {
+                balance: rate,
+                updated: now,
+                limited: 0,
+            });
+
+        if now > account.updated {
+            let earned = elapsed_secs(now, account.updated) * rate;
+            account.balance = (account.balance + earned).min(rate);
+            account.updated = now;
+        }
+        account.balance = (account.balance - 1.0).max(-rate * window);
+
+        if account.balance >= 0.0 {
+            return RateLimitAction::Send;
+        }
+
+        account.limited = account.limited.wrapping_add(1);
+        if self.limits.slip != 0 && account.limited % self.limits.slip == 0 {
+            RateLimitAction::Slip
+        } else {
+            RateLimitAction::Drop
+        }
+    }
+
+    /// The responses per second of the identity, 0 if they are not limited
+    fn rate(&self, identity: &ResponseIdentity) -> u32 {
+        match *identity {
+            ResponseIdentity::Answer(..) => self.limits.responses_per_second,
+            ResponseIdentity::NxDomain => self.limits.nxdomains_per_second,
+            ResponseIdentity::Error => self.limits.errors_per_second,
+        }
+    }
+}
+
+/// Seconds from then until now, 0 if now is not later
+fn elapsed_secs(now: Instant, then: Instant) -> f64 {
+    if now <= then {
+        return 0.0;
+    }
+
+    secs(now - then)
+}
+
+fn secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trust_dns::rr::Name;
+
+    fn answer(name: &str) -> ResponseIdentity {
+        ResponseIdentity::Answer(
+            LowerName::new(&Name::parse(name, None).unwrap()),
+            RecordType::A,
+        )
+    }
+
+    fn limits() -> RateLimits {
+        RateLimits {
+            responses_per_second: 2,
+            nxdomains_per_second: 1,
+            errors_per_second: 0,
+            window: Duration::from_secs(5),
+            slip: 2,
+            ..RateLimits::default()
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limiter = RateLimiter::new(limits());
+        let src = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        let actions: Vec<RateLimitAction> = (0..6)
+            .map(|_| limiter.check(src, answer("www.example.com."), now))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                RateLimitAction::Send,
+                RateLimitAction::Send,
+                RateLimitAction::Drop,
+                RateLimitAction::Slip,
+                RateLimitAction::Drop,
+                RateLimitAction::Slip,
+            ]
+        );
+
+        // other clients in the network are limited, other names and networks are not
+        assert_ne!(
+            limiter.check(IpAddr::from([192, 0, 2, 2]), answer("www.example.com."), now),
+            RateLimitAction::Send
+        );
+        assert_eq!(
+            limiter.check(src, answer("example.com."), now),
+            RateLimitAction::Send
+        );
+        assert_eq!(
+            limiter.check(IpAddr::from([192, 0, 3, 1]), answer("www.example.com."), now),
+            RateLimitAction::Send
+        );
+
+        // errors are not limited
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.check(src, ResponseIdentity::Error, now),
+                RateLimitAction::Send
+            );
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_recovers() {
+        let limiter = RateLimiter::new(limits());
+        let src = IpAddr::from([192, 0, 2, 1]);
+        let now = Instant::now();
+
+        assert_eq!(
+            limiter.check(src, ResponseIdentity::NxDomain, now),
+            RateLimitAction::Send
+        );
+        for _ in 0..100 {
+            assert_ne!(
+                limiter.check(src, ResponseIdentity::NxDomain, now),
+                RateLimitAction::Send
+            );
+        }
+
+        // overdrawn by at most the window's worth of responses
+        let later = now + Duration::from_secs(5);
+        assert_ne!(
+            limiter.check(src, ResponseIdentity::NxDomain, later),
+            RateLimitAction::Send
+        );
+        let later = later + Duration::from_secs(2);
+        assert_eq!(
+            limiter.check(src, ResponseIdentity::NxDomain, later),
+            RateLimitAction::Send
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_ipv6_prefix() {
+        let limiter = RateLimiter::new(limits());
+        let now = Instant::now();
+        let name = answer("www.example.com.");
+
+        for _ in 0..2 {
+            let src = "2001:db8:0:1::1".parse().unwrap();
+            assert_eq!(limiter.check(src, name.clone(), now), RateLimitAction::Send);
+        }
+        assert_ne!(
+            limiter.check("2001:db8:0:1::2".parse().unwrap(), name.clone(), now),
+            RateLimitAction::Send
+        );
+        assert_eq!(
+            limiter.check("2001:db8:0:100::1".parse().unwrap(), name, now),
+            RateLimitAction::Send
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_max_accounts() {
+        let limiter = RateLimiter::new(RateLimits {
+            max_accounts: 4,
+            ..limits()
+        });
+        let name = answer("www.example.com.");
+        let now = Instant::now();
+
+        let overdrawn = IpAddr::from([192, 0, 2, 1]);
+        for _ in 0..3 {
+            limiter.check(overdrawn, name.clone(), now);
+        }
+
+        let mut later = now;
+        for network in 3..7 {
+            later += Duration::from_millis(1);
+            let src = IpAddr::from([192, 0, network, 1]);
+            assert_eq!(limiter.check(src, name.clone(), later), RateLimitAction::Send);
+            assert!(limiter.accounts.lock().unwrap().accounts.len() <= 4);
+        }
+
+        // the least recently charged account was evicted, its network starts over
+        later += Duration::from_millis(1);
+        assert_eq!(
+            limiter.check(overdrawn, name.clone(), later),
+            RateLimitAction::Send
+        );
+        assert_eq!(limiter.accounts.lock().unwrap().accounts.len(), 4);
+    }
+}
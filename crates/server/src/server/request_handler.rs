@@ -9,9 +9,18 @@
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[cfg(feature = "dns-over-rustls")]
+use rustls::{ServerSession, Session};
+#[cfg(all(
+    feature = "dns-over-openssl",
+    not(feature = "dns-over-rustls")
+))]
+use trust_dns_openssl::tls_server::{NameType, SslRef};
 
 use authority::MessageRequest;
-use server::ResponseHandler;
+use server::{Protocol, ResponseHandler};
 
 /// An incoming request to the DNS catalog
 pub struct Request<'r> {
@@ -19,6 +28,65 @@ pub struct Request<'r> {
     pub message: MessageRequest<'r>,
     /// Source address of the Client
     pub src: SocketAddr,
+    /// Local address on which the request was received
+    ///
+    /// Over TCP, TLS and HTTPS this is the local address of the connection. Over UDP it is the
+    ///  address the socket is bound to, the destination address of the datagram is not read,
+    ///  e.g. with `IP_PKTINFO`, so for a socket which is bound to the unspecified address, e.g.
+    ///  `0.0.0.0`, this is the unspecified address.
+    pub dst: SocketAddr,
+    /// Protocol on which the request was received
+    pub protocol: Protocol,
+    /// The TLS session of the connection on which the request was received, for TLS and HTTPS
+    pub tls: Option<Arc<TlsInfo>>,
+}
+
+/// The TLS session of the connection on which a request was received
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    /// The name of the server which the client asked for in the handshake, i.e. SNI
+    pub server_name: Option<String>,
+    /// The DER encoded certificates of the client, its own certificate first, this is empty
+    ///  unless the client authenticated with a certificate
+    pub peer_certificates: Vec<Vec<u8>>,
+}
+
+impl TlsInfo {
+    /// Returns the session of an accepted openssl connection
+    #[cfg(all(
+        feature = "dns-over-openssl",
+        not(feature = "dns-over-rustls")
+    ))]
+    pub(crate) fn from_ssl(ssl: &SslRef) -> Self {
+        let mut peer_certificates = Vec::new();
+        if let Some(certificate) = ssl.peer_certificate() {
+            peer_certificates.extend(certificate.to_der().ok());
+        }
+
+        // on the server side, the chain does not include the certificate of the client
+        if let Some(chain) = ssl.peer_cert_chain() {
+            peer_certificates.extend(chain.iter().filter_map(|c| c.to_der().ok()));
+        }
+
+        TlsInfo {
+            server_name: ssl.servername(NameType::HOST_NAME).map(str::to_string),
+            peer_certificates,
+        }
+    }
+
+    /// Returns the session of an accepted rustls connection
+    #[cfg(feature = "dns-over-rustls")]
+    pub(crate) fn from_session(session: &ServerSession) -> Self {
+        TlsInfo {
+            server_name: session.get_sni_hostname().map(str::to_string),
+            peer_certificates: session
+                .get_peer_certificates()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|certificate| certificate.0)
+                .collect(),
+        }
+    }
 }
 
 /// Trait for handling incoming requests, and providing a message response.
@@ -7,12 +7,18 @@
 
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
 
-use trust_dns::serialize::binary::BinEncoder;
+use trust_dns::op::{Header, Query, ResponseCode};
+use trust_dns::serialize::binary::{BinEncodable, BinEncoder};
 use trust_dns::BufStreamHandle;
 use proto::xfer::SerialMessage;
 
 use authority::MessageResponse;
+#[cfg(feature = "dnssec")]
+use authority::TsigResponseSigner;
+use server::{RateLimitAction, RateLimiter, ResponseIdentity};
 
 /// A handler for send a response to a client
 pub trait ResponseHandler: Send {
@@ -23,6 +29,24 @@ pub trait ResponseHandler: Send {
     ///
     /// self is consumed as only one message should ever be sent in response to a Request
     fn send_response(self, response: MessageResponse) -> io::Result<()>;
+
+    /// Serializes and sends a series of messages in response to a single Request, e.g. an AXFR
+    ///
+    /// This is only valid on stream based protocols, the default implementation only supports a
+    ///  single message.
+    fn send_responses(self, mut responses: Vec<MessageResponse>) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        if responses.len() != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("multiple responses not supported: {}", responses.len()),
+            ));
+        }
+
+        self.send_response(responses.remove(0))
+    }
 }
 
 /// A handler for wraping a BufStreamHandle, which will properly serialize the message and add the
@@ -30,12 +54,47 @@ pub trait ResponseHandler: Send {
 pub struct ResponseHandle {
     dst: SocketAddr,
     stream_handle: BufStreamHandle,
+    max_size: u16,
+    rate_limit: Option<(Arc<RateLimiter>, Option<Query>)>,
 }
 
 impl ResponseHandle {
-    /// Returns a new `ResponseHandle` for sending a response message
+    /// Returns a new `ResponseHandle` for sending a response message on a stream
     pub fn new(dst: SocketAddr, stream_handle: BufStreamHandle) -> Self {
-        ResponseHandle { dst, stream_handle }
+        Self::with_max_size(dst, stream_handle, u16::max_value())
+    }
+
+    /// Returns a new `ResponseHandle` for sending a response message in a datagram, responses
+    ///  which do not fit are truncated
+    ///
+    /// # Arguments
+    ///
+    /// * `dst` - the address of the client
+    /// * `stream_handle` - the handle to send the datagram with
+    /// * `max_size` - the maximum size of a response, see `MessageRequest::max_payload()`
+    pub fn with_max_size(dst: SocketAddr, stream_handle: BufStreamHandle, max_size: u16) -> Self {
+        ResponseHandle {
+            dst,
+            stream_handle,
+            max_size,
+            rate_limit: None,
+        }
+    }
+
+    /// Limits the rate of the responses to the client, and the others in its network, responses
+    ///  over the limits are dropped, or slipped, see `RateLimiter::check()`
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limiter` - the limiter shared by the responses to all clients
+    /// * `query` - the query of the request, the responses to which are counted together
+    pub fn with_rate_limiter(
+        mut self,
+        rate_limiter: Arc<RateLimiter>,
+        query: Option<Query>,
+    ) -> Self {
+        self.rate_limit = Some((rate_limiter, query));
+        self
     }
 }
 
@@ -44,6 +103,37 @@ impl ResponseHandler for ResponseHandle {
     ///
     /// self is consumed as only one message should ever be sent in response to a Request
     fn send_response(self, response: MessageResponse) -> io::Result<()> {
+        self.send(response)
+    }
+
+    /// Serializes and sends each message in order to the wrapped handle
+    fn send_responses(self, responses: Vec<MessageResponse>) -> io::Result<()> {
+        for response in responses {
+            self.send(response)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ResponseHandle {
+    fn send(&self, response: MessageResponse) -> io::Result<()> {
+        if let Some((ref rate_limiter, ref query)) = self.rate_limit {
+            let response_code = ResponseCode::from(0, response.header().response_code());
+            let identity = ResponseIdentity::new(response_code, query.as_ref());
+            match rate_limiter.check(self.dst.ip(), identity, Instant::now()) {
+                RateLimitAction::Send => (),
+                RateLimitAction::Slip => {
+                    debug!("response: {} to {} slipped", response.header().id(), self.dst);
+                    return self.send_slip(response.header(), query.as_ref());
+                }
+                RateLimitAction::Drop => {
+                    debug!("response: {} to {} dropped", response.header().id(), self.dst);
+                    return Ok(());
+                }
+            }
+        }
+
         info!(
             "response: {} response_code: {}",
             response.header().id(),
@@ -52,6 +142,7 @@ impl ResponseHandler for ResponseHandle {
         let mut buffer = Vec::with_capacity(512);
         let encode_result = {
             let mut encoder: BinEncoder = BinEncoder::new(&mut buffer);
+            encoder.set_max_size(self.max_size);
             response.destructive_emit(&mut encoder)
         };
 
@@ -62,8 +153,74 @@ impl ResponseHandler for ResponseHandle {
             )
         })?;
 
+        self.send_buffer(buffer)
+    }
+
+    /// Sends a truncated response with only the question in place of a limited response, a real
+    ///  client retries over TCP, which can't be forged
+    fn send_slip(&self, header: &Header, query: Option<&Query>) -> io::Result<()> {
+        let mut header = header.clone();
+        header
+            .set_truncated(true)
+            .set_query_count(if query.is_some() { 1 } else { 0 })
+            .set_answer_count(0)
+            .set_name_server_count(0)
+            .set_additional_count(0);
+
+        let mut buffer = Vec::with_capacity(512);
+        let encode_result = {
+            let mut encoder: BinEncoder = BinEncoder::new(&mut buffer);
+            header
+                .emit(&mut encoder)
+                .and_then(|_| query.map_or(Ok(()), |query| query.emit(&mut encoder)))
+        };
+
+        encode_result.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("error encoding message: {}", e),
+            )
+        })?;
+
+        self.send_buffer(buffer)
+    }
+
+    fn send_buffer(&self, buffer: Vec<u8>) -> io::Result<()> {
         self.stream_handle
             .unbounded_send(SerialMessage::new(buffer, self.dst))
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))
     }
 }
+
+/// A handler which signs the responses to a TSIG signed request, before they are sent by the
+///  wrapped handler
+#[cfg(feature = "dnssec")]
+pub struct TsigResponseHandle<R: ResponseHandler> {
+    handle: R,
+    signer: TsigResponseSigner,
+}
+
+#[cfg(feature = "dnssec")]
+impl<R: ResponseHandler> TsigResponseHandle<R> {
+    /// Returns a new `TsigResponseHandle`, which signs every response with the signer
+    pub fn new(handle: R, signer: TsigResponseSigner) -> Self {
+        TsigResponseHandle { handle, signer }
+    }
+}
+
+#[cfg(feature = "dnssec")]
+impl<R: ResponseHandler> ResponseHandler for TsigResponseHandle<R> {
+    fn send_response(self, mut response: MessageResponse) -> io::Result<()> {
+        response.set_tsig_signer(self.signer);
+        self.handle.send_response(response)
+    }
+
+    /// Signs each message, the MAC of each covers the MAC of the one before it
+    fn send_responses(self, mut responses: Vec<MessageResponse>) -> io::Result<()> {
+        for response in &mut responses {
+            response.set_tsig_signer(self.signer.clone());
+        }
+
+        self.handle.send_responses(responses)
+    }
+}
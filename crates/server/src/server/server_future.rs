@@ -31,13 +31,17 @@ use proto::BufStreamHandle;
 use trust_dns_openssl::tls_server::*;
 
 use authority::MessageRequest;
-use server::{Request, RequestHandler, ResponseHandle, ResponseHandler, TimeoutStream};
+use server::{
+    Protocol, RateLimiter, RateLimits, Request, RequestHandler, ResponseHandle, ResponseHandler,
+    TimeoutStream, TlsInfo,
+};
 
 // TODO, would be nice to have a Slab for buffers here...
 
 /// A Futures based implementation of a DNS server
 pub struct ServerFuture<T: RequestHandler> {
     handler: Arc<Mutex<T>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl<T: RequestHandler> ServerFuture<T> {
@@ -45,24 +49,47 @@ impl<T: RequestHandler> ServerFuture<T> {
     pub fn new(handler: T) -> ServerFuture<T> {
         ServerFuture {
             handler: Arc::new(Mutex::new(handler)),
+            rate_limiter: None,
         }
     }
 
+    /// Limits the rate of the responses over UDP to each network, the limits only apply to the
+    ///  sockets registered after they are set
+    ///
+    /// Responses over UDP may be sent to the forged source address of a request, so the server can
+    ///  be used to reflect amplified traffic at it. Responses over TCP are not limited.
+    pub fn set_rate_limits(&mut self, limits: RateLimits) {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limits)));
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
+    ///
+    /// The `dst` of the requests is the address of the socket, see `Request::dst`, bind it to a
+    ///  specific address where the handler depends on the address a request was sent to.
     pub fn register_socket(&self, socket: tokio_udp::UdpSocket) {
         debug!("registered udp: {:?}", socket);
+        let dst_addr = socket.local_addr().expect("udp socket is not bound");
 
         // create the new UdpStream
         let (buf_stream, stream_handle) = UdpStream::with_bound(socket);
         //let request_stream = RequestStream::new(buf_stream, stream_handle);
         let handler = self.handler.clone();
+        let rate_limiter = self.rate_limiter.clone();
 
         // this spawns a ForEach future which handles all the requests into a Handler.
         tokio_executor::spawn(
             buf_stream
                 .for_each(move |message| {
                     let src_addr = message.addr();
-                    self::handle_raw_request(message, handler.clone(), stream_handle.clone())
+                    self::handle_raw_request(
+                        message,
+                        dst_addr,
+                        Protocol::Udp,
+                        None,
+                        rate_limiter.clone(),
+                        handler.clone(),
+                        stream_handle.clone(),
+                    )
                         .map_err(move |e| {
                             debug!("error parsing UDP request src: {:?} error: {}", src_addr, e)
                         }).ok();
@@ -106,6 +133,7 @@ impl<T: RequestHandler> ServerFuture<T> {
                 .incoming()
                 .for_each(move |tcp_stream| {
                     let src_addr = tcp_stream.peer_addr().unwrap();
+                    let dst_addr = tcp_stream.local_addr().unwrap();
                     debug!("accepted request from: {}", src_addr);
                     // take the created stream...
                     let (buf_stream, stream_handle) = TcpStream::from_stream(tcp_stream, src_addr);
@@ -119,6 +147,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                             .for_each(move |message| {
                                 self::handle_raw_request(
                                     message,
+                                    dst_addr,
+                                    Protocol::Tcp,
+                                    None,
+                                    None,
                                     handler.clone(),
                                     stream_handle.clone(),
                                 )
@@ -198,6 +230,7 @@ impl<T: RequestHandler> ServerFuture<T> {
                 .incoming()
                 .for_each(move |tcp_stream| {
                     let src_addr = tcp_stream.peer_addr().unwrap();
+                    let dst_addr = tcp_stream.local_addr().unwrap();
                     debug!("accepted request from: {}", src_addr);
                     let handler = handler.clone();
 
@@ -210,6 +243,8 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 format!("tls error: {}", e),
                             )
                         }).and_then(move |tls_stream| {
+                            let tls_info = TlsInfo::from_ssl(tls_stream.get_ref().ssl());
+                            let tls_info = Arc::new(tls_info);
                             let (buf_stream, stream_handle) =
                                 TlsStream::from_stream(tls_stream, src_addr);
                             let timeout_stream = TimeoutStream::new(buf_stream, timeout);
@@ -222,6 +257,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                                     .for_each(move |message| {
                                         self::handle_raw_request(
                                             message,
+                                            dst_addr,
+                                            Protocol::Tls,
+                                            Some(tls_info.clone()),
+                                            None,
                                             handler.clone(),
                                             stream_handle.clone(),
                                         )
@@ -318,6 +357,7 @@ impl<T: RequestHandler> ServerFuture<T> {
                 .incoming()
                 .for_each(move |tcp_stream| {
                     let src_addr = tcp_stream.peer_addr().unwrap();
+                    let dst_addr = tcp_stream.local_addr().unwrap();
                     debug!("accepted request from: {}", src_addr);
                     let handler = handler.clone();
 
@@ -331,6 +371,8 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 format!("tls error: {}", e),
                             )
                         }).and_then(move |tls_stream| {
+                            let tls_info = TlsInfo::from_session(tls_stream.get_ref().1);
+                            let tls_info = Arc::new(tls_info);
                             let (buf_stream, stream_handle) = tls_from_stream(tls_stream, src_addr);
                             let timeout_stream = TimeoutStream::new(buf_stream, timeout);
                             //let request_stream = RequestStream::new(timeout_stream, stream_handle);
@@ -342,6 +384,10 @@ impl<T: RequestHandler> ServerFuture<T> {
                                     .for_each(move |message| {
                                         self::handle_raw_request(
                                             message,
+                                            dst_addr,
+                                            Protocol::Tls,
+                                            Some(tls_info.clone()),
+                                            None,
                                             handler.clone(),
                                             stream_handle.clone(),
                                         )
@@ -441,6 +487,7 @@ impl<T: RequestHandler> ServerFuture<T> {
                 .incoming()
                 .for_each(move |tcp_stream| {
                     let src_addr = tcp_stream.peer_addr().unwrap();
+                    let dst_addr = tcp_stream.local_addr().unwrap();
                     debug!("accepted request from: {}", src_addr);
                     let handler = handler.clone();
                     let dns_hostname = dns_hostname.clone();
@@ -455,7 +502,15 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 format!("tls error: {}", e),
                             )
                         }).and_then(move |tls_stream| {
-                            h2_handler(handler, tls_stream, src_addr, dns_hostname)
+                            let tls_info = TlsInfo::from_session(tls_stream.get_ref().1);
+                            h2_handler(
+                                handler,
+                                tls_stream,
+                                src_addr,
+                                dst_addr,
+                                Arc::new(tls_info),
+                                dns_hostname,
+                            )
                         })
                     // FIXME: need to map this error to Ok, otherwise this is a DOS potential
                     // .map_err(move |e| {
@@ -470,11 +525,14 @@ impl<T: RequestHandler> ServerFuture<T> {
 
 pub(crate) fn handle_raw_request<T: RequestHandler>(
     message: SerialMessage,
+    dst_addr: SocketAddr,
+    protocol: Protocol,
+    tls: Option<Arc<TlsInfo>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
     request_handler: Arc<Mutex<T>>,
     response_handler: BufStreamHandle,
 ) -> io::Result<()> {
     let src_addr = message.addr();
-    let response_handler = ResponseHandle::new(message.addr(), response_handler);
 
     // TODO: rather than decoding the message here, this RequestStream should instead
     //       forward the request to another sender such that we could pull serialization off
@@ -482,23 +540,39 @@ pub(crate) fn handle_raw_request<T: RequestHandler>(
     // decode any messages that are ready
     let mut decoder = BinDecoder::new(message.bytes());
     let message = MessageRequest::read(&mut decoder)?;
-    self::handle_request(message, src_addr, request_handler, response_handler)
-}
 
-pub(crate) fn handle_request<'q, R: ResponseHandler + 'static, T: RequestHandler>(
-    message: MessageRequest<'q>,
-    src_addr: SocketAddr,
-    request_handler: Arc<Mutex<T>>,
-    response_handler: R,
-) -> io::Result<()> {
+    // responses over UDP are limited to the payload size of the client
+    let mut response_handler = match protocol {
+        Protocol::Udp => {
+            ResponseHandle::with_max_size(src_addr, response_handler, message.max_payload())
+        }
+        _ => ResponseHandle::new(src_addr, response_handler),
+    };
+    if let Some(rate_limiter) = rate_limiter {
+        let query = message.queries().first().map(|query| query.original().clone());
+        response_handler = response_handler.with_rate_limiter(rate_limiter, query);
+    }
     let request = Request {
         message,
         src: src_addr,
+        dst: dst_addr,
+        protocol,
+        tls,
     };
+    self::handle_request(request, request_handler, response_handler)
+}
 
+pub(crate) fn handle_request<R: ResponseHandler + 'static, T: RequestHandler>(
+    request: Request,
+    request_handler: Arc<Mutex<T>>,
+    response_handler: R,
+) -> io::Result<()> {
     info!(
-        "request: {} type: {:?} op_code: {:?} dnssec: {} {}",
+        "request: {} src: {} dst: {} protocol: {} type: {:?} op_code: {:?} dnssec: {} {}",
         request.message.id(),
+        request.src,
+        request.dst,
+        request.protocol,
         request.message.message_type(),
         request.message.op_code(),
         request
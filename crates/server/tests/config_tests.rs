@@ -13,17 +13,19 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+#[cfg(feature = "dnssec")]
+extern crate chrono;
 extern crate log;
 extern crate trust_dns;
 extern crate trust_dns_proto;
 extern crate trust_dns_server;
 
 use std::env;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use trust_dns_server::authority::ZoneType;
+use trust_dns_server::authority::{AclOperation, ZoneType};
 use trust_dns_server::config::*;
 
 #[test]
@@ -155,6 +157,252 @@ fn test_parse_toml() {
     assert_eq!(config.get_directory(), Path::new("/dev/null"));
 }
 
+#[test]
+fn test_parse_slave_masters() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Slave\"
+file = \"example.com.zone\"
+masters = [\"127.0.0.1\", \"127.0.0.2:5353\", \"[::1]:53\"]
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_zone_type(), ZoneType::Slave);
+    assert_eq!(
+        zone.get_masters().unwrap(),
+        vec![
+            "127.0.0.1:53".parse::<SocketAddr>().unwrap(),
+            "127.0.0.2:5353".parse::<SocketAddr>().unwrap(),
+            "[::1]:53".parse::<SocketAddr>().unwrap(),
+        ]
+    );
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Slave\"
+file = \"example.com.zone\"
+masters = [\"not an address\"]
+"
+        .parse()
+        .unwrap();
+
+    assert!(config.get_zones()[0].get_masters().is_err());
+}
+
+#[test]
+fn test_parse_also_notify() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+also_notify = [\"127.0.0.2\", \"127.0.0.3:5353\"]
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(
+        zone.get_also_notify().unwrap(),
+        vec![
+            "127.0.0.2:53".parse::<SocketAddr>().unwrap(),
+            "127.0.0.3:5353".parse::<SocketAddr>().unwrap(),
+        ]
+    );
+    assert!(zone.get_masters().unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_acls() {
+    use trust_dns::rr::{LowerName, Name};
+
+    let config: Config = "
+acl = { transfer = [\"192.0.2.0/24\"], notify = [] }
+
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+acl = { query = [\"!192.0.2.66\", \"any\"], update = [\"2001:db8::1\", \"key:key.example.com\"] }
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+acl = { query = [\"192.0.2.0/33\"] }
+"
+        .parse()
+        .unwrap();
+
+    let ip = |addr: &str| addr.parse::<IpAddr>().unwrap();
+    let key_name = LowerName::from(Name::parse("key.example.com.", None).unwrap());
+
+    let acls = config.get_acls().unwrap();
+    assert!(acls.get(AclOperation::Query).is_none());
+    assert!(acls.get(AclOperation::Update).is_none());
+    let transfer = acls.get(AclOperation::Transfer).unwrap();
+    assert!(transfer.is_allowed(ip("192.0.2.1"), None));
+    assert!(transfer.is_allowed(ip("::ffff:192.0.2.1"), None));
+    assert!(!transfer.is_allowed(ip("192.0.3.1"), None));
+    assert!(!acls
+        .get(AclOperation::Notify)
+        .unwrap()
+        .is_allowed(ip("192.0.2.1"), None));
+
+    let acls = config.get_zones()[0].get_acls().unwrap();
+    let query = acls.get(AclOperation::Query).unwrap();
+    assert!(!query.is_allowed(ip("192.0.2.66"), None));
+    assert!(query.is_allowed(ip("192.0.2.67"), None));
+    assert!(query.is_allowed(ip("2001:db8::2"), None));
+    let update = acls.get(AclOperation::Update).unwrap();
+    assert!(update.is_allowed(ip("2001:db8::1"), None));
+    assert!(!update.is_allowed(ip("2001:db8::2"), None));
+    assert!(update.is_allowed(ip("2001:db8::2"), Some(&key_name)));
+    assert!(acls.get(AclOperation::Transfer).is_none());
+
+    assert!(config.get_zones()[1].get_acls().is_err());
+}
+
+#[test]
+fn test_parse_rate_limit() {
+    use trust_dns_server::server::RateLimits;
+
+    let config: Config = "
+rate_limit = { responses_per_second = 5, errors_per_second = 0, window = 10, ipv6_prefix_len = 48 }
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        config.get_rate_limit().unwrap().to_rate_limits().unwrap(),
+        RateLimits {
+            responses_per_second: 5,
+            nxdomains_per_second: 5,
+            errors_per_second: 0,
+            window: Duration::from_secs(10),
+            slip: 2,
+            ipv4_prefix_len: 24,
+            ipv6_prefix_len: 48,
+            max_accounts: 20_000,
+        }
+    );
+
+    let config: Config = "rate_limit = { ipv4_prefix_len = 33 }".parse().unwrap();
+    assert!(config.get_rate_limit().unwrap().to_rate_limits().is_err());
+
+    let config: Config = "rate_limit = { max_accounts = 0 }".parse().unwrap();
+    assert!(config.get_rate_limit().unwrap().to_rate_limits().is_err());
+
+    let config: Config = "".parse().unwrap();
+    assert!(config.get_rate_limit().is_none());
+}
+
+#[test]
+fn test_parse_cookies() {
+    let config: Config = "cookies = { require = true }".parse().unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert!(cookies.is_required());
+    assert_eq!(cookies.get_rotation().unwrap(), Some(Duration::from_secs(86400)));
+    assert_eq!(cookies.get_secret().unwrap(), None);
+
+    let config: Config = "
+cookies = { secret = \"000102030405060708090a0b0c0d0e0f\" }
+"
+        .parse()
+        .unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert!(!cookies.is_required());
+    assert_eq!(cookies.get_rotation().unwrap(), None);
+    assert_eq!(
+        cookies.get_secret().unwrap(),
+        Some([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15])
+    );
+    assert!(cookies.to_server_cookies().is_ok());
+
+    let config: Config = "cookies = { secret = \"0001\" }".parse().unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert!(cookies.get_secret().is_err());
+
+    // a shared secret is not replaced by a random one
+    let config: Config = "
+cookies = { secret = \"000102030405060708090a0b0c0d0e0f\", rotation = 3600 }
+"
+        .parse()
+        .unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert!(cookies.get_rotation().is_err());
+    assert!(cookies.to_server_cookies().is_err());
+
+    let config: Config = "cookies = { rotation = 3600 }".parse().unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert_eq!(cookies.get_rotation().unwrap(), Some(Duration::from_secs(3600)));
+
+    let config: Config = "cookies = { rotation = 0 }".parse().unwrap();
+    let cookies = config.get_cookies().unwrap();
+    assert_eq!(cookies.get_rotation().unwrap(), None);
+
+    let config: Config = "".parse().unwrap();
+    assert!(config.get_cookies().is_none());
+}
+
+#[test]
+fn test_parse_flush() {
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+allow_update = true
+flush_interval = 300
+flush_on_shutdown = true
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_flush_interval(), Some(Duration::from_secs(300)));
+    assert!(zone.is_flush_on_shutdown());
+
+    let zone = &config.get_zones()[1];
+    assert_eq!(zone.get_flush_interval(), None);
+    assert!(!zone.is_flush_on_shutdown());
+}
+
+#[cfg(feature = "resolver")]
+#[test]
+fn test_parse_forwarders() {
+    let config: Config = "
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Forward\"
+forwarders = [
+    { socket_addr = \"127.0.0.1:53\", protocol = \"Udp\" },
+    { socket_addr = \"127.0.0.1:53\", protocol = \"Tcp\" },
+]
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_zone_type(), ZoneType::Forward);
+
+    let forwarders = zone.get_forwarders().expect("forwarders");
+    assert_eq!(forwarders.len(), 2);
+    assert_eq!(
+        forwarders[0].socket_addr,
+        "127.0.0.1:53".parse::<SocketAddr>().unwrap()
+    );
+}
+
 #[cfg(feature = "dnsssec")]
 #[test]
 fn test_parse_zone_keys() {
@@ -233,6 +481,248 @@ signer_name = \"ns.example.com.\"
     );
 }
 
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_parse_key_timing() {
+    use chrono::{DateTime, Utc};
+    use trust_dns_server::authority::KeyState;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+
+[[zones.keys]]
+key_path = \"/path/to/ksk.pem\"
+algorithm = \"RSASHA256\"
+is_key_signing_key = true
+
+[[zones.keys]]
+key_path = \"/path/to/zsk.pem\"
+algorithm = \"RSASHA256\"
+publish = \"2019-01-01T00:00:00Z\"
+activate = \"2019-01-08T00:00:00Z\"
+inactive = \"2019-04-01T00:00:00+02:00\"
+delete = \"2019-04-08T00:00:00Z\"
+
+[[zones.keys]]
+key_path = \"/path/to/bad_order.pem\"
+algorithm = \"RSASHA256\"
+publish = \"2019-01-08T00:00:00Z\"
+activate = \"2019-01-01T00:00:00Z\"
+
+[[zones.keys]]
+key_path = \"/path/to/bad_time.pem\"
+algorithm = \"RSASHA256\"
+activate = \"tomorrow\"
+"
+        .parse()
+        .unwrap();
+
+    let time = |time: &str| -> DateTime<Utc> { time.parse().unwrap() };
+    let keys = config.get_zones()[0].get_keys();
+
+    // a key without times is always active
+    assert!(keys[0].is_key_signing_key());
+    let timing = keys[0].timing().unwrap();
+    assert_eq!(timing.state(time("1970-01-01T00:00:00Z")), KeyState::Active);
+    assert_eq!(timing.next_change(time("1970-01-01T00:00:00Z")), None);
+
+    assert!(!keys[1].is_key_signing_key());
+    let timing = keys[1].timing().unwrap();
+    assert_eq!(timing.state(time("2018-12-31T00:00:00Z")), KeyState::Generated);
+    assert_eq!(timing.state(time("2019-01-01T00:00:00Z")), KeyState::Published);
+    assert_eq!(timing.state(time("2019-01-08T00:00:00Z")), KeyState::Active);
+    assert_eq!(timing.state(time("2019-03-31T23:00:00Z")), KeyState::Retired);
+    assert_eq!(timing.state(time("2019-04-08T00:00:00Z")), KeyState::Removed);
+    assert_eq!(
+        timing.next_change(time("2019-02-01T00:00:00Z")),
+        Some(time("2019-03-31T22:00:00Z"))
+    );
+    assert_eq!(timing.next_change(time("2019-04-08T00:00:00Z")), None);
+
+    assert!(keys[2].timing().is_err());
+    assert!(keys[3].timing().is_err());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_parse_tsig_keys() {
+    use trust_dns::rr::rdata::tsig::TsigAlgorithm;
+    use trust_dns::rr::Name;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+tsig_keys = [\"key.example.com\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+
+[[tsig_keys]]
+name = \"key.example.com\"
+algorithm = \"hmac-sha256\"
+key_path = \"key.example.com.secret\"
+"
+        .parse()
+        .unwrap();
+
+    let keys = config.get_tsig_keys();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(
+        keys[0].name().unwrap(),
+        Name::parse("key.example.com.", None).unwrap()
+    );
+    assert_eq!(keys[0].algorithm().unwrap(), TsigAlgorithm::HmacSha256);
+    assert_eq!(keys[0].key_path(), Path::new("key.example.com.secret"));
+
+    assert_eq!(
+        config.get_zones()[0].get_tsig_keys().unwrap(),
+        vec![Name::parse("key.example.com.", None).unwrap()]
+    );
+    assert!(config.get_zones()[1].get_tsig_keys().unwrap().is_empty());
+
+    let config: Config = "
+[[tsig_keys]]
+name = \"key.example.com\"
+algorithm = \"hmac-md5.sig-alg.reg.int\"
+key_path = \"key.example.com.secret\"
+"
+        .parse()
+        .unwrap();
+    assert!(config.get_tsig_keys()[0].algorithm().is_err());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_parse_nsec3() {
+    use trust_dns::rr::dnssec::Nsec3HashAlgorithm;
+    use trust_dns::rr::rdata::NSEC3PARAM;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+
+[zones.nsec3]
+salt = \"aabbccdd\"
+iterations = 1
+opt_out = true
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        config.get_zones()[0]
+            .get_nsec3()
+            .unwrap()
+            .to_nsec3param()
+            .unwrap(),
+        NSEC3PARAM::new(
+            Nsec3HashAlgorithm::SHA1,
+            true,
+            1,
+            vec![0xaa, 0xbb, 0xcc, 0xdd]
+        )
+    );
+    assert!(config.get_zones()[1].get_nsec3().is_none());
+
+    // defaults, no salt and no additional iterations
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+nsec3 = { salt = \"-\" }
+"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        config.get_zones()[0]
+            .get_nsec3()
+            .unwrap()
+            .to_nsec3param()
+            .unwrap(),
+        NSEC3PARAM::new(Nsec3HashAlgorithm::SHA1, false, 0, vec![])
+    );
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+nsec3 = { salt = \"not hex\" }
+"
+        .parse()
+        .unwrap();
+    assert!(config.get_zones()[0].get_nsec3().unwrap().to_nsec3param().is_err());
+
+    // at most 100 additional iterations
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+nsec3 = { iterations = 100 }
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Master\"
+file = \"example.net.zone\"
+nsec3 = { iterations = 101 }
+"
+        .parse()
+        .unwrap();
+    assert!(config.get_zones()[0].get_nsec3().unwrap().to_nsec3param().is_ok());
+    assert!(config.get_zones()[1].get_nsec3().unwrap().to_nsec3param().is_err());
+}
+
+#[test]
+fn test_parse_sig_refresh() {
+    // defaults, a quarter of the validity and a quarter of the refresh
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_sig_validity(), Duration::from_secs(52 * 7 * 24 * 60 * 60));
+    assert_eq!(zone.get_sig_refresh(), Duration::from_secs(13 * 7 * 24 * 60 * 60));
+    assert_eq!(zone.get_sig_jitter(), Duration::from_secs(13 * 7 * 6 * 60 * 60));
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Master\"
+file = \"example.com.zone\"
+sig_validity = 2592000
+sig_refresh = 604800
+sig_jitter = 3600
+"
+        .parse()
+        .unwrap();
+
+    let zone = &config.get_zones()[0];
+    assert_eq!(zone.get_sig_validity(), Duration::from_secs(2592000));
+    assert_eq!(zone.get_sig_refresh(), Duration::from_secs(604800));
+    assert_eq!(zone.get_sig_jitter(), Duration::from_secs(3600));
+}
+
 #[test]
 fn test_parse_tls() {
     // defaults
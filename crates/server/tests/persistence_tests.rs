@@ -99,3 +99,18 @@ fn test_iterator() {
     );
     assert_eq!(None, iter.next());
 }
+
+#[test]
+fn test_delete_through() {
+    let (mut record, journal) = create_test_journal();
+    let row_id = journal.last_row_id().unwrap();
+    assert!(row_id > 0);
+
+    record.set_rdata(RData::A(Ipv4Addr::from_str("127.0.2.1").unwrap()));
+    journal.insert_record(0, &record).unwrap();
+
+    // only the records after row_id remain
+    assert_eq!(journal.delete_through(row_id).unwrap(), 2);
+    assert_eq!(journal.iter().collect::<Vec<_>>(), vec![record]);
+    assert_eq!(journal.last_row_id().unwrap(), row_id + 1);
+}
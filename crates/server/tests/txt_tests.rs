@@ -2,7 +2,10 @@ extern crate trust_dns;
 extern crate trust_dns_proto;
 extern crate trust_dns_server;
 
+use std::collections::BTreeMap;
+use std::env;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use trust_dns::proto::rr::rdata::tlsa::*;
@@ -353,3 +356,305 @@ _443._tcp.www.example.com. IN TLSA (
         assert!(false);
     }
 }
+
+fn include_path(file: &str) -> PathBuf {
+    let server_path = env::var("TDNS_SERVER_SRC_ROOT").unwrap_or_else(|_| ".".to_owned());
+    PathBuf::from(server_path)
+        .join("tests/named_test_configs/include")
+        .join(file)
+}
+
+#[test]
+fn test_include() {
+    let (origin, records) = Parser::new()
+        .parse_file(include_path("example.com.zone"), None)
+        .expect("failed to parse");
+
+    assert_eq!(origin, Name::from_str("example.com.").unwrap());
+
+    let address = |name: &str| -> Option<RData> {
+        records
+            .get(&RrKey::new(
+                Name::from_str(name).unwrap().into(),
+                RecordType::A,
+            )).map(|rrset| rrset.records_without_rrsigs().next().unwrap().rdata().clone())
+    };
+
+    // relative to the including file, with its origin
+    assert_eq!(
+        address("ns.example.com."),
+        Some(RData::A(Ipv4Addr::new(192, 0, 2, 1)))
+    );
+    assert_eq!(
+        address("www.example.com."),
+        Some(RData::A(Ipv4Addr::new(192, 0, 2, 80)))
+    );
+    assert_eq!(
+        address("ftp.other.example.com."),
+        Some(RData::A(Ipv4Addr::new(192, 0, 2, 21)))
+    );
+
+    // with the origin given in the $INCLUDE
+    assert_eq!(
+        address("host.sub.example.com."),
+        Some(RData::A(Ipv4Addr::new(192, 0, 2, 30)))
+    );
+
+    // the origin of the including file is unchanged
+    assert_eq!(
+        address("mail.example.com."),
+        Some(RData::A(Ipv4Addr::new(192, 0, 2, 25)))
+    );
+    assert_eq!(address("mail.other.example.com."), None);
+}
+
+#[test]
+fn test_include_depth() {
+    let error = Parser::new()
+        .parse_file(include_path("loop.zone"), None)
+        .expect_err("recursive $INCLUDE should fail");
+
+    assert!(
+        error.to_string().contains("exceeds the maximum depth"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_include_error_location() {
+    let error = Parser::new()
+        .parse_file(include_path("broken.zone"), None)
+        .expect_err("broken record should fail");
+
+    // the error names the $INCLUDE entry and the failing record
+    let error = error.to_string();
+    assert!(error.contains("broken.zone:3: "), "{}", error);
+    assert!(error.contains("broken_record.zone:2: "), "{}", error);
+}
+
+fn generate(zone: &str) -> BTreeMap<RrKey, RecordSet> {
+    let lexer = Lexer::new(zone);
+    let (_, records) = Parser::new()
+        .set_default_class(DNSClass::IN)
+        .parse(lexer, Some(Name::from_str("0.10.in-addr.arpa.").unwrap()))
+        .unwrap_or_else(|e| panic!("failed to parse $GENERATE: {}", e));
+    records
+}
+
+fn generated<'r>(
+    records: &'r BTreeMap<RrKey, RecordSet>,
+    name: &str,
+    rtype: RecordType,
+) -> &'r Record {
+    let name = LowerName::new(&Name::from_str(name).unwrap());
+    let set = records
+        .get(&RrKey::new(name, rtype))
+        .expect("record not generated");
+    assert_eq!(set.records_without_rrsigs().count(), 1);
+    set.records_without_rrsigs().next().unwrap()
+}
+
+#[test]
+fn test_generate() {
+    let records = generate(
+        "$TTL 3600
+$GENERATE 1-10/3 $ PTR host-$.example.com.
+$GENERATE 250-255 ${-250}.1 7200 IN TXT cost-\\$${0,3,d}",
+    );
+
+    // 1, 4, 7, 10 and 250 to 255
+    assert_eq!(records.len(), 10);
+
+    let ptr = generated(&records, "7.0.10.in-addr.arpa.", RecordType::PTR);
+    assert_eq!(ptr.ttl(), 3600);
+    assert_eq!(ptr.dns_class(), DNSClass::IN);
+    assert_eq!(
+        *ptr.rdata(),
+        RData::PTR(Name::from_str("host-7.example.com.").unwrap())
+    );
+    assert!(
+        records
+            .keys()
+            .all(|key| key.name != LowerName::from_str("8.0.10.in-addr.arpa.").unwrap())
+    );
+
+    let txt = generated(&records, "3.1.0.10.in-addr.arpa.", RecordType::TXT);
+    assert_eq!(txt.ttl(), 7200);
+    assert_eq!(
+        *txt.rdata(),
+        RData::TXT(rdata::TXT::new(vec!["cost-$253".to_string()]))
+    );
+}
+
+#[test]
+fn test_generate_modifiers() {
+    let records = generate(
+        "$TTL 3600
+$GENERATE 10-11 host-${0,4,x} A 10.0.0.$
+$GENERATE 255-255 ${0,8,o} A 10.0.0.${-5}
+$GENERATE 26-26 ${0,0,N} A 10.0.0.1
+$GENERATE 1-1 ${0,7,n}.ip6 A 10.0.0.1",
+    );
+
+    let a = generated(&records, "host-000b.0.10.in-addr.arpa.", RecordType::A);
+    assert_eq!(*a.rdata(), RData::A(Ipv4Addr::new(10, 0, 0, 11)));
+
+    let a = generated(&records, "00000377.0.10.in-addr.arpa.", RecordType::A);
+    assert_eq!(*a.rdata(), RData::A(Ipv4Addr::new(10, 0, 0, 250)));
+
+    // nibbles are the labels of ip6.arpa. names, least significant first
+    generated(&records, "A.1.0.10.in-addr.arpa.", RecordType::A);
+    generated(&records, "1.0.0.0.ip6.0.10.in-addr.arpa.", RecordType::A);
+}
+
+#[test]
+fn test_generate_errors() {
+    for zone in &[
+        "$GENERATE 1-10 $ PTR host-$.example.com.",
+        "$TTL 3600\n$GENERATE 10-1 $ PTR host-$.example.com.",
+        "$TTL 3600\n$GENERATE 1-10 $ PTR host-${-2}.example.com.",
+        "$TTL 3600\n$GENERATE 1-10 $ PTR host-${0,3,z}.example.com.",
+        "$TTL 3600\n$GENERATE 1-10 $ PTR",
+        "$TTL 3600\n$GENERATE 0-65536 $ PTR host-$.example.com.",
+        "$TTL 3600\n$GENERATE 0-4294967295 $ PTR host-$.example.com.",
+    ] {
+        let result = Parser::new().parse(
+            Lexer::new(zone),
+            Some(Name::from_str("0.10.in-addr.arpa.").unwrap()),
+        );
+        assert!(result.is_err(), "expected an error for: {}", zone);
+    }
+}
+
+#[test]
+fn test_meta_type_errors() {
+    for zone in &[
+        "key 3600 IN TSIG hmac-sha256. 1554000000 300 32 AAAA 42 0 0\n",
+        "any 3600 IN ANY 10.0.0.1\n",
+        "axfr 3600 IN AXFR example.com.\n",
+        "ixfr 3600 IN IXFR example.com.\n",
+        "opt 3600 IN TYPE41 4096\n",
+    ] {
+        let result = Parser::new().parse(
+            Lexer::new(zone),
+            Some(Name::from_str("example.com.").unwrap()),
+        );
+        assert!(result.is_err(), "expected an error for: {}", zone);
+    }
+}
+
+fn parse_zone(zone: &str) -> BTreeMap<RrKey, RecordSet> {
+    let (_, records) = Parser::new()
+        .parse(Lexer::new(zone), Some(Name::from_str("example.com.").unwrap()))
+        .unwrap_or_else(|e| panic!("failed to parse: {}\n{}", e, zone));
+    records
+}
+
+#[test]
+fn test_writer_round_trip() {
+    let records = parse_zone(
+        "$TTL 3600
+@   IN  SOA     ns      action\\.domains (20 7200 600 3600000 60)
+        NS      ns
+        MX      10 mail
+ns      A       10.0.0.1
+        AAAA    4321:0:1:2:3:4:567:89ab
+www     CNAME   ns
+old     DNAME   new.example.net.
+1.0.0.10.in-addr.arpa. PTR ns
+_ldap._tcp SRV 1 2 389 ns
+txt     TXT     \"a \\\"quoted\\\" \\\\ string\" tab\\009 \"\\255\\000\" rust-❤️-🦀
+caa     CAA     128 issue \"ca.example.net; account=230123\"
+        CAA     0 iodef \"mailto:security@example.com\"
+ssh     SSHFP   1 1 dd465c09cfa51fb45020cc83316fff21b9ec74ac
+_443._tcp.www TLSA 3 1 1 d2abde240d7cd3ee6b4b28c54df034b97983a1d16e8a410e4561cb106618e971
+pgp     OPENPGPKEY dHJ1c3RfZG5zIGlzIGF3ZXNvbWU=
+null    NULL    \\# 3 000102
+unknown 60 TYPE65280 \\# 4 0A000001
+generic A       \\# 4 0A000002
+@       DNSKEY  257 3 8 AwEAAa+HnwnJ4vmeLATUyMm5FFYhm9ySdtrZQkzJTBNh8eNzi5kk
+        DNSKEY  256 3 8 AwEAAbAhzl6Le5LrzpxOX/4yGLWQwgTx
+        RRSIG   DNSKEY 8 2 3600 20190309120000 20190207120000 12345 example.com. aGVsbG8gd29ybGQ=
+        NSEC    ns A NS SOA MX RRSIG NSEC DNSKEY TYPE65280
+        NSEC3PARAM 1 0 10 AABBCCDD
+1JIRG8LNMKUD2A5FH3FGGNOJ2RBFVDTA NSEC3 1 1 10 - 2T7B4G4VSA5SMI47K61MV5BV1A22BOJR A RRSIG
+child   DS      60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118
+sig0    KEY     512 3 8 AwEAAa+HnwnJ4vmeLATUyMm5FFYhm9yS
+",
+    );
+
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .write_zone(&Name::from_str("example.com.").unwrap(), records.values())
+        .expect("failed to write zone");
+    let zone = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert!(zone.starts_with("$ORIGIN example.com.\nexample.com. "), "{}", zone);
+    assert!(
+        zone.contains("unknown.example.com. 60 IN TYPE65280 \\# 4 0A000001\n"),
+        "{}",
+        zone
+    );
+    assert!(zone.contains("old.example.com. 3600 IN DNAME new.example.net.\n"), "{}", zone);
+    assert!(zone.contains(" RRSIG DNSKEY 8 2 3600 20190309120000 20190207120000 12345 "));
+
+    let written = parse_zone(&zone);
+    assert_eq!(records.len(), written.len());
+    for (key, rrset) in &records {
+        let written = written.get(key).expect("record set missing");
+        let records = rrset.iter().collect::<Vec<_>>();
+        let written = written.iter().collect::<Vec<_>>();
+
+        // equality of records does not include the TTL
+        assert_eq!(records, written);
+        for (record, written) in records.iter().zip(written) {
+            if record.rr_type() != RecordType::SOA {
+                assert_eq!(record.ttl(), written.ttl());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_writer_round_trip_octets() {
+    let octets = (0..256).map(|b| b as u8).collect::<Vec<u8>>();
+    let escaped = octets
+        .chunks(128)
+        .map(|chunk| {
+            let escaped = chunk.iter().map(|b| format!("\\{:03}", b)).collect::<String>();
+            format!("\"{}\"", escaped)
+        }).collect::<Vec<_>>()
+        .join(" ");
+    let records = parse_zone(&format!(
+        "@ 3600 IN SOA ns admin (20 7200 600 3600000 60)\nbin 3600 IN TXT {}\n",
+        escaped
+    ));
+
+    let txt_data = |records: &BTreeMap<RrKey, RecordSet>| -> Vec<Box<[u8]>> {
+        let key = RrKey::new(
+            LowerName::new(&Name::from_str("bin.example.com.").unwrap()),
+            RecordType::TXT,
+        );
+        match *records[&key].records_without_rrsigs().next().unwrap().rdata() {
+            RData::TXT(ref txt) => txt.txt_data().to_vec(),
+            ref rdata => panic!("expected TXT: {:?}", rdata),
+        }
+    };
+
+    assert_eq!(
+        txt_data(&records),
+        vec![
+            octets[..128].to_vec().into_boxed_slice(),
+            octets[128..].to_vec().into_boxed_slice(),
+        ]
+    );
+
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .write_zone(&Name::from_str("example.com.").unwrap(), records.values())
+        .expect("failed to write zone");
+    let zone = String::from_utf8(writer.into_inner()).unwrap();
+
+    assert_eq!(txt_data(&parse_zone(&zone)), txt_data(&records));
+}
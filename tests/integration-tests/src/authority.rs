@@ -1,5 +1,7 @@
 use std::collections::BTreeMap;
 
+#[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::Signer;
 use trust_dns::rr::*;
 
 use trust_dns_server::authority::Authority;
@@ -164,26 +166,132 @@ pub fn create_example() -> Authority {
     records
 }
 
+/// adds `*.example.com.` and `a.b.example.com.`, which makes `b.example.com.` an empty non-terminal
+#[allow(unused)]
+pub fn add_wildcard(authority: &mut Authority) {
+    use std::net::Ipv4Addr;
+
+    let serial = authority.serial();
+    authority.upsert(
+        Record::new()
+            .set_name(Name::parse("*.example.com.", None).unwrap())
+            .set_ttl(86400)
+            .set_rr_type(RecordType::A)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 1)))
+            .clone(),
+        serial,
+    );
+    authority.upsert(
+        Record::new()
+            .set_name(Name::parse("a.b.example.com.", None).unwrap())
+            .set_ttl(86400)
+            .set_rr_type(RecordType::A)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 2)))
+            .clone(),
+        serial,
+    );
+}
+
+/// delegates `sub.example.com.` to `ns1.sub.example.com.`, with glue, and `ns.example.net.`
+#[allow(unused)]
+pub fn add_delegation(authority: &mut Authority) {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let serial = authority.serial();
+    let sub = Name::parse("sub.example.com.", None).unwrap();
+    let ns1 = Name::parse("ns1.sub.example.com.", None).unwrap();
+
+    for name_server in &[ns1.clone(), Name::parse("ns.example.net.", None).unwrap()] {
+        authority.upsert(
+            Record::new()
+                .set_name(sub.clone())
+                .set_ttl(86400)
+                .set_rr_type(RecordType::NS)
+                .set_dns_class(DNSClass::IN)
+                .set_rdata(RData::NS(name_server.clone()))
+                .clone(),
+            serial,
+        );
+    }
+
+    authority.upsert(
+        Record::new()
+            .set_name(ns1.clone())
+            .set_ttl(86400)
+            .set_rr_type(RecordType::A)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::A(Ipv4Addr::new(10, 0, 1, 1)))
+            .clone(),
+        serial,
+    );
+    authority.upsert(
+        Record::new()
+            .set_name(ns1)
+            .set_ttl(86400)
+            .set_rr_type(RecordType::AAAA)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::AAAA(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1)))
+            .clone(),
+        serial,
+    );
+}
+
+/// redirects the names below `old.example.com.` to the same names below `example.com.`
+#[allow(unused)]
+pub fn add_dname(authority: &mut Authority) {
+    let serial = authority.serial();
+    authority.upsert(
+        Record::new()
+            .set_name(Name::parse("old.example.com.", None).unwrap())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::DNAME)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::DNAME(Name::parse("example.com.", None).unwrap()))
+            .clone(),
+        serial,
+    );
+}
+
 #[cfg(feature = "dnssec")]
 #[allow(unused)]
 pub fn create_secure_example() -> Authority {
+    let mut authority: Authority = create_example();
+    let signer = create_zone_signer(&authority, false);
+
+    authority.add_secure_key(signer);
+    authority.secure_zone();
+
+    authority
+}
+
+/// Generates a new key for the zone, with signatures valid for a week
+///
+/// A key signing key has the secure entry point flag set in its DNSKEY.
+#[cfg(feature = "dnssec")]
+#[allow(unused)]
+pub fn create_zone_signer(authority: &Authority, key_signing_key: bool) -> Signer {
     use chrono::Duration;
     use openssl::rsa::Rsa;
     use trust_dns::rr::dnssec::*;
+    use trust_dns::rr::rdata::DNSKEY;
 
-    let mut authority: Authority = create_example();
     let rsa = Rsa::generate(2048).unwrap();
     let key = KeyPair::from_rsa(rsa).unwrap();
     let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
-    let signer = Signer::dnssec(
+    let dnskey = DNSKEY::new(
+        true,
+        key_signing_key,
+        false,
+        Algorithm::RSASHA256,
+        dnskey.public_key().to_vec(),
+    );
+
+    Signer::dnssec(
         dnskey,
         key,
         authority.origin().clone().into(),
         Duration::weeks(1),
-    );
-
-    authority.add_secure_key(signer);
-    authority.secure_zone();
-
-    authority
+    )
 }
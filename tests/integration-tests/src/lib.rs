@@ -13,6 +13,7 @@ extern crate trust_dns_server;
 
 use std::fmt;
 use std::io;
+use std::mem;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -35,7 +36,7 @@ use trust_dns_proto::xfer::{
 use trust_dns_proto::StreamHandle;
 
 use trust_dns_server::authority::{Catalog, MessageRequest, MessageResponse};
-use trust_dns_server::server::{Request, RequestHandler, ResponseHandler};
+use trust_dns_server::server::{Protocol, Request, RequestHandler, ResponseHandler};
 
 pub mod authority;
 pub mod mock_client;
@@ -69,17 +70,29 @@ impl TestClientStream {
 
 #[derive(Clone, Default)]
 pub struct TestResponseHandler {
-    buf: Arc<Mutex<Vec<u8>>>,
+    bufs: Arc<Mutex<Vec<Vec<u8>>>>,
 }
 
 impl TestResponseHandler {
     pub fn new() -> Self {
-        let buf = Arc::new(Mutex::new(Vec::with_capacity(512)));
-        TestResponseHandler { buf }
+        let bufs = Arc::new(Mutex::new(Vec::new()));
+        TestResponseHandler { bufs }
     }
 
     pub fn into_inner(self) -> Vec<u8> {
-        Arc::try_unwrap(self.buf).unwrap().into_inner().unwrap()
+        self.into_inners()
+            .into_iter()
+            .next()
+            .expect("no response sent")
+    }
+
+    pub fn into_inners(self) -> Vec<Vec<u8>> {
+        mem::replace(&mut *self.bufs.lock().unwrap(), Vec::new())
+    }
+
+    /// Returns true once a response has been sent, e.g. by a spawned lookup
+    pub fn is_sent(&self) -> bool {
+        !self.bufs.lock().unwrap().is_empty()
     }
 
     pub fn into_message(self) -> Message {
@@ -87,15 +100,38 @@ impl TestResponseHandler {
         let mut decoder = BinDecoder::new(&bytes);
         Message::read(&mut decoder).expect("could not decode message")
     }
+
+    pub fn into_messages(self) -> Vec<Message> {
+        self.into_inners()
+            .iter()
+            .map(|bytes| {
+                let mut decoder = BinDecoder::new(bytes);
+                Message::read(&mut decoder).expect("could not decode message")
+            }).collect()
+    }
+
+    fn push(&self, response: MessageResponse) {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            response
+                .destructive_emit(&mut encoder)
+                .expect("could not encode");
+        }
+        self.bufs.lock().unwrap().push(buf);
+    }
 }
 
 impl ResponseHandler for TestResponseHandler {
     fn send_response(self, response: MessageResponse) -> io::Result<()> {
-        let buf = &mut self.buf.lock().unwrap();
-        let mut encoder = BinEncoder::new(buf);
-        response
-            .destructive_emit(&mut encoder)
-            .expect("could not encode");
+        self.push(response);
+        Ok(())
+    }
+
+    fn send_responses(self, responses: Vec<MessageResponse>) -> io::Result<()> {
+        for response in responses {
+            self.push(response);
+        }
         Ok(())
     }
 }
@@ -131,6 +167,9 @@ impl Stream for TestClientStream {
                 let request = Request {
                     message,
                     src: src_addr,
+                    dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+                    protocol: Protocol::Tcp,
+                    tls: None,
                 };
 
                 let response_handler = TestResponseHandler::new();
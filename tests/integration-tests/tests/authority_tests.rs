@@ -1,24 +1,35 @@
+#[cfg(feature = "dnssec")]
+extern crate chrono;
 extern crate rusqlite;
 extern crate trust_dns;
 extern crate trust_dns_integration;
 extern crate trust_dns_server;
 
 use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
 use std::net::*;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 
+#[cfg(feature = "dnssec")]
+use chrono::{Duration, Utc};
 use rusqlite::*;
 
 use trust_dns::op::*;
 use trust_dns::rr::dnssec::*;
 use trust_dns::rr::rdata::*;
 use trust_dns::rr::*;
+use trust_dns::serialize::txt::Parser;
 
 use trust_dns_server::authority::*;
 
-use trust_dns_integration::authority::create_example;
+use trust_dns_integration::authority::{add_delegation, add_dname, add_wildcard, create_example};
 #[cfg(feature = "dnssec")]
-use trust_dns_integration::authority::create_secure_example;
+use trust_dns_integration::authority::{create_secure_example, create_zone_signer};
 
 #[test]
 fn test_search() {
@@ -867,6 +878,832 @@ fn test_get_nsec() {
     }
 }
 
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_get_nsec_covering() {
+    let authority: Authority = create_secure_example();
+    let nsec_owners = |name: &str| {
+        let name = LowerName::from(Name::from_str(name).unwrap());
+        authority
+            .get_nsec_records(&name, false, SupportedAlgorithms::all())
+            .map(|r| r.name().clone())
+            .collect::<Vec<_>>()
+    };
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let www = Name::from_str("www.example.com.").unwrap();
+
+    assert_eq!(nsec_owners("example.com."), vec![origin.clone()]);
+    assert_eq!(nsec_owners("aaa.example.com."), vec![origin.clone()]);
+    assert_eq!(nsec_owners("www.example.com."), vec![www.clone()]);
+    assert_eq!(nsec_owners("zzz.example.com."), vec![www.clone()]);
+    assert_eq!(nsec_owners("a.www.example.com."), vec![www.clone()]);
+}
+
+/// returns the kind of result of the search, for readable assertions
+fn search_kind(authority: &Authority, name: &str, query_type: RecordType) -> &'static str {
+    let query = LowerQuery::from(Query::query(Name::from_str(name).unwrap(), query_type));
+
+    match authority.search(&query, false, SupportedAlgorithms::new()) {
+        AuthLookup::NxDomain => "NxDomain",
+        AuthLookup::NameExists => "NameExists",
+        AuthLookup::Records(_) => "Records",
+        AuthLookup::Wildcard(_) => "Wildcard",
+        AuthLookup::Referral(_) => "Referral",
+        AuthLookup::Dname(_) => "Dname",
+        _ => "Other",
+    }
+}
+
+#[test]
+fn test_wildcard() {
+    let mut authority: Authority = create_example();
+    add_wildcard(&mut authority);
+
+    // synthesized from the wildcard, the owner is rewritten by the catalog
+    let query = LowerQuery::from(Query::query(
+        Name::from_str("foo.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let records = authority
+        .search(&query, false, SupportedAlgorithms::new())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].name(), Name::from_str("*.example.com.").unwrap());
+    assert_eq!(*records[0].rdata(), RData::A(Ipv4Addr::new(10, 0, 0, 1)));
+
+    assert_eq!(search_kind(&authority, "foo.example.com.", RecordType::A), "Wildcard");
+    assert_eq!(search_kind(&authority, "deep.foo.example.com.", RecordType::A), "Wildcard");
+    assert_eq!(search_kind(&authority, "foo.example.com.", RecordType::ANY), "Wildcard");
+
+    // the wildcard has no records of the type
+    assert_eq!(search_kind(&authority, "foo.example.com.", RecordType::AAAA), "NameExists");
+
+    // existing names are not synthesized
+    assert_eq!(search_kind(&authority, "www.example.com.", RecordType::A), "Records");
+    assert_eq!(search_kind(&authority, "www.example.com.", RecordType::MX), "NameExists");
+
+    // empty non-terminals exist
+    assert_eq!(search_kind(&authority, "b.example.com.", RecordType::A), "NameExists");
+
+    // the closest encloser has no wildcard
+    assert_eq!(search_kind(&authority, "c.b.example.com.", RecordType::A), "NxDomain");
+    assert_eq!(search_kind(&authority, "a.www.example.com.", RecordType::A), "NxDomain");
+}
+
+#[test]
+fn test_zone_cut() {
+    let mut authority: Authority = create_example();
+    add_delegation(&mut authority);
+    add_wildcard(&mut authority);
+
+    assert_eq!(search_kind(&authority, "sub.example.com.", RecordType::NS), "Referral");
+    assert_eq!(search_kind(&authority, "sub.example.com.", RecordType::ANY), "Referral");
+    assert_eq!(search_kind(&authority, "ns1.sub.example.com.", RecordType::A), "Referral");
+
+    // the wildcard is not synthesized below the cut
+    assert_eq!(search_kind(&authority, "a.b.sub.example.com.", RecordType::A), "Referral");
+
+    // the DS at the cut belongs to this zone
+    #[cfg(feature = "dnssec")]
+    assert_eq!(
+        search_kind(
+            &authority,
+            "sub.example.com.",
+            RecordType::DNSSEC(DNSSECRecordType::DS)
+        ),
+        "NameExists"
+    );
+    #[cfg(feature = "dnssec")]
+    assert_eq!(
+        search_kind(
+            &authority,
+            "www.sub.example.com.",
+            RecordType::DNSSEC(DNSSECRecordType::DS)
+        ),
+        "Referral"
+    );
+
+    let query = LowerQuery::from(Query::query(
+        Name::from_str("www.sub.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let name_servers = authority
+        .search(&query, false, SupportedAlgorithms::new())
+        .collect::<Vec<_>>();
+    assert_eq!(name_servers.len(), 2);
+
+    // only the name server in the zone has glue
+    let glue = authority.additionals(&name_servers, false, SupportedAlgorithms::new());
+    assert_eq!(glue.len(), 2);
+    assert!(
+        glue.iter()
+            .all(|r| r.name().to_string() == "ns1.sub.example.com.")
+    );
+}
+
+#[test]
+fn test_dname() {
+    let mut authority: Authority = create_example();
+    add_dname(&mut authority);
+    add_wildcard(&mut authority);
+
+    assert_eq!(search_kind(&authority, "www.old.example.com.", RecordType::A), "Dname");
+    assert_eq!(search_kind(&authority, "a.b.old.example.com.", RecordType::ANY), "Dname");
+    assert_eq!(search_kind(&authority, "www.old.example.com.", RecordType::DNAME), "Dname");
+
+    // the owner itself is not redirected
+    assert_eq!(search_kind(&authority, "old.example.com.", RecordType::DNAME), "Records");
+    assert_eq!(search_kind(&authority, "old.example.com.", RecordType::A), "NameExists");
+
+    let query = LowerQuery::from(Query::query(
+        Name::from_str("foo.old.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let records = authority
+        .search(&query, false, SupportedAlgorithms::new())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].name(), Name::from_str("old.example.com.").unwrap());
+    assert_eq!(
+        *records[0].rdata(),
+        RData::DNAME(Name::from_str("example.com.").unwrap())
+    );
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_wildcard_nsec_proof() {
+    let nsec_owners = |authority: &Authority, name: &str, rtype: RecordType| {
+        let name = LowerName::from(Name::from_str(name).unwrap());
+        let mut owners = authority
+            .get_nsec_proof(&name, rtype, false, SupportedAlgorithms::all())
+            .inspect(|r| assert_eq!(r.rr_type(), RecordType::DNSSEC(DNSSECRecordType::NSEC)))
+            .map(|r| r.name().to_string())
+            .collect::<Vec<_>>();
+        owners.sort();
+        owners
+    };
+
+    let mut authority: Authority = create_secure_example();
+
+    // name error, proves the name and the wildcard at the closest encloser do not exist
+    assert_eq!(
+        nsec_owners(&authority, "zzz.example.com.", RecordType::A),
+        vec!["example.com.", "www.example.com."]
+    );
+
+    // no data
+    assert_eq!(
+        nsec_owners(&authority, "www.example.com.", RecordType::MX),
+        vec!["www.example.com."]
+    );
+
+    add_wildcard(&mut authority);
+    authority.secure_zone().expect("failed to sign zone");
+
+    // wildcard answer, proves the name does not exist
+    assert_eq!(
+        nsec_owners(&authority, "foo.example.com.", RecordType::A),
+        vec!["a.b.example.com."]
+    );
+    assert_eq!(
+        nsec_owners(&authority, "zzz.example.com.", RecordType::A),
+        vec!["www.example.com."]
+    );
+
+    // wildcard no data, also proves the wildcard has no records of the type
+    assert_eq!(
+        nsec_owners(&authority, "zzz.example.com.", RecordType::AAAA),
+        vec!["*.example.com.", "www.example.com."]
+    );
+
+    // empty non-terminal, the NSEC covering it
+    assert_eq!(
+        nsec_owners(&authority, "b.example.com.", RecordType::A),
+        vec!["*.example.com."]
+    );
+
+    // name error below the empty non-terminal, there is no *.b.example.com.
+    assert_eq!(
+        nsec_owners(&authority, "c.b.example.com.", RecordType::A),
+        vec!["*.example.com.", "a.b.example.com."]
+    );
+}
+
+#[cfg(feature = "dnssec")]
+const NSEC3_SALT: &[u8] = &[0xAA, 0xBB, 0xCC, 0xDD];
+
+#[cfg(feature = "dnssec")]
+fn create_nsec3_example(opt_out: bool) -> Authority {
+    let mut authority: Authority = create_secure_example();
+    add_wildcard(&mut authority);
+    add_delegation(&mut authority);
+
+    authority.set_nsec3(Some(NSEC3PARAM::new(
+        Nsec3HashAlgorithm::SHA1,
+        opt_out,
+        2,
+        NSEC3_SALT.to_vec(),
+    )));
+    authority.secure_zone().expect("failed to sign zone");
+    authority
+}
+
+/// the owner name of the NSEC3 record for the name, in the zone of `create_nsec3_example()`
+#[cfg(feature = "dnssec")]
+fn nsec3_owner(name: &str) -> Name {
+    let hash = Nsec3HashAlgorithm::SHA1
+        .hash(NSEC3_SALT, &Name::from_str(name).unwrap(), 2)
+        .unwrap();
+    Nsec3HashAlgorithm::hashed_owner_name(hash.as_ref(), &Name::from_str("example.com.").unwrap())
+        .unwrap()
+}
+
+/// true if the hash of the name falls strictly between the owner and the next hashed owner name
+#[cfg(feature = "dnssec")]
+fn nsec3_covers(record: &Record, name: &str) -> bool {
+    let nsec3 = match *record.rdata() {
+        RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => nsec3,
+        _ => panic!("not an NSEC3: {:?}", record),
+    };
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let next = Nsec3HashAlgorithm::hashed_owner_name(nsec3.next_hashed_owner_name(), &origin)
+        .unwrap();
+    let hashed = nsec3_owner(name);
+    let owner = record.name();
+
+    if *owner < next {
+        *owner < hashed && hashed < next
+    } else {
+        // the last record of the chain wraps around to the first
+        *owner < hashed || hashed < next
+    }
+}
+
+/// true if one of the records is the NSEC3 record for the name
+#[cfg(feature = "dnssec")]
+fn matches_any(records: &[Record], name: &str) -> bool {
+    records.iter().any(|r| *r.name() == nsec3_owner(name))
+}
+
+#[cfg(feature = "dnssec")]
+fn nsec3_records(authority: &Authority) -> Vec<Record> {
+    let origin = authority.origin().clone();
+    authority
+        .lookup(&origin, RecordType::AXFR, false, SupportedAlgorithms::all())
+        .filter(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC3))
+        .cloned()
+        .collect()
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_nsec3_zone_signing() {
+    let authority = create_nsec3_example(false);
+    let origin = authority.origin().clone();
+
+    // the NSEC records are replaced
+    assert!(
+        authority
+            .lookup(&origin, RecordType::AXFR, false, SupportedAlgorithms::all())
+            .all(|r| r.rr_type() != RecordType::DNSSEC(DNSSECRecordType::NSEC))
+    );
+
+    let param = authority
+        .lookup(
+            &origin,
+            RecordType::DNSSEC(DNSSECRecordType::NSEC3PARAM),
+            false,
+            SupportedAlgorithms::all(),
+        )
+        .next()
+        .expect("NSEC3PARAM not found");
+    assert_eq!(
+        *param.rdata(),
+        RData::DNSSEC(DNSSECRData::NSEC3PARAM(NSEC3PARAM::new(
+            Nsec3HashAlgorithm::SHA1,
+            false,
+            2,
+            NSEC3_SALT.to_vec(),
+        )))
+    );
+
+    // one per name, including the empty non-terminal and the delegation, but not the glue
+    let nsec3s = nsec3_records(&authority);
+    let mut owners = nsec3s.iter().map(|r| r.name().clone()).collect::<Vec<_>>();
+    owners.sort();
+    let mut expected = [
+        "example.com.",
+        "www.example.com.",
+        "*.example.com.",
+        "b.example.com.",
+        "a.b.example.com.",
+        "sub.example.com.",
+    ].iter()
+        .map(|n| nsec3_owner(n))
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(owners, expected);
+
+    // the next hashed owner names form a single cycle through all the records
+    let zone = Name::from_str("example.com.").unwrap();
+    let mut nexts = nsec3s
+        .iter()
+        .map(|r| match *r.rdata() {
+            RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => {
+                Nsec3HashAlgorithm::hashed_owner_name(nsec3.next_hashed_owner_name(), &zone)
+                    .unwrap()
+            }
+            _ => panic!("not an NSEC3: {:?}", r),
+        })
+        .collect::<Vec<_>>();
+    nexts.sort();
+    assert_eq!(nexts, expected);
+
+    // the types at the name, the empty non-terminal has none
+    for record in &nsec3s {
+        let types = match *record.rdata() {
+            RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => nsec3.type_bit_maps().to_vec(),
+            _ => panic!("not an NSEC3: {:?}", record),
+        };
+
+        if *record.name() == nsec3_owner("b.example.com.") {
+            assert!(types.is_empty());
+        } else if *record.name() == nsec3_owner("sub.example.com.") {
+            // the insecure delegation has no signed records
+            assert!(types.contains(&RecordType::NS));
+            assert!(!types.contains(&RecordType::DNSSEC(DNSSECRecordType::RRSIG)));
+        } else if *record.name() == nsec3_owner("www.example.com.") {
+            assert!(types.contains(&RecordType::A));
+            assert!(types.contains(&RecordType::DNSSEC(DNSSECRecordType::RRSIG)));
+        }
+    }
+
+    // the NS records of the delegation and the glue below it are not signed
+    let origin = authority.origin().clone();
+    let sub = Name::from_str("sub.example.com.").unwrap();
+    let rrsigs = authority
+        .lookup(&origin, RecordType::AXFR, true, SupportedAlgorithms::all())
+        .filter(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+        .cloned()
+        .collect::<Vec<_>>();
+    assert!(!rrsigs.is_empty());
+    assert!(rrsigs.iter().all(|r| !sub.zone_of(r.name())));
+
+    // the NSEC3 records are signed
+    let hashed = LowerName::from(nsec3_owner("www.example.com."));
+    assert!(
+        authority
+            .lookup(
+                &hashed,
+                RecordType::DNSSEC(DNSSECRecordType::NSEC3),
+                true,
+                SupportedAlgorithms::all(),
+            )
+            .any(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+    );
+
+    // the hashed owner names are not names in the zone, the wildcard answers for them
+    assert_eq!(
+        search_kind(&authority, &nsec3_owner("www.example.com.").to_string(), RecordType::A),
+        "Wildcard"
+    );
+    assert_eq!(search_kind(&authority, "www.example.com.", RecordType::A), "Records");
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_nsec3_proof() {
+    let authority = create_nsec3_example(false);
+
+    let proof = |name: &str, rtype: RecordType| {
+        let name = LowerName::from(Name::from_str(name).unwrap());
+        authority
+            .get_nsec_proof(&name, rtype, false, SupportedAlgorithms::all())
+            .inspect(|r| assert_eq!(r.rr_type(), RecordType::DNSSEC(DNSSECRecordType::NSEC3)))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let covers = |records: &[Record], name: &str| records.iter().any(|r| nsec3_covers(r, name));
+
+    // no data
+    let records = proof("www.example.com.", RecordType::MX);
+    assert_eq!(records.len(), 1);
+    assert!(matches_any(&records, "www.example.com."));
+
+    // no data at the empty non-terminal
+    let records = proof("b.example.com.", RecordType::A);
+    assert_eq!(records.len(), 1);
+    assert!(matches_any(&records, "b.example.com."));
+
+    // wildcard answer, only the next closer name needs to be covered
+    let records = proof("foo.example.com.", RecordType::A);
+    assert_eq!(records.len(), 1);
+    assert!(covers(&records, "foo.example.com."));
+
+    // wildcard no data, the closest encloser proof and the NSEC3 of the wildcard
+    let records = proof("foo.example.com.", RecordType::AAAA);
+    assert!(matches_any(&records, "example.com."));
+    assert!(covers(&records, "foo.example.com."));
+    assert!(matches_any(&records, "*.example.com."));
+
+    // name error below the empty non-terminal, there is no *.b.example.com.
+    let records = proof("c.b.example.com.", RecordType::A);
+    assert!(matches_any(&records, "b.example.com."));
+    assert!(covers(&records, "c.b.example.com."));
+    assert!(covers(&records, "*.b.example.com."));
+
+    // the delegation has its own NSEC3, proving there is no DS
+    let sub = LowerName::from(Name::from_str("sub.example.com.").unwrap());
+    let records = authority
+        .delegation_signer(&sub, false, SupportedAlgorithms::all())
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), 1);
+    assert!(matches_any(&records, "sub.example.com."));
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_nsec3_opt_out() {
+    let authority = create_nsec3_example(true);
+
+    // the insecure delegation is left out of the chain
+    let nsec3s = nsec3_records(&authority);
+    assert!(!matches_any(&nsec3s, "sub.example.com."));
+    assert!(nsec3s.iter().all(|r| match *r.rdata() {
+        RData::DNSSEC(DNSSECRData::NSEC3(ref nsec3)) => nsec3.opt_out(),
+        _ => false,
+    }));
+
+    // the closest encloser proof, the next closer name is covered by an opt-out NSEC3
+    let sub = LowerName::from(Name::from_str("sub.example.com.").unwrap());
+    let records = authority
+        .delegation_signer(&sub, false, SupportedAlgorithms::all())
+        .cloned()
+        .collect::<Vec<_>>();
+    assert!(matches_any(&records, "example.com."));
+    assert!(records.iter().any(|r| nsec3_covers(r, "sub.example.com.")));
+}
+
+/// the earliest expiration of the RRSIGs of each record set
+#[cfg(feature = "dnssec")]
+fn sig_expirations(authority: &Authority) -> BTreeMap<RrKey, u32> {
+    authority
+        .records()
+        .iter()
+        .filter_map(|(key, rr_set)| {
+            rr_set
+                .rrsigs()
+                .iter()
+                .filter_map(|rrsig| match *rrsig.rdata() {
+                    RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(sig.sig_expiration()),
+                    _ => None,
+                })
+                .min()
+                .map(|expiration| (key.clone(), expiration))
+        })
+        .collect()
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_sig_jitter() {
+    let mut authority: Authority = create_secure_example();
+    authority.set_sig_jitter(Duration::days(3));
+
+    let before = Utc::now().timestamp() as u32;
+    authority.secure_zone().expect("failed to sign zone");
+    let after = Utc::now().timestamp() as u32;
+
+    // the signer of the example is valid for a week
+    let week = Duration::weeks(1).num_seconds() as u32;
+    let days = Duration::days(3).num_seconds() as u32;
+    let expirations = sig_expirations(&authority);
+    assert!(
+        expirations
+            .values()
+            .all(|e| *e >= before + week - days && *e <= after + week)
+    );
+    assert_eq!(
+        authority.next_sig_expiration(),
+        expirations.values().cloned().min()
+    );
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_resign_expiring() {
+    let mut authority: Authority = create_secure_example();
+    authority.set_sig_jitter(Duration::days(3));
+    authority.secure_zone().expect("failed to sign zone");
+    let serial = authority.serial();
+
+    // nothing expires within a day
+    assert_eq!(authority.resign_expiring(Duration::days(1)).unwrap(), 0);
+    assert_eq!(authority.serial(), serial);
+
+    // re-sign the record sets expiring no later than the median
+    let expirations = sig_expirations(&authority);
+    let mut sorted = expirations.values().cloned().collect::<Vec<_>>();
+    sorted.sort();
+    let median = sorted[sorted.len() / 2];
+    let refresh = Duration::seconds(i64::from(median) - Utc::now().timestamp());
+
+    let soa_key = RrKey::new(authority.origin().clone(), RecordType::SOA);
+    let due = expirations
+        .iter()
+        .filter(|&(key, e)| *e <= median || *key == soa_key)
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    assert!(due.len() < expirations.len());
+
+    assert_eq!(authority.resign_expiring(refresh).unwrap(), due.len());
+    assert_eq!(authority.serial(), serial + 1);
+
+    // the others keep their signatures, and all the records are still signed
+    let resigned = sig_expirations(&authority);
+    assert_eq!(resigned.len(), expirations.len());
+    for (key, expiration) in &expirations {
+        if !due.contains(key) {
+            assert_eq!(resigned[key], *expiration);
+        }
+    }
+
+    assert!(
+        authority
+            .soa_secure(true, SupportedAlgorithms::all())
+            .any(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+    );
+
+    // a zone without keys is never re-signed
+    let mut unsigned = create_example();
+    assert_eq!(unsigned.resign_expiring(Duration::weeks(100)).unwrap(), 0);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_resign_expiring_journal() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority: Authority = create_secure_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+    authority.secure_zone().expect("failed to sign zone");
+
+    assert!(authority.resign_expiring(Duration::weeks(100)).unwrap() > 0);
+
+    // the SOA of the re-signed zone is the last version in the journal
+    let mut recovered_authority = Authority::new(
+        authority.origin().clone().into(),
+        BTreeMap::new(),
+        ZoneType::Master,
+        false,
+        false,
+        false,
+    );
+    recovered_authority
+        .recover_with_journal(authority.journal().expect("journal not Some"))
+        .expect("recovery");
+    assert_eq!(recovered_authority.serial(), authority.serial());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_zone_resign() {
+    let authority = Arc::new(RwLock::new(create_secure_example()));
+    let serial = authority.read().unwrap().serial();
+
+    // the signatures are valid for a week, they are due within the refresh
+    let zone_resign = ZoneResign::new(authority.clone(), Duration::weeks(2));
+    assert_eq!(zone_resign.next_resign(), std::time::Duration::from_secs(60));
+    assert!(zone_resign.resign().unwrap() > 0);
+    assert_eq!(authority.read().unwrap().serial(), serial + 1);
+
+    // nothing is due for days, the zone is checked again within the hour
+    let zone_resign = ZoneResign::new(authority.clone(), Duration::days(1));
+    assert_eq!(zone_resign.next_resign(), std::time::Duration::from_secs(3600));
+    assert_eq!(zone_resign.resign().unwrap(), 0);
+    assert_eq!(authority.read().unwrap().serial(), serial + 1);
+}
+
+/// the key tags of the RRSIGs of the record set
+#[cfg(feature = "dnssec")]
+fn rrsig_key_tags(authority: &Authority, record_type: RecordType) -> Vec<u16> {
+    let rr_key = RrKey::new(authority.origin().clone(), record_type);
+    let mut key_tags = authority.records()[&rr_key]
+        .rrsigs()
+        .iter()
+        .filter_map(|rrsig| match *rrsig.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref sig)) => Some(sig.key_tag()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    key_tags.sort();
+    key_tags
+}
+
+/// the DNSKEYs published in the zone
+#[cfg(feature = "dnssec")]
+fn published_dnskeys(authority: &Authority) -> Vec<DNSKEY> {
+    let rr_key = RrKey::new(
+        authority.origin().clone(),
+        RecordType::DNSSEC(DNSSECRecordType::DNSKEY),
+    );
+    authority
+        .records()
+        .get(&rr_key)
+        .map(|rr_set| {
+            rr_set
+                .records_without_rrsigs()
+                .filter_map(|record| match *record.rdata() {
+                    RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => Some(dnskey.clone()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "dnssec")]
+fn signer_dnskey(signer: &Signer) -> DNSKEY {
+    match *signer.key_rdata() {
+        RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey)) => dnskey.clone(),
+        _ => panic!("not a DNSKEY"),
+    }
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_key_timing_without_activation() {
+    let now = Utc::now();
+    let day = |days: i64| Some(now + Duration::days(days));
+
+    // a key which is only published never signs
+    let timing = KeyTiming::new(day(1), None, None, None).unwrap();
+    assert_eq!(timing.state(now), KeyState::Generated);
+    assert_eq!(timing.state(now + Duration::days(2)), KeyState::Published);
+    assert_eq!(timing.next_change(now), day(1));
+
+    // a key without a publish time is active until it is retired
+    let timing = KeyTiming::new(None, None, day(1), None).unwrap();
+    assert_eq!(timing.state(now), KeyState::Active);
+    assert_eq!(timing.state(now + Duration::days(2)), KeyState::Retired);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_key_states() {
+    let mut authority: Authority = create_example();
+    let now = Utc::now();
+    let day = |days: i64| Some(now + Duration::days(days));
+
+    let timings = vec![
+        // generated, published, active, retired and removed
+        KeyTiming::new(day(1), day(2), None, None).unwrap(),
+        KeyTiming::new(day(-1), day(1), None, None).unwrap(),
+        KeyTiming::new(day(-2), day(-1), None, None).unwrap(),
+        KeyTiming::new(day(-3), day(-2), day(-1), day(2)).unwrap(),
+        KeyTiming::new(day(-4), day(-3), day(-2), day(-1)).unwrap(),
+    ];
+    let signers = timings
+        .iter()
+        .map(|_| create_zone_signer(&authority, false))
+        .collect::<Vec<_>>();
+    let dnskeys = signers.iter().map(signer_dnskey).collect::<Vec<_>>();
+    let active_key_tag = signers[2].calculate_key_tag().unwrap();
+
+    for (signer, timing) in signers.into_iter().zip(timings) {
+        authority
+            .add_secure_key_with_timing(signer, timing)
+            .expect("failed to add key");
+    }
+    authority.secure_zone().expect("failed to sign zone");
+
+    assert_eq!(
+        authority.key_states(now),
+        vec![
+            KeyState::Generated,
+            KeyState::Published,
+            KeyState::Active,
+            KeyState::Retired,
+            KeyState::Removed,
+        ]
+    );
+    assert_eq!(authority.next_key_change(), day(1));
+
+    // the published, active and retired keys are in the zone
+    let published = published_dnskeys(&authority);
+    assert_eq!(published.len(), 3);
+    assert!(dnskeys[1..4].iter().all(|dnskey| published.contains(dnskey)));
+
+    // only the active key signs
+    assert_eq!(rrsig_key_tags(&authority, RecordType::SOA), vec![active_key_tag]);
+    assert_eq!(
+        rrsig_key_tags(&authority, RecordType::DNSSEC(DNSSECRecordType::DNSKEY)),
+        vec![active_key_tag]
+    );
+
+    // nothing changed since the zone was signed
+    assert!(!authority.roll_keys().unwrap());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_key_signing_key() {
+    let mut authority: Authority = create_example();
+    let zsk = create_zone_signer(&authority, false);
+    let ksk = create_zone_signer(&authority, true);
+    let zsk_tag = zsk.calculate_key_tag().unwrap();
+    let ksk_tag = ksk.calculate_key_tag().unwrap();
+    assert!(signer_dnskey(&ksk).secure_entry_point());
+
+    authority.add_secure_key(ksk).expect("failed to add key");
+
+    // without a zone signing key, the key signing key signs the whole zone
+    authority.secure_zone().expect("failed to sign zone");
+    assert_eq!(rrsig_key_tags(&authority, RecordType::SOA), vec![ksk_tag]);
+
+    authority.add_secure_key(zsk).expect("failed to add key");
+    authority.secure_zone().expect("failed to sign zone");
+    assert_eq!(published_dnskeys(&authority).len(), 2);
+    assert_eq!(rrsig_key_tags(&authority, RecordType::SOA), vec![zsk_tag]);
+    assert_eq!(rrsig_key_tags(&authority, RecordType::A), vec![zsk_tag]);
+    assert_eq!(
+        rrsig_key_tags(&authority, RecordType::DNSSEC(DNSSECRecordType::DNSKEY)),
+        vec![ksk_tag]
+    );
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_roll_keys() {
+    let mut authority: Authority = create_secure_example();
+    let serial = authority.serial();
+    assert!(!authority.roll_keys().unwrap());
+    assert_eq!(authority.serial(), serial);
+
+    // pre-publish the next key, it is added to the zone when the zone is re-signed
+    let now = Utc::now();
+    let timing = KeyTiming::new(Some(now), Some(now + Duration::days(1)), None, None).unwrap();
+    let signer = create_zone_signer(&authority, false);
+    let dnskey = signer_dnskey(&signer);
+    let key_tag = signer.calculate_key_tag().unwrap();
+    authority
+        .add_secure_key_with_timing(signer, timing)
+        .expect("failed to add key");
+
+    assert!(authority.roll_keys().unwrap());
+    assert_eq!(authority.serial(), serial + 1);
+    assert!(published_dnskeys(&authority).contains(&dnskey));
+    assert!(!rrsig_key_tags(&authority, RecordType::SOA).contains(&key_tag));
+    assert_eq!(
+        authority.next_key_change(),
+        Some(now + Duration::days(1))
+    );
+
+    assert!(!authority.roll_keys().unwrap());
+    assert_eq!(authority.serial(), serial + 1);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_roll_keys_journal() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority: Authority = create_secure_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+
+    let now = Utc::now();
+    let timing = KeyTiming::new(Some(now), Some(now + Duration::days(1)), None, None).unwrap();
+    let signer = create_zone_signer(&authority, false);
+    authority
+        .add_secure_key_with_timing(signer, timing)
+        .expect("failed to add key");
+    assert!(authority.roll_keys().unwrap());
+
+    // the SOA of the re-signed zone is the last version in the journal
+    let mut recovered_authority = Authority::new(
+        authority.origin().clone().into(),
+        BTreeMap::new(),
+        ZoneType::Master,
+        false,
+        false,
+        false,
+    );
+    recovered_authority
+        .recover_with_journal(authority.journal().expect("journal not Some"))
+        .expect("recovery");
+    assert_eq!(recovered_authority.serial(), authority.serial());
+}
+
 #[test]
 fn test_journal() {
     // test that this message can be inserted
@@ -944,6 +1781,131 @@ fn test_journal() {
     assert!(delete_rrset.was_empty());
 }
 
+#[test]
+fn test_journal_changes_since() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+    let serial = authority.serial();
+
+    let new_record = Record::new()
+        .set_name(Name::from_str("new.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    let delete_record = Record::new()
+        .set_name(Name::from_str("www.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+        .set_dns_class(DNSClass::NONE)
+        .clone();
+    authority
+        .update_records(&[new_record.clone(), delete_record], true)
+        .unwrap();
+    assert!(authority.serial() != serial);
+
+    let changes = authority
+        .changes_since(serial)
+        .expect("journal failed")
+        .expect("serial not in journal");
+
+    match *changes.old_soa.rdata() {
+        RData::SOA(ref soa) => assert_eq!(soa.serial(), serial),
+        ref rdata => panic!("expected SOA: {:?}", rdata),
+    }
+    assert_eq!(changes.added, vec![new_record]);
+    assert_eq!(changes.removed.len(), 1);
+    assert_eq!(
+        *changes.removed[0].name(),
+        Name::from_str("www.example.com").unwrap()
+    );
+    assert_eq!(
+        *changes.removed[0].rdata(),
+        RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+
+    // the current version has no changes, and unknown versions are not available
+    let current = authority.changes_since(authority.serial()).unwrap().unwrap();
+    assert!(current.added.is_empty() && current.removed.is_empty());
+    assert!(authority.changes_since(serial - 1).unwrap().is_none());
+}
+
+#[test]
+fn test_journal_changes_since_intermediate() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+
+    let first_record = Record::new()
+        .set_name(Name::from_str("first.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 1)))
+        .clone();
+    let second_record = Record::new()
+        .set_name(Name::from_str("second.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 2)))
+        .clone();
+
+    authority.update_records(&[first_record], true).unwrap();
+    let serial = authority.serial();
+    authority
+        .update_records(&[second_record.clone()], true)
+        .unwrap();
+
+    // only the changes after the requested version are returned
+    let changes = authority
+        .changes_since(serial)
+        .expect("journal failed")
+        .expect("serial not in journal");
+    match *changes.old_soa.rdata() {
+        RData::SOA(ref soa) => assert_eq!(soa.serial(), serial),
+        ref rdata => panic!("expected SOA: {:?}", rdata),
+    }
+    assert_eq!(changes.added, vec![second_record]);
+    assert!(changes.removed.is_empty());
+}
+
+#[test]
+fn test_journal_history() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+    let serial = authority.serial();
+
+    let first_record = Record::new()
+        .set_name(Name::from_str("first.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 1)))
+        .clone();
+    let second_record = Record::new()
+        .set_name(Name::from_str("second.example.com").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 0, 0, 2)))
+        .clone();
+
+    authority
+        .update_records(&[first_record.clone()], true)
+        .unwrap();
+    let history = authority.history().expect("the zone has a journal");
+    assert_eq!(history.soa(), authority.soa().next().unwrap());
+
+    // the changes are those up to the version of the history, not of the updated zone
+    authority.update_records(&[second_record], true).unwrap();
+    let changes = history
+        .changes_since(serial)
+        .expect("journal failed")
+        .expect("serial not in journal");
+    assert_eq!(changes.added, vec![first_record]);
+    assert!(changes.removed.is_empty());
+}
+
 #[test]
 fn test_recovery() {
     // test that this message can be inserted
@@ -1011,6 +1973,221 @@ fn test_recovery() {
     }));
 }
 
+#[test]
+fn test_journal_compaction() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut authority = create_example();
+    authority.set_journal(journal);
+    authority.persist_to_journal().unwrap();
+    let serial = authority.serial();
+
+    let new_record = Record::new()
+        .set_name(Name::from_str("new.example.com.").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    authority
+        .update_records(&[new_record.clone()], true)
+        .unwrap();
+    assert!(authority.changes_since(serial).unwrap().is_some());
+
+    // the history since the first version of the zone is kept
+    authority.compact_journal().unwrap();
+    let compacted_serial = authority.serial();
+    assert!(authority.changes_since(serial).unwrap().is_some());
+
+    let newer_record = Record::new()
+        .set_name(Name::from_str("newer.example.com.").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 14)))
+        .clone();
+    authority
+        .update_records(&[newer_record], true)
+        .unwrap();
+
+    // only the history since the previous compaction remains, from its AXFR marker on
+    authority.compact_journal().unwrap();
+    let axfr_count = authority
+        .journal()
+        .unwrap()
+        .iter()
+        .filter(|record| record.rr_type() == RecordType::AXFR)
+        .count();
+    assert_eq!(axfr_count, 2);
+    assert!(authority.changes_since(serial).unwrap().is_none());
+    assert!(
+        authority
+            .changes_since(compacted_serial)
+            .unwrap()
+            .is_some()
+    );
+    assert!(
+        authority
+            .changes_since(authority.serial())
+            .unwrap()
+            .is_some()
+    );
+
+    let mut recovered_authority = Authority::new(
+        authority.origin().clone().into(),
+        BTreeMap::new(),
+        ZoneType::Master,
+        false,
+        false,
+        false,
+    );
+    recovered_authority
+        .recover_with_journal(authority.journal().unwrap())
+        .expect("recovery");
+
+    assert_eq!(recovered_authority.serial(), authority.serial());
+    assert_eq!(
+        recovered_authority.records().len(),
+        authority.records().len()
+    );
+    let new_rrset: Vec<&Record> = recovered_authority
+        .lookup(
+            &LowerName::from(new_record.name().clone()),
+            RecordType::A,
+            false,
+            SupportedAlgorithms::new(),
+        ).collect();
+    assert_eq!(new_rrset, vec![&new_record]);
+}
+
+fn zone_file_path(test: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "trust-dns-{}-{}.zone",
+        test,
+        process::id()
+    ))
+}
+
+fn assert_zone_file(authority: &Authority, path: &Path) {
+    let (origin, records) = Parser::new()
+        .parse_file(path, Some(authority.origin().clone().into()))
+        .expect("failed to parse zone file");
+
+    assert_eq!(LowerName::from(origin), *authority.origin());
+    assert_eq!(records.len(), authority.records().len());
+
+    for (rr_key, rr_set) in authority.records() {
+        let written = records
+            .get(rr_key)
+            .unwrap_or_else(|| panic!("key not written: {:?}", rr_key));
+        assert_eq!(
+            written.records_without_rrsigs().collect::<Vec<_>>(),
+            rr_set.records_without_rrsigs().collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_write_zone_file() {
+    let path = zone_file_path("write_zone_file");
+    let authority = create_example();
+
+    // a file with the same stem, e.g. of another zone, is not used as the temporary file
+    let other_path = path.with_extension("tmp");
+    fs::write(&other_path, "other").unwrap();
+
+    authority.write_zone_file(&path).expect("failed to write");
+    assert!(!path.with_extension("zone.tmp").exists());
+    assert_eq!(fs::read_to_string(&other_path).unwrap(), "other");
+    fs::remove_file(&other_path).unwrap();
+    assert_zone_file(&authority, &path);
+
+    // the file is replaced
+    authority.write_zone_file(&path).expect("failed to rewrite");
+    assert_zone_file(&authority, &path);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_write_secure_zone_file() {
+    let path = zone_file_path("write_secure_zone_file");
+    let authority = create_secure_example();
+
+    // the RRSIGs are not written, the zone is resigned when loaded
+    authority.write_zone_file(&path).expect("failed to write");
+    assert_zone_file(&authority, &path);
+
+    let (_, records) = Parser::new()
+        .parse_file(&path, Some(authority.origin().clone().into()))
+        .unwrap();
+    assert!(records.values().all(|rr_set| rr_set.rrsigs().is_empty()));
+    assert!(
+        records
+            .keys()
+            .any(|rr_key| rr_key.record_type == RecordType::DNSSEC(DNSSECRecordType::DNSKEY))
+    );
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_zone_flush() {
+    let path = zone_file_path("zone_flush");
+    let authority = Arc::new(RwLock::new(create_example()));
+    let zone_flush = ZoneFlush::new(authority.clone(), path.clone());
+
+    // the first flush always writes, and only changes to the zone are written after it
+    assert!(zone_flush.flush().unwrap());
+    assert!(!zone_flush.flush().unwrap());
+    assert_zone_file(&authority.read().unwrap(), &path);
+
+    let new_record = Record::new()
+        .set_name(Name::from_str("new.example.com.").unwrap())
+        .set_rdata(RData::A(Ipv4Addr::new(10, 11, 12, 13)))
+        .clone();
+    authority
+        .write()
+        .unwrap()
+        .update_records(&[new_record], true)
+        .unwrap();
+
+    assert!(zone_flush.flush().unwrap());
+    assert_zone_file(&authority.read().unwrap(), &path);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_zone_flush_include_or_generate() {
+    let path = zone_file_path("zone_flush_include_or_generate");
+    let authority = Arc::new(RwLock::new(create_example()));
+    let zone_flush = ZoneFlush::new(authority, path.clone());
+
+    assert!(zone_flush.has_include_or_generate().is_err());
+
+    // a written zone has neither, they are replaced by their records
+    assert!(zone_flush.flush().unwrap());
+    assert!(!zone_flush.has_include_or_generate().unwrap());
+
+    let mut zone = String::new();
+    fs::File::open(&path)
+        .unwrap()
+        .read_to_string(&mut zone)
+        .unwrap();
+
+    for directive in &[
+        "$INCLUDE other.zone\n",
+        "$GENERATE 1-4 host$ IN A 10.0.0.$\n",
+    ] {
+        let mut file = fs::File::create(&path).unwrap();
+        file.write_all(zone.as_bytes()).unwrap();
+        file.write_all(directive.as_bytes()).unwrap();
+        drop(file);
+
+        assert!(zone_flush.has_include_or_generate().unwrap());
+    }
+
+    fs::remove_file(&path).unwrap();
+}
+
 #[test]
 fn test_axfr() {
     let mut authority = create_example();
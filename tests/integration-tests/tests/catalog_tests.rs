@@ -1,9 +1,13 @@
+extern crate rusqlite;
 extern crate trust_dns;
 extern crate trust_dns_integration;
 extern crate trust_dns_server;
 
 use std::collections::*;
 use std::net::*;
+use std::sync::{Arc, RwLock};
+
+use rusqlite::Connection;
 
 use trust_dns::op::*;
 use trust_dns::rr::rdata::*;
@@ -11,8 +15,11 @@ use trust_dns::rr::*;
 use trust_dns::serialize::binary::{BinDecodable, BinEncodable};
 
 use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, Request, RequestHandler};
 
-use trust_dns_integration::authority::create_example;
+use trust_dns_integration::authority::{add_delegation, add_dname, add_wildcard, create_example};
+#[cfg(feature = "dnssec")]
+use trust_dns_integration::authority::create_secure_example;
 use trust_dns_integration::*;
 
 pub fn create_test() -> Authority {
@@ -242,6 +249,633 @@ fn test_catalog_nx_soa() {
     );
 }
 
+#[test]
+fn test_catalog_wildcard() {
+    let mut example = create_example();
+    add_wildcard(&mut example);
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), example);
+
+    let name = Name::parse("foo.example.com.", None).unwrap();
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name.clone(), RecordType::A));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .unwrap();
+    let result = response_handler.into_message();
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+
+    // the owner of the synthesized record is the query name
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(*answers[0].name(), name);
+    assert_eq!(*answers[0].rdata(), RData::A(Ipv4Addr::new(10, 0, 0, 1)));
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_catalog_secure_wildcard() {
+    use trust_dns::rr::dnssec::SupportedAlgorithms;
+    use trust_dns::rr::rdata::opt::EdnsOption;
+
+    let mut example = create_secure_example();
+    add_wildcard(&mut example);
+    example.secure_zone().expect("failed to sign zone");
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), example);
+
+    let name = Name::parse("foo.example.com.", None).unwrap();
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name.clone(), RecordType::A));
+
+    let mut edns = Edns::new();
+    edns.set_dnssec_ok(true);
+    edns.set_option(EdnsOption::DAU(SupportedAlgorithms::all()));
+    question.set_edns(edns);
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .unwrap();
+    let result = response_handler.into_message();
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+
+    // the RRSIG is also synthesized, its labels reveal the wildcard
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 2);
+    assert!(answers.iter().all(|r| *r.name() == name));
+
+    let rrsig = answers
+        .iter()
+        .find(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::RRSIG))
+        .expect("RRSIG not found");
+    if let RData::DNSSEC(DNSSECRData::SIG(ref rrsig)) = *rrsig.rdata() {
+        assert_eq!(rrsig.num_labels(), 2);
+    } else {
+        panic!("not an RRSIG");
+    }
+
+    // the NSEC proving that foo.example.com. does not exist
+    let nsec = result
+        .name_servers()
+        .iter()
+        .find(|r| r.rr_type() == RecordType::DNSSEC(DNSSECRecordType::NSEC))
+        .expect("NSEC not found");
+    assert_eq!(*nsec.name(), Name::parse("a.b.example.com.", None).unwrap());
+    if let RData::DNSSEC(DNSSECRData::NSEC(ref nsec)) = *nsec.rdata() {
+        assert!(name < *nsec.next_domain_name());
+    } else {
+        panic!("not an NSEC");
+    }
+}
+
+fn cname(name: &str, target: &str) -> Record {
+    Record::new()
+        .set_name(Name::parse(name, None).unwrap())
+        .set_ttl(3600)
+        .set_rr_type(RecordType::CNAME)
+        .set_dns_class(DNSClass::IN)
+        .set_rdata(RData::CNAME(Name::parse(target, None).unwrap()))
+        .clone()
+}
+
+fn query_catalog(catalog: &Catalog, name: &str, query_type: RecordType) -> Message {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(Name::parse(name, None).unwrap(), query_type));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .unwrap();
+    response_handler.into_message()
+}
+
+/// the owners and types of the answers, in order
+fn answer_names(message: &Message) -> Vec<(String, RecordType)> {
+    message
+        .answers()
+        .iter()
+        .map(|r| (r.name().to_string(), r.rr_type()))
+        .collect()
+}
+
+/// queries the catalog with the DO bit set, for the signed records and the proofs of denial
+#[cfg(feature = "dnssec")]
+fn secure_query_catalog(catalog: &Catalog, name: &str, query_type: RecordType) -> Message {
+    use trust_dns::rr::dnssec::SupportedAlgorithms;
+    use trust_dns::rr::rdata::opt::EdnsOption;
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(Name::parse(name, None).unwrap(), query_type));
+
+    let mut edns = Edns::new();
+    edns.set_dnssec_ok(true);
+    edns.set_option(EdnsOption::DAU(SupportedAlgorithms::all()));
+    question.set_edns(edns);
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .unwrap();
+    response_handler.into_message()
+}
+
+/// the owners and types of the records in the authority section, sorted
+#[cfg(feature = "dnssec")]
+fn name_server_names(message: &Message) -> Vec<(String, RecordType)> {
+    let mut names: Vec<(String, RecordType)> = message
+        .name_servers()
+        .iter()
+        .map(|r| (r.name().to_string(), r.rr_type()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// the records each RRSIG in the authority section covers, sorted
+#[cfg(feature = "dnssec")]
+fn name_server_signed(message: &Message) -> Vec<(String, RecordType)> {
+    let mut names: Vec<(String, RecordType)> = message
+        .name_servers()
+        .iter()
+        .filter_map(|r| match *r.rdata() {
+            RData::DNSSEC(DNSSECRData::SIG(ref rrsig)) => {
+                Some((r.name().to_string(), rrsig.type_covered()))
+            }
+            _ => None,
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_catalog_secure_denial() {
+    let nsec = RecordType::DNSSEC(DNSSECRecordType::NSEC);
+    let rrsig = RecordType::DNSSEC(DNSSECRecordType::RRSIG);
+
+    let mut example = create_secure_example();
+    add_wildcard(&mut example);
+    example.secure_zone().expect("failed to sign zone");
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    // name error, the NSEC covering the name and the NSEC proving there is no wildcard at the
+    //  closest encloser, b.example.com. is an empty non-terminal
+    let result = secure_query_catalog(&catalog, "c.b.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(result.authoritative());
+    assert!(result.answers().is_empty());
+
+    let expected = vec![
+        ("*.example.com.".to_string(), nsec),
+        ("a.b.example.com.".to_string(), nsec),
+        ("example.com.".to_string(), RecordType::SOA),
+    ];
+    assert_eq!(name_server_signed(&result), expected);
+    assert_eq!(
+        name_server_names(&result)
+            .into_iter()
+            .filter(|&(_, rtype)| rtype != rrsig)
+            .collect::<Vec<_>>(),
+        expected
+    );
+
+    // no data, the NSEC at the name
+    let result = secure_query_catalog(&catalog, "www.example.com.", RecordType::MX);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+    assert!(result.answers().is_empty());
+
+    let expected = vec![
+        ("example.com.".to_string(), RecordType::SOA),
+        ("www.example.com.".to_string(), nsec),
+    ];
+    assert_eq!(name_server_signed(&result), expected);
+
+    // no data at a wildcard, the name does not exist and the wildcard has no records of the type
+    let result = secure_query_catalog(&catalog, "zzz.example.com.", RecordType::AAAA);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+
+    let expected = vec![
+        ("*.example.com.".to_string(), nsec),
+        ("example.com.".to_string(), RecordType::SOA),
+        ("www.example.com.".to_string(), nsec),
+    ];
+    assert_eq!(name_server_signed(&result), expected);
+
+    // without the DO bit only the SOA is returned
+    let result = query_catalog(&catalog, "c.b.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert_eq!(
+        name_server_names(&result),
+        vec![("example.com.".to_string(), RecordType::SOA)]
+    );
+}
+
+#[test]
+fn test_catalog_cname_chase() {
+    let mut example = create_example();
+    let serial = example.serial();
+    example.upsert(cname("alias.example.com.", "www.example.com."), serial);
+    example.upsert(cname("alias2.example.com.", "alias.example.com."), serial);
+    example.upsert(cname("other.example.com.", "www.test.com."), serial);
+    example.upsert(cname("external.example.com.", "www.example.net."), serial);
+    example.upsert(cname("missing.example.com.", "missing.test.com."), serial);
+    example.upsert(cname("loop1.example.com.", "loop2.example.com."), serial);
+    example.upsert(cname("loop2.example.com.", "loop1.example.com."), serial);
+    let test = create_test();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+    catalog.upsert(test.origin().clone(), test);
+
+    // within the zone, through a chain
+    let result = query_catalog(&catalog, "alias2.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("alias2.example.com.".to_string(), RecordType::CNAME),
+            ("alias.example.com.".to_string(), RecordType::CNAME),
+            ("www.example.com.".to_string(), RecordType::A),
+        ]
+    );
+
+    // to another zone of the catalog
+    let result = query_catalog(&catalog, "other.example.com.", RecordType::A);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("other.example.com.".to_string(), RecordType::CNAME),
+            ("www.test.com.".to_string(), RecordType::A),
+        ]
+    );
+    assert_eq!(
+        *result.answers()[1].rdata(),
+        RData::A(Ipv4Addr::new(94, 184, 216, 34))
+    );
+
+    // targets which are not in the catalog, or do not exist, end the chain
+    for name in &["external.example.com.", "missing.example.com."] {
+        let result = query_catalog(&catalog, name, RecordType::A);
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        assert_eq!(
+            answer_names(&result),
+            vec![(name.to_string(), RecordType::CNAME)]
+        );
+    }
+
+    // the CNAME itself is not followed
+    let result = query_catalog(&catalog, "alias.example.com.", RecordType::CNAME);
+    assert_eq!(
+        answer_names(&result),
+        vec![("alias.example.com.".to_string(), RecordType::CNAME)]
+    );
+
+    // loops are broken
+    let result = query_catalog(&catalog, "loop1.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("loop1.example.com.".to_string(), RecordType::CNAME),
+            ("loop2.example.com.".to_string(), RecordType::CNAME),
+        ]
+    );
+}
+
+#[test]
+fn test_catalog_wildcard_cname_chase() {
+    let mut example = create_example();
+    let serial = example.serial();
+    example.upsert(cname("*.alias.example.com.", "www.example.com."), serial);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    let result = query_catalog(&catalog, "foo.alias.example.com.", RecordType::AAAA);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("foo.alias.example.com.".to_string(), RecordType::CNAME),
+            ("www.example.com.".to_string(), RecordType::AAAA),
+        ]
+    );
+}
+
+#[test]
+fn test_catalog_referral() {
+    let mut example = create_example();
+    add_delegation(&mut example);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    // the glue is not authoritative data, it is also referred
+    for name in &["sub.example.com.", "www.sub.example.com.", "ns1.sub.example.com."] {
+        let result = query_catalog(&catalog, name, RecordType::A);
+
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        assert!(!result.authoritative());
+        assert!(result.answers().is_empty());
+
+        let mut ns: Vec<Record> = result.name_servers().to_vec();
+        ns.sort();
+        assert_eq!(ns.len(), 2);
+        assert!(ns.iter().all(|r| r.rr_type() == RecordType::NS));
+        assert!(ns.iter().all(|r| r.name().to_string() == "sub.example.com."));
+
+        let mut glue: Vec<Record> = result.additionals().to_vec();
+        glue.sort();
+        assert_eq!(glue.len(), 2);
+        assert_eq!(*glue[0].rdata(), RData::A(Ipv4Addr::new(10, 0, 1, 1)));
+        assert_eq!(
+            *glue[1].rdata(),
+            RData::AAAA(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1))
+        );
+    }
+
+    // names above the cut are still answered
+    let result = query_catalog(&catalog, "www.example.com.", RecordType::A);
+    assert!(result.authoritative());
+    assert_eq!(result.answers().len(), 1);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_catalog_secure_referral() {
+    use trust_dns::rr::dnssec::SupportedAlgorithms;
+    use trust_dns::rr::rdata::opt::EdnsOption;
+
+    let mut example = create_secure_example();
+    add_delegation(&mut example);
+    example.secure_zone().expect("failed to sign zone");
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    let secure_query = |name: &str, query_type: RecordType| {
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(Name::parse(name, None).unwrap(), query_type));
+
+        let mut edns = Edns::new();
+        edns.set_dnssec_ok(true);
+        edns.set_option(EdnsOption::DAU(SupportedAlgorithms::all()));
+        question.set_edns(edns);
+
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(&question_req, None, response_handler.clone())
+            .unwrap();
+        response_handler.into_message()
+    };
+
+    // there is no DS, the NSEC at the cut proves the delegated zone is unsigned
+    let result = secure_query("www.sub.example.com.", RecordType::A);
+    assert!(!result.authoritative());
+
+    let types: Vec<RecordType> = result.name_servers().iter().map(|r| r.rr_type()).collect();
+    assert_eq!(types.iter().filter(|t| **t == RecordType::NS).count(), 2);
+    assert!(types.contains(&RecordType::DNSSEC(DNSSECRecordType::NSEC)));
+    assert!(types.contains(&RecordType::DNSSEC(DNSSECRecordType::RRSIG)));
+    assert!(
+        result
+            .name_servers()
+            .iter()
+            .all(|r| r.name().to_string() == "sub.example.com.")
+    );
+
+    // the DS is answered by the parent, there is none
+    let result = secure_query("sub.example.com.", RecordType::DNSSEC(DNSSECRecordType::DS));
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+    assert!(
+        result
+            .name_servers()
+            .iter()
+            .any(|r| r.rr_type() == RecordType::SOA)
+    );
+}
+
+#[test]
+fn test_catalog_dname() {
+    let mut example = create_example();
+    add_dname(&mut example);
+    let serial = example.serial();
+    example.upsert(
+        Record::new()
+            .set_name(Name::parse("legacy.example.com.", None).unwrap())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::DNAME)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::DNAME(Name::parse("test.com.", None).unwrap()))
+            .clone(),
+        serial,
+    );
+    let test = create_test();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+    catalog.upsert(test.origin().clone(), test);
+
+    // the synthesized CNAME is followed within the zone
+    let result = query_catalog(&catalog, "www.old.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("old.example.com.".to_string(), RecordType::DNAME),
+            ("www.old.example.com.".to_string(), RecordType::CNAME),
+            ("www.example.com.".to_string(), RecordType::A),
+        ]
+    );
+    let cname = &result.answers()[1];
+    assert_eq!(
+        *cname.rdata(),
+        RData::CNAME(Name::parse("www.example.com.", None).unwrap())
+    );
+    assert_eq!(cname.ttl(), 3600);
+
+    // and to another zone of the catalog
+    let result = query_catalog(&catalog, "www.legacy.example.com.", RecordType::AAAA);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("legacy.example.com.".to_string(), RecordType::DNAME),
+            ("www.legacy.example.com.".to_string(), RecordType::CNAME),
+            ("www.test.com.".to_string(), RecordType::AAAA),
+        ]
+    );
+
+    // the CNAME itself is not followed
+    let result = query_catalog(&catalog, "www.old.example.com.", RecordType::CNAME);
+    assert_eq!(
+        answer_names(&result),
+        vec![
+            ("old.example.com.".to_string(), RecordType::DNAME),
+            ("www.old.example.com.".to_string(), RecordType::CNAME),
+        ]
+    );
+
+    // the owner is not redirected
+    let result = query_catalog(&catalog, "old.example.com.", RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+}
+
+#[test]
+fn test_catalog_dname_too_long() {
+    let mut example = create_example();
+    let serial = example.serial();
+    let label = "a".repeat(63);
+    let target = format!("{0}.{0}.{0}.example.net.", label);
+    example.upsert(
+        Record::new()
+            .set_name(Name::parse("long.example.com.", None).unwrap())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::DNAME)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::DNAME(Name::parse(&target, None).unwrap()))
+            .clone(),
+        serial,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), example);
+
+    // the redirected name would be longer than 255 bytes
+    let name = format!("{}.long.example.com.", "b".repeat(60));
+    let result = query_catalog(&catalog, &name, RecordType::A);
+    assert_eq!(result.response_code(), ResponseCode::YXDomain);
+    assert_eq!(
+        answer_names(&result),
+        vec![("long.example.com.".to_string(), RecordType::DNAME)]
+    );
+}
+
+#[test]
+fn test_catalog_additionals() {
+    let mut example = create_example();
+    let serial = example.serial();
+    let origin = example.origin().clone();
+    example.upsert(
+        Record::new()
+            .set_name(origin.clone().into())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::MX)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::MX(MX::new(
+                10,
+                Name::parse("www.example.com.", None).unwrap(),
+            ))).clone(),
+        serial,
+    );
+    example.upsert(
+        Record::new()
+            .set_name(Name::parse("_ldap._tcp.example.com.", None).unwrap())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::SRV)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::SRV(SRV::new(
+                0,
+                0,
+                389,
+                Name::parse("www.example.com.", None).unwrap(),
+            ))).clone(),
+        serial,
+    );
+    example.upsert(
+        Record::new()
+            .set_name(Name::parse("_ldap._udp.example.com.", None).unwrap())
+            .set_ttl(3600)
+            .set_rr_type(RecordType::SRV)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::SRV(SRV::new(
+                0,
+                0,
+                389,
+                Name::parse("ldap.example.net.", None).unwrap(),
+            ))).clone(),
+        serial,
+    );
+    example.upsert(cname("alias.example.com.", "www.example.com."), serial);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), example);
+
+    let additional_types = |message: &Message| {
+        let mut types: Vec<(String, RecordType)> = message
+            .additionals()
+            .iter()
+            .map(|r| (r.name().to_string(), r.rr_type()))
+            .collect();
+        types.sort();
+        types
+    };
+    let www_addresses = vec![
+        ("www.example.com.".to_string(), RecordType::A),
+        ("www.example.com.".to_string(), RecordType::AAAA),
+    ];
+
+    let result = query_catalog(&catalog, "example.com.", RecordType::MX);
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(additional_types(&result), www_addresses);
+
+    let result = query_catalog(&catalog, "_ldap._tcp.example.com.", RecordType::SRV);
+    assert_eq!(additional_types(&result), www_addresses);
+
+    let result = query_catalog(&catalog, "alias.example.com.", RecordType::CNAME);
+    assert_eq!(additional_types(&result), www_addresses);
+
+    // the addresses of the CNAME target are already in the answer
+    let result = query_catalog(&catalog, "alias.example.com.", RecordType::A);
+    assert_eq!(
+        additional_types(&result),
+        vec![("www.example.com.".to_string(), RecordType::AAAA)]
+    );
+
+    // targets outside of the zone have no additional records
+    let result = query_catalog(&catalog, "_ldap._udp.example.com.", RecordType::SRV);
+    assert_eq!(result.answers().len(), 1);
+    assert!(result.additionals().is_empty());
+
+    let result = query_catalog(&catalog, "example.com.", RecordType::NS);
+    assert!(result.additionals().is_empty());
+}
+
 #[test]
 fn test_axfr() {
     let mut test = create_test();
@@ -404,5 +1038,934 @@ fn test_axfr_refused() {
     assert!(result.additionals().is_empty());
 }
 
+fn axfr_request(
+    origin: Name,
+    protocol: Protocol,
+    handler: TestResponseHandler,
+    catalog: &Catalog,
+) {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(origin, RecordType::AXFR));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol,
+        tls: None,
+    };
+
+    catalog
+        .handle_request(&request, handler)
+        .expect("handle_request failed");
+}
+
 #[test]
-fn test_truncated_returns_records() {}
+fn test_axfr_multiple_messages() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+
+    let origin = test.origin().clone();
+    let serial = test.serial();
+    for i in 0..2000u32 {
+        test.upsert(
+            Record::from_rdata(
+                Name::parse(&format!("host{}.test.com.", i), None).unwrap(),
+                86400,
+                RecordType::A,
+                RData::A(Ipv4Addr::from(0x0a00_0000 + i)),
+            ),
+            serial,
+        );
+    }
+    let record_count: usize = test
+        .records()
+        .values()
+        .map(|rrset| rrset.records_without_rrsigs().count())
+        .sum();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), test);
+
+    let response_handler = TestResponseHandler::new();
+    axfr_request(origin.into(), Protocol::Tcp, response_handler.clone(), &catalog);
+    let messages = response_handler.into_messages();
+
+    assert!(messages.len() > 1);
+    for message in &messages {
+        assert_eq!(message.response_code(), ResponseCode::NoError);
+        assert!(message.header().authoritative());
+    }
+
+    let answers: Vec<&Record> = messages.iter().flat_map(|m| m.answers()).collect();
+    assert_eq!(answers.len(), record_count + 1);
+    assert_eq!(answers.first().unwrap().rr_type(), RecordType::SOA);
+    assert_eq!(answers.last().unwrap().rr_type(), RecordType::SOA);
+    assert!(
+        answers[1..answers.len() - 1]
+            .iter()
+            .all(|record| record.rr_type() != RecordType::SOA)
+    );
+
+    // only the first message carries the question
+    assert_eq!(messages.first().unwrap().queries().len(), 1);
+    assert!(messages.last().unwrap().queries().is_empty());
+}
+
+#[test]
+fn test_axfr_refused_over_udp() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), test);
+
+    let response_handler = TestResponseHandler::new();
+    axfr_request(origin.into(), Protocol::Udp, response_handler.clone(), &catalog);
+    let result = response_handler.into_message();
+
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+}
+
+fn ixfr_request(
+    soa: &Record,
+    protocol: Protocol,
+    handler: TestResponseHandler,
+    catalog: &Catalog,
+) {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(soa.name().clone(), RecordType::IXFR));
+    question.add_name_server(soa.clone());
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol,
+        tls: None,
+    };
+
+    catalog
+        .handle_request(&request, handler)
+        .expect("handle_request failed");
+}
+
+fn create_journaled_test() -> (Authority, Record) {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+    test.set_journal(journal);
+    test.persist_to_journal().unwrap();
+    let old_soa = test.soa().next().unwrap().clone();
+
+    test.update_records(
+        &[Record::from_rdata(
+            Name::parse("new.test.com.", None).unwrap(),
+            86400,
+            RecordType::A,
+            RData::A(Ipv4Addr::new(10, 0, 0, 1)),
+        )],
+        true,
+    ).unwrap();
+
+    (test, old_soa)
+}
+
+#[test]
+fn test_ixfr_from_journal() {
+    let (test, old_soa) = create_journaled_test();
+    let new_soa = test.soa().next().unwrap().clone();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+
+    let response_handler = TestResponseHandler::new();
+    ixfr_request(&old_soa, Protocol::Tcp, response_handler.clone(), &catalog);
+    let messages = response_handler.into_messages();
+
+    let answers: Vec<&Record> = messages.iter().flat_map(|m| m.answers()).collect();
+    assert_eq!(answers.len(), 5);
+    assert_eq!(*answers[0], new_soa);
+    assert_eq!(answers[1].rdata(), old_soa.rdata());
+    assert_eq!(*answers[2], new_soa);
+    assert_eq!(
+        *answers[3].name(),
+        Name::parse("new.test.com.", None).unwrap()
+    );
+    assert_eq!(*answers[4], new_soa);
+}
+
+#[test]
+fn test_ixfr_current_soa_over_udp() {
+    let (test, old_soa) = create_journaled_test();
+    let new_soa = test.soa().next().unwrap().clone();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+
+    // the client must retry over TCP
+    let response_handler = TestResponseHandler::new();
+    ixfr_request(&old_soa, Protocol::Udp, response_handler.clone(), &catalog);
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(result.answers()[0].rdata(), new_soa.rdata());
+
+    // the client is already up to date
+    let response_handler = TestResponseHandler::new();
+    ixfr_request(&new_soa, Protocol::Tcp, response_handler.clone(), &catalog);
+    let result = response_handler.into_message();
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(result.answers()[0].rdata(), new_soa.rdata());
+}
+
+#[test]
+fn test_ixfr_falls_back_to_axfr() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+
+    let origin = test.origin().clone();
+    let old_soa = Record::from_rdata(
+        origin.clone().into(),
+        3600,
+        RecordType::SOA,
+        RData::SOA(SOA::new(
+            Name::parse("sns.dns.icann.org.", None).unwrap(),
+            Name::parse("noc.dns.icann.org.", None).unwrap(),
+            2015082402,
+            7200,
+            3600,
+            1209600,
+            3600,
+        )),
+    );
+    let record_count: usize = test
+        .records()
+        .values()
+        .map(|rrset| rrset.records_without_rrsigs().count())
+        .sum();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+
+    // without a journal the entire zone is sent
+    let response_handler = TestResponseHandler::new();
+    ixfr_request(&old_soa, Protocol::Tcp, response_handler.clone(), &catalog);
+    let messages = response_handler.into_messages();
+
+    let answers: Vec<&Record> = messages.iter().flat_map(|m| m.answers()).collect();
+    assert_eq!(answers.len(), record_count + 1);
+    assert_eq!(answers.first().unwrap().rr_type(), RecordType::SOA);
+    assert_eq!(answers.last().unwrap().rr_type(), RecordType::SOA);
+}
+
+#[test]
+fn test_slave_not_loaded() {
+    let origin: Name = Name::parse("test.com.", None).unwrap();
+    let slave = Authority::new(
+        origin.clone(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+        false,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), slave);
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(origin, RecordType::A));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .expect("lookup failed");
+    let result = response_handler.into_message();
+
+    assert_eq!(result.response_code(), ResponseCode::ServFail);
+    assert!(result.answers().is_empty());
+}
+
+#[test]
+fn test_slave_transferred() {
+    let test = create_test();
+    let origin = test.origin().clone();
+    let transferred: Vec<Record> = test
+        .records()
+        .values()
+        .flat_map(|rrset| rrset.records_without_rrsigs().cloned())
+        .collect();
+
+    let slave = Arc::new(RwLock::new(Authority::new(
+        origin.clone().into(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+        false,
+    )));
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert_shared(origin.clone(), slave.clone());
+
+    slave.write().unwrap().replace_records(transferred);
+    assert_eq!(slave.read().unwrap().serial(), 2015082403);
+
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(origin.into(), RecordType::A));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .expect("lookup failed");
+    let result = response_handler.into_message();
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.header().authoritative());
+    assert_eq!(
+        result.answers().first().unwrap().rdata(),
+        &RData::A(Ipv4Addr::new(94, 184, 216, 34))
+    );
+}
+
+fn notify_request(origin: Name, src: SocketAddr, handler: TestResponseHandler, catalog: &Catalog) {
+    let mut question: Message = Message::new();
+    question.set_op_code(OpCode::Notify);
+    question.add_query(Query::query(origin, RecordType::SOA));
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src,
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol: Protocol::Udp,
+        tls: None,
+    };
+
+    catalog
+        .handle_request(&request, handler)
+        .expect("handle_request failed");
+}
+
+#[test]
+fn test_notify_slave() {
+    let origin: Name = Name::parse("test.com.", None).unwrap();
+    let slave = Arc::new(RwLock::new(Authority::new(
+        origin.clone(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+        false,
+    )));
+    let slave_refresh = SlaveRefresh::new(slave.clone(), vec![([127, 0, 0, 2], 53).into()]);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert_shared(origin.clone().into(), slave);
+    catalog.set_refresh_handle(origin.clone().into(), slave_refresh.handle());
+    catalog.upsert(
+        Name::parse("example.com.", None).unwrap().into(),
+        create_example(),
+    );
+
+    // from the master
+    let response_handler = TestResponseHandler::new();
+    notify_request(
+        origin.clone(),
+        ([127, 0, 0, 2], 1234).into(),
+        response_handler.clone(),
+        &catalog,
+    );
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.op_code(), OpCode::Notify);
+    assert_eq!(result.message_type(), MessageType::Response);
+
+    // not from a master of the zone
+    let response_handler = TestResponseHandler::new();
+    notify_request(
+        origin,
+        ([127, 0, 0, 3], 1234).into(),
+        response_handler.clone(),
+        &catalog,
+    );
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+
+    // not a slave zone
+    let response_handler = TestResponseHandler::new();
+    notify_request(
+        Name::parse("example.com.", None).unwrap(),
+        ([127, 0, 0, 2], 1234).into(),
+        response_handler.clone(),
+        &catalog,
+    );
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::NotAuth);
+}
+
+fn request_from(
+    op_code: OpCode,
+    query: Query,
+    src: SocketAddr,
+    handler: TestResponseHandler,
+    catalog: &Catalog,
+) {
+    let mut question: Message = Message::new();
+    question.set_op_code(op_code);
+    question.add_query(query);
+
+    // temp request
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src,
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol: Protocol::Tcp,
+        tls: None,
+    };
+
+    catalog
+        .handle_request(&request, handler)
+        .expect("handle_request failed");
+}
+
+fn acl(networks: &[(IpAddr, u8)]) -> Acl {
+    let mut acl = Acl::new();
+    for &(addr, prefix_len) in networks {
+        acl.allow(AclMatch::network(addr, prefix_len).unwrap());
+    }
+    acl
+}
+
+#[test]
+fn test_acl_query() {
+    let example = create_example();
+    let test = create_test();
+    let example_origin = example.origin().clone();
+    let test_origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example_origin, example);
+    catalog.upsert(test_origin.clone(), test);
+
+    let mut acls = Acls::new();
+    acls.set(AclOperation::Query, acl(&[(IpAddr::from([127, 0, 0, 0]), 8)]));
+    acls.set(AclOperation::Update, Acl::new());
+    catalog.set_acls(acls);
+
+    let mut test_acls = Acls::new();
+    test_acls.set(AclOperation::Query, acl(&[(IpAddr::from([192, 0, 2, 0]), 24)]));
+    catalog.set_zone_acls(test_origin, test_acls);
+
+    let query = |name: &str, src: [u8; 4]| {
+        let response_handler = TestResponseHandler::new();
+        request_from(
+            OpCode::Query,
+            Query::query(Name::parse(name, None).unwrap(), RecordType::A),
+            SocketAddr::from((src, 1234)),
+            response_handler.clone(),
+            &catalog,
+        );
+        response_handler.into_message().response_code()
+    };
+
+    assert_eq!(query("www.example.com.", [127, 0, 0, 1]), ResponseCode::NoError);
+    assert_eq!(query("www.example.com.", [192, 0, 2, 1]), ResponseCode::Refused);
+    assert_eq!(query("www.test.com.", [192, 0, 2, 1]), ResponseCode::NoError);
+    assert_eq!(query("www.test.com.", [127, 0, 0, 1]), ResponseCode::Refused);
+    assert_eq!(query("www.example.net.", [192, 0, 2, 1]), ResponseCode::Refused);
+
+    // the Acls of the test.com. zone do not restrict updates, those of all zones do
+    let response_handler = TestResponseHandler::new();
+    request_from(
+        OpCode::Update,
+        Query::query(Name::parse("test.com.", None).unwrap(), RecordType::SOA),
+        SocketAddr::from(([192, 0, 2, 1], 1234)),
+        response_handler.clone(),
+        &catalog,
+    );
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert_eq!(result.op_code(), OpCode::Update);
+}
+
+#[test]
+fn test_acl_transfer_and_notify() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+    let origin = test.origin().clone();
+
+    let slave_origin: Name = Name::parse("slave.com.", None).unwrap();
+    let slave = Arc::new(RwLock::new(Authority::new(
+        slave_origin.clone(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+        false,
+    )));
+    let slave_refresh = SlaveRefresh::new(slave.clone(), vec![([127, 0, 0, 2], 53).into()]);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), test);
+    catalog.upsert_shared(slave_origin.clone().into(), slave);
+    catalog.set_refresh_handle(slave_origin.clone().into(), slave_refresh.handle());
+
+    let mut acls = Acls::new();
+    acls.set(AclOperation::Transfer, acl(&[(IpAddr::from([10, 0, 0, 0]), 8)]));
+    acls.set(AclOperation::Notify, acl(&[(IpAddr::from([127, 0, 0, 3]), 32)]));
+    catalog.set_acls(acls);
+
+    // queries are not restricted
+    let response_handler = TestResponseHandler::new();
+    request_from(
+        OpCode::Query,
+        Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A),
+        SocketAddr::from(([127, 0, 0, 1], 1234)),
+        response_handler.clone(),
+        &catalog,
+    );
+    assert_eq!(
+        response_handler.into_message().response_code(),
+        ResponseCode::NoError
+    );
+
+    let response_handler = TestResponseHandler::new();
+    axfr_request(origin.clone().into(), Protocol::Tcp, response_handler.clone(), &catalog);
+    assert_eq!(
+        response_handler.into_message().response_code(),
+        ResponseCode::Refused
+    );
+
+    let response_handler = TestResponseHandler::new();
+    request_from(
+        OpCode::Query,
+        Query::query(origin.into(), RecordType::AXFR),
+        SocketAddr::from(([10, 1, 2, 3], 1234)),
+        response_handler.clone(),
+        &catalog,
+    );
+    let messages = response_handler.into_messages();
+    assert_eq!(messages[0].response_code(), ResponseCode::NoError);
+    assert!(!messages[0].answers().is_empty());
+
+    // the master of the zone is not allowed to NOTIFY
+    let response_handler = TestResponseHandler::new();
+    notify_request(
+        slave_origin.clone(),
+        ([127, 0, 0, 2], 1234).into(),
+        response_handler.clone(),
+        &catalog,
+    );
+    let result = response_handler.into_message();
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert_eq!(result.op_code(), OpCode::Notify);
+
+    // allowed, but still not a master of the zone
+    let response_handler = TestResponseHandler::new();
+    notify_request(
+        slave_origin,
+        ([127, 0, 0, 3], 1234).into(),
+        response_handler.clone(),
+        &catalog,
+    );
+    assert_eq!(
+        response_handler.into_message().response_code(),
+        ResponseCode::Refused
+    );
+}
+
+#[test]
+fn test_truncated_returns_records() {}
+
+#[cfg(feature = "dnssec")]
+fn tsig_signer(secret: &[u8]) -> trust_dns::rr::dnssec::TSigner {
+    use trust_dns::rr::dnssec::TSigner;
+
+    TSigner::new(
+        secret.to_vec(),
+        tsig::TsigAlgorithm::HmacSha256,
+        Name::parse("key.test.com.", None).unwrap(),
+        300,
+    ).unwrap()
+}
+
+#[cfg(feature = "dnssec")]
+fn now() -> u32 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32
+}
+
+/// Sends the query signed with the key at the time, returns the MAC of the request and the bytes
+///  of the responses
+#[cfg(feature = "dnssec")]
+fn tsig_request(
+    query: Query,
+    signer: &trust_dns::rr::dnssec::TSigner,
+    time: u32,
+    catalog: &Catalog,
+) -> (Vec<u8>, Vec<Vec<u8>>) {
+    let mut question: Message = Message::new();
+    question.set_id(4321).add_query(query);
+    question.finalize(signer, time).unwrap();
+
+    let request_mac = match *question.sig0()[0].rdata() {
+        RData::DNSSEC(DNSSECRData::TSIG(ref tsig)) => tsig.mac().to_vec(),
+        _ => panic!("not a tsig"),
+    };
+
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol: Protocol::Tcp,
+        tls: None,
+    };
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .expect("handle_request failed");
+
+    (request_mac, response_handler.into_inners())
+}
+
+#[cfg(feature = "dnssec")]
+fn response_tsig(response: &Message) -> TSIG {
+    match response.sig0().last().map(|record| record.rdata()) {
+        Some(&RData::DNSSEC(DNSSECRData::TSIG(ref tsig))) => tsig.clone(),
+        _ => panic!("response is not signed"),
+    }
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_tsig_signed_query() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+    catalog.add_tsig_key(signer.clone());
+
+    let query = Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A);
+    let (request_mac, responses) = tsig_request(query, &signer, now(), &catalog);
+    assert_eq!(responses.len(), 1);
+
+    let tsig = signer
+        .verify_message_byte(Some(&request_mac), &responses[0], true)
+        .expect("response did not verify");
+    assert_eq!(tsig.error(), 0);
+    assert_eq!(tsig.oid(), 4321);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(response.answers().len(), 1);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_tsig_unknown_key() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+
+    let query = Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A);
+    let (_, responses) = tsig_request(query, &signer, now(), &catalog);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NotAuth);
+    assert!(response.answers().is_empty());
+
+    let tsig = response_tsig(&response);
+    assert_eq!(tsig.error(), u16::from(ResponseCode::BADKEY));
+    assert!(tsig.mac().is_empty());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_tsig_bad_signature() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+    catalog.add_tsig_key(signer);
+
+    // the same key name, with a different secret
+    let query = Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A);
+    let (_, responses) = tsig_request(query, &tsig_signer(b"not the secret"), now(), &catalog);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NotAuth);
+    assert!(response.answers().is_empty());
+
+    let tsig = response_tsig(&response);
+    assert_eq!(tsig.error(), u16::from(ResponseCode::BADSIG));
+    assert!(tsig.mac().is_empty());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_tsig_bad_time() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, test);
+    catalog.add_tsig_key(signer.clone());
+
+    let signed_at = now() - 3600;
+    let query = Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A);
+    let (request_mac, responses) = tsig_request(query, &signer, signed_at, &catalog);
+
+    // the error is signed, with the time of the server
+    let tsig = signer
+        .verify_message_byte(Some(&request_mac), &responses[0], true)
+        .expect("response did not verify");
+    assert_eq!(tsig.error(), u16::from(ResponseCode::BADTIME));
+    assert_eq!(tsig.time(), u64::from(signed_at));
+    assert_eq!(tsig.other().len(), 6);
+
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NotAuth);
+    assert!(response.answers().is_empty());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_axfr_tsig() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let mut test = create_test();
+    test.set_allow_axfr(false);
+    test.add_tsig_key(signer.clone());
+
+    let origin = test.origin().clone();
+    let serial = test.serial();
+    for i in 0..2000u32 {
+        test.upsert(
+            Record::from_rdata(
+                Name::parse(&format!("host{}.test.com.", i), None).unwrap(),
+                86400,
+                RecordType::A,
+                RData::A(Ipv4Addr::from(0x0a00_0000 + i)),
+            ),
+            serial,
+        );
+    }
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), test);
+    catalog.add_tsig_key(signer.clone());
+
+    // without the key the transfer is refused
+    let response_handler = TestResponseHandler::new();
+    axfr_request(origin.clone().into(), Protocol::Tcp, response_handler.clone(), &catalog);
+    assert_eq!(
+        response_handler.into_message().response_code(),
+        ResponseCode::Refused
+    );
+
+    let query = Query::query(origin.into(), RecordType::AXFR);
+    let (request_mac, responses) = tsig_request(query, &signer, now(), &catalog);
+    assert!(responses.len() > 1);
+
+    // each message is signed, covering the MAC of the message before it
+    let mut previous_mac = request_mac;
+    for (i, bytes) in responses.iter().enumerate() {
+        let tsig = signer
+            .verify_message_byte(Some(&previous_mac), bytes, i == 0)
+            .expect("response did not verify");
+        previous_mac = tsig.mac().to_vec();
+
+        let response = Message::from_bytes(bytes).unwrap();
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+    }
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_acl_tsig_key() {
+    let signer = tsig_signer(b"a secret of the test.com. zone");
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), test);
+    catalog.add_tsig_key(signer.clone());
+
+    let mut query_acl = Acl::new();
+    query_acl.allow(AclMatch::Key(LowerName::from(signer.signer_name())));
+    let mut acls = Acls::new();
+    acls.set(AclOperation::Query, query_acl);
+    catalog.set_zone_acls(origin, acls);
+
+    let query = Query::query(Name::parse("www.test.com.", None).unwrap(), RecordType::A);
+
+    let response_handler = TestResponseHandler::new();
+    request_from(
+        OpCode::Query,
+        query.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 1234)),
+        response_handler.clone(),
+        &catalog,
+    );
+    assert_eq!(
+        response_handler.into_message().response_code(),
+        ResponseCode::Refused
+    );
+
+    // the refusal of a request signed with another key is signed too
+    let other = trust_dns::rr::dnssec::TSigner::new(
+        b"another secret".to_vec(),
+        tsig::TsigAlgorithm::HmacSha256,
+        Name::parse("other.test.com.", None).unwrap(),
+        300,
+    ).unwrap();
+    catalog.add_tsig_key(other.clone());
+    let (request_mac, responses) = tsig_request(query.clone(), &other, now(), &catalog);
+    other
+        .verify_message_byte(Some(&request_mac), &responses[0], true)
+        .expect("response did not verify");
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::Refused);
+
+    let (_, responses) = tsig_request(query, &signer, now(), &catalog);
+    let response = Message::from_bytes(&responses[0]).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.answers().is_empty());
+}
+
+fn cookie_request(
+    cookie: Option<opt::EdnsOption>,
+    protocol: Protocol,
+    catalog: &Catalog,
+) -> Message {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(
+        Name::parse("www.example.com.", None).unwrap(),
+        RecordType::A,
+    ));
+    {
+        let edns = question.edns_mut();
+        edns.set_max_payload(1232);
+        if let Some(cookie) = cookie {
+            edns.set_option(cookie);
+        }
+    }
+
+    let question_bytes = question.to_bytes().unwrap();
+    let request = Request {
+        message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+        src: SocketAddr::from(([192, 0, 2, 1], 1234)),
+        dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+        protocol,
+        tls: None,
+    };
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .expect("handle_request failed");
+    response_handler.into_message()
+}
+
+fn response_cookie(response: &Message) -> opt::Cookie {
+    match response
+        .edns()
+        .and_then(|edns| edns.option(opt::EdnsCode::Cookie))
+    {
+        Some(&opt::EdnsOption::Cookie(ref cookie)) => cookie.clone(),
+        option => panic!("expected a cookie: {:?}", option),
+    }
+}
+
+#[test]
+fn test_server_cookies() {
+    use trust_dns::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
+
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, example);
+
+    // without server cookies, the cookie of the request is ignored
+    let client = Cookie::new([1, 2, 3, 4, 5, 6, 7, 8], Vec::new()).unwrap();
+    let option = EdnsOption::Cookie(client.clone());
+    let response = cookie_request(Some(option.clone()), Protocol::Udp, &catalog);
+    assert!(response.edns().unwrap().option(EdnsCode::Cookie).is_none());
+
+    let mut cookies = ServerCookies::new(None);
+    cookies.set_required(true);
+    catalog.set_server_cookies(cookies);
+
+    // a request without a cookie is answered as usual
+    let response = cookie_request(None, Protocol::Udp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.answers().is_empty());
+
+    // a client cookie alone is answered with a server cookie to retry with
+    let response = cookie_request(Some(option.clone()), Protocol::Udp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::BADCOOKIE);
+    assert!(response.answers().is_empty());
+    let cookie = response_cookie(&response);
+    assert_eq!(cookie.client(), client.client());
+    assert!(cookie.server().is_some());
+
+    // over TCP the request is answered along with the server cookie
+    let response = cookie_request(Some(option), Protocol::Tcp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response_cookie(&response).server().is_some());
+
+    // the server cookie is accepted, and returned
+    let option = EdnsOption::Cookie(cookie.clone());
+    let response = cookie_request(Some(option), Protocol::Udp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.answers().is_empty());
+    assert_eq!(response_cookie(&response), cookie);
+
+    // but not for another client cookie
+    let other = Cookie::new([8; 8], cookie.server().unwrap().to_vec()).unwrap();
+    let response = cookie_request(Some(EdnsOption::Cookie(other)), Protocol::Udp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::BADCOOKIE);
+
+    // a malformed cookie is a format error
+    let malformed = EdnsOption::Unknown(u16::from(EdnsCode::Cookie), vec![1, 2, 3]);
+    let response = cookie_request(Some(malformed), Protocol::Udp, &catalog);
+    assert_eq!(response.response_code(), ResponseCode::FormErr);
+}
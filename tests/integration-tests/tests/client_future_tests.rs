@@ -313,6 +313,54 @@ fn test_create() {
     assert_eq!(result.response_code(), ResponseCode::YXRRSet);
 }
 
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_create_tsig() {
+    use trust_dns::rr::dnssec::TSigner;
+    use trust_dns_proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+
+    let mut io_loop = Runtime::new().unwrap();
+
+    let signer = TSigner::new(
+        b"a secret shared by the client and server".to_vec(),
+        TsigAlgorithm::HmacSha256,
+        Name::from_str("key.example.com.").unwrap(),
+        300,
+    ).unwrap();
+
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+    authority.add_tsig_key(signer.clone());
+    let origin: Name = authority.origin().clone().into();
+
+    let mut catalog = Catalog::new();
+    catalog.add_tsig_key(signer.clone());
+    catalog.upsert(authority.origin().clone(), authority);
+
+    let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(catalog)));
+    let (bg, mut client) = ClientFuture::with_tsigner(stream, Box::new(sender), Arc::new(signer));
+    io_loop.spawn(bg);
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).num_seconds() as u32,
+    );
+    record.set_rdata(RData::A(Ipv4Addr::new(100, 10, 100, 10)));
+
+    let result = io_loop
+        .block_on(client.create(record.clone(), origin))
+        .expect("create failed");
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.sig0().len(), 1);
+
+    let result = io_loop
+        .block_on(client.query(record.name().clone(), record.dns_class(), record.rr_type()))
+        .expect("query failed");
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers(), &[record]);
+}
+
 #[cfg(feature = "dnssec")]
 #[test]
 fn test_create_multi() {
@@ -24,6 +24,8 @@ use trust_dns::client::{Client, ClientConnection, SyncClient};
 use trust_dns::error::ClientErrorKind;
 use trust_dns::rr::dnssec::Signer;
 #[cfg(feature = "dnssec")]
+use trust_dns::rr::dnssec::TSigner;
+#[cfg(feature = "dnssec")]
 use trust_dns::rr::Record;
 use trust_dns::rr::{DNSClass, Name, RData, RecordType};
 use trust_dns::tcp::TcpClientConnection;
@@ -64,6 +66,44 @@ impl ClientConnection for TestClientConnection {
     }
 }
 
+/// A TestClientConnection which signs all messages with TSIG
+#[cfg(feature = "dnssec")]
+pub struct TSigTestClientConnection {
+    catalog: Arc<Mutex<Catalog>>,
+    signer: Arc<TSigner>,
+}
+
+#[cfg(feature = "dnssec")]
+impl TSigTestClientConnection {
+    pub fn new(catalog: Catalog, signer: TSigner) -> TSigTestClientConnection {
+        TSigTestClientConnection {
+            catalog: Arc::new(Mutex::new(catalog)),
+            signer: Arc::new(signer),
+        }
+    }
+}
+
+#[cfg(feature = "dnssec")]
+impl ClientConnection for TSigTestClientConnection {
+    type Sender = DnsMultiplexer<TestClientStream, TSigner>;
+    type Response = <Self::Sender as DnsRequestSender>::DnsResponseFuture;
+    type SenderFuture = DnsMultiplexerConnect<
+        Box<Future<Item = TestClientStream, Error = ProtoError> + Send>,
+        TestClientStream,
+        TSigner,
+    >;
+
+    fn new_stream(&self, _signer: Option<Arc<Signer>>) -> Self::SenderFuture {
+        let (client_stream, handle) = TestClientStream::new(self.catalog.clone());
+
+        DnsMultiplexer::new(
+            Box::new(client_stream),
+            Box::new(handle),
+            Some(self.signer.clone()),
+        )
+    }
+}
+
 #[test]
 #[allow(deprecated)]
 fn test_query_nonet() {
@@ -464,6 +504,128 @@ fn test_create() {
     assert_eq!(result.response_code(), ResponseCode::YXRRSet);
 }
 
+#[cfg(feature = "dnssec")]
+fn create_tsig_ready_client(
+    mut catalog: Catalog,
+) -> (SyncClient<TSigTestClientConnection>, Name) {
+    use trust_dns_proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+    let origin = authority.origin().clone();
+
+    let signer = TSigner::new(
+        b"a secret shared by the client and server".to_vec(),
+        TsigAlgorithm::HmacSha256,
+        Name::from_str("key.example.com.").unwrap(),
+        300,
+    ).unwrap();
+
+    // the catalog verifies the requests, the zone only accepts updates of its own keys
+    authority.add_tsig_key(signer.clone());
+    catalog.add_tsig_key(signer.clone());
+
+    catalog.upsert(authority.origin().clone(), authority);
+    let client = SyncClient::new(TSigTestClientConnection::new(catalog, signer));
+
+    (client, origin.into())
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_create_tsig() {
+    let catalog = Catalog::new();
+    let (client, origin) = create_tsig_ready_client(catalog);
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).num_seconds() as u32,
+    );
+    record.set_rdata(RData::A(Ipv4Addr::new(100, 10, 100, 10)));
+
+    let result = client
+        .create(record.clone(), origin.clone())
+        .expect("create failed");
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+
+    // the response is signed with the key of the request
+    assert_eq!(result.sig0().len(), 1);
+    assert_eq!(
+        result.sig0()[0].name(),
+        &Name::from_str("key.example.com.").unwrap()
+    );
+
+    let result = client
+        .query(record.name(), record.dns_class(), record.rr_type())
+        .expect("query failed");
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers().len(), 1);
+    assert_eq!(result.answers()[0], record);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_create_tsig_without_zone_key() {
+    use trust_dns_proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+
+    // the key is known to the catalog, but not allowed to update the zone
+    let mut catalog = Catalog::new();
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+    let origin: Name = authority.origin().clone().into();
+
+    let signer = TSigner::new(
+        b"a secret shared by the client and server".to_vec(),
+        TsigAlgorithm::HmacSha256,
+        Name::from_str("key.example.com.").unwrap(),
+        300,
+    ).unwrap();
+    catalog.add_tsig_key(signer.clone());
+    catalog.upsert(authority.origin().clone(), authority);
+    let client = SyncClient::new(TSigTestClientConnection::new(catalog, signer));
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).num_seconds() as u32,
+    );
+    record.set_rdata(RData::A(Ipv4Addr::new(100, 10, 100, 10)));
+
+    let result = client.create(record, origin).expect("create failed");
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_create_tsig_unknown_key() {
+    use trust_dns_proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+
+    // the catalog answers with an unsigned BADKEY, which the client must not accept
+    let mut catalog = Catalog::new();
+    let mut authority = create_example();
+    authority.set_allow_update(true);
+    let origin: Name = authority.origin().clone().into();
+    catalog.upsert(authority.origin().clone(), authority);
+
+    let signer = TSigner::new(
+        b"a secret shared by the client and server".to_vec(),
+        TsigAlgorithm::HmacSha256,
+        Name::from_str("key.example.com.").unwrap(),
+        300,
+    ).unwrap();
+    let client = SyncClient::new(TSigTestClientConnection::new(catalog, signer));
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).num_seconds() as u32,
+    );
+    record.set_rdata(RData::A(Ipv4Addr::new(100, 10, 100, 10)));
+
+    assert!(client.create(record, origin).is_err());
+}
+
 #[cfg(feature = "dnssec")]
 #[test]
 fn test_append() {
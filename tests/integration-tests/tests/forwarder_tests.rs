@@ -0,0 +1,251 @@
+extern crate futures;
+extern crate tokio;
+extern crate tokio_udp;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_resolver;
+extern crate trust_dns_server;
+
+use std::net::*;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use futures::future;
+use tokio::runtime::Runtime;
+use tokio_udp::UdpSocket;
+
+use trust_dns::op::*;
+use trust_dns::rr::*;
+use trust_dns::serialize::binary::{BinDecodable, BinEncodable};
+use trust_dns::serialize::txt::{Lexer, Parser};
+use trust_dns_resolver::config::NameServerConfigGroup;
+
+use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, Request, RequestHandler};
+use trust_dns_server::ServerFuture;
+
+use trust_dns_integration::authority::create_example;
+use trust_dns_integration::TestResponseHandler;
+
+/// Starts a server for example.com, and returns a forward zone for it
+fn forward_example(io_loop: &mut Runtime) -> ForwardAuthority {
+    let mut example = create_example();
+
+    // alias.example.com.	300	IN	CNAME	www.example.com.
+    example.upsert(
+        Record::from_rdata(
+            Name::from_str("alias.example.com.").unwrap(),
+            300,
+            RecordType::CNAME,
+            RData::CNAME(Name::from_str("www.example.com.").unwrap()),
+        ),
+        0,
+    );
+
+    let mut upstream_catalog = Catalog::new();
+    upstream_catalog.upsert(example.origin().clone(), example);
+
+    let udp_socket = UdpSocket::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+    let upstream_addr = udp_socket.local_addr().unwrap();
+
+    let server = ServerFuture::new(upstream_catalog);
+    io_loop
+        .block_on(future::lazy(move || {
+            server.register_socket(udp_socket);
+            Ok::<(), ()>(())
+        })).unwrap();
+
+    let (forwarder, background) = ForwardAuthority::new(
+        Name::from_str("example.com.").unwrap(),
+        NameServerConfigGroup::from_ips_clear(&[upstream_addr.ip()], upstream_addr.port()),
+    );
+    io_loop.spawn(background);
+
+    forwarder
+}
+
+#[test]
+fn test_forward_lookup() {
+    let mut io_loop = Runtime::new().unwrap();
+    let forwarder = forward_example(&mut io_loop);
+
+    let query = LowerQuery::from(Query::query(
+        Name::from_str("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let records = io_loop
+        .block_on(forwarder.lookup(&query))
+        .expect("lookup failed");
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(*records[0].name(), Name::from_str("www.example.com.").unwrap());
+    assert_eq!(
+        *records[0].rdata(),
+        RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+    assert!(records[0].ttl() <= 86400 && records[0].ttl() > 86000);
+}
+
+#[test]
+fn test_forward_lookup_cname() {
+    let mut io_loop = Runtime::new().unwrap();
+    let forwarder = forward_example(&mut io_loop);
+
+    let query = LowerQuery::from(Query::query(
+        Name::from_str("alias.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let records = io_loop
+        .block_on(forwarder.lookup(&query))
+        .expect("lookup failed");
+
+    // the records keep their owners, the TTLs are at most those of the chain
+    assert_eq!(records.len(), 2);
+    assert_eq!(*records[0].name(), Name::from_str("alias.example.com.").unwrap());
+    assert_eq!(
+        *records[0].rdata(),
+        RData::CNAME(Name::from_str("www.example.com.").unwrap())
+    );
+    assert!(records[0].ttl() <= 300 && records[0].ttl() > 0);
+    assert_eq!(*records[1].name(), Name::from_str("www.example.com.").unwrap());
+    assert_eq!(
+        *records[1].rdata(),
+        RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+    assert!(records[1].ttl() <= 300 && records[1].ttl() > 0);
+}
+
+/// Sends the query through a catalog holding the forwarder, and waits for the response
+fn forward_through_catalog(
+    io_loop: &mut Runtime,
+    forwarder: ForwardAuthority,
+    query: Query,
+) -> Message {
+    let mut catalog = Catalog::new();
+    catalog.upsert_forward(forwarder.origin().clone(), forwarder);
+
+    let mut question: Message = Message::new();
+    question.add_query(query);
+    question.set_recursion_desired(true);
+
+    let question_bytes = question.to_bytes().unwrap();
+    let response_handler = TestResponseHandler::new();
+    let handler = response_handler.clone();
+
+    io_loop
+        .block_on(future::lazy(move || {
+            let request = Request {
+                message: MessageRequest::from_bytes(&question_bytes).unwrap(),
+                src: SocketAddr::from(([127, 0, 0, 1], 1234)),
+                dst: SocketAddr::from(([127, 0, 0, 1], 53)),
+                protocol: Protocol::Udp,
+                tls: None,
+            };
+
+            catalog.handle_request(&request, handler).map_err(|_| ())
+        })).unwrap();
+
+    // the response is sent once the upstream answers
+    for _ in 0..100 {
+        if response_handler.is_sent() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    response_handler.into_message()
+}
+
+#[test]
+fn test_catalog_forwards() {
+    let mut io_loop = Runtime::new().unwrap();
+    let forwarder = forward_example(&mut io_loop);
+
+    let result = forward_through_catalog(
+        &mut io_loop,
+        forwarder,
+        Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A),
+    );
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.header().recursion_available());
+    assert!(!result.header().authoritative());
+    assert_eq!(
+        *result.answers()[0].rdata(),
+        RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+}
+
+#[test]
+fn test_catalog_forwards_nxdomain() {
+    let mut io_loop = Runtime::new().unwrap();
+    let forwarder = forward_example(&mut io_loop);
+
+    let result = forward_through_catalog(
+        &mut io_loop,
+        forwarder,
+        Query::query(Name::from_str("nx.example.com.").unwrap(), RecordType::A),
+    );
+
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(result.answers().is_empty());
+    assert_eq!(result.name_servers().len(), 1);
+    assert_eq!(result.name_servers()[0].rr_type(), RecordType::SOA);
+    assert_eq!(
+        *result.name_servers()[0].name(),
+        Name::from_str("example.com.").unwrap()
+    );
+}
+
+#[test]
+fn test_catalog_forwards_no_data() {
+    let mut io_loop = Runtime::new().unwrap();
+    let forwarder = forward_example(&mut io_loop);
+
+    let result = forward_through_catalog(
+        &mut io_loop,
+        forwarder,
+        Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::SRV),
+    );
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+    assert_eq!(result.name_servers().len(), 1);
+    assert_eq!(result.name_servers()[0].rr_type(), RecordType::SOA);
+}
+
+#[test]
+fn test_root_hints() {
+    let hints = "
+.                        3600000      NS    A.ROOT-SERVERS.NET.
+A.ROOT-SERVERS.NET.      3600000      A     198.41.0.4
+A.ROOT-SERVERS.NET.      3600000      AAAA  2001:503:ba3e::2:30
+;
+.                        3600000      NS    B.ROOT-SERVERS.NET.
+B.ROOT-SERVERS.NET.      3600000      A     199.9.14.201
+OTHER.EXAMPLE.           3600000      A     192.0.2.1
+";
+
+    let (_, records) = Parser::new()
+        .set_default_class(DNSClass::IN)
+        .parse(Lexer::new(hints), Some(Name::root()))
+        .unwrap();
+    let roots = root_hints(records.values().flat_map(|rrset| rrset.records_without_rrsigs()));
+
+    let mut addresses = roots
+        .iter()
+        .map(|config| config.socket_addr)
+        .collect::<Vec<_>>();
+    addresses.sort();
+    addresses.dedup();
+
+    let mut expected = vec![
+        SocketAddr::new(IpAddr::from([198, 41, 0, 4]), 53),
+        SocketAddr::new(IpAddr::from_str("2001:503:ba3e::2:30").unwrap(), 53),
+        SocketAddr::new(IpAddr::from([199, 9, 14, 201]), 53),
+    ];
+    expected.sort();
+
+    assert_eq!(addresses, expected);
+}
@@ -9,17 +9,18 @@ use std::net::*;
 use std::str::FromStr;
 use std::sync::{
     atomic::{AtomicIsize, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 
 use futures::future::{self, Future, Loop};
 use tokio::runtime::current_thread::Runtime;
 
-use trust_dns::op::Query;
+use trust_dns::op::{Message, Query, ResponseCode};
+use trust_dns::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
 use trust_dns::rr::{Name, RecordType};
 use trust_dns_integration::mock_client::*;
 use trust_dns_proto::error::{ProtoError, ProtoResult};
-use trust_dns_proto::xfer::{DnsHandle, DnsResponse};
+use trust_dns_proto::xfer::{DnsHandle, DnsRequest, DnsResponse};
 use trust_dns_resolver::config::*;
 use trust_dns_resolver::name_server_pool::{ConnectionProvider, NameServer, NameServerPool};
 
@@ -542,3 +543,131 @@ fn test_concurrent_requests_0_conn() {
     let response = reactor.block_on(future).unwrap();
     assert_eq!(response.answers()[0], udp_record);
 }
+
+// === DNS cookies ===
+
+const SERVER_COOKIE: [u8; 16] = [7; 16];
+
+/// A name server which requires a server cookie, it records the cookies of the requests
+#[derive(Clone)]
+struct CookieServer {
+    requests: Arc<Mutex<Vec<Cookie>>>,
+    /// The client cookie of the responses, the one of the request if None
+    client: Option<[u8; 8]>,
+}
+
+impl CookieServer {
+    fn new(client: Option<[u8; 8]>) -> Self {
+        CookieServer {
+            requests: Arc::new(Mutex::new(Vec::new())),
+            client,
+        }
+    }
+
+    fn requests(&self) -> Vec<Cookie> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl ConnectionProvider for CookieServer {
+    type ConnHandle = CookieServer;
+
+    fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::ConnHandle {
+        self.clone()
+    }
+}
+
+impl DnsHandle for CookieServer {
+    type Response = Box<Future<Item = DnsResponse, Error = ProtoError> + Send>;
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        let request = request.into();
+        let cookie = match request.edns().and_then(|edns| edns.option(EdnsCode::Cookie)) {
+            Some(&EdnsOption::Cookie(ref cookie)) => cookie.clone(),
+            option => panic!("expected a cookie: {:?}", option),
+        };
+        self.requests.lock().unwrap().push(cookie.clone());
+
+        let response_code = if cookie.server() == Some(&SERVER_COOKIE[..]) {
+            ResponseCode::NoError
+        } else {
+            ResponseCode::BADCOOKIE
+        };
+        let client = self.client.unwrap_or(*cookie.client());
+
+        let mut response = Message::new();
+        response.set_id(request.id());
+        response.set_response_code(response_code);
+        {
+            let edns = response.edns_mut();
+            edns.set_rcode_high(response_code.high() as u8);
+            edns.set_option(EdnsOption::Cookie(
+                Cookie::new(client, SERVER_COOKIE.to_vec()).unwrap(),
+            ));
+        }
+
+        Box::new(future::ok(response.into()))
+    }
+}
+
+fn cookie_request() -> Message {
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let mut request = message(query, vec![], vec![], vec![]).unwrap();
+    request.edns_mut();
+    request
+}
+
+#[test]
+fn test_cookies() {
+    let server = CookieServer::new(None);
+    let mut name_server = NameServer::from_conn(
+        NameServerConfig {
+            socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 0),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+        },
+        ResolverOpts::default(),
+        server.clone(),
+        server.clone(),
+    );
+
+    let mut reactor = Runtime::new().unwrap();
+
+    // the request without a server cookie is answered with BADCOOKIE, and sent again with it
+    let response = reactor.block_on(name_server.send(cookie_request())).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].server(), None);
+    assert_eq!(requests[1].client(), requests[0].client());
+    assert_eq!(requests[1].server(), Some(&SERVER_COOKIE[..]));
+
+    // the server cookie is remembered
+    let response = reactor.block_on(name_server.send(cookie_request())).unwrap();
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2], requests[1]);
+}
+
+#[test]
+fn test_cookies_mismatch() {
+    // a response which does not return the client cookie is rejected
+    let server = CookieServer::new(Some([0; 8]));
+    let mut name_server = NameServer::from_conn(
+        NameServerConfig {
+            socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 0),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+        },
+        ResolverOpts::default(),
+        server.clone(),
+        server.clone(),
+    );
+
+    let mut reactor = Runtime::new().unwrap();
+    assert!(reactor.block_on(name_server.send(cookie_request())).is_err());
+    assert_eq!(server.requests().len(), 1);
+}
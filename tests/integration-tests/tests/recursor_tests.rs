@@ -0,0 +1,252 @@
+extern crate futures;
+extern crate tokio;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_proto;
+extern crate trust_dns_resolver;
+
+use std::collections::HashMap;
+use std::net::*;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use tokio::runtime::current_thread::Runtime;
+
+use trust_dns::op::{Message, Query, ResponseCode};
+use trust_dns::rr::rdata::SOA;
+use trust_dns::rr::{Name, RData, Record, RecordType};
+use trust_dns_integration::mock_client::*;
+use trust_dns_proto::xfer::DnsResponse;
+use trust_dns_resolver::config::*;
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::name_server_pool::ConnectionProvider;
+use trust_dns_resolver::Recursor;
+
+const ROOT: [u8; 4] = [198, 41, 0, 4];
+const COM: [u8; 4] = [192, 5, 6, 30];
+const NET: [u8; 4] = [192, 5, 6, 31];
+const EXAMPLE: [u8; 4] = [10, 0, 0, 3];
+
+/// Connects to the mocked name server for the address, each of which returns its responses in
+///  the order given
+#[derive(Clone)]
+struct MockNameServers(Arc<HashMap<IpAddr, MockClientHandle<DefaultOnSend>>>);
+
+impl MockNameServers {
+    fn new(name_servers: Vec<([u8; 4], Vec<Message>)>) -> Self {
+        let name_servers = name_servers
+            .into_iter()
+            .map(|(ip, mut responses)| {
+                responses.reverse();
+                let responses = responses
+                    .into_iter()
+                    .map(|message| Ok(DnsResponse::from(message)))
+                    .collect();
+                (IpAddr::from(ip), MockClientHandle::mock(responses))
+            }).collect();
+
+        MockNameServers(Arc::new(name_servers))
+    }
+
+    fn recursor(&self) -> Recursor<MockClientHandle<DefaultOnSend>, MockNameServers> {
+        let roots = NameServerConfigGroup::from_ips_clear(&[IpAddr::from(ROOT)], 53);
+        Recursor::with_provider(roots, ResolverOpts::default(), self.clone())
+    }
+}
+
+impl ConnectionProvider for MockNameServers {
+    type ConnHandle = MockClientHandle<DefaultOnSend>;
+
+    fn new_connection(&self, config: &NameServerConfig, _: &ResolverOpts) -> Self::ConnHandle {
+        self.0
+            .get(&config.socket_addr.ip())
+            .cloned()
+            .unwrap_or_else(|| MockClientHandle::mock(vec![]))
+    }
+}
+
+fn name(name: &str) -> Name {
+    Name::from_str(name).unwrap()
+}
+
+fn referral(query: &Query, zone: &str, name_server: &str, glue: Option<[u8; 4]>) -> Message {
+    let ns = Record::from_rdata(name(zone), 86400, RecordType::NS, RData::NS(name(name_server)));
+    let glue = glue
+        .map(|ip| vec![v4_record(name(name_server), Ipv4Addr::from(ip))])
+        .unwrap_or_default();
+
+    message(query.clone(), vec![], vec![ns], glue).unwrap()
+}
+
+fn answer(query: &Query, answers: Vec<Record>) -> Message {
+    let mut message = message(query.clone(), answers, vec![], vec![]).unwrap();
+    message.set_authoritative(true);
+    message
+}
+
+#[test]
+fn test_recursor_follows_referrals() {
+    let www = Query::query(name("www.example.com."), RecordType::A);
+    let mail = Query::query(name("mail.example.com."), RecordType::A);
+    let www_record = v4_record(name("www.example.com."), Ipv4Addr::new(10, 0, 0, 10));
+    let mail_record = v4_record(name("mail.example.com."), Ipv4Addr::new(10, 0, 0, 11));
+
+    // the root and com servers are only asked once, the example.com delegation is cached
+    let name_servers = MockNameServers::new(vec![
+        (ROOT, vec![referral(&www, "com.", "a.gtld-servers.net.", Some(COM))]),
+        (COM, vec![referral(&www, "example.com.", "ns.example.com.", Some(EXAMPLE))]),
+        (
+            EXAMPLE,
+            vec![
+                answer(&www, vec![www_record.clone()]),
+                answer(&mail, vec![mail_record.clone()]),
+            ],
+        ),
+    ]);
+
+    let recursor = name_servers.recursor();
+    let mut io_loop = Runtime::new().unwrap();
+
+    let records = io_loop.block_on(recursor.resolve(www)).unwrap();
+    assert_eq!(records, vec![www_record]);
+
+    let records = io_loop.block_on(recursor.resolve(mail)).unwrap();
+    assert_eq!(records, vec![mail_record]);
+}
+
+#[test]
+fn test_recursor_follows_cname_across_zones() {
+    let www = Query::query(name("www.example.com."), RecordType::A);
+    let ns = Query::query(name("ns.example.net."), RecordType::A);
+    let www_net = Query::query(name("www.example.net."), RecordType::A);
+
+    let cname = cname_record(name("www.example.com."), name("www.example.net."));
+    let www_net_record = v4_record(name("www.example.net."), Ipv4Addr::new(10, 0, 0, 20));
+
+    // the name server for example.com has no glue, and is looked up in net.
+    let name_servers = MockNameServers::new(vec![
+        (
+            ROOT,
+            vec![
+                referral(&www, "com.", "a.gtld-servers.net.", Some(COM)),
+                referral(&ns, "net.", "b.gtld-servers.net.", Some(NET)),
+            ],
+        ),
+        (COM, vec![referral(&www, "example.com.", "ns.example.net.", None)]),
+        (
+            NET,
+            vec![
+                answer(&ns, vec![v4_record(name("ns.example.net."), Ipv4Addr::from(EXAMPLE))]),
+                answer(&www_net, vec![www_net_record.clone()]),
+            ],
+        ),
+        (EXAMPLE, vec![answer(&www, vec![cname.clone()])]),
+    ]);
+
+    let recursor = name_servers.recursor();
+    let mut io_loop = Runtime::new().unwrap();
+
+    let records = io_loop.block_on(recursor.resolve(www)).unwrap();
+    assert_eq!(records, vec![cname, www_net_record]);
+}
+
+#[test]
+fn test_recursor_ignores_out_of_zone_records() {
+    let www = Query::query(name("www.example.com."), RecordType::A);
+    let ns = Query::query(name("ns.example.net."), RecordType::A);
+    let www_net = Query::query(name("www.example.net."), RecordType::A);
+
+    let poisoned = Ipv4Addr::new(10, 66, 6, 6);
+    let cname = cname_record(name("www.example.com."), name("www.example.net."));
+    let www_net_record = v4_record(name("www.example.net."), Ipv4Addr::new(10, 0, 0, 20));
+
+    // com. has no authority over the address of ns.example.net., nor example.com. over the
+    //  address of www.example.net., both are looked up in net.
+    let name_servers = MockNameServers::new(vec![
+        (
+            ROOT,
+            vec![
+                referral(&www, "com.", "a.gtld-servers.net.", Some(COM)),
+                referral(&ns, "net.", "b.gtld-servers.net.", Some(NET)),
+            ],
+        ),
+        (
+            COM,
+            vec![referral(&www, "example.com.", "ns.example.net.", Some(poisoned.octets()))],
+        ),
+        (
+            NET,
+            vec![
+                answer(&ns, vec![v4_record(name("ns.example.net."), Ipv4Addr::from(EXAMPLE))]),
+                answer(&www_net, vec![www_net_record.clone()]),
+            ],
+        ),
+        (
+            EXAMPLE,
+            vec![answer(
+                &www,
+                vec![cname.clone(), v4_record(name("www.example.net."), poisoned)],
+            )],
+        ),
+    ]);
+
+    let recursor = name_servers.recursor();
+    let mut io_loop = Runtime::new().unwrap();
+
+    let records = io_loop.block_on(recursor.resolve(www)).unwrap();
+    assert_eq!(records, vec![cname, www_net_record]);
+}
+
+#[test]
+fn test_recursor_nxdomain() {
+    let query = Query::query(name("none.example.com."), RecordType::A);
+    let soa = Record::from_rdata(
+        name("example.com."),
+        3600,
+        RecordType::SOA,
+        RData::SOA(SOA::new(
+            name("ns.example.com."),
+            name("root.example.com."),
+            1,
+            3600,
+            600,
+            86400,
+            300,
+        )),
+    );
+
+    let mut nxdomain = message(query.clone(), vec![], vec![soa], vec![]).unwrap();
+    nxdomain.set_response_code(ResponseCode::NXDomain);
+
+    let name_servers = MockNameServers::new(vec![
+        (ROOT, vec![referral(&query, "example.com.", "ns.example.com.", Some(EXAMPLE))]),
+        (EXAMPLE, vec![nxdomain]),
+    ]);
+
+    let recursor = name_servers.recursor();
+    let mut io_loop = Runtime::new().unwrap();
+
+    let error = io_loop.block_on(recursor.resolve(query.clone())).unwrap_err();
+    assert_nxdomain(error.kind(), &query);
+
+    // the negative answer is cached, the name servers have no more responses
+    let error = io_loop.block_on(recursor.resolve(query.clone())).unwrap_err();
+    assert_nxdomain(error.kind(), &query);
+}
+
+fn assert_nxdomain(kind: &ResolveErrorKind, query: &Query) {
+    match *kind {
+        ResolveErrorKind::NoRecordsFound {
+            query: ref nx_query,
+            valid_until,
+            response_code,
+            ref soa,
+        } => {
+            assert_eq!(nx_query, query);
+            assert!(valid_until.is_some());
+            assert_eq!(response_code, ResponseCode::NXDomain);
+            assert!(soa.is_some());
+        }
+        ref kind => panic!("unexpected error: {:?}", kind),
+    }
+}
@@ -97,14 +97,13 @@ where
     let response = io_loop
         .block_on(client.query(name.clone(), DNSClass::IN, RecordType::A))
         .expect("query failed");
-    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(response.response_code(), ResponseCode::NXDomain);
 }
 
-// TODO: NSEC response code wrong in Trust-DNS? Issue #53
-// #[test]
-// fn test_nsec_query_type_nonet() {
-//   with_nonet(test_nsec_query_type);
-// }
+#[test]
+fn test_nsec_query_type_nonet() {
+    with_nonet(test_nsec_query_type);
+}
 
 #[test]
 #[ignore]
@@ -16,7 +16,7 @@ use std::io;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -28,6 +28,7 @@ use tokio_udp::UdpSocket;
 
 use trust_dns::client::*;
 use trust_dns::op::*;
+use trust_dns::rr::rdata::TXT;
 use trust_dns::rr::*;
 use trust_dns::tcp::TcpClientConnection;
 use trust_dns::udp::UdpClientConnection;
@@ -35,6 +36,7 @@ use trust_dns_proto::error::ProtoError;
 use trust_dns_proto::xfer::DnsRequestSender;
 
 use trust_dns_server::authority::*;
+use trust_dns_server::server::{Protocol, RateLimits, Request, RequestHandler, ResponseHandler};
 use trust_dns_server::ServerFuture;
 
 use trust_dns_integration::authority::create_example;
@@ -99,6 +101,200 @@ fn test_server_www_tcp() {
     server_thread.join().unwrap();;
 }
 
+#[test]
+fn test_server_truncated_udp() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+    let tcp_listener = TcpListener::bind(&addr).unwrap();
+
+    let udp_addr = udp_socket.local_addr().unwrap();
+    let tcp_addr = tcp_listener.local_addr().unwrap();
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue_udp = server_continue.clone();
+    let server_continue_tcp = server_continue.clone();
+
+    let udp_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp(udp_socket, server_continue_udp))
+        .unwrap();
+    let tcp_thread = thread::Builder::new()
+        .name("test_server:tcp:server".to_string())
+        .spawn(move || server_thread_tcp(tcp_listener, server_continue_tcp))
+        .unwrap();
+
+    // the record set does not fit in the payload of the client, it is left out
+    let name = Name::from_str("big.example.com.").unwrap();
+    let client = SyncClient::new(lazy_udp_client(udp_addr));
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::TXT)
+        .expect("query failed");
+
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(response.truncated());
+    assert!(response.answers().is_empty());
+
+    // the whole record set is sent over TCP
+    let client = SyncClient::new(lazy_tcp_client(tcp_addr));
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::TXT)
+        .expect("query failed");
+
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.truncated());
+    assert_eq!(response.answers().len(), BIG_TXT_COUNT);
+
+    server_continue.store(false, Ordering::Relaxed);
+    udp_thread.join().unwrap();
+    tcp_thread.join().unwrap();
+}
+
+#[test]
+fn test_server_rate_limited_udp() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+
+    let udp_addr = udp_socket.local_addr().unwrap();
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue_udp = server_continue.clone();
+
+    // every limited response is slipped, rather than dropped, so that the client gets an answer
+    let rate_limits = RateLimits {
+        responses_per_second: 1,
+        slip: 1,
+        ..RateLimits::default()
+    };
+    let udp_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || {
+            server_thread_udp_limited(udp_socket, Some(rate_limits), server_continue_udp)
+        }).unwrap();
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let client = SyncClient::new(lazy_udp_client(udp_addr));
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("query failed");
+    assert!(!response.truncated());
+    assert!(!response.answers().is_empty());
+
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("query failed");
+    assert!(response.truncated());
+    assert!(response.answers().is_empty());
+    assert_eq!(response.queries().len(), 1);
+
+    // other responses are not limited together with those
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::AAAA)
+        .expect("query failed");
+    assert!(!response.truncated());
+    assert!(!response.answers().is_empty());
+
+    server_continue.store(false, Ordering::Relaxed);
+    udp_thread.join().unwrap();
+}
+
+#[test]
+fn test_server_request_metadata() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+    let tcp_listener = TcpListener::bind(&addr).unwrap();
+
+    let udp_addr = udp_socket.local_addr().unwrap();
+    let tcp_addr = tcp_listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let server_continue = Arc::new(AtomicBool::new(true));
+
+    let handler = RecordingHandler {
+        catalog: new_catalog(),
+        requests: requests.clone(),
+    };
+    let server_continue2 = server_continue.clone();
+    let server_thread = thread::Builder::new()
+        .name("test_server:metadata:server".to_string())
+        .spawn(move || server_thread_recording(handler, udp_socket, tcp_listener, server_continue2))
+        .unwrap();
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let udp_client = SyncClient::new(lazy_udp_client(udp_addr));
+    udp_client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("udp query failed");
+    let tcp_client = SyncClient::new(lazy_tcp_client(tcp_addr));
+    tcp_client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("tcp query failed");
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+
+    let (protocol, src, dst, tls) = requests[0];
+    assert_eq!(protocol, Protocol::Udp);
+    assert_eq!(src.ip(), Ipv4Addr::new(127, 0, 0, 1));
+    assert_eq!(dst, udp_addr);
+    assert!(!tls);
+
+    let (protocol, src, dst, tls) = requests[1];
+    assert_eq!(protocol, Protocol::Tcp);
+    assert_eq!(src.ip(), Ipv4Addr::new(127, 0, 0, 1));
+    assert_eq!(dst, tcp_addr);
+    assert!(!tls);
+}
+
+#[test]
+fn test_server_request_metadata_wildcard() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 0));
+    let udp_socket = UdpSocket::bind(&addr).unwrap();
+    let tcp_listener = TcpListener::bind(&addr).unwrap();
+
+    let localhost = Ipv4Addr::new(127, 0, 0, 1);
+    let udp_addr = SocketAddr::new(localhost.into(), udp_socket.local_addr().unwrap().port());
+    let tcp_addr = SocketAddr::new(localhost.into(), tcp_listener.local_addr().unwrap().port());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let server_continue = Arc::new(AtomicBool::new(true));
+
+    let handler = RecordingHandler {
+        catalog: new_catalog(),
+        requests: requests.clone(),
+    };
+    let server_continue2 = server_continue.clone();
+    let server_thread = thread::Builder::new()
+        .name("test_server:metadata_wildcard:server".to_string())
+        .spawn(move || server_thread_recording(handler, udp_socket, tcp_listener, server_continue2))
+        .unwrap();
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let udp_client = SyncClient::new(lazy_udp_client(udp_addr));
+    udp_client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("udp query failed");
+    let tcp_client = SyncClient::new(lazy_tcp_client(tcp_addr));
+    tcp_client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("tcp query failed");
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+
+    // the destination of datagrams is not known, only the address the socket is bound to
+    let (protocol, _, dst, _) = requests[0];
+    assert_eq!(protocol, Protocol::Udp);
+    assert!(dst.ip().is_unspecified());
+    assert_eq!(dst.port(), udp_addr.port());
+
+    // the accepted connection has the address it was made to
+    let (protocol, _, dst, _) = requests[1];
+    assert_eq!(protocol, Protocol::Tcp);
+    assert_eq!(dst, tcp_addr);
+}
+
 #[test]
 fn test_server_unknown_type() {
     let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
@@ -263,20 +459,96 @@ where
     );
 }
 
+/// the count of the TXT records of big.example.com., they do not fit in a UDP response
+const BIG_TXT_COUNT: usize = 30;
+
 fn new_catalog() -> Catalog {
-    let example = create_example();
+    let mut example = create_example();
     let origin = example.origin().clone();
 
+    let serial = example.serial();
+    for i in 0..BIG_TXT_COUNT {
+        let txt = format!("{:0100}", i);
+        example.upsert(
+            Record::from_rdata(
+                Name::from_str("big.example.com.").unwrap(),
+                86400,
+                RecordType::TXT,
+                RData::TXT(TXT::new(vec![txt])),
+            ),
+            serial,
+        );
+    }
+
     let mut catalog: Catalog = Catalog::new();
     catalog.upsert(origin, example);
     catalog
 }
 
+/// the protocol, source and destination of a request, and whether it was received over TLS
+type RequestMetadata = (Protocol, SocketAddr, SocketAddr, bool);
+
+/// Records the metadata of each request, before the catalog handles it
+struct RecordingHandler {
+    catalog: Catalog,
+    requests: Arc<Mutex<Vec<RequestMetadata>>>,
+}
+
+impl RequestHandler for RecordingHandler {
+    fn handle_request<'q, 'a, R: ResponseHandler + 'static>(
+        &'a self,
+        request: &'q Request,
+        response_handle: R,
+    ) -> io::Result<()> {
+        self.requests.lock().unwrap().push((
+            request.protocol,
+            request.src,
+            request.dst,
+            request.tls.is_some(),
+        ));
+        self.catalog.handle_request(request, response_handle)
+    }
+}
+
+fn server_thread_recording(
+    handler: RecordingHandler,
+    udp_socket: UdpSocket,
+    tcp_listener: TcpListener,
+    server_continue: Arc<AtomicBool>,
+) {
+    let mut io_loop = Runtime::new().unwrap();
+    let server = ServerFuture::new(handler);
+    io_loop
+        .block_on::<Box<Future<Item = (), Error = io::Error> + Send>>(Box::new(future::lazy(
+            || {
+                server.register_socket(udp_socket);
+                future::result(server.register_listener(tcp_listener, Duration::from_secs(30)))
+            },
+        ))).expect("registration failed");
+
+    while server_continue.load(Ordering::Relaxed) {
+        io_loop
+            .block_on(Delay::new(Instant::now() + Duration::from_millis(10)))
+            .unwrap();
+    }
+}
+
 fn server_thread_udp(udp_socket: UdpSocket, server_continue: Arc<AtomicBool>) {
+    server_thread_udp_limited(udp_socket, None, server_continue)
+}
+
+fn server_thread_udp_limited(
+    udp_socket: UdpSocket,
+    rate_limits: Option<RateLimits>,
+    server_continue: Arc<AtomicBool>,
+) {
     let catalog = new_catalog();
 
     let mut io_loop = Runtime::new().unwrap();
-    let server = ServerFuture::new(catalog);
+    let mut server = ServerFuture::new(catalog);
+    if let Some(rate_limits) = rate_limits {
+        server.set_rate_limits(rate_limits);
+    }
     io_loop
         .block_on::<Box<Future<Item = (), Error = ()> + Send>>(Box::new(future::lazy(|| {
             server.register_socket(udp_socket);
@@ -0,0 +1,199 @@
+extern crate futures;
+extern crate rusqlite;
+extern crate tokio;
+extern crate tokio_tcp;
+extern crate tokio_timer;
+extern crate tokio_udp;
+extern crate trust_dns;
+extern crate trust_dns_integration;
+extern crate trust_dns_server;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::{future, Future};
+use rusqlite::Connection;
+use tokio::runtime::current_thread::Runtime;
+use tokio_tcp::TcpListener;
+use tokio_timer::Delay;
+use tokio_udp::UdpSocket;
+
+use trust_dns::rr::{DNSClass, Name, RData, Record, RecordType, RrKey};
+use trust_dns_server::authority::*;
+use trust_dns_server::server::{Request, RequestHandler, ResponseHandler};
+use trust_dns_server::ServerFuture;
+
+use trust_dns_integration::authority::create_example;
+
+/// Records the query type of each zone transfer, before the catalog handles it
+struct TransferRecorder {
+    catalog: Catalog,
+    transfers: Arc<Mutex<Vec<RecordType>>>,
+}
+
+impl RequestHandler for TransferRecorder {
+    fn handle_request<'q, 'a, R: ResponseHandler + 'static>(
+        &'a self,
+        request: &'q Request,
+        response_handle: R,
+    ) -> io::Result<()> {
+        for query in request.message.queries() {
+            match query.query_type() {
+                RecordType::AXFR | RecordType::IXFR => {
+                    self.transfers.lock().unwrap().push(query.query_type())
+                }
+                _ => (),
+            }
+        }
+
+        self.catalog.handle_request(request, response_handle)
+    }
+}
+
+fn server_thread(
+    handler: TransferRecorder,
+    udp_socket: UdpSocket,
+    tcp_listener: TcpListener,
+    server_continue: Arc<AtomicBool>,
+) {
+    let mut io_loop = Runtime::new().unwrap();
+    let server = ServerFuture::new(handler);
+    io_loop
+        .block_on::<Box<Future<Item = (), Error = io::Error> + Send>>(Box::new(future::lazy(
+            || {
+                server.register_socket(udp_socket);
+                future::result(server.register_listener(tcp_listener, Duration::from_secs(30)))
+            },
+        ))).expect("registration failed");
+
+    while server_continue.load(Ordering::Relaxed) {
+        io_loop
+            .block_on(Delay::new(Instant::now() + Duration::from_millis(10)))
+            .unwrap();
+    }
+}
+
+/// Runs the refresh of the slave zone until it has the serial
+fn wait_for_serial(io_loop: &mut Runtime, slave: &RwLock<Authority>, serial: u32) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while slave.read().unwrap().serial() != serial {
+        assert!(
+            Instant::now() < deadline,
+            "slave zone not transferred, serial: {} expected: {}",
+            slave.read().unwrap().serial(),
+            serial
+        );
+
+        io_loop
+            .block_on(Delay::new(Instant::now() + Duration::from_millis(10)))
+            .unwrap();
+    }
+}
+
+fn a_records(authority: &RwLock<Authority>, name: &Name) -> Vec<RData> {
+    authority
+        .read()
+        .unwrap()
+        .records()
+        .get(&RrKey::new(name.into(), RecordType::A))
+        .map(|rr_set| {
+            rr_set
+                .records_without_rrsigs()
+                .map(|r| r.rdata().clone())
+                .collect()
+        }).unwrap_or_default()
+}
+
+#[test]
+fn test_slave_transfer_and_refresh() {
+    let conn = Connection::open_in_memory().expect("could not create in memory DB");
+    let mut journal = Journal::new(conn).unwrap();
+    journal.schema_up().unwrap();
+
+    let mut example = create_example();
+    example.set_allow_axfr(true);
+    example.set_journal(journal);
+    example.persist_to_journal().unwrap();
+
+    let origin = example.origin().clone();
+    let master = Arc::new(RwLock::new(example));
+    let mut catalog = Catalog::new();
+    catalog.upsert_shared(origin.clone(), master.clone());
+
+    // the SOA is checked over UDP, and the zone transferred over TCP, on the same port
+    let udp_socket = UdpSocket::bind(&SocketAddr::from((Ipv4Addr::new(127, 0, 0, 1), 0))).unwrap();
+    let master_addr = udp_socket.local_addr().unwrap();
+    let tcp_listener = TcpListener::bind(&master_addr).unwrap();
+
+    let transfers = Arc::new(Mutex::new(Vec::new()));
+    let handler = TransferRecorder {
+        catalog,
+        transfers: transfers.clone(),
+    };
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+    let server = thread::Builder::new()
+        .name("test_slave:master".to_string())
+        .spawn(move || server_thread(handler, udp_socket, tcp_listener, server_continue2))
+        .unwrap();
+
+    let slave = Arc::new(RwLock::new(Authority::new(
+        origin.clone().into(),
+        BTreeMap::new(),
+        ZoneType::Slave,
+        false,
+        false,
+        false,
+    )));
+    let refresh = SlaveRefresh::new(slave.clone(), vec![master_addr]);
+    let handle = refresh.handle();
+
+    let mut io_loop = Runtime::new().unwrap();
+    io_loop.spawn(refresh);
+
+    // the empty zone is transferred in full
+    let serial = master.read().unwrap().serial();
+    wait_for_serial(&mut io_loop, &slave, serial);
+
+    let www = Name::from_str("www.example.com.").unwrap();
+    let www_a = RData::A(Ipv4Addr::new(93, 184, 216, 34));
+    assert_eq!(a_records(&slave, &www), vec![www_a.clone()]);
+
+    // the serial is bumped, the changes are transferred incrementally on NOTIFY
+    let new = Name::from_str("new.example.com.").unwrap();
+    let new_a = RData::A(Ipv4Addr::new(10, 0, 0, 1));
+    let mut removed_www = Record::from_rdata(www.clone(), 0, RecordType::A, www_a);
+    removed_www.set_dns_class(DNSClass::NONE);
+
+    master
+        .write()
+        .unwrap()
+        .update_records(
+            &[
+                Record::from_rdata(new.clone(), 86400, RecordType::A, new_a.clone()),
+                removed_www,
+            ],
+            true,
+        ).unwrap();
+    let bumped = master.read().unwrap().serial();
+    assert_ne!(bumped, serial);
+
+    assert!(handle.refresh());
+    wait_for_serial(&mut io_loop, &slave, bumped);
+
+    assert_eq!(a_records(&slave, &new), vec![new_a]);
+    assert!(a_records(&slave, &www).is_empty());
+    assert_eq!(
+        *transfers.lock().unwrap(),
+        vec![RecordType::AXFR, RecordType::IXFR]
+    );
+
+    server_continue.store(false, Ordering::Relaxed);
+    server.join().unwrap();
+}